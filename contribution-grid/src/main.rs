@@ -1,7 +1,7 @@
 use std::f32::consts::PI;
 
 use macroquad::{prelude::*, rand::gen_range};
-use utility::{DebugText, TextPosition, RotatedBy, GameCamera, GameCameraParameters, create_camera_from_game_camera};
+use utility::{DebugText, TextPosition, RotatedBy, GameCamera, GameCameraController, GameCameraMode, GameCameraParameters, create_camera_from_game_camera};
 
 const WORLD_UP: Vec3 = vec3(0.0, 1.0, 0.0);
 
@@ -26,8 +26,15 @@ struct Orb {
     normal: Vec3
 }
 
+fn create_camera_controller() -> GameCameraController {
+    let mut camera_controller = GameCameraController::new(GameCameraMode::Fly);
+    camera_controller.world_up = WORLD_UP;
+    camera_controller
+}
+
 struct Game {
     camera: GameCamera,
+    camera_controller: GameCameraController,
     debug_text: DebugText,
     world_bounds: (Vec3, Vec3),
     orbs: Vec<Orb>
@@ -41,7 +48,7 @@ impl Game {
                 parameters: GameCameraParameters {
                     movement_speed: 16.0,
                     rotation_speed: PI/2.0,
-                    zoom_speed: 1.0
+                    zoom_speed: 0.1
                 },
 
                 position: Vec3::ZERO,
@@ -49,6 +56,7 @@ impl Game {
                 target: Vec3::ZERO,
 
             },
+            camera_controller: create_camera_controller(),
             world_bounds: (Vec3::ZERO, Vec3::ZERO),
             debug_text: DebugText::new(),
             orbs: Vec::new()
@@ -76,51 +84,6 @@ fn calculate_contribution(p: Vec3, t: Triangle) -> Vec3 {
 
 }
 
-fn handle_camera_input(active: &mut GameCamera, _last_mouse_position: Vec2, dt: f32) {
-
-    let is_forwards_pressed = is_key_down(KeyCode::W);
-    let is_backwards_pressed = is_key_down(KeyCode::S);
-    let is_left_pressed = is_key_down(KeyCode::A);
-    let is_right_pressed = is_key_down(KeyCode::D);
-
-    let is_up_pressed = is_key_down(KeyCode::Space);
-    let is_down_pressed = is_key_down(KeyCode::LeftControl);
-
-    let mut camera_movement_delta = Vec3::ZERO;
-
-    let forward_in_plane = vec3(active.forward().x, 0.0, active.forward().z);
-    let left_in_plane = vec3(active.left().x, 0.0, active.left().z);
-    let up_in_plane = WORLD_UP;
-
-    if is_forwards_pressed {
-        camera_movement_delta += forward_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_backwards_pressed {
-        camera_movement_delta -= forward_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_left_pressed {
-        camera_movement_delta += left_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_right_pressed {
-        camera_movement_delta -= left_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_up_pressed {
-        camera_movement_delta += up_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_down_pressed {
-        camera_movement_delta -= up_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    active.position += camera_movement_delta;
-    active.target += camera_movement_delta;
-
-}
-
 fn draw_orbs(game: &Game) {
 
     for orb in &game.orbs {
@@ -361,7 +324,6 @@ fn rotate_orbs_around_center_of_grid(game: &mut Game, dt: f32) {
 async fn main() {
 
     let mut game = Game::new();
-    let mut last_mouse_position: Vec2 = mouse_position().into();
 
     let mut should_generate_the_world = true;
     let mut should_be_rotating_the_grid = false;
@@ -410,8 +372,7 @@ async fn main() {
         set_camera(&create_camera_from_game_camera(&game.camera));
         clear_background(WHITE);
 
-        handle_camera_input(&mut game.camera, last_mouse_position, dt);
-        last_mouse_position = mouse_position().into();
+        game.camera_controller.tick(&mut game.camera, dt);
 
         // update scene
         if should_be_rotating_the_grid {
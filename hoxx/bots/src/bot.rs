@@ -0,0 +1,268 @@
+use std::{collections::{HashMap, HashSet}, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use hexx::Hex;
+use hoxx_shared::{hex_to_chunk, ChunkCoordinate, ClientID, ClientMessage, GameState, GameStateDelta, HexChunk};
+use nanoserde::{DeJson, SerJson};
+use ws::util::Token;
+
+use crate::{stats::{BotStats, LoadTestStats}, strategy::{BotStrategy, BotWorld}};
+
+const CLAIM_TIMEOUT: Token = Token(1);
+
+/// Claims not answered by the server within this long are given up on, the server says nothing at all about claims on hexes you already hold.
+const CLAIM_ANSWER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many chunks around a claim the bot subscribes to, on each side.
+const CHUNK_SUBSCRIBE_RADIUS: i32 = 1;
+
+/// Once subscribed to more chunks than this the bot starts over with only the chunks around its latest claim, well below what the server allows.
+const MAX_SUBSCRIBED_CHUNKS: usize = 64;
+
+/// A single bot, connected to the server like any other client would be.
+pub struct BotConnection {
+    ws: ws::Sender,
+    index: usize,
+    nickname: String,
+    world: BotWorld,
+    strategy: BotStrategy,
+    subscribed_chunks: HashSet<ChunkCoordinate>,
+    pending_claims: HashMap<Hex, Instant>,
+    claim_interval: Duration,
+    stop_at: Instant,
+    stats: Arc<Mutex<LoadTestStats>>
+}
+
+impl BotConnection {
+
+    pub fn new(ws: ws::Sender, index: usize, nickname: String, start_hex: Hex, strategy: BotStrategy, claim_interval: Duration, stop_at: Instant, stats: Arc<Mutex<LoadTestStats>>) -> BotConnection {
+        BotConnection {
+            ws,
+            index,
+            nickname,
+            world: BotWorld::new(start_hex),
+            strategy,
+            subscribed_chunks: HashSet::new(),
+            pending_claims: HashMap::new(),
+            claim_interval,
+            stop_at,
+            stats
+        }
+    }
+
+    fn with_stats(&self, stats_fn: impl FnOnce(&mut BotStats)) {
+        if let Ok(mut stats) = self.stats.lock() {
+            stats_fn(&mut stats.bots[self.index]);
+        }
+    }
+
+    fn send_message(&self, message: &ClientMessage) {
+        if let Err(error) = self.ws.send(message.serialize_json()) {
+            println!("[hoxx-bots] {} failed to send message with error: {}", self.nickname, error);
+        }
+    }
+
+    fn schedule_next_claim(&self) -> ws::Result<()> {
+        self.ws.timeout(self.claim_interval.as_millis() as u64, CLAIM_TIMEOUT)
+    }
+
+    /// Makes sure we hear about the hex from the server, so the claim can be confirmed and the bot sees who else is around.
+    fn subscribe_around(&mut self, hex: Hex) {
+
+        let (chunk_x, chunk_y) = hex_to_chunk(hex.x, hex.y);
+        if self.subscribed_chunks.contains(&(chunk_x, chunk_y)) {
+            return;
+        }
+
+        if self.subscribed_chunks.len() > MAX_SUBSCRIBED_CHUNKS {
+            self.subscribed_chunks.clear();
+        }
+
+        for x in -CHUNK_SUBSCRIBE_RADIUS..=CHUNK_SUBSCRIBE_RADIUS {
+            for y in -CHUNK_SUBSCRIBE_RADIUS..=CHUNK_SUBSCRIBE_RADIUS {
+                self.subscribed_chunks.insert((chunk_x + x, chunk_y + y));
+            }
+        }
+
+        self.send_message(&ClientMessage::Subscribe { chunks: self.subscribed_chunks.iter().copied().collect() });
+
+    }
+
+    fn confirm_claim(&mut self, hex: Hex, owner: ClientID) {
+
+        let Some(sent_at) = self.pending_claims.remove(&hex) else { return; };
+        let latency_ms = sent_at.elapsed().as_secs_f32() * 1000.0;
+        let is_ours = owner == self.world.id;
+
+        self.with_stats(|stats| {
+            stats.claim_latencies_ms.push(latency_ms);
+            if is_ours {
+                stats.claims_confirmed += 1;
+            }
+        });
+
+    }
+
+    fn expire_pending_claims(&mut self) {
+
+        let number_of_pending_claims = self.pending_claims.len();
+        self.pending_claims.retain(|_, sent_at| sent_at.elapsed() < CLAIM_ANSWER_TIMEOUT);
+
+        let number_of_expired_claims = (number_of_pending_claims - self.pending_claims.len()) as u64;
+        if number_of_expired_claims > 0 {
+            self.with_stats(|stats| stats.claims_unanswered += number_of_expired_claims);
+        }
+
+    }
+
+    fn claim(&mut self) {
+
+        let Some(hex) = self.strategy.next_claim(&self.world) else { return; };
+        let world_position = self.world.state.hex_to_world(hex.x, hex.y);
+
+        // claimed straight away like the real client does, so the next claim can build on this one
+        self.world.set_hex(hex, self.world.id);
+        self.pending_claims.insert(hex, Instant::now());
+        self.subscribe_around(hex);
+
+        self.send_message(&ClientMessage::Claim { world_x: world_position.x as i32, world_y: world_position.y as i32 });
+        self.with_stats(|stats| stats.claims_sent += 1);
+
+    }
+
+    fn handle_join(&mut self, id: ClientID) {
+        self.world.id = id;
+        self.subscribe_around(self.world.start_hex);
+    }
+
+    fn handle_full_update(&mut self, state: &GameState) {
+        self.world.state.update_state_from(state.clone());
+        self.world.refresh_own_hexes();
+    }
+
+    fn handle_delta(&mut self, delta: &GameStateDelta) {
+        for hex_claim in &delta.hexes {
+            let hex = Hex::new(hex_claim.x, hex_claim.y);
+            let owner = ClientID { id: hex_claim.owner };
+            self.world.set_hex(hex, owner);
+            self.confirm_claim(hex, owner);
+        }
+    }
+
+    fn handle_chunks(&mut self, chunks: &[(ChunkCoordinate, HexChunk)]) {
+
+        for (chunk, hex_chunk) in chunks {
+            self.world.state.set_chunk(*chunk, hex_chunk.clone());
+        }
+
+        self.world.refresh_own_hexes();
+
+    }
+
+    fn handle_claim_rejected(&mut self, world_x: i32, world_y: i32, current_owner: Option<i64>) {
+
+        let hex = self.world.state.world_to_hex(world_x, world_y);
+
+        match current_owner {
+            Some(owner) => self.world.set_hex(hex, ClientID { id: owner }),
+            None => {
+                self.world.state.remove_hex(hex.x, hex.y);
+                self.world.own_hexes.remove(&hex);
+            }
+        }
+
+        self.confirm_claim(hex, ClientID::INVALID);
+        self.strategy.on_claim_rejected();
+        self.with_stats(|stats| stats.claims_rejected += 1);
+
+    }
+
+    fn handle_message(&mut self, message: ClientMessage) {
+        match message {
+            // never sent by server to client
+            ClientMessage::Identify { .. } => (),
+            ClientMessage::Claim { .. } => (),
+            ClientMessage::RequestResync => (),
+            ClientMessage::Subscribe { .. } => (),
+
+            // sent by server to client, sequence numbers are ignored as a bot doesn't much care if it misses something
+            ClientMessage::Join { id, .. } => self.handle_join(id),
+            ClientMessage::Update { state, .. } => self.handle_full_update(&state),
+            ClientMessage::Delta { delta, .. } => self.handle_delta(&delta),
+            ClientMessage::Chunks { chunks, .. } => self.handle_chunks(&chunks),
+            ClientMessage::ClaimRejected { world_x, world_y, current_owner, .. } => self.handle_claim_rejected(world_x, world_y, current_owner),
+            ClientMessage::Enclosure { id, .. } => {
+                if id == self.world.id {
+                    self.with_stats(|stats| stats.enclosures += 1);
+                }
+            },
+            ClientMessage::Leaderboard { .. } => ()
+        }
+    }
+
+}
+
+impl ws::Handler for BotConnection {
+
+    fn on_open(&mut self, _shake: ws::Handshake) -> ws::Result<()> {
+
+        self.send_message(&ClientMessage::Identify { token: None, nickname: self.nickname.clone() });
+        self.with_stats(|stats| stats.is_connected = true);
+        self.schedule_next_claim()
+
+    }
+
+    fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
+
+        let message_length = msg.len() as u64;
+        self.with_stats(|stats| {
+            stats.messages_received += 1;
+            stats.bytes_received += message_length;
+        });
+
+        let Ok(text) = msg.as_text() else { return Ok(()); };
+
+        match ClientMessage::deserialize_json(text) {
+            Ok(message) => self.handle_message(message),
+            Err(error) => println!("[hoxx-bots] {} got invalid payload with error: {}, ignoring message!", self.nickname, error)
+        }
+
+        Ok(())
+
+    }
+
+    fn on_timeout(&mut self, event: Token) -> ws::Result<()> {
+
+        if event != CLAIM_TIMEOUT {
+            return Ok(());
+        }
+
+        if Instant::now() >= self.stop_at {
+            return self.ws.close(ws::CloseCode::Normal);
+        }
+
+        self.expire_pending_claims();
+
+        // nothing to build on until the server has told us who we are
+        if self.world.id != ClientID::INVALID {
+            self.claim();
+        }
+
+        self.schedule_next_claim()
+
+    }
+
+    fn on_close(&mut self, _code: ws::CloseCode, reason: &str) {
+
+        self.with_stats(|stats| stats.is_connected = false);
+
+        if reason.is_empty() == false {
+            println!("[hoxx-bots] {} disconnected with reason: {}", self.nickname, reason);
+        }
+
+    }
+
+    fn on_error(&mut self, err: ws::Error) {
+        println!("[hoxx-bots] {} got error: {}", self.nickname, err);
+    }
+
+}
@@ -0,0 +1,159 @@
+use std::{sync::{Arc, Mutex}, thread::JoinHandle, time::{Duration, Instant}};
+
+use bot::BotConnection;
+use hexx::Hex;
+use hoxx_shared::{HEX_CLAIM_COOLDOWN, SERVER_ADDRESS, SERVER_INTERNAL_PORT};
+use rand::Rng;
+use stats::LoadTestStats;
+use strategy::{BotStrategy, BotStrategyKind};
+
+mod bot;
+mod stats;
+mod strategy;
+
+const DEFAULT_NUMBER_OF_BOTS: usize = 16;
+const DEFAULT_DURATION_SECONDS: f32 = 60.0;
+const DEFAULT_RAMP_UP_SECONDS: f32 = 5.0;
+const DEFAULT_SPREAD: i32 = 64;
+
+/// How often the progress line is printed while the bots are running.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+struct BotArguments {
+    address: String,
+    number_of_bots: usize,
+    /// None means every strategy gets an equal share of the bots.
+    strategy: Option<BotStrategyKind>,
+    duration_seconds: f32,
+    /// Bots connect evenly spread out over this long, rather than all at once.
+    ramp_up_seconds: f32,
+    claims_per_second: f32,
+    /// Bots start at a random hex at most this far from the origin, lower means more fighting over hexes.
+    spread: i32
+}
+
+impl BotArguments {
+
+    /// Parses ```[--address ws://host:port] [--bots N] [--strategy random|enclosure|pusher|mixed] [--duration S] [--ramp-up S] [--claims-per-second N] [--spread N]```.
+    fn from_args<I>(args: I) -> Result<BotArguments, String>
+        where I: Iterator<Item = String>
+    {
+
+        let mut arguments = BotArguments {
+            address: format!("ws://{}:{}", SERVER_ADDRESS, SERVER_INTERNAL_PORT),
+            number_of_bots: DEFAULT_NUMBER_OF_BOTS,
+            strategy: None,
+            duration_seconds: DEFAULT_DURATION_SECONDS,
+            ramp_up_seconds: DEFAULT_RAMP_UP_SECONDS,
+            claims_per_second: 1.0 / HEX_CLAIM_COOLDOWN,
+            spread: DEFAULT_SPREAD
+        };
+
+        let args: Vec<String> = args.collect();
+        let mut args_iterator = args.iter().skip(1);
+
+        while let Some(arg) = args_iterator.next() {
+
+            let mut next_value = || args_iterator.next().cloned().ok_or_else(|| format!("missing value for: {}", arg));
+            let parse_error = |value: &str| format!("invalid value for: {}, got: {}", arg, value);
+
+            match arg.as_str() {
+                "--address" => arguments.address = next_value()?,
+                "--bots" => { let v = next_value()?; arguments.number_of_bots = v.parse().map_err(|_| parse_error(&v))?; },
+                "--duration" => { let v = next_value()?; arguments.duration_seconds = v.parse().map_err(|_| parse_error(&v))?; },
+                "--ramp-up" => { let v = next_value()?; arguments.ramp_up_seconds = v.parse().map_err(|_| parse_error(&v))?; },
+                "--claims-per-second" => { let v = next_value()?; arguments.claims_per_second = v.parse().map_err(|_| parse_error(&v))?; },
+                "--spread" => { let v = next_value()?; arguments.spread = v.parse().map_err(|_| parse_error(&v))?; },
+                "--strategy" => {
+                    let v = next_value()?;
+                    arguments.strategy = if v == "mixed" { None } else { Some(BotStrategyKind::from_name(&v).ok_or_else(|| parse_error(&v))?) };
+                },
+                other => return Err(format!("unknown argument: {}", other))
+            }
+
+        }
+
+        if arguments.claims_per_second <= 0.0 {
+            return Err("claims per second must be above zero".to_string());
+        }
+
+        Ok(arguments)
+
+    }
+
+    fn strategy_for_bot(&self, index: usize) -> BotStrategyKind {
+        const MIXED_STRATEGIES: [BotStrategyKind; 3] = [BotStrategyKind::RandomWalk, BotStrategyKind::EnclosureBuilder, BotStrategyKind::BorderPusher];
+        self.strategy.unwrap_or(MIXED_STRATEGIES[index % MIXED_STRATEGIES.len()])
+    }
+
+}
+
+fn spawn_bot(arguments: &BotArguments, index: usize, nickname: String, stop_at: Instant, stats: Arc<Mutex<LoadTestStats>>) -> JoinHandle<()> {
+
+    let mut rng = rand::thread_rng();
+    let start_hex = Hex::new(rng.gen_range(-arguments.spread..=arguments.spread), rng.gen_range(-arguments.spread..=arguments.spread));
+    let strategy_kind = arguments.strategy_for_bot(index);
+    let claim_interval = Duration::from_secs_f32(1.0 / arguments.claims_per_second);
+    let address = arguments.address.clone();
+
+    std::thread::spawn(move || {
+        if let Err(error) = ws::connect(address, |out| {
+            BotConnection::new(out, index, nickname.clone(), start_hex, BotStrategy::new(strategy_kind), claim_interval, stop_at, stats.clone())
+        }) {
+            println!("[hoxx-bots] {} failed to connect with error: {}", nickname, error);
+        }
+    })
+
+}
+
+fn main() {
+
+    let arguments = match BotArguments::from_args(std::env::args()) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            println!("[hoxx-bots] {}", error);
+            println!("usage: hoxx-bots [--address ws://host:port] [--bots N] [--strategy random|enclosure|pusher|mixed] [--duration S] [--ramp-up S] [--claims-per-second N] [--spread N]");
+            return;
+        }
+    };
+
+    let bot_names: Vec<String> = (0..arguments.number_of_bots)
+        .map(|index| format!("bot-{}-{}", index, arguments.strategy_for_bot(index).name()))
+        .collect();
+
+    let stats = Arc::new(Mutex::new(LoadTestStats::new(bot_names.clone())));
+    let ramp_up_interval = Duration::from_secs_f32(arguments.ramp_up_seconds / (arguments.number_of_bots.max(1) as f32));
+
+    println!("[hoxx-bots] starting {} bots against: {} for {} seconds", arguments.number_of_bots, arguments.address, arguments.duration_seconds);
+
+    let mut last_progress = Instant::now();
+    let mut bot_threads = Vec::new();
+
+    for (index, nickname) in bot_names.into_iter().enumerate() {
+
+        // every bot runs for the full duration, counted from when it connects
+        let stop_at = Instant::now() + Duration::from_secs_f32(arguments.duration_seconds);
+        bot_threads.push(spawn_bot(&arguments, index, nickname, stop_at, stats.clone()));
+        std::thread::sleep(ramp_up_interval);
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            stats.lock().unwrap().print_progress();
+            last_progress = Instant::now();
+        }
+
+    }
+
+    while bot_threads.iter().any(|t| t.is_finished() == false) {
+
+        std::thread::sleep(Duration::from_millis(100));
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            stats.lock().unwrap().print_progress();
+            last_progress = Instant::now();
+        }
+
+    }
+
+    stats.lock().unwrap().print_report();
+
+}
@@ -0,0 +1,134 @@
+use std::time::Instant;
+
+/// Everything measured for a single bot over the run.
+#[derive(Debug, Clone)]
+pub struct BotStats {
+    pub name: String,
+    pub is_connected: bool,
+    pub messages_received: u64,
+    pub bytes_received: u64,
+    pub claims_sent: u64,
+    /// Claims which showed up in a delta from the server.
+    pub claims_confirmed: u64,
+    pub claims_rejected: u64,
+    /// Claims the server never answered within the timeout, either dropped or silently ignored.
+    pub claims_unanswered: u64,
+    pub enclosures: u64,
+    /// Time from sending a claim until the server confirmed or rejected it.
+    pub claim_latencies_ms: Vec<f32>
+}
+
+impl BotStats {
+
+    pub fn new(name: String) -> BotStats {
+        BotStats {
+            name,
+            is_connected: false,
+            messages_received: 0,
+            bytes_received: 0,
+            claims_sent: 0,
+            claims_confirmed: 0,
+            claims_rejected: 0,
+            claims_unanswered: 0,
+            enclosures: 0,
+            claim_latencies_ms: Vec::new()
+        }
+    }
+
+}
+
+fn percentile(sorted_samples_ms: &[f32], percentile: f32) -> f32 {
+    if sorted_samples_ms.is_empty() {
+        return 0.0;
+    }
+    let index = ((sorted_samples_ms.len() - 1) as f32 * percentile).round() as usize;
+    sorted_samples_ms[index]
+}
+
+pub struct LoadTestStats {
+    pub bots: Vec<BotStats>,
+    started_at: Instant
+}
+
+impl LoadTestStats {
+
+    pub fn new(bot_names: Vec<String>) -> LoadTestStats {
+        LoadTestStats {
+            bots: bot_names.into_iter().map(BotStats::new).collect(),
+            started_at: Instant::now()
+        }
+    }
+
+    fn print_latencies(name: &str, samples_ms: &[f32]) {
+
+        let mut sorted_samples_ms = samples_ms.to_vec();
+        sorted_samples_ms.sort_by(|a, b| a.total_cmp(b));
+
+        let mean_ms = sorted_samples_ms.iter().sum::<f32>() / (sorted_samples_ms.len().max(1) as f32);
+
+        println!(
+            "{:<24} mean: {:>8.3} ms  p50: {:>8.3} ms  p95: {:>8.3} ms  p99: {:>8.3} ms  max: {:>8.3} ms",
+            name,
+            mean_ms,
+            percentile(&sorted_samples_ms, 0.50),
+            percentile(&sorted_samples_ms, 0.95),
+            percentile(&sorted_samples_ms, 0.99),
+            percentile(&sorted_samples_ms, 1.0)
+        );
+
+    }
+
+    /// One line summing up all the bots so far, printed every now and then while the test runs.
+    pub fn print_progress(&self) {
+
+        let elapsed_seconds = self.started_at.elapsed().as_secs_f32().max(f32::EPSILON);
+        let connected_bots = self.bots.iter().filter(|b| b.is_connected).count();
+        let claims_sent: u64 = self.bots.iter().map(|b| b.claims_sent).sum();
+        let claims_confirmed: u64 = self.bots.iter().map(|b| b.claims_confirmed).sum();
+        let bytes_received: u64 = self.bots.iter().map(|b| b.bytes_received).sum();
+
+        println!(
+            "[hoxx-bots] {:>6.1}s  connected: {}/{}  claims sent: {}  confirmed: {} ({:.1}/s)  received: {:.1} KiB/s",
+            elapsed_seconds,
+            connected_bots,
+            self.bots.len(),
+            claims_sent,
+            claims_confirmed,
+            claims_confirmed as f32 / elapsed_seconds,
+            bytes_received as f32 / 1024.0 / elapsed_seconds
+        );
+
+    }
+
+    pub fn print_report(&self) {
+
+        let elapsed_seconds = self.started_at.elapsed().as_secs_f32().max(f32::EPSILON);
+
+        println!();
+        println!("hoxx-bots: {} bots over {:.1} seconds", self.bots.len(), elapsed_seconds);
+        println!();
+
+        println!("{:<24} {:>10} {:>12} {:>10} {:>10} {:>10} {:>10} {:>10}", "bot", "messages", "bytes", "sent", "confirmed", "rejected", "unanswered", "loops");
+        for bot in &self.bots {
+            println!(
+                "{:<24} {:>10} {:>12} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                bot.name, bot.messages_received, bot.bytes_received, bot.claims_sent, bot.claims_confirmed, bot.claims_rejected, bot.claims_unanswered, bot.enclosures
+            );
+        }
+
+        println!();
+        println!("claim latency:");
+
+        let all_latencies_ms: Vec<f32> = self.bots.iter().flat_map(|b| b.claim_latencies_ms.iter().copied()).collect();
+        Self::print_latencies("all bots", &all_latencies_ms);
+
+        let claims_confirmed: u64 = self.bots.iter().map(|b| b.claims_confirmed).sum();
+        let bytes_received: u64 = self.bots.iter().map(|b| b.bytes_received).sum();
+
+        println!();
+        println!("claim throughput: {:.1} confirmed claims/s", claims_confirmed as f32 / elapsed_seconds);
+        println!("received per bot: {:.1} KiB/s", bytes_received as f32 / 1024.0 / elapsed_seconds / (self.bots.len().max(1) as f32));
+
+    }
+
+}
@@ -0,0 +1,221 @@
+use std::collections::{HashSet, VecDeque};
+
+use hexx::Hex;
+use hoxx_shared::{ClientID, GameState};
+use rand::{seq::IteratorRandom, Rng};
+
+/// Smallest and largest radius of the loops the enclosure builder draws.
+const ENCLOSURE_MIN_RADIUS: u32 = 2;
+const ENCLOSURE_MAX_RADIUS: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BotStrategyKind {
+    /// Wanders around claiming a random neighbour of the last claim.
+    RandomWalk,
+    /// Draws loops out from its own territory so the server has to flood fill them.
+    EnclosureBuilder,
+    /// Takes hexes from other players along its border wherever it can, otherwise pushes outwards in one direction.
+    BorderPusher
+}
+
+impl BotStrategyKind {
+
+    pub fn from_name(name: &str) -> Option<BotStrategyKind> {
+        match name {
+            "random" => Some(BotStrategyKind::RandomWalk),
+            "enclosure" => Some(BotStrategyKind::EnclosureBuilder),
+            "pusher" => Some(BotStrategyKind::BorderPusher),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BotStrategyKind::RandomWalk => "random",
+            BotStrategyKind::EnclosureBuilder => "enclosure",
+            BotStrategyKind::BorderPusher => "pusher"
+        }
+    }
+
+}
+
+/// What a bot knows about the world, kept up to date from the messages the server sends it.
+pub struct BotWorld {
+    pub id: ClientID,
+    pub state: GameState,
+    pub own_hexes: HashSet<Hex>,
+    pub start_hex: Hex
+}
+
+impl BotWorld {
+
+    pub fn new(start_hex: Hex) -> BotWorld {
+        BotWorld {
+            id: ClientID::INVALID,
+            state: GameState::new(),
+            own_hexes: HashSet::new(),
+            start_hex
+        }
+    }
+
+    pub fn get_owner(&self, hex: Hex) -> Option<ClientID> {
+        self.state.get_hex(hex.x, hex.y).map(|id| ClientID { id })
+    }
+
+    pub fn is_own(&self, hex: Hex) -> bool {
+        self.own_hexes.contains(&hex)
+    }
+
+    pub fn set_hex(&mut self, hex: Hex, owner: ClientID) {
+
+        self.state.set_hex(hex.x, hex.y, owner.id);
+
+        if owner == self.id {
+            self.own_hexes.insert(hex);
+        } else {
+            self.own_hexes.remove(&hex);
+        }
+
+    }
+
+    /// Rebuilds the own hexes from the game state, after receiving a full update or new chunks.
+    pub fn refresh_own_hexes(&mut self) {
+        self.own_hexes = self.state.get_chunks().values()
+            .flat_map(|hex_chunk| hex_chunk.get_hexes().iter())
+            .filter(|(_, &owner)| owner == self.id.id)
+            .map(|(&(x, y), _)| Hex::new(x, y))
+            .collect();
+    }
+
+    /// Hexes next to our territory which we don't hold, so claims there pass the adjacency rule.
+    fn frontier(&self) -> HashSet<Hex> {
+        self.own_hexes.iter()
+            .flat_map(|hex| hex.all_neighbors())
+            .filter(|hex| self.is_own(*hex) == false)
+            .collect()
+    }
+
+}
+
+pub struct BotStrategy {
+    kind: BotStrategyKind,
+    /// Where the random walk is right now.
+    walk_position: Option<Hex>,
+    /// The rest of the path the enclosure builder is drawing.
+    planned_hexes: VecDeque<Hex>,
+    /// Which way the border pusher is heading when there is nobody to take hexes from.
+    push_direction: Hex
+}
+
+impl BotStrategy {
+
+    pub fn new(kind: BotStrategyKind) -> BotStrategy {
+        let mut rng = rand::thread_rng();
+        BotStrategy {
+            kind,
+            walk_position: None,
+            planned_hexes: VecDeque::new(),
+            push_direction: Hex::ZERO.all_neighbors()[rng.gen_range(0..6)]
+        }
+    }
+
+    /// Called when the server rejected a claim, whatever was planned most likely depended on it.
+    pub fn on_claim_rejected(&mut self) {
+        self.walk_position = None;
+        self.planned_hexes.clear();
+    }
+
+    /// The next hex to claim, if there is anything worth claiming.
+    pub fn next_claim(&mut self, world: &BotWorld) -> Option<Hex> {
+
+        // the very first claim can be anywhere
+        if world.own_hexes.is_empty() {
+            return Some(world.start_hex);
+        }
+
+        match self.kind {
+            BotStrategyKind::RandomWalk => self.next_random_walk_claim(world),
+            BotStrategyKind::EnclosureBuilder => self.next_enclosure_claim(world),
+            BotStrategyKind::BorderPusher => self.next_border_push_claim(world)
+        }
+
+    }
+
+    fn next_random_walk_claim(&mut self, world: &BotWorld) -> Option<Hex> {
+
+        let mut rng = rand::thread_rng();
+
+        let walk_position = match self.walk_position {
+            Some(walk_position) if world.is_own(walk_position) => walk_position,
+            _ => *world.own_hexes.iter().choose(&mut rng)?
+        };
+
+        let next_position = walk_position.all_neighbors().into_iter()
+            .filter(|hex| world.is_own(*hex) == false)
+            .choose(&mut rng)
+            .unwrap_or_else(|| walk_position.all_neighbors()[rng.gen_range(0..6)]);
+
+        self.walk_position = Some(next_position);
+
+        if world.is_own(next_position) {
+            // boxed in by our own hexes, just keep walking without claiming anything this time
+            return None;
+        }
+
+        Some(next_position)
+
+    }
+
+    /// Plans a spoke out from one of our hexes to a ring around it and then the ring itself, closing the ring makes the server fill it.
+    fn plan_enclosure(&mut self, world: &BotWorld) {
+
+        let mut rng = rand::thread_rng();
+        let Some(&centre) = world.own_hexes.iter().choose(&mut rng) else { return; };
+
+        let radius = rng.gen_range(ENCLOSURE_MIN_RADIUS..=ENCLOSURE_MAX_RADIUS);
+        let ring: Vec<Hex> = centre.ring(radius).collect();
+        let ring_start_index = rng.gen_range(0..ring.len());
+        let ring_start = ring[ring_start_index];
+
+        self.planned_hexes.extend(centre.line_to(ring_start).skip(1));
+        self.planned_hexes.extend(ring.iter().cycle().skip(ring_start_index + 1).take(ring.len() - 1).copied());
+
+    }
+
+    fn next_enclosure_claim(&mut self, world: &BotWorld) -> Option<Hex> {
+
+        while let Some(planned_hex) = self.planned_hexes.pop_front() {
+            if world.is_own(planned_hex) == false {
+                return Some(planned_hex);
+            }
+        }
+
+        self.plan_enclosure(world);
+        self.planned_hexes.pop_front()
+
+    }
+
+    fn next_border_push_claim(&mut self, world: &BotWorld) -> Option<Hex> {
+
+        let frontier = world.frontier();
+        let number_of_own_neighbours = |hex: &Hex| hex.all_neighbors().iter().filter(|n| world.is_own(**n)).count();
+
+        // take from others where we're surrounding them the most, as that's what the contest rules tend to want
+        let contested_hex = frontier.iter()
+            .filter(|hex| world.get_owner(**hex).is_some())
+            .max_by_key(|hex| number_of_own_neighbours(*hex));
+
+        if let Some(&contested_hex) = contested_hex {
+            if number_of_own_neighbours(&contested_hex) > 1 {
+                return Some(contested_hex);
+            }
+        }
+
+        let push_target = world.start_hex + self.push_direction * 1024;
+        frontier.into_iter()
+            .filter(|hex| world.get_owner(*hex).is_none())
+            .min_by_key(|hex| hex.unsigned_distance_to(push_target))
+
+    }
+
+}
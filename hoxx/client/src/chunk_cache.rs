@@ -0,0 +1,207 @@
+use std::collections::{HashMap, HashSet};
+
+use hexx::Hex;
+use hoxx_shared::{hex_to_chunk, ChunkCoordinate, ClientColor, ClientID, GameState, HEX_SIZE};
+use macroquad::{color::Color, math::{vec2, Vec2}, models::{draw_mesh, Mesh, Vertex}};
+use utility::AdjustHue;
+
+use crate::utils::to_macroquad_color;
+
+/// macroquad clamps anything drawn in one go to 5000 indices, so the geometry of a chunk is split into meshes staying below that.
+const MAX_MESH_INDICES: usize = 4800;
+
+/// Rebuilding a chunk is fairly cheap but a whole screen of them at once still shows, the rest wait for the next frame.
+const MAX_CHUNK_REBUILDS_PER_FRAME: usize = 8;
+
+/// Width of the border drawn on the inside of a hex where it meets a hex with a different owner.
+const HEX_BORDER_WIDTH: f32 = 2.0;
+
+/// Collects triangles into meshes, starting a new mesh whenever the current one is full.
+struct ChunkMeshBuilder {
+    meshes: Vec<Mesh>
+}
+
+impl ChunkMeshBuilder {
+
+    fn new() -> ChunkMeshBuilder {
+        ChunkMeshBuilder {
+            meshes: Vec::new()
+        }
+    }
+
+    fn current_mesh(&mut self, number_of_indices: usize) -> &mut Mesh {
+
+        let needs_new_mesh = match self.meshes.last() {
+            Some(mesh) => mesh.indices.len() + number_of_indices > MAX_MESH_INDICES,
+            None => true
+        };
+
+        if needs_new_mesh {
+            self.meshes.push(Mesh { vertices: Vec::new(), indices: Vec::new(), texture: None });
+        }
+
+        self.meshes.last_mut().unwrap()
+
+    }
+
+    /// Adds a convex polygon as a triangle fan.
+    fn push_polygon(&mut self, points: &[Vec2], colour: Color) {
+
+        let number_of_indices = (points.len() - 2) * 3;
+        let mesh = self.current_mesh(number_of_indices);
+        let first_vertex = mesh.vertices.len() as u16;
+
+        for p in points {
+            mesh.vertices.push(Vertex::new(p.x, p.y, 0.0, 0.0, 0.0, colour));
+        }
+
+        for i in 1..(points.len() as u16 - 1) {
+            mesh.indices.extend_from_slice(&[first_vertex, first_vertex + i, first_vertex + i + 1]);
+        }
+
+    }
+
+    fn build(self) -> Vec<Mesh> {
+        self.meshes
+    }
+
+}
+
+/// The corners of a hex as drawn by draw_hex, starting from the right going clockwise in screen space.
+fn hex_corners(centre: Vec2, size: f32) -> [Vec2; 6] {
+    std::array::from_fn(|i| {
+        let angle = (i as f32 * 60.0).to_radians();
+        centre + vec2(angle.cos(), angle.sin()) * size
+    })
+}
+
+/// The index of the edge of a hex facing in the given direction, where edge i goes from corner i to corner i + 1.
+fn hex_edge_facing(direction: Vec2) -> usize {
+    let angle = direction.y.atan2(direction.x).to_degrees() - 30.0;
+    ((angle / 60.0).round() as i32).rem_euclid(6) as usize
+}
+
+/// Territory in a chunk, rasterized into meshes and only rebuilt when something in the chunk changes.
+pub struct ChunkRenderCache {
+    meshes: HashMap<ChunkCoordinate, Vec<Mesh>>,
+    dirty_chunks: HashSet<ChunkCoordinate>
+}
+
+impl ChunkRenderCache {
+
+    pub fn new() -> ChunkRenderCache {
+        ChunkRenderCache {
+            meshes: HashMap::new(),
+            dirty_chunks: HashSet::new()
+        }
+    }
+
+    pub fn number_of_cached_chunks(&self) -> usize {
+        self.meshes.len()
+    }
+
+    pub fn number_of_dirty_chunks(&self) -> usize {
+        self.dirty_chunks.len()
+    }
+
+    /// Call whenever the owner of a hex changes, the chunks of its neighbours are rebuilt too as the borders between them might have changed.
+    pub fn invalidate_hex(&mut self, x: i32, y: i32) {
+
+        let hex = Hex::new(x, y);
+        self.dirty_chunks.insert(hex_to_chunk(hex.x, hex.y));
+
+        for neighbour in hex.all_neighbors() {
+            self.dirty_chunks.insert(hex_to_chunk(neighbour.x, neighbour.y));
+        }
+
+    }
+
+    /// Call when a chunk was replaced entirely, the chunks around it share borders with it so are rebuilt too.
+    pub fn invalidate_chunk(&mut self, chunk: ChunkCoordinate) {
+        for x in -1..=1 {
+            for y in -1..=1 {
+                self.dirty_chunks.insert((chunk.0 + x, chunk.1 + y));
+            }
+        }
+    }
+
+    /// Call when colours change or the whole state was replaced.
+    pub fn invalidate_all(&mut self) {
+        self.dirty_chunks.extend(self.meshes.keys().copied());
+    }
+
+    pub fn remove_chunk(&mut self, chunk: ChunkCoordinate) {
+        self.meshes.remove(&chunk);
+        self.dirty_chunks.remove(&chunk);
+    }
+
+    pub fn clear(&mut self) {
+        self.meshes.clear();
+        self.dirty_chunks.clear();
+    }
+
+    fn build_chunk_meshes(state: &GameState, chunk: ChunkCoordinate) -> Vec<Mesh> {
+
+        let mut mesh_builder = ChunkMeshBuilder::new();
+        let Some(hex_chunk) = state.get_chunk(chunk) else { return mesh_builder.build(); };
+
+        for (&(x, y), &owner) in hex_chunk.get_hexes() {
+
+            let hex = Hex::new(x, y);
+            let hex_colour = to_macroquad_color(state.get_client_colour(ClientID { id: owner }).unwrap_or(ClientColor::white()));
+            let hex_border_colour = hex_colour.darken(0.25);
+            let hex_world_position = state.hex_to_world(x, y);
+
+            let outer_corners = hex_corners(hex_world_position, HEX_SIZE);
+            mesh_builder.push_polygon(&outer_corners, hex_colour);
+
+            // borders only go where the owner changes, which are the same edges trace_hex_boundary walks along for each territory
+            let inner_corners = hex_corners(hex_world_position, HEX_SIZE - HEX_BORDER_WIDTH);
+            for neighbour in hex.all_neighbors() {
+
+                if state.get_hex(neighbour.x, neighbour.y) == Some(owner) {
+                    continue;
+                }
+
+                let edge = hex_edge_facing(state.hex_to_world(neighbour.x, neighbour.y) - hex_world_position);
+                let next_edge = (edge + 1) % 6;
+
+                mesh_builder.push_polygon(
+                    &[outer_corners[edge], outer_corners[next_edge], inner_corners[next_edge], inner_corners[edge]],
+                    hex_border_colour
+                );
+
+            }
+
+        }
+
+        mesh_builder.build()
+
+    }
+
+    /// Rebuilds the dirty chunks among the given ones, dirty chunks out of view are left until they come into view.
+    pub fn rebuild_dirty_chunks(&mut self, state: &GameState, visible_chunks: &HashSet<ChunkCoordinate>) {
+
+        let chunks_to_rebuild: Vec<ChunkCoordinate> = visible_chunks.iter()
+            .filter(|c| self.dirty_chunks.contains(c) || (self.meshes.contains_key(c) == false && state.get_chunk(**c).is_some()))
+            .take(MAX_CHUNK_REBUILDS_PER_FRAME)
+            .copied()
+            .collect();
+
+        for chunk in chunks_to_rebuild {
+            self.meshes.insert(chunk, Self::build_chunk_meshes(state, chunk));
+            self.dirty_chunks.remove(&chunk);
+        }
+
+    }
+
+    /// Expected to be drawn in world space.
+    pub fn draw(&self, visible_chunks: &HashSet<ChunkCoordinate>) {
+        for chunk in visible_chunks {
+            for mesh in self.meshes.get(chunk).into_iter().flatten() {
+                draw_mesh(mesh);
+            }
+        }
+    }
+
+}
@@ -0,0 +1,63 @@
+use hoxx_shared::{HEX_IS_VERTICAL, HEX_SIZE};
+use macroquad::{color::Color, math::Vec2, shapes::draw_hexagon};
+use utility::{draw_text_centered, WithAlpha};
+
+/// How long the effect for a closed loop is shown, in seconds.
+pub const ENCLOSURE_EFFECT_TIME: f32 = 1.5;
+
+/// How many hexes wide the ring of the effect has grown to when it fades out.
+const ENCLOSURE_EFFECT_RADIUS: f32 = 8.0;
+
+/// An expanding ring and a rising number where someone closed a loop and got the hexes inside it.
+pub struct EnclosureEffect {
+    pub world_position: Vec2,
+    pub colour: Color,
+    pub hexes: i32,
+    pub time_left: f32
+}
+
+impl EnclosureEffect {
+
+    pub fn new(world_position: Vec2, colour: Color, hexes: i32) -> EnclosureEffect {
+        EnclosureEffect {
+            world_position,
+            colour,
+            hexes,
+            time_left: ENCLOSURE_EFFECT_TIME
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.time_left <= 0.0
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        self.time_left -= dt;
+    }
+
+    /// Expected to be drawn in world space.
+    pub fn draw(&self) {
+
+        let progress = 1.0 - (self.time_left / ENCLOSURE_EFFECT_TIME).clamp(0.0, 1.0);
+        let alpha = 1.0 - progress;
+
+        draw_hexagon(
+            self.world_position.x, self.world_position.y,
+            HEX_SIZE * (1.0 + progress * ENCLOSURE_EFFECT_RADIUS),
+            2.0,
+            HEX_IS_VERTICAL,
+            self.colour.with_alpha(alpha),
+            self.colour.with_alpha(0.0)
+        );
+
+        draw_text_centered(
+            &format!("+{}", self.hexes),
+            self.world_position.x,
+            self.world_position.y - progress * HEX_SIZE * 4.0,
+            32.0,
+            Color::new(0.0, 0.0, 0.0, alpha)
+        );
+
+    }
+
+}
@@ -0,0 +1,76 @@
+use macroquad::logging::{info, warn};
+use nanoserde::{DeJson, SerJson};
+
+/// Where the identity is kept, a file next to the game or the key in local storage on the web.
+pub const IDENTITY_PATH: &str = "hoxx_identity.json";
+
+/// Who we are to the server, the token is handed out by the server on first join and lets us get our id, colour and hexes back on reconnect.
+#[derive(Debug, Clone, Default, SerJson, DeJson)]
+pub struct HoxxIdentity {
+    pub token: Option<String>,
+    pub nickname: String
+}
+
+impl HoxxIdentity {
+
+    /// Returns an empty identity if none has been saved yet, the server picks a nickname if we don't have one.
+    pub fn load() -> HoxxIdentity {
+
+        let Some(identity_data) = Self::read_identity_data() else {
+            return HoxxIdentity::default();
+        };
+
+        match HoxxIdentity::deserialize_json(&identity_data) {
+            Ok(identity) => identity,
+            Err(error) => {
+                warn!("[hoxx-client] failed to parse identity from: {} with error: {}, starting with a new identity", IDENTITY_PATH, error);
+                HoxxIdentity::default()
+            }
+        }
+
+    }
+
+    pub fn save(&self) {
+        Self::write_identity_data(&self.serialize_json());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_identity_data() -> Option<String> {
+        std::fs::read_to_string(IDENTITY_PATH).ok()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write_identity_data(identity_data: &str) {
+        match std::fs::write(IDENTITY_PATH, identity_data) {
+            Ok(()) => info!("[hoxx-client] saved identity to: {}", IDENTITY_PATH),
+            Err(error) => warn!("[hoxx-client] failed to save identity to: {} with error: {}", IDENTITY_PATH, error)
+        }
+    }
+
+    /// There's no filesystem on the web, so the identity goes in the local storage of the browser instead.
+    #[cfg(target_arch = "wasm32")]
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read_identity_data() -> Option<String> {
+        Self::local_storage()?.get_item(IDENTITY_PATH).ok().flatten()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write_identity_data(identity_data: &str) {
+
+        let Some(local_storage) = Self::local_storage() else {
+            warn!("[hoxx-client] no local storage available, identity will only last until the page is reloaded");
+            return;
+        };
+
+        match local_storage.set_item(IDENTITY_PATH, identity_data) {
+            Ok(()) => info!("[hoxx-client] saved identity to local storage as: {}", IDENTITY_PATH),
+            Err(error) => warn!("[hoxx-client] failed to save identity to local storage as: {} with error: {:?}", IDENTITY_PATH, error)
+        }
+
+    }
+
+}
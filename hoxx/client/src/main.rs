@@ -1,17 +1,21 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 
-use camera::GameCamera2D;
+use chunk_cache::ChunkRenderCache;
 use drawing::draw_hex;
+use effects::EnclosureEffect;
 use hexx::Hex;
-use hoxx_shared::{utils::trace_hex_boundary, ClientColor, ClientID, ClientMessage, GameState, HEX_SIZE, IS_RUNNING_LOCALLY, SERVER_ADDRESS, SERVER_INTERNAL_PORT};
+use identity::HoxxIdentity;
+use hoxx_shared::{utils::trace_hex_boundary, ChunkCoordinate, ClaimRejection, ClientColor, ClientID, ClientMessage, GameState, GameStateDelta, HexChunk, LeaderboardEntry, HEX_CLAIM_COOLDOWN, HEX_SIZE, IS_RUNNING_LOCALLY, SERVER_ADDRESS, SERVER_INTERNAL_PORT};
 use macroquad::{experimental::camera::mouse, prelude::*};
 use nanoserde::{DeJson, SerJson};
 use network::{ConnectionState, NetworkClient};
-use utility::{draw_text_centered, screen_dimensions, AdjustHue, DebugText, TextPosition, WithAlpha};
+use utility::{draw_text_centered, screen_dimensions, AdjustHue, DebugText, GameCamera2D, TextPosition, WithAlpha};
 use utils::to_macroquad_color;
 
-mod camera;
+mod chunk_cache;
 mod drawing;
+mod effects;
+mod identity;
 mod network;
 mod utils;
 
@@ -27,10 +31,15 @@ impl HoxxClientDebug {
     }
 }
 
+/// How long the reason for a rejected claim stays on screen, in seconds.
+const CLAIM_REJECTION_DISPLAY_TIME: f32 = 3.0;
+
 struct HoxxClientState {
     claim_start_position: Option<Vec2>,
     queued_hex_claims: VecDeque<Vec2>,
-    claim_cooldown: f32
+    claim_cooldown: f32,
+    /// The reason the last claim was rejected, along with how long it is still shown for.
+    last_claim_rejection: Option<(ClaimRejection, f32)>
 }
 
 impl HoxxClientState {
@@ -39,7 +48,8 @@ impl HoxxClientState {
         HoxxClientState {
             claim_start_position: None,
             queued_hex_claims: VecDeque::new(),
-            claim_cooldown: 0.0
+            claim_cooldown: 0.0,
+            last_claim_rejection: None
         }
     }
 
@@ -53,24 +63,86 @@ impl HoxxClientState {
             return None;
         }
 
-        self.queued_hex_claims.pop_front()
+        let claim = self.queued_hex_claims.pop_front();
+        if claim.is_some() {
+            self.claim_cooldown = HEX_CLAIM_COOLDOWN;
+        }
+
+        claim
+
+    }
 
+    /// The rest of the queue most likely depended on the rejected claim going through, so it is dropped too.
+    pub fn reject_claim(&mut self, reason: ClaimRejection) {
+        self.queued_hex_claims.clear();
+        self.last_claim_rejection = Some((reason, CLAIM_REJECTION_DISPLAY_TIME));
     }
 
     pub fn update(&mut self, net: &mut NetworkClient, dt: f32) {
+
         self.claim_cooldown = (self.claim_cooldown - dt).max(0.0);
+
+        if let Some((_, time_left)) = &mut self.last_claim_rejection {
+            *time_left -= dt;
+            if *time_left <= 0.0 {
+                self.last_claim_rejection = None;
+            }
+        }
+
     }
 
 }
 
 trait HoxxNetworkClient {
+    fn send_identify_message(&mut self, identity: &HoxxIdentity);
     fn send_claim_message(&mut self, world_x: i32, world_y: i32);
+    fn send_resync_request(&mut self);
+    fn send_subscribe_message(&mut self, chunks: &HashSet<ChunkCoordinate>);
 }
 
 impl HoxxNetworkClient for NetworkClient {
+    fn send_identify_message(&mut self, identity: &HoxxIdentity) {
+        self.send_text(ClientMessage::Identify { token: identity.token.clone(), nickname: identity.nickname.clone() }.serialize_json());
+    }
+
     fn send_claim_message(&mut self, world_x: i32, world_y: i32) {
         self.send_text(ClientMessage::Claim { world_x, world_y }.serialize_json());
     }
+
+    fn send_resync_request(&mut self) {
+        self.send_text(ClientMessage::RequestResync.serialize_json());
+    }
+
+    fn send_subscribe_message(&mut self, chunks: &HashSet<ChunkCoordinate>) {
+        self.send_text(ClientMessage::Subscribe { chunks: chunks.iter().copied().collect() }.serialize_json());
+    }
+}
+
+/// Chunks within this many chunks of the view get subscribed to, so panning a little doesn't show empty space while waiting for the server.
+const CHUNK_SUBSCRIBE_MARGIN: i32 = 1;
+
+/// Chunks are only evicted once they are this far from the view, so moving back and forth over a chunk border doesn't keep resubscribing.
+const CHUNK_EVICT_MARGIN: i32 = 3;
+
+/// Keeps track of which deltas from the server have been applied, so a missed one can be noticed and the full state asked for again.
+struct HoxxSyncState {
+    /// None until the first full update has arrived, deltas received before then are meaningless.
+    last_sequence: Option<u64>,
+    is_awaiting_resync: bool,
+    /// The chunks the server is sending us, which are also exactly the chunks kept in the game state.
+    subscribed_chunks: HashSet<ChunkCoordinate>
+}
+
+impl HoxxSyncState {
+
+    pub fn new() -> HoxxSyncState {
+        HoxxSyncState {
+            last_sequence: None,
+            is_awaiting_resync: false,
+            subscribed_chunks: HashSet::new()
+        }
+    }
+
 }
 
 struct HoxxClient {
@@ -78,11 +150,18 @@ struct HoxxClient {
     camera: GameCamera2D,
     render_target: Option<RenderTarget>,
     render_target_size: Vec2,
+    chunk_cache: ChunkRenderCache,
     debug_text: DebugText,
     state: GameState,
     net: NetworkClient,
+    sync: HoxxSyncState,
+    identity: HoxxIdentity,
     id: ClientID,
 
+    // scoring, sent by the server
+    leaderboard: Vec<LeaderboardEntry>,
+    enclosure_effects: Vec<EnclosureEffect>,
+
     // intermediate client state
     client_state: HoxxClientState,
     
@@ -93,16 +172,22 @@ struct HoxxClient {
 
 impl HoxxClient {
 
-    pub fn new() -> HoxxClient {
+    pub fn new(identity: HoxxIdentity) -> HoxxClient {
         HoxxClient {
             camera: GameCamera2D::new(),
             render_target: None,
             render_target_size: Vec2::ZERO,
+            chunk_cache: ChunkRenderCache::new(),
             debug_text: DebugText::new(),
             state: GameState::new(),
             net: NetworkClient::new(),
+            sync: HoxxSyncState::new(),
+            identity,
             id: ClientID::INVALID,
 
+            leaderboard: Vec::new(),
+            enclosure_effects: Vec::new(),
+
             client_state: HoxxClientState::new(),
             debug_state: HoxxClientDebug::new()
         }
@@ -166,9 +251,12 @@ impl HoxxClient {
         }
 
         self.client_state.update(&mut self.net, dt);
+        self.update_chunk_subscriptions();
+        self.update_enclosure_effects(dt);
 
         if let Some(new_world_position_claim) = self.client_state.try_pop_claim_world() {
             self.state.set_world(new_world_position_claim.x as i32, new_world_position_claim.y as i32, *self.id);
+            self.invalidate_world_hex(new_world_position_claim.x as i32, new_world_position_claim.y as i32);
             self.net.send_claim_message(new_world_position_claim.x as i32, new_world_position_claim.y as i32);
         }
 
@@ -176,15 +264,172 @@ impl HoxxClient {
 
     }
 
+    fn update_enclosure_effects(&mut self, dt: f32) {
+
+        for effect in &mut self.enclosure_effects {
+            effect.tick(dt);
+        }
+
+        self.enclosure_effects.retain(|effect| effect.is_finished() == false);
+
+    }
+
+    /// Subscribes to the chunks around the camera and evicts the ones which have gone far out of view.
+    fn update_chunk_subscriptions(&mut self) {
+
+        if self.sync.last_sequence.is_none() {
+            return;
+        }
+
+        let world_screen_top_left = self.camera.screen_to_world(Vec2::ZERO);
+        let world_screen_bottom_right = self.camera.screen_to_world(screen_dimensions());
+
+        let wanted_chunks = self.state.chunks_within_world_rect(world_screen_top_left, world_screen_bottom_right, CHUNK_SUBSCRIBE_MARGIN);
+        let kept_chunks = self.state.chunks_within_world_rect(world_screen_top_left, world_screen_bottom_right, CHUNK_EVICT_MARGIN);
+
+        let new_subscribed_chunks: HashSet<ChunkCoordinate> = self.sync.subscribed_chunks.intersection(&kept_chunks).copied()
+            .chain(wanted_chunks.into_iter())
+            .collect();
+
+        if new_subscribed_chunks == self.sync.subscribed_chunks {
+            return;
+        }
+
+        for &evicted_chunk in self.sync.subscribed_chunks.difference(&new_subscribed_chunks) {
+            self.state.remove_chunk(evicted_chunk);
+            self.chunk_cache.remove_chunk(evicted_chunk);
+        }
+
+        self.sync.subscribed_chunks = new_subscribed_chunks;
+        self.net.send_subscribe_message(&self.sync.subscribed_chunks);
+
+    }
+
     fn handle_message(&mut self, message: &ClientMessage) {
         match message {
             // never sent by server to client
+            ClientMessage::Identify { .. } => (),
             ClientMessage::Claim { .. } => (),
+            ClientMessage::RequestResync => (),
+            ClientMessage::Subscribe { .. } => (),
 
             // sent by server to client
-            ClientMessage::Update { state } => self.state.update_state_from(state.clone()),
-            ClientMessage::Join { id } => self.id = *id
+            ClientMessage::Update { state, sequence } => self.handle_full_update(state, *sequence),
+            ClientMessage::Delta { delta, sequence } => self.handle_delta(delta, *sequence),
+            ClientMessage::Chunks { chunks, sequence } => self.handle_chunks(chunks, *sequence),
+            ClientMessage::ClaimRejected { world_x, world_y, reason, current_owner } => self.handle_claim_rejected(*world_x, *world_y, *reason, *current_owner),
+            ClientMessage::Leaderboard { entries } => self.leaderboard = entries.clone(),
+            ClientMessage::Enclosure { id, world_x, world_y, hexes } => self.handle_enclosure(*id, *world_x, *world_y, *hexes),
+            ClientMessage::Join { id, token } => self.handle_join(*id, token)
+        }
+    }
+
+    fn handle_enclosure(&mut self, id: ClientID, world_x: i32, world_y: i32, hexes: i32) {
+
+        if id == self.id {
+            info!("[hoxx-client] enclosed {} hexes!", hexes);
+        }
+
+        let colour = to_macroquad_color(self.state.get_client_colour(id).unwrap_or(ClientColor::white())).darken(0.25);
+        let world_position = self.state.clamp_world_to_hex(world_x, world_y);
+        self.enclosure_effects.push(EnclosureEffect::new(world_position, colour, hexes));
+
+    }
+
+    fn handle_join(&mut self, id: ClientID, token: &str) {
+
+        self.id = id;
+
+        // the server hands out a new token if ours was unknown to it, hang on to it so we come back as the same player next time
+        if self.identity.token.as_deref() != Some(token) {
+            self.identity.token = Some(token.to_string());
+            self.identity.save();
+        }
+
+    }
+
+    fn invalidate_world_hex(&mut self, world_x: i32, world_y: i32) {
+        let hex_coordinate = self.state.world_to_hex(world_x, world_y);
+        self.chunk_cache.invalidate_hex(hex_coordinate.x, hex_coordinate.y);
+    }
+
+    fn handle_claim_rejected(&mut self, world_x: i32, world_y: i32, reason: ClaimRejection, current_owner: Option<i64>) {
+
+        // the hex was set as ours as soon as we claimed it, put back whatever the server says is really there
+        match current_owner {
+            Some(owner) => self.state.set_world(world_x, world_y, owner),
+            None => {
+                let hex_coordinate = self.state.world_to_hex(world_x, world_y);
+                self.state.remove_hex(hex_coordinate.x, hex_coordinate.y);
+            }
         }
+
+        self.invalidate_world_hex(world_x, world_y);
+        self.client_state.reject_claim(reason);
+
+    }
+
+    fn handle_full_update(&mut self, state: &GameState, sequence: u64) {
+        self.state.update_state_from(state.clone());
+        self.chunk_cache.clear();
+        self.sync.last_sequence = Some(sequence);
+        self.sync.is_awaiting_resync = false;
+    }
+
+    /// Returns true if the message with the sequence number is the next one expected, requesting a resync if any were missed.
+    fn accept_sequence(&mut self, sequence: u64) -> bool {
+
+        // anything before the first full update or while waiting for a resync is already part of the full update we're waiting for
+        let Some(last_sequence) = self.sync.last_sequence else { return false; };
+        if self.sync.is_awaiting_resync || sequence <= last_sequence {
+            return false;
+        }
+
+        if sequence != last_sequence + 1 {
+            warn!("[hoxx-client] expected sequence: {} but got: {}, requesting resync!", last_sequence + 1, sequence);
+            self.sync.is_awaiting_resync = true;
+            self.net.send_resync_request();
+            return false;
+        }
+
+        self.sync.last_sequence = Some(sequence);
+        true
+
+    }
+
+    fn handle_delta(&mut self, delta: &GameStateDelta, sequence: u64) {
+
+        if self.accept_sequence(sequence) == false {
+            return;
+        }
+
+        // the server may have sent this before it knew we had evicted some chunks
+        let subscribed_delta = delta.filtered_to_chunks(&self.sync.subscribed_chunks);
+        self.state.apply_delta(&subscribed_delta);
+
+        if subscribed_delta.colours.is_empty() == false || subscribed_delta.removed_colours.is_empty() == false {
+            self.chunk_cache.invalidate_all();
+        }
+
+        for hex_claim in &subscribed_delta.hexes {
+            self.chunk_cache.invalidate_hex(hex_claim.x, hex_claim.y);
+        }
+
+    }
+
+    fn handle_chunks(&mut self, chunks: &[(ChunkCoordinate, HexChunk)], sequence: u64) {
+
+        if self.accept_sequence(sequence) == false {
+            return;
+        }
+
+        for (chunk, hex_chunk) in chunks {
+            if self.sync.subscribed_chunks.contains(chunk) {
+                self.state.set_chunk(*chunk, hex_chunk.clone());
+                self.chunk_cache.invalidate_chunk(*chunk);
+            }
+        }
+
     }
 
     fn handle_network_messages(&mut self) {
@@ -192,7 +437,7 @@ impl HoxxClient {
             Some(msg) => {
                 match msg {
                     ewebsock::WsEvent::Opened => {
-                        // some nice initialization maybe?
+                        self.net.send_identify_message(&self.identity);
                     },
                     ewebsock::WsEvent::Message(ewebsock::WsMessage::Text(text)) => {
                         // handle some messages, paint some hexes! do some stuff!
@@ -208,6 +453,7 @@ impl HoxxClient {
                     },
                     ewebsock::WsEvent::Closed | ewebsock::WsEvent::Error(_) => {
                         // nuke the game state? :D ... or do something else? show a nice message?
+                        self.sync = HoxxSyncState::new();
                     },
                     _ => ()
                 }
@@ -358,43 +604,26 @@ impl HoxxClient {
 
     }
 
-    fn draw_game_state(&self) {
-
-        clear_background(WHITE);
-
-        let world_hex_padding = vec2(HEX_SIZE, HEX_SIZE);
-        let world_screen_top_left = self.camera.screen_to_world(vec2(0.0, 0.0)) - world_hex_padding;
-        let world_screen_bottom_right = self.camera.screen_to_world(screen_dimensions()) + world_hex_padding;
-        let world_screen_rect = Rect {
-            x: world_screen_top_left.x,
-            y: world_screen_top_left.y,
-            w: world_screen_bottom_right.x - world_screen_top_left.x,
-            h: world_screen_bottom_right.y - world_screen_top_left.y
-        };
-        
-        for (&(x, y), &value) in self.state.get_hexes() {
+    /// The chunks on screen, the rest of the cached chunks are just waiting for the camera to come back.
+    fn visible_chunks(&self) -> HashSet<ChunkCoordinate> {
+        let world_screen_top_left = self.camera.screen_to_world(vec2(0.0, 0.0));
+        let world_screen_bottom_right = self.camera.screen_to_world(screen_dimensions());
+        self.state.chunks_within_world_rect(world_screen_top_left, world_screen_bottom_right, 0)
+    }
 
-            let colour = self.state.get_client_colour(ClientID { id: value }).unwrap_or(ClientColor::white());
+    fn draw_game_state(&self, visible_chunks: &HashSet<ChunkCoordinate>) {
 
-            let hex_colour = to_macroquad_color(colour);
-            let hex_border_colour = if self.camera.current_zoom() > 1.0 { hex_colour } else { hex_colour.darken(0.1) };
-            let hex_world_position = self.state.hex_to_world(x, y);
+        clear_background(WHITE);
 
-            if world_screen_rect.contains(hex_world_position) == false {
-                continue;
-            }
-
-            draw_hex(
-                hex_world_position.x as f32, hex_world_position.y as f32,
-                hex_border_colour,
-                hex_colour
-            );
-            
-        }
+        self.chunk_cache.draw(visible_chunks);
 
         self.draw_hex_highlights();
         self.draw_hex_coordinates();
 
+        for effect in &self.enclosure_effects {
+            effect.draw();
+        }
+
     }
 
     fn draw_debug_ui_state(&mut self) {
@@ -410,8 +639,41 @@ impl HoxxClient {
     fn draw_game_ui_state(&mut self) {
 
         // #HACK: this is like, the dodgiest/hackiest way of doing this lol
-        let number_of_owned_hexes = self.state.get_hexes().iter().filter(|(k, v)| **v == *self.id).count();
-        self.debug_text.draw_text(format!("owned hexes: {}", number_of_owned_hexes), TextPosition::TopRight, BLACK);
+        let number_of_owned_hexes = self.state.get_chunks().values().flat_map(|hex_chunk| hex_chunk.get_hexes().values()).filter(|v| **v == *self.id).count();
+        self.debug_text.draw_text(format!("owned hexes nearby: {}", number_of_owned_hexes), TextPosition::TopRight, BLACK);
+
+        if let Some((reason, _)) = self.client_state.last_claim_rejection {
+            self.debug_text.draw_text(format!("claim rejected: {}", reason), TextPosition::TopRight, RED);
+        }
+
+        self.draw_leaderboard();
+
+    }
+
+    fn draw_leaderboard(&mut self) {
+
+        if self.leaderboard.is_empty() {
+            return;
+        }
+
+        self.debug_text.skip_line(TextPosition::TopRight);
+        self.debug_text.draw_text("leaderboard (hexes / largest area / loops closed)", TextPosition::TopRight, BLACK);
+
+        for (rank, entry) in self.leaderboard.iter().enumerate() {
+
+            let entry_colour = if entry.id == self.id {
+                to_macroquad_color(self.state.get_client_colour(entry.id).unwrap_or(ClientColor::white())).darken(0.5)
+            } else {
+                BLACK
+            };
+
+            self.debug_text.draw_text(
+                format!("{}. {} - {} / {} / {} (+{})", rank + 1, entry.name, entry.hexes, entry.largest_territory, entry.enclosures, entry.enclosed_hexes),
+                TextPosition::TopRight,
+                entry_colour
+            );
+
+        }
 
     }
 
@@ -422,8 +684,9 @@ impl HoxxClient {
         let mouse_world_position = self.camera.mouse_world_position();
         let hex_mouse_position = self.state.world_to_hex(mouse_world_position.x as i32, mouse_world_position.y as i32);
         let hex_under_mouse = self.state.get_world(mouse_world_position.x as i32, mouse_world_position.y as i32);
+        let owner_under_mouse = hex_under_mouse.and_then(|owner| self.state.get_client_name(ClientID { id: owner }));
         
-        self.debug_text.draw_text(format!("hex under mouse: {} (value: {:?})", hex_mouse_position.as_ivec2(), hex_under_mouse), TextPosition::TopLeft, BLACK);
+        self.debug_text.draw_text(format!("hex under mouse: {} (value: {:?}, owner: {:?})", hex_mouse_position.as_ivec2(), hex_under_mouse, owner_under_mouse), TextPosition::TopLeft, BLACK);
 
     }
     
@@ -438,7 +701,10 @@ impl HoxxClient {
         }
     
         if self.connection_state() == ConnectionState::Connected {
-            self.debug_text.draw_text(format!("client id: {}", self.id), TextPosition::TopLeft, BLACK);
+            self.debug_text.draw_text(format!("client id: {} ({})", self.id, self.state.get_client_name(self.id).unwrap_or("unnamed")), TextPosition::TopLeft, BLACK);
+            self.debug_text.draw_text(format!("sequence: {:?} (awaiting resync: {})", self.sync.last_sequence, self.sync.is_awaiting_resync), TextPosition::TopLeft, BLACK);
+            self.debug_text.draw_text(format!("subscribed chunks: {}", self.sync.subscribed_chunks.len()), TextPosition::TopLeft, BLACK);
+            self.debug_text.draw_text(format!("cached chunks: {} (dirty: {})", self.chunk_cache.number_of_cached_chunks(), self.chunk_cache.number_of_dirty_chunks()), TextPosition::TopLeft, BLACK);
         }
     
     }
@@ -472,7 +738,9 @@ impl HoxxClient {
         } else {
             self.camera.push();
         }
-        self.draw_game_state();
+        let visible_chunks = self.visible_chunks();
+        self.chunk_cache.rebuild_dirty_chunks(&self.state, &visible_chunks);
+        self.draw_game_state(&visible_chunks);
         self.camera.pop();
 
         if let Some(render_target) = &self.render_target {
@@ -501,7 +769,15 @@ impl HoxxClient {
 #[macroquad::main("hoxx-client")]
 async fn main() {
 
-    let mut client = HoxxClient::new();
+    let mut identity = HoxxIdentity::load();
+
+    // a nickname can be passed on the command line, otherwise the saved one (if any) is used
+    if let Some(nickname) = std::env::args().nth(1) {
+        identity.nickname = nickname;
+        identity.save();
+    }
+
+    let mut client = HoxxClient::new(identity);
     let is_connecting = client.connect();
     if is_connecting == false {
         error!("[hoxx-client] failed to attempt to connect to server!");
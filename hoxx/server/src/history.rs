@@ -0,0 +1,69 @@
+use std::{fs::{File, OpenOptions}, io::{LineWriter, Write}};
+
+use hoxx_shared::{history::{HistoryAction, HistoryEvent}, ClientID};
+use nanoserde::SerJson;
+
+use crate::{persistence::PlayerRecord, state::HoxxGameState};
+
+pub const HISTORY_LOG_PATH: &str = "save/history.log";
+
+/// Appends every change to the world to a log, one event per line, so the map can be replayed later.
+pub struct HistoryLog {
+    /// None if the log could not be opened, the server keeps going without history in that case.
+    writer: Option<LineWriter<File>>
+}
+
+impl HistoryLog {
+
+    /// A log which doesn't record anything, until replaced by an opened one.
+    pub fn new() -> HistoryLog {
+        HistoryLog {
+            writer: None
+        }
+    }
+
+    /// Opens the log for appending, a new log starts with everything already in the world so it can be replayed on its own.
+    pub fn open(path: &str, state: &HoxxGameState, players: &[PlayerRecord]) -> HistoryLog {
+
+        let is_new_log = std::fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true);
+
+        if let Some(history_directory) = std::path::Path::new(path).parent() {
+            let _ = std::fs::create_dir_all(history_directory);
+        }
+
+        let writer = match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(LineWriter::new(file)),
+            Err(error) => {
+                println!("[hoxx-server] failed to open history log at: {} with error: {}, not recording history!", path, error);
+                None
+            }
+        };
+
+        let mut history_log = HistoryLog { writer };
+
+        if is_new_log {
+            history_log.record(ClientID::INVALID, HistoryAction::Initial { hexes: state.get_hex_claims() });
+        }
+
+        // players could have been added to the save without the log knowing about them, this makes sure every colour is in there
+        for player in players {
+            history_log.record(player.id, HistoryAction::Colour { colour: player.colour });
+        }
+
+        history_log
+
+    }
+
+    pub fn record(&mut self, owner: ClientID, action: HistoryAction) {
+
+        let Some(writer) = &mut self.writer else { return; };
+
+        let event = HistoryEvent::now(owner, action);
+        if let Err(error) = writeln!(writer, "{}", event.serialize_json()) {
+            println!("[hoxx-server] failed to write to history log with error: {}, not recording history anymore!", error);
+            self.writer = None;
+        }
+
+    }
+
+}
@@ -1,8 +1,18 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::{HashMap, HashSet}, io::BufRead, rc::Rc, time::{Duration, Instant}};
+use history::{HistoryLog, HISTORY_LOG_PATH};
+use hexx::Hex;
+use hoxx_shared::history::HistoryAction;
+use persistence::{PlayerRecord, WorldSave, WORLD_SAVE_INTERVAL_SECONDS, WORLD_SAVE_PATH};
+use rules::{ClaimLimiters, ClaimRules};
+use scoring::HoxxScores;
 use state::HoxxGameState;
-use hoxx_shared::{utils::{flood_fill_hexes, trace_hex_boundary}, Client, ClientColor, ClientID, ClientMessage, ClientState, SERVER_INTERNAL_PORT};
+use hoxx_shared::{utils::{count_flood_fill_hexes, flood_fill_hexes, trace_hex_boundary}, ChunkCoordinate, ClaimRejection, Client, ClientColor, ClientID, ClientMessage, ClientState, HexChunk, SERVER_INTERNAL_PORT};
 use nanoserde::{DeJson, SerJson};
 
+mod history;
+mod persistence;
+mod rules;
+mod scoring;
 mod state;
 
 struct Router {
@@ -16,12 +26,10 @@ impl ws::Handler for Router {
 
         // Clone the sender so that we can move it into the child handler
         let out = self.sender.clone();
-        
-        // Allocate a client on the server and associate it with our session
-        let client_id = self.server.borrow_mut().spawn_client(self.sender.clone());
 
+        // the client is only allocated on the server once it has identified itself, as it might be someone coming back
         match req.resource() {
-            "/" => self.inner = Box::new(Session { ws: out, id: client_id, server: self.server.clone() }),
+            "/" => self.inner = Box::new(Session { ws: out, id: ClientID::INVALID, address: None, server: self.server.clone() }),
             _ => (),
         }
 
@@ -51,19 +59,41 @@ impl ws::Handler for Router {
         self.inner.on_error(err);
     }
 
+    fn on_timeout(&mut self, event: ws::util::Token) -> ws::Result<()> {
+        self.inner.on_timeout(event)
+    }
+
 }
 
+/// Fires every so often on each open connection to save the world, the server has no loop of its own to do it from.
+const WORLD_SAVE_TIMER: ws::util::Token = ws::util::Token(1);
+
 struct Session {
     ws: ws::Sender,
     id: ClientID,
+    /// Where the client is connecting from, taken from the proxy in front of us only when it is one we trust.
+    address: Option<String>,
     server: Rc<RefCell<HoxxServer>>
 }
 
 impl ws::Handler for Session {
 
-    fn on_open(&mut self, _shake: ws::Handshake) -> ws::Result<()> {
+    fn on_open(&mut self, shake: ws::Handshake) -> ws::Result<()> {
+        let forwarded_for = shake.request.header("X-Forwarded-For").and_then(|value| std::str::from_utf8(value).ok());
+        self.address = self.server.borrow().rules.resolve_client_address(shake.peer_addr.map(|peer_addr| peer_addr.ip()), forwarded_for);
         println!("[hoxx-server] id: {:?} connected!", self.ws.connection_id());
-        Ok(())
+        self.ws.timeout(WORLD_SAVE_INTERVAL_SECONDS * 1000, WORLD_SAVE_TIMER)
+    }
+
+    fn on_timeout(&mut self, event: ws::util::Token) -> ws::Result<()> {
+
+        if event != WORLD_SAVE_TIMER {
+            return Ok(());
+        }
+
+        self.server.borrow_mut().save_world_if_due();
+        self.ws.timeout(WORLD_SAVE_INTERVAL_SECONDS * 1000, WORLD_SAVE_TIMER)
+
     }
 
     fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
@@ -71,13 +101,33 @@ impl ws::Handler for Session {
         let Ok(text) = msg.as_text() else { return Ok(()); };
 
         match ClientMessage::deserialize_json(text) {
+
+            Ok(ClientMessage::Identify { token, nickname }) => {
+                if self.id == ClientID::INVALID {
+                    self.id = self.server.borrow_mut().spawn_client(self.ws.clone(), token, &nickname, self.address.clone());
+                }
+            },
+
+            // nothing else is accepted until the client has identified itself
+            Ok(_) if self.id == ClientID::INVALID => {
+                println!("[hoxx-server] id: {:?} sent a message before identifying itself, ignoring message!", self.ws.connection_id());
+            },
+
             Ok(msg) => match msg {
 
                 // sent by clients to server :)
+                ClientMessage::Identify { .. } => (),
                 ClientMessage::Claim { world_x, world_y } => self.server.borrow_mut().put_claim(self.id, world_x, world_y),
+                ClientMessage::RequestResync => self.server.borrow_mut().resync_client(self.id),
+                ClientMessage::Subscribe { chunks } => self.server.borrow_mut().subscribe_client(self.id, chunks),
 
                 // only sent by server to clients, never sent by clients to server
                 ClientMessage::Update { .. } => (),
+                ClientMessage::Delta { .. } => (),
+                ClientMessage::Chunks { .. } => (),
+                ClientMessage::ClaimRejected { .. } => (),
+                ClientMessage::Leaderboard { .. } => (),
+                ClientMessage::Enclosure { .. } => (),
                 ClientMessage::Join { .. } => ()
 
             },
@@ -92,7 +142,9 @@ impl ws::Handler for Session {
 
     fn on_close(&mut self, _code: ws::CloseCode, reason: &str) {
         
-        self.server.borrow_mut().despawn_client(self.id);
+        if self.id != ClientID::INVALID {
+            self.server.borrow_mut().despawn_client(self.id);
+        }
 
         if reason.is_empty() == false {
             println!("[hoxx-server] id: {} disconnected with reason: {}!", self.ws.connection_id(), reason);
@@ -115,24 +167,81 @@ impl ws::Handler for NotFound {
     }
 }
 
+/// More than enough to cover a large screen zoomed all the way out, anything beyond is most likely someone trying to pull the whole world.
+const MAX_SUBSCRIBED_CHUNKS: usize = 256;
+
+/// The chunks a client is receiving updates for.
+struct ClientSubscription {
+    chunks: HashSet<ChunkCoordinate>,
+    /// Incremented for every message carrying game state sent to the client, so it can tell when it missed one.
+    sequence: u64
+}
+
+impl ClientSubscription {
+
+    fn new() -> ClientSubscription {
+        ClientSubscription {
+            chunks: HashSet::new(),
+            sequence: 0
+        }
+    }
+
+}
+
 struct HoxxServer {
     current_client_id: ClientID,
     senders: HashMap<ClientID, ws::Sender>,
     clients: HashMap<ClientID, Client>,
+    subscriptions: HashMap<ClientID, ClientSubscription>,
+    /// Kept around after clients leave so that reconnecting does not reset the limit, see [`HoxxServer::validate_claim`].
+    limiters: ClaimLimiters,
+    client_addresses: HashMap<ClientID, String>,
+    /// Everyone who has ever played, by their identity token.
+    players: HashMap<String, PlayerRecord>,
+    rules: ClaimRules,
     state: HoxxGameState,
+    scores: HoxxScores,
+    history: HistoryLog,
+    /// Set whenever something worth saving changed since the last save.
+    is_world_dirty: bool,
+    last_world_save: Instant,
     port: u16
 }
 
+/// Nicknames are shown to everyone, so they are kept short and on a single line.
+const MAX_NICKNAME_LENGTH: usize = 24;
+
 impl HoxxServer {
 
     fn new() -> HoxxServer {
-        HoxxServer {
+
+        let mut hoxx_server = HoxxServer {
             current_client_id: ClientID::INVALID + 1,
             senders: HashMap::new(),
             clients: HashMap::new(),
+            subscriptions: HashMap::new(),
+            limiters: ClaimLimiters::new(),
+            client_addresses: HashMap::new(),
+            players: HashMap::new(),
+            rules: ClaimRules::load(),
             state: HoxxGameState::new(),
+            scores: HoxxScores::new(),
+            history: HistoryLog::new(),
+            is_world_dirty: false,
+            last_world_save: Instant::now(),
             port: SERVER_INTERNAL_PORT
+        };
+
+        if let Some(world_save) = WorldSave::load(WORLD_SAVE_PATH) {
+            hoxx_server.load_world(world_save);
         }
+
+        // opened after loading so a new log can start out with whatever is in the world already
+        let players: Vec<PlayerRecord> = hoxx_server.players.values().cloned().collect();
+        hoxx_server.history = HistoryLog::open(HISTORY_LOG_PATH, &hoxx_server.state, &players);
+
+        hoxx_server
+
     }
 
     pub fn start() {
@@ -140,16 +249,102 @@ impl HoxxServer {
         let new_hoxx_server = Rc::new(RefCell::new(HoxxServer::new()));
 
         // Listen on an address and call the closure for each connection
-        if let Err(error) = ws::listen(format!("localhost:{}", new_hoxx_server.borrow().port), |out| {
+        let web_socket = ws::WebSocket::new(|out| {
             Router {
                 sender: out,
                 inner: Box::new(NotFound),
                 server: new_hoxx_server.clone()
             }
-        }) {
-            println!("[hoxx-server] failed to create WebSocket due to {:?}, exiting!", error);
+        }).and_then(|web_socket| web_socket.bind(format!("localhost:{}", new_hoxx_server.borrow().port)));
+
+        let web_socket = match web_socket {
+            Ok(web_socket) => web_socket,
+            Err(error) => {
+                println!("[hoxx-server] failed to create WebSocket due to {:?}, exiting!", error);
+                return;
+            }
+        };
+
+        // typing quit shuts the server down cleanly, so the world gets saved on the way out
+        let broadcaster = web_socket.broadcaster();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+                if line.trim() == "quit" {
+                    println!("[hoxx-server] shutting down!");
+                    let _ = broadcaster.shutdown();
+                    break;
+                }
+            }
+        });
+
+        // so does ctrl-c or being told to stop by whatever is running the server
+        let broadcaster = web_socket.broadcaster();
+        let signal_result = ctrlc::set_handler(move || {
+            println!("[hoxx-server] received signal, shutting down!");
+            let _ = broadcaster.shutdown();
+        });
+
+        if let Err(error) = signal_result {
+            println!("[hoxx-server] failed to set signal handler due to {:?}, the world will only be saved on quit!", error);
+        }
+
+        if let Err(error) = web_socket.run() {
+            println!("[hoxx-server] WebSocket stopped due to {:?}!", error);
+        }
+
+        new_hoxx_server.borrow_mut().save_world();
+
+    }
+
+    fn load_world(&mut self, world_save: WorldSave) {
+
+        self.current_client_id = ClientID { id: world_save.next_client_id.max(self.current_client_id.id) };
+        self.state.load_hex_claims(&world_save.hexes);
+
+        for player in world_save.players {
+            self.state.set_client_colour(player.id, player.colour);
+            self.state.set_client_name(player.id, &player.nickname);
+            self.scores.set_enclosures(player.id, player.enclosures, player.enclosed_hexes);
+            self.players.insert(player.token.clone(), player);
+        }
+
+        // nobody is connected to receive any of this yet
+        let _ = self.state.take_delta();
+        let _ = self.scores.take_changed_leaderboard(&self.state, Instant::now());
+
+        println!("[hoxx-server] loaded world with {} players and {} claimed hexes from: {}", self.players.len(), world_save.hexes.len(), WORLD_SAVE_PATH);
+
+    }
+
+    fn save_world(&mut self) {
+
+        for player in self.players.values_mut() {
+            let score = self.scores.get_score(player.id);
+            player.enclosures = score.enclosures;
+            player.enclosed_hexes = score.enclosed_hexes;
+        }
+
+        let world_save = WorldSave {
+            next_client_id: self.current_client_id.id,
+            players: self.players.values().cloned().collect(),
+            hexes: self.state.get_hex_claims()
+        };
+
+        match world_save.save(WORLD_SAVE_PATH) {
+            Ok(()) => println!("[hoxx-server] saved world with {} players and {} claimed hexes to: {}", world_save.players.len(), world_save.hexes.len(), WORLD_SAVE_PATH),
+            Err(error) => println!("[hoxx-server] failed to save world to: {} with error: {}", WORLD_SAVE_PATH, error)
         }
 
+        self.is_world_dirty = false;
+        self.last_world_save = Instant::now();
+
+    }
+
+    /// Called on a timer from every open connection as well as after anything which changes the world, so nothing is saved more often than the interval.
+    fn save_world_if_due(&mut self) {
+        if self.is_world_dirty && self.last_world_save.elapsed() >= Duration::from_secs(WORLD_SAVE_INTERVAL_SECONDS) {
+            self.save_world();
+        }
     }
 
     fn send_message_to_client(&self, client_id: ClientID, message: &ClientMessage) {
@@ -168,16 +363,97 @@ impl HoxxServer {
         }
     }
 
-    fn send_updated_game_state_to_all_clients(&mut self) {
-        self.send_message_to_all_clients(&ClientMessage::Update { state: self.state.get_game_state().clone() });
+    fn next_sequence_for_client(&mut self, client_id: ClientID) -> u64 {
+        let subscription = self.subscriptions.entry(client_id).or_insert_with(ClientSubscription::new);
+        subscription.sequence += 1;
+        subscription.sequence
+    }
+
+    /// Sends whatever changed in the game state since the last delta to every client subscribed to the chunks it changed in.
+    fn send_pending_delta_to_all_clients(&mut self) {
+
+        let delta = self.state.take_delta();
+        if delta.is_empty() {
+            return;
+        }
+
+        let client_ids: Vec<ClientID> = self.clients.keys().copied().collect();
+
+        for client_id in client_ids {
+
+            let client_delta = match self.subscriptions.get(&client_id) {
+                Some(subscription) => delta.filtered_to_chunks(&subscription.chunks),
+                None => delta.filtered_to_chunks(&HashSet::new())
+            };
+
+            if client_delta.is_empty() {
+                continue;
+            }
+
+            let sequence = self.next_sequence_for_client(client_id);
+            self.send_message_to_client(client_id, &ClientMessage::Delta { delta: client_delta, sequence });
+
+        }
+
+    }
+
+    fn send_changed_leaderboard_to_all_clients(&mut self) {
+        if let Some(entries) = self.scores.take_changed_leaderboard(&self.state, Instant::now()) {
+            self.send_message_to_all_clients(&ClientMessage::Leaderboard { entries });
+        }
+    }
+
+    fn send_leaderboard_to_client(&mut self, client_id: ClientID) {
+        let entries = self.scores.get_last_leaderboard().clone();
+        self.send_message_to_client(client_id, &ClientMessage::Leaderboard { entries });
     }
 
-    fn send_join_message_to_client(&mut self, client_id: ClientID) {
-        self.send_message_to_client(client_id, &ClientMessage::Join { id: client_id });
+    fn send_join_message_to_client(&mut self, client_id: ClientID, token: String) {
+        self.send_message_to_client(client_id, &ClientMessage::Join { id: client_id, token });
     }
 
     fn send_updated_game_state_to_client(&mut self, client_id: ClientID) {
-        self.send_message_to_client(client_id, &ClientMessage::Update { state: self.state.get_game_state().clone() });
+
+        let client_state = match self.subscriptions.get(&client_id) {
+            Some(subscription) => self.state.get_game_state().filtered_to_chunks(&subscription.chunks),
+            None => self.state.get_game_state().filtered_to_chunks(&HashSet::new())
+        };
+
+        let sequence = self.next_sequence_for_client(client_id);
+        self.send_message_to_client(client_id, &ClientMessage::Update { state: client_state, sequence });
+
+    }
+
+    fn resync_client(&mut self, client_id: ClientID) {
+        println!("[hoxx-server] id: {} requested a resync", client_id);
+        self.send_updated_game_state_to_client(client_id);
+    }
+
+    /// Replaces the chunks the client is subscribed to, sending it snapshots of the chunks it did not have before.
+    fn subscribe_client(&mut self, client_id: ClientID, mut chunks: Vec<ChunkCoordinate>) {
+
+        if chunks.len() > MAX_SUBSCRIBED_CHUNKS {
+            println!("[hoxx-server] id: {} tried to subscribe to {} chunks, only keeping the first {}!", client_id, chunks.len(), MAX_SUBSCRIBED_CHUNKS);
+            chunks.truncate(MAX_SUBSCRIBED_CHUNKS);
+        }
+
+        let new_chunks: HashSet<ChunkCoordinate> = chunks.into_iter().collect();
+        let subscription = self.subscriptions.entry(client_id).or_insert_with(ClientSubscription::new);
+        let added_chunks: Vec<ChunkCoordinate> = new_chunks.difference(&subscription.chunks).copied().collect();
+        subscription.chunks = new_chunks;
+
+        if added_chunks.is_empty() {
+            return;
+        }
+
+        // empty chunks are sent too, so the client knows it has everything there is for them
+        let chunk_snapshots: Vec<(ChunkCoordinate, HexChunk)> = added_chunks.into_iter()
+            .map(|c| (c, self.state.get_game_state().get_chunk(c).cloned().unwrap_or_else(HexChunk::new)))
+            .collect();
+
+        let sequence = self.next_sequence_for_client(client_id);
+        self.send_message_to_client(client_id, &ClientMessage::Chunks { chunks: chunk_snapshots, sequence });
+
     }
 
     fn update_fill_state(&mut self, client_id: ClientID, x: i32, y: i32) {
@@ -190,15 +466,35 @@ impl HoxxServer {
 
                 if let Some(hex_in_boundary) = boundary.hex_inside_boundary(|h| self.state.is_claimed_by(h.x, h.y, client_id) == false) {
 
+                    let enclosed_area = count_flood_fill_hexes(hex_in_boundary, |h| self.state.is_claimed_by(h.x, h.y, client_id) == false, self.rules.max_enclosure_area);
+                    if enclosed_area.is_none() {
+                        println!("[hoxx-server] id: {} enclosed an area larger than: {} hexes, not filling it!", client_id, self.rules.max_enclosure_area);
+                        self.send_claim_rejection_to_client(client_id, x, y, ClaimRejection::EnclosureTooLarge);
+                        return;
+                    }
+
+                    let mut filled_hexes = Vec::new();
                     let number_of_claimed_hexes = flood_fill_hexes(
                         &mut self.state,
                         hex_in_boundary,
                         |state, h| state.is_claimed_by(h.x, h.y, client_id) == false,
-                        |state, h| state.put_claim_hex(h.x, h.y, client_id)
+                        |state, h| {
+                            state.put_claim_hex(h.x, h.y, client_id);
+                            filled_hexes.push((h.x, h.y));
+                        }
                     );
 
+                    for &(filled_x, filled_y) in &filled_hexes {
+                        self.scores.on_territory_grown(client_id, Hex::new(filled_x, filled_y));
+                    }
+
+                    self.history.record(client_id, HistoryAction::Fill { hexes: filled_hexes });
+
                     println!("[hoxx-server] id: {} claimed {} hexes!", client_id, number_of_claimed_hexes);
 
+                    self.scores.on_enclosure(client_id, number_of_claimed_hexes);
+                    self.send_message_to_all_clients(&ClientMessage::Enclosure { id: client_id, world_x: x, world_y: y, hexes: number_of_claimed_hexes });
+
                 }
                 
             }
@@ -207,10 +503,51 @@ impl HoxxServer {
 
     }
 
+    fn send_claim_rejection_to_client(&mut self, client_id: ClientID, x: i32, y: i32, reason: ClaimRejection) {
+        let current_owner = self.state.get_claim_world(x, y).map(|owner| owner.id);
+        self.send_message_to_client(client_id, &ClientMessage::ClaimRejected { world_x: x, world_y: y, reason, current_owner });
+    }
+
+    fn validate_claim(&mut self, client_id: ClientID, x: i32, y: i32) -> Result<(), ClaimRejection> {
+
+        // limited both per player, which stays the same for anyone coming back with their token, and per address, so new identities don't get around it
+        let now = Instant::now();
+        self.limiters.try_claim(&format!("player:{}", client_id), &self.rules, now)?;
+        if let Some(address) = self.client_addresses.get(&client_id) {
+            self.limiters.try_claim(&format!("address:{}", address), &self.rules, now)?;
+        }
+
+        let hex = self.state.get_game_state().world_to_hex(x, y);
+        self.rules.validate_claim(&self.state, client_id, hex)
+
+    }
+
     fn put_claim(&mut self, client_id: ClientID, x: i32, y: i32) {
+
+        // the client draws lines across its own hexes too, there is nothing to do for those
+        let hex = self.state.get_game_state().world_to_hex(x, y);
+        if self.state.is_claimed_by(hex.x, hex.y, client_id) {
+            return;
+        }
+
+        if let Err(reason) = self.validate_claim(client_id, x, y) {
+            println!("[hoxx-server] id: {} had claim at: ({}, {}) rejected: {:?}", client_id, x, y, reason);
+            self.send_claim_rejection_to_client(client_id, x, y, reason);
+            return;
+        }
+
         self.state.put_claim_world(x, y, client_id);
+        self.history.record(client_id, HistoryAction::Claim { x: hex.x, y: hex.y });
         self.update_fill_state(client_id, x, y);
-        self.send_updated_game_state_to_all_clients();
+        self.send_pending_delta_to_all_clients();
+
+        self.scores.on_territories_shrunk(self.state.take_shrunk_territories());
+        self.scores.on_territory_grown(client_id, hex);
+        self.send_changed_leaderboard_to_all_clients();
+
+        self.is_world_dirty = true;
+        self.save_world_if_due();
+
     }
 
     fn get_claim(&mut self, client_id: ClientID, x: i32, y: i32) -> Option<ClientID> {
@@ -237,39 +574,119 @@ impl HoxxServer {
         
     }
 
-    fn spawn_client(&mut self, sender: ws::Sender) -> ClientID {
-        let created_client_id = self.create_client(sender);
-        self.initialize_client(created_client_id);
-        created_client_id
+    fn create_identity_token() -> String {
+        format!("{:032x}", rand::random::<u128>())
+    }
+
+    fn sanitize_nickname(nickname: &str, client_id: ClientID) -> String {
+
+        let sanitized_nickname: String = nickname.trim().chars()
+            .filter(|c| c.is_control() == false)
+            .take(MAX_NICKNAME_LENGTH)
+            .collect();
+
+        if sanitized_nickname.is_empty() {
+            format!("player {}", client_id)
+        } else {
+            sanitized_nickname
+        }
+
+    }
+
+    /// Finds the player the token belongs to, or creates a new one if the token is unknown or that player is already connected from somewhere else.
+    fn find_or_create_player(&mut self, token: Option<String>) -> PlayerRecord {
+
+        let returning_player = token
+            .and_then(|token| self.players.get(&token))
+            .filter(|player| self.clients.contains_key(&player.id) == false)
+            .cloned();
+
+        if let Some(returning_player) = returning_player {
+            println!("[hoxx-server] id: {} returned as: {}", returning_player.id, returning_player.nickname);
+            return returning_player;
+        }
+
+        let created_player = PlayerRecord {
+            id: self.current_client_id,
+            token: Self::create_identity_token(),
+            nickname: String::new(),
+            colour: Self::create_client_colour(),
+            enclosures: 0,
+            enclosed_hexes: 0
+        };
+
+        self.current_client_id += 1;
+        created_player
+
     }
 
-    fn initialize_client(&mut self, created_client_id: ClientID) {
+    fn spawn_client(&mut self, sender: ws::Sender, token: Option<String>, nickname: &str, address: Option<String>) -> ClientID {
+
+        let mut player = self.find_or_create_player(token);
+        player.nickname = Self::sanitize_nickname(nickname, player.id);
+
+        // tell everyone already here about the colour and name before the new client is added, it gets them with the full game state instead
+        self.state.set_client_colour(player.id, player.colour);
+        self.state.set_client_name(player.id, &player.nickname);
+        self.history.record(player.id, HistoryAction::Colour { colour: player.colour });
+        self.send_pending_delta_to_all_clients();
+
+        let created_client_id = player.id;
+        let created_client_token = player.token.clone();
+        self.players.insert(player.token.clone(), player);
+
+        if let Some(address) = address {
+            self.client_addresses.insert(created_client_id, address);
+        }
+
+        self.create_client(created_client_id, sender);
+        self.initialize_client(created_client_id, created_client_token);
+
+        // the name might have changed since the last visit
+        self.send_changed_leaderboard_to_all_clients();
+
+        self.is_world_dirty = true;
+        self.save_world_if_due();
+
+        created_client_id
+
+    }
 
-        // set up client colour
-        self.state.set_client_colour(created_client_id, Self::create_client_colour());
+    fn initialize_client(&mut self, created_client_id: ClientID, created_client_token: String) {
     
         // send welcome message to client with their id and the game state
-        self.send_join_message_to_client(created_client_id);
+        self.send_join_message_to_client(created_client_id, created_client_token);
         self.send_updated_game_state_to_client(created_client_id);
+        self.send_leaderboard_to_client(created_client_id);
 
     }
     
-    fn create_client(&mut self, sender: ws::Sender) -> ClientID {
-        let created_client_id = self.current_client_id;
-        let created_client = Client { id: created_client_id, state: ClientState::Connected };
-        self.clients.insert(created_client_id, created_client);
-        self.senders.insert(created_client_id, sender);
-        self.current_client_id += 1;
-        created_client_id
+    fn create_client(&mut self, client_id: ClientID, sender: ws::Sender) {
+        let created_client = Client { id: client_id, state: ClientState::Connected };
+        self.clients.insert(client_id, created_client);
+        self.senders.insert(client_id, sender);
     }
 
     fn remove_client(&mut self, client_id: ClientID) {
         self.clients.remove(&client_id);
         self.senders.remove(&client_id);
+        self.subscriptions.remove(&client_id);
+        self.client_addresses.remove(&client_id);
+        self.limiters.remove_idle(&self.rules, Instant::now());
     }
     
+    /// Players keep their colour and name after leaving, as their hexes stay in the world and they may come back for them.
     fn despawn_client(&mut self, client_id: ClientID) {
+
         self.remove_client(client_id);
+
+        // with nobody left there are no timers to save it later
+        if self.clients.is_empty() && self.is_world_dirty {
+            self.save_world();
+        } else {
+            self.save_world_if_due();
+        }
+
     }
 
 }
@@ -0,0 +1,62 @@
+use hoxx_shared::{ClientColor, ClientID, HexClaim};
+use nanoserde::{DeJson, SerJson};
+
+pub const WORLD_SAVE_PATH: &str = "save/world.json";
+
+/// The world is saved at most this often while people are playing, and always when the server shuts down.
+pub const WORLD_SAVE_INTERVAL_SECONDS: u64 = 60;
+
+/// Everything the server remembers about a player between visits.
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct PlayerRecord {
+    pub id: ClientID,
+    pub token: String,
+    pub nickname: String,
+    pub colour: ClientColor,
+    #[nserde(default)]
+    pub enclosures: i32,
+    #[nserde(default)]
+    pub enclosed_hexes: i32
+}
+
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct WorldSave {
+    pub next_client_id: i64,
+    pub players: Vec<PlayerRecord>,
+    pub hexes: Vec<HexClaim>
+}
+
+impl WorldSave {
+
+    /// Returns None if there is no save yet, or it could not be read, in which case the server starts over with an empty world.
+    pub fn load(path: &str) -> Option<WorldSave> {
+
+        let Ok(world_save_data) = std::fs::read_to_string(path) else {
+            println!("[hoxx-server] no world save found at: {}, starting with an empty world", path);
+            return None;
+        };
+
+        match WorldSave::deserialize_json(&world_save_data) {
+            Ok(world_save) => Some(world_save),
+            Err(error) => {
+                println!("[hoxx-server] failed to load world save from: {} with error: {}, starting with an empty world", path, error);
+                None
+            }
+        }
+
+    }
+
+    /// Writes to a temporary file first and moves it into place after, so the server dying halfway through doesn't leave a broken save behind.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+
+        if let Some(world_save_directory) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(world_save_directory).map_err(|e| e.to_string())?;
+        }
+
+        let temporary_path = format!("{}.tmp", path);
+        std::fs::write(&temporary_path, self.serialize_json()).map_err(|e| e.to_string())?;
+        std::fs::rename(&temporary_path, path).map_err(|e| e.to_string())
+
+    }
+
+}
@@ -0,0 +1,289 @@
+use std::{collections::HashMap, net::IpAddr, time::Instant};
+
+use hexx::Hex;
+use hoxx_shared::{ClaimRejection, ClientID, HEX_CLAIM_COOLDOWN};
+use nanoserde::{DeJson, SerJson};
+
+use crate::state::HoxxGameState;
+
+pub const CLAIM_RULES_PATH: &str = "config/rules.json";
+
+/// When a player may take a hex that already belongs to someone else.
+#[derive(Debug, Clone, Copy, SerJson, DeJson)]
+pub enum ContestRule {
+    Never,
+    Always,
+    /// Only if at least this many of the neighbouring hexes belong to the player taking it.
+    Surrounded { minimum_neighbours: i32 }
+}
+
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct ClaimRules {
+    /// Minimum time between two claims in seconds, lower than the client's cooldown so that claims arriving bunched up over the network still get through.
+    pub claim_cooldown: f32,
+    /// How many claims can be made in a row before the rate limit kicks in.
+    pub claim_burst: f32,
+    /// How quickly claims come back after a burst, per second.
+    pub claims_per_second: f32,
+    pub contest_rule: ContestRule,
+    /// Largest area an enclosure can have and still be filled in, also stops a fill from running away if the boundary was not really closed.
+    pub max_enclosure_area: i32,
+    /// Addresses of the proxies in front of the server, only connections coming from these have their forwarded address believed.
+    #[nserde(default)]
+    pub trusted_proxies: Vec<String>
+}
+
+impl ClaimRules {
+
+    pub fn new() -> ClaimRules {
+        ClaimRules {
+            claim_cooldown: HEX_CLAIM_COOLDOWN * 0.5,
+            claim_burst: 20.0,
+            claims_per_second: 1.0 / HEX_CLAIM_COOLDOWN,
+            contest_rule: ContestRule::Surrounded { minimum_neighbours: 3 },
+            max_enclosure_area: 4096,
+            trusted_proxies: Vec::new()
+        }
+    }
+
+    pub fn load() -> ClaimRules {
+
+        let Ok(claim_rules_data) = std::fs::read_to_string(CLAIM_RULES_PATH) else {
+            println!("[hoxx-server] no rules found at: {}, using defaults", CLAIM_RULES_PATH);
+            return ClaimRules::new();
+        };
+
+        match ClaimRules::deserialize_json(&claim_rules_data) {
+            Ok(claim_rules) => claim_rules,
+            Err(error) => {
+                println!("[hoxx-server] failed to load rules from: {} with error: {}, using defaults", CLAIM_RULES_PATH, error);
+                ClaimRules::new()
+            }
+        }
+
+    }
+
+    /// Where the client is connecting from as far as the limits go, anyone can send a forwarded header so it is only used when it was added by one of our own proxies.
+    pub fn resolve_client_address(&self, peer_address: Option<IpAddr>, forwarded_for: Option<&str>) -> Option<String> {
+
+        let peer_address = peer_address?.to_string();
+        if self.trusted_proxies.contains(&peer_address) == false {
+            return Some(peer_address);
+        }
+
+        // the proxy adds the address it saw to the end, anything before that came from the client
+        let forwarded_address = forwarded_for
+            .and_then(|forwarded_for| forwarded_for.rsplit(',').next())
+            .map(|forwarded_address| forwarded_address.trim())
+            .filter(|forwarded_address| forwarded_address.is_empty() == false);
+
+        Some(forwarded_address.map(|forwarded_address| forwarded_address.to_string()).unwrap_or(peer_address))
+
+    }
+
+    /// Checks whether the client is allowed to claim the hex according to where it is, not how often they are claiming.
+    pub fn validate_claim(&self, state: &HoxxGameState, client_id: ClientID, hex: Hex) -> Result<(), ClaimRejection> {
+
+        let number_of_own_neighbours = hex.all_neighbors().iter().filter(|n| state.is_claimed_by(n.x, n.y, client_id)).count() as i32;
+
+        // anyone without any territory can start anywhere, which is also how you get back in after losing everything
+        if state.get_number_of_claimed_hexes(client_id) > 0 && number_of_own_neighbours == 0 {
+            return Err(ClaimRejection::NotAdjacent);
+        }
+
+        let is_owned_by_someone_else = state.get_claim_hex(hex.x, hex.y).is_some_and(|owner| owner != client_id);
+        if is_owned_by_someone_else {
+
+            let can_contest = match self.contest_rule {
+                ContestRule::Never => false,
+                ContestRule::Always => true,
+                ContestRule::Surrounded { minimum_neighbours } => number_of_own_neighbours >= minimum_neighbours
+            };
+
+            if can_contest == false {
+                return Err(ClaimRejection::Contested);
+            }
+
+        }
+
+        Ok(())
+
+    }
+
+}
+
+/// Tracks how often a single client is claiming, a cooldown between claims along with a token bucket for the rate over time.
+pub struct ClaimLimiter {
+    tokens: f32,
+    last_refill: Instant,
+    last_claim: Option<Instant>
+}
+
+impl ClaimLimiter {
+
+    pub fn new(rules: &ClaimRules, now: Instant) -> ClaimLimiter {
+        ClaimLimiter {
+            tokens: rules.claim_burst,
+            last_refill: now,
+            last_claim: None
+        }
+    }
+
+    /// True once the limiter has recovered completely, at which point it is no different from a new one.
+    pub fn is_idle(&self, rules: &ClaimRules, now: Instant) -> bool {
+        let seconds_since_refill = now.duration_since(self.last_refill).as_secs_f32();
+        let is_cooling_down = self.last_claim.is_some_and(|last_claim| now.duration_since(last_claim).as_secs_f32() < rules.claim_cooldown);
+        is_cooling_down == false && self.tokens + seconds_since_refill * rules.claims_per_second >= rules.claim_burst
+    }
+
+    /// Every attempt counts towards the limit, even the ones which turn out to break the rules, so spamming invalid claims is limited too.
+    pub fn try_claim(&mut self, rules: &ClaimRules, now: Instant) -> Result<(), ClaimRejection> {
+
+        let seconds_since_refill = now.duration_since(self.last_refill).as_secs_f32();
+        self.tokens = (self.tokens + seconds_since_refill * rules.claims_per_second).min(rules.claim_burst);
+        self.last_refill = now;
+
+        let is_cooling_down = self.last_claim.is_some_and(|last_claim| now.duration_since(last_claim).as_secs_f32() < rules.claim_cooldown);
+        if is_cooling_down {
+            return Err(ClaimRejection::Cooldown);
+        }
+
+        if self.tokens < 1.0 {
+            return Err(ClaimRejection::RateLimited);
+        }
+
+        self.tokens -= 1.0;
+        self.last_claim = Some(now);
+
+        Ok(())
+
+    }
+
+}
+
+/// The claim limiters of everyone who has claimed recently, kept by player and by address rather than by connection so that reconnecting or coming back as someone new does not start over with a fresh limit.
+pub struct ClaimLimiters {
+    limiters: HashMap<String, ClaimLimiter>
+}
+
+impl ClaimLimiters {
+
+    pub fn new() -> ClaimLimiters {
+        ClaimLimiters {
+            limiters: HashMap::new()
+        }
+    }
+
+    pub fn try_claim(&mut self, key: &str, rules: &ClaimRules, now: Instant) -> Result<(), ClaimRejection> {
+        let limiter = self.limiters.entry(key.to_string()).or_insert_with(|| ClaimLimiter::new(rules, now));
+        limiter.try_claim(rules, now)
+    }
+
+    /// Forgets the limiters which have fully recovered, as those would behave just like new ones anyway.
+    pub fn remove_idle(&mut self, rules: &ClaimRules, now: Instant) {
+        self.limiters.retain(|_, limiter| limiter.is_idle(rules, now) == false);
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use super::*;
+
+    fn create_test_rules() -> ClaimRules {
+        ClaimRules {
+            claim_cooldown: 0.1,
+            claim_burst: 2.0,
+            claims_per_second: 0.5,
+            ..ClaimRules::new()
+        }
+    }
+
+    #[test]
+    fn test_cooldown_between_claims() {
+
+        let rules = create_test_rules();
+        let mut limiters = ClaimLimiters::new();
+        let now = Instant::now();
+
+        assert_eq!(Ok(()), limiters.try_claim("a", &rules, now));
+        assert_eq!(Err(ClaimRejection::Cooldown), limiters.try_claim("a", &rules, now + Duration::from_millis(50)));
+        assert_eq!(Ok(()), limiters.try_claim("a", &rules, now + Duration::from_millis(150)));
+
+    }
+
+    #[test]
+    fn test_rate_limit_after_burst() {
+
+        let rules = create_test_rules();
+        let mut limiters = ClaimLimiters::new();
+        let now = Instant::now();
+
+        assert_eq!(Ok(()), limiters.try_claim("a", &rules, now));
+        assert_eq!(Ok(()), limiters.try_claim("a", &rules, now + Duration::from_millis(200)));
+        assert_eq!(Err(ClaimRejection::RateLimited), limiters.try_claim("a", &rules, now + Duration::from_millis(400)));
+
+        // someone else is not affected
+        assert_eq!(Ok(()), limiters.try_claim("b", &rules, now + Duration::from_millis(400)));
+
+    }
+
+    #[test]
+    fn test_reconnecting_does_not_reset_limit() {
+
+        let rules = create_test_rules();
+        let mut limiters = ClaimLimiters::new();
+        let now = Instant::now();
+
+        assert_eq!(Ok(()), limiters.try_claim("a", &rules, now));
+        assert_eq!(Ok(()), limiters.try_claim("a", &rules, now + Duration::from_millis(200)));
+
+        // what happens when the client disconnects, then it comes back from the same address
+        limiters.remove_idle(&rules, now + Duration::from_millis(300));
+        assert_eq!(Err(ClaimRejection::RateLimited), limiters.try_claim("a", &rules, now + Duration::from_millis(400)));
+
+    }
+
+    #[test]
+    fn test_forwarded_address_only_trusted_from_proxy() {
+
+        let proxy_address: IpAddr = "10.0.0.1".parse().unwrap();
+        let client_address: IpAddr = "203.0.113.7".parse().unwrap();
+
+        let mut rules = create_test_rules();
+        assert_eq!(Some("203.0.113.7".to_string()), rules.resolve_client_address(Some(client_address), Some("198.51.100.1")));
+        assert_eq!(Some("10.0.0.1".to_string()), rules.resolve_client_address(Some(proxy_address), Some("198.51.100.1")));
+
+        rules.trusted_proxies = vec!["10.0.0.1".to_string()];
+        assert_eq!(Some("203.0.113.7".to_string()), rules.resolve_client_address(Some(client_address), Some("198.51.100.1")));
+        assert_eq!(Some("198.51.100.1".to_string()), rules.resolve_client_address(Some(proxy_address), Some("198.51.100.1")));
+        assert_eq!(Some("10.0.0.1".to_string()), rules.resolve_client_address(Some(proxy_address), None));
+
+        // whatever the client put in the header itself comes before what the proxy added
+        assert_eq!(Some("198.51.100.1".to_string()), rules.resolve_client_address(Some(proxy_address), Some("192.0.2.99, 198.51.100.1")));
+
+        assert_eq!(None, rules.resolve_client_address(None, Some("198.51.100.1")));
+
+    }
+
+    #[test]
+    fn test_recovered_limiters_are_forgotten() {
+
+        let rules = create_test_rules();
+        let mut limiters = ClaimLimiters::new();
+        let now = Instant::now();
+
+        assert_eq!(Ok(()), limiters.try_claim("a", &rules, now));
+
+        limiters.remove_idle(&rules, now + Duration::from_millis(500));
+        assert_eq!(1, limiters.limiters.len());
+
+        limiters.remove_idle(&rules, now + Duration::from_secs(3));
+        assert_eq!(0, limiters.limiters.len());
+
+    }
+
+}
@@ -0,0 +1,376 @@
+use std::{collections::{HashMap, HashSet}, time::{Duration, Instant}};
+
+use hexx::Hex;
+use hoxx_shared::{ClientID, LeaderboardEntry, LEADERBOARD_SIZE};
+
+use crate::state::HoxxGameState;
+
+/// Territories which shrunk are counted again at most this often, as someone losing hexes one at a time to a neighbour would otherwise have all of theirs counted on every claim.
+const TERRITORY_RECOUNT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The parts of a player's score which can't be read straight off the world.
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerScore {
+    pub largest_territory: i32,
+    /// Set when the player lost hexes, as the largest territory might have been split or shrunk and has to be counted again, until then it shows what it was before.
+    pub is_largest_territory_stale: bool,
+    pub enclosures: i32,
+    pub enclosed_hexes: i32
+}
+
+impl PlayerScore {
+
+    pub fn new() -> PlayerScore {
+        PlayerScore {
+            largest_territory: 0,
+            is_largest_territory_stale: true,
+            enclosures: 0,
+            enclosed_hexes: 0
+        }
+    }
+
+}
+
+/// Each of a player's territories as a set of hexes joined together, so that growing one only has to look at the neighbours of the new hex rather than count the whole territory again.
+/// Hexes can't be taken back out, so when a player loses any this is thrown away and built again from the hexes they still hold.
+struct Territories {
+    /// Points towards the hex standing in for the territory, which is the one pointing at itself.
+    parents: HashMap<Hex, Hex>,
+    /// Size of each territory, by the hex standing in for it.
+    sizes: HashMap<Hex, i32>
+}
+
+impl Territories {
+
+    fn new() -> Territories {
+        Territories {
+            parents: HashMap::new(),
+            sizes: HashMap::new()
+        }
+    }
+
+    fn from_hexes(hexes: &HashSet<Hex>) -> Territories {
+        let mut territories = Territories::new();
+        for &hex in hexes {
+            territories.insert(hex);
+        }
+        territories
+    }
+
+    fn find(&mut self, hex: Hex) -> Hex {
+
+        let mut root = hex;
+        while self.parents[&root] != root {
+            root = self.parents[&root];
+        }
+
+        // point everything on the way straight at the root, so the next time is quicker
+        let mut current = hex;
+        while current != root {
+            let next = self.parents[&current];
+            self.parents.insert(current, root);
+            current = next;
+        }
+
+        root
+
+    }
+
+    /// Adds the hex and joins together the territories around it, returns the size of the territory it ended up in.
+    fn insert(&mut self, hex: Hex) -> i32 {
+
+        if self.parents.contains_key(&hex) {
+            let root = self.find(hex);
+            return self.sizes[&root];
+        }
+
+        self.parents.insert(hex, hex);
+        self.sizes.insert(hex, 1);
+
+        let mut root = hex;
+        for neighbour in hex.all_neighbors() {
+
+            if self.parents.contains_key(&neighbour) == false {
+                continue;
+            }
+
+            let neighbour_root = self.find(neighbour);
+            if neighbour_root == root {
+                continue;
+            }
+
+            // the smaller territory goes under the larger one, which keeps the way to the root short
+            let (larger, smaller) = if self.sizes[&root] >= self.sizes[&neighbour_root] { (root, neighbour_root) } else { (neighbour_root, root) };
+            let smaller_size = self.sizes.remove(&smaller).unwrap_or(0);
+            self.parents.insert(smaller, larger);
+            *self.sizes.entry(larger).or_insert(0) += smaller_size;
+            root = larger;
+
+        }
+
+        self.sizes[&root]
+
+    }
+
+    fn largest(&self) -> i32 {
+        self.sizes.values().copied().max().unwrap_or(0)
+    }
+
+}
+
+pub struct HoxxScores {
+    scores: HashMap<ClientID, PlayerScore>,
+    /// Only kept for players whose largest territory is up to date, anyone else has theirs built when they are counted again.
+    territories: HashMap<ClientID, Territories>,
+    last_recount: Option<Instant>,
+    /// What was sent to clients last, so the leaderboard is only sent again when something on it changed.
+    last_leaderboard: Vec<LeaderboardEntry>
+}
+
+impl HoxxScores {
+
+    pub fn new() -> HoxxScores {
+        HoxxScores {
+            scores: HashMap::new(),
+            territories: HashMap::new(),
+            last_recount: None,
+            last_leaderboard: Vec::new()
+        }
+    }
+
+    pub fn get_score(&self, client_id: ClientID) -> PlayerScore {
+        self.scores.get(&client_id).copied().unwrap_or_else(PlayerScore::new)
+    }
+
+    /// Used when loading the world, the largest territory is always counted again after.
+    pub fn set_enclosures(&mut self, client_id: ClientID, enclosures: i32, enclosed_hexes: i32) {
+        let score = self.scores.entry(client_id).or_insert_with(PlayerScore::new);
+        score.enclosures = enclosures;
+        score.enclosed_hexes = enclosed_hexes;
+    }
+
+    pub fn on_enclosure(&mut self, client_id: ClientID, enclosed_hexes: i32) {
+        let score = self.scores.entry(client_id).or_insert_with(PlayerScore::new);
+        score.enclosures += 1;
+        score.enclosed_hexes += enclosed_hexes;
+    }
+
+    /// Call after the client claimed the hex, growing only ever makes the territory around the hex larger so only that one can have become the largest.
+    pub fn on_territory_grown(&mut self, client_id: ClientID, hex: Hex) {
+
+        let score = self.scores.entry(client_id).or_insert_with(PlayerScore::new);
+        if score.is_largest_territory_stale {
+            return;
+        }
+
+        let territory_size = self.territories.entry(client_id).or_insert_with(Territories::new).insert(hex);
+        score.largest_territory = score.largest_territory.max(territory_size);
+
+    }
+
+    pub fn on_territories_shrunk(&mut self, client_ids: HashSet<ClientID>) {
+        for client_id in client_ids {
+            self.scores.entry(client_id).or_insert_with(PlayerScore::new).is_largest_territory_stale = true;
+            self.territories.remove(&client_id);
+        }
+    }
+
+    fn build_leaderboard(&mut self, state: &HoxxGameState, now: Instant) -> Vec<LeaderboardEntry> {
+
+        let is_recount_due = self.last_recount.is_none_or(|last_recount| now.duration_since(last_recount) >= TERRITORY_RECOUNT_INTERVAL);
+
+        let mut ranked_clients: Vec<(ClientID, usize)> = state.get_claimed_hex_counts().iter()
+            .filter(|(_, &hexes)| hexes > 0)
+            .map(|(&client_id, &hexes)| (client_id, hexes))
+            .collect();
+
+        // ties are broken by id so the order doesn't jump around between updates
+        ranked_clients.sort_by(|(a_id, a_hexes), (b_id, b_hexes)| b_hexes.cmp(a_hexes).then(a_id.id.cmp(&b_id.id)));
+        ranked_clients.truncate(LEADERBOARD_SIZE);
+
+        ranked_clients.into_iter().map(|(client_id, hexes)| {
+
+            // anyone who has never been counted is counted straight away, otherwise they would show nothing until the next recount
+            let score = self.scores.entry(client_id).or_insert_with(PlayerScore::new);
+            let has_been_counted = score.largest_territory > 0;
+            if score.is_largest_territory_stale && (is_recount_due || has_been_counted == false) {
+
+                let territories = state.get_claimed_hexes(client_id).map(Territories::from_hexes).unwrap_or_else(Territories::new);
+                score.largest_territory = territories.largest();
+                score.is_largest_territory_stale = false;
+                self.territories.insert(client_id, territories);
+
+                if is_recount_due {
+                    self.last_recount = Some(now);
+                }
+
+            }
+
+            LeaderboardEntry {
+                id: client_id,
+                name: state.get_game_state().get_client_name(client_id).unwrap_or("unnamed").to_string(),
+                hexes: hexes as i32,
+                largest_territory: score.largest_territory,
+                enclosures: score.enclosures,
+                enclosed_hexes: score.enclosed_hexes
+            }
+
+        }).collect()
+
+    }
+
+    /// Returns the leaderboard if it changed since the last time it was returned from here.
+    pub fn take_changed_leaderboard(&mut self, state: &HoxxGameState, now: Instant) -> Option<Vec<LeaderboardEntry>> {
+
+        let leaderboard = self.build_leaderboard(state, now);
+        if leaderboard == self.last_leaderboard {
+            return None;
+        }
+
+        self.last_leaderboard = leaderboard.clone();
+        Some(leaderboard)
+
+    }
+
+    pub fn get_last_leaderboard(&self) -> &Vec<LeaderboardEntry> {
+        &self.last_leaderboard
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// What the server does when a claim goes through.
+    fn claim(state: &mut HoxxGameState, scores: &mut HoxxScores, client_id: ClientID, x: i32, y: i32) {
+        state.put_claim_hex(x, y, client_id);
+        scores.on_territories_shrunk(state.take_shrunk_territories());
+        scores.on_territory_grown(client_id, Hex::new(x, y));
+    }
+
+    fn get_largest_territory(scores: &HoxxScores, client_id: ClientID) -> i32 {
+        scores.get_last_leaderboard().iter().find(|entry| entry.id == client_id).map(|entry| entry.largest_territory).unwrap_or(0)
+    }
+
+    #[test]
+    fn test_largest_territory_grows_with_claims() {
+
+        let mut state = HoxxGameState::new();
+        let mut scores = HoxxScores::new();
+        let client_id = ClientID { id: 1 };
+        let now = Instant::now();
+
+        claim(&mut state, &mut scores, client_id, 0, 0);
+        let _ = scores.take_changed_leaderboard(&state, now);
+        assert_eq!(1, get_largest_territory(&scores, client_id));
+
+        claim(&mut state, &mut scores, client_id, 1, 0);
+        claim(&mut state, &mut scores, client_id, 2, 0);
+        let _ = scores.take_changed_leaderboard(&state, now);
+        assert_eq!(3, get_largest_territory(&scores, client_id));
+
+        // a separate territory which is smaller doesn't change anything
+        claim(&mut state, &mut scores, client_id, 10, 10);
+        let _ = scores.take_changed_leaderboard(&state, now);
+        assert_eq!(3, get_largest_territory(&scores, client_id));
+
+    }
+
+    #[test]
+    fn test_territories_are_joined_by_claim_between() {
+
+        let mut state = HoxxGameState::new();
+        let mut scores = HoxxScores::new();
+        let client_id = ClientID { id: 1 };
+        let now = Instant::now();
+
+        claim(&mut state, &mut scores, client_id, 0, 0);
+        let _ = scores.take_changed_leaderboard(&state, now);
+
+        claim(&mut state, &mut scores, client_id, 1, 0);
+        claim(&mut state, &mut scores, client_id, 4, 0);
+        claim(&mut state, &mut scores, client_id, 3, 0);
+        let _ = scores.take_changed_leaderboard(&state, now);
+        assert_eq!(2, get_largest_territory(&scores, client_id));
+
+        claim(&mut state, &mut scores, client_id, 2, 0);
+        let _ = scores.take_changed_leaderboard(&state, now);
+        assert_eq!(5, get_largest_territory(&scores, client_id));
+
+    }
+
+    #[test]
+    fn test_split_territory_is_counted_again_after_interval() {
+
+        let mut state = HoxxGameState::new();
+        let mut scores = HoxxScores::new();
+        let client_id = ClientID { id: 1 };
+        let other_client_id = ClientID { id: 2 };
+        let now = Instant::now();
+
+        for x in 0..5 {
+            claim(&mut state, &mut scores, client_id, x, 0);
+        }
+
+        let _ = scores.take_changed_leaderboard(&state, now);
+        assert_eq!(5, get_largest_territory(&scores, client_id));
+
+        // cut in the middle, leaving two territories of two
+        claim(&mut state, &mut scores, other_client_id, 2, 0);
+
+        let _ = scores.take_changed_leaderboard(&state, now + Duration::from_secs(1));
+        assert_eq!(5, get_largest_territory(&scores, client_id));
+
+        let _ = scores.take_changed_leaderboard(&state, now + TERRITORY_RECOUNT_INTERVAL);
+        assert_eq!(2, get_largest_territory(&scores, client_id));
+
+        // taking it back joins them up again
+        claim(&mut state, &mut scores, client_id, 2, 0);
+        let _ = scores.take_changed_leaderboard(&state, now + TERRITORY_RECOUNT_INTERVAL);
+        assert_eq!(5, get_largest_territory(&scores, client_id));
+
+    }
+
+    #[test]
+    fn test_enclosures_add_up() {
+
+        let mut scores = HoxxScores::new();
+        let client_id = ClientID { id: 1 };
+
+        scores.set_enclosures(client_id, 2, 10);
+        scores.on_enclosure(client_id, 5);
+
+        let score = scores.get_score(client_id);
+        assert_eq!(3, score.enclosures);
+        assert_eq!(15, score.enclosed_hexes);
+
+    }
+
+    #[test]
+    fn test_leaderboard_order_and_changes() {
+
+        let mut state = HoxxGameState::new();
+        let mut scores = HoxxScores::new();
+        let first_client_id = ClientID { id: 1 };
+        let second_client_id = ClientID { id: 2 };
+        let now = Instant::now();
+
+        claim(&mut state, &mut scores, second_client_id, 10, 10);
+        claim(&mut state, &mut scores, first_client_id, 0, 0);
+
+        // tied on hexes, so the lower id goes first
+        let leaderboard = scores.take_changed_leaderboard(&state, now).expect("leaderboard should have changed");
+        assert_eq!(vec![first_client_id, second_client_id], leaderboard.iter().map(|entry| entry.id).collect::<Vec<_>>());
+
+        // nothing changed, nothing to send
+        assert!(scores.take_changed_leaderboard(&state, now).is_none());
+
+        claim(&mut state, &mut scores, second_client_id, 11, 10);
+        let leaderboard = scores.take_changed_leaderboard(&state, now).expect("leaderboard should have changed");
+        assert_eq!(vec![second_client_id, first_client_id], leaderboard.iter().map(|entry| entry.id).collect::<Vec<_>>());
+
+    }
+
+}
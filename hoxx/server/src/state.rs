@@ -1,16 +1,39 @@
-use hoxx_shared::{ClientColor, ClientID, GameState};
+use std::collections::{HashMap, HashSet};
+
+use hexx::Hex;
+use hoxx_shared::{ClientColor, ClientID, GameState, GameStateDelta, HexClaim};
 
 pub struct HoxxGameState {
-    state: GameState
+    state: GameState,
+    /// How many hexes each client holds, so we don't have to go through the whole world to find out.
+    claimed_hex_counts: HashMap<ClientID, usize>,
+    /// Which hexes each client holds, for counting their territories without going through the whole world.
+    claimed_hexes: HashMap<ClientID, HashSet<Hex>>,
+    /// Clients which lost hexes to someone else since the last call to take_shrunk_territories.
+    shrunk_territories: HashSet<ClientID>,
+    /// Everything changed since the last call to take_delta, so it can be sent out to clients.
+    pending_delta: GameStateDelta
 }
 
 impl HoxxGameState {
     pub fn new() -> HoxxGameState {
         HoxxGameState {
-            state: GameState::new()
+            state: GameState::new(),
+            claimed_hex_counts: HashMap::new(),
+            claimed_hexes: HashMap::new(),
+            shrunk_territories: HashSet::new(),
+            pending_delta: GameStateDelta::new()
         }
     }
 
+    pub fn take_delta(&mut self) -> GameStateDelta {
+        std::mem::replace(&mut self.pending_delta, GameStateDelta::new())
+    }
+
+    pub fn take_shrunk_territories(&mut self) -> HashSet<ClientID> {
+        std::mem::take(&mut self.shrunk_territories)
+    }
+
     pub fn get_game_state(&self) -> &GameState {
         &self.state
     }
@@ -21,6 +44,31 @@ impl HoxxGameState {
 
     pub fn set_client_colour(&mut self, client_id: ClientID, client_colour: ClientColor) {
         self.state.set_client_colour(client_id, client_colour);
+        self.pending_delta.colours.push((client_id, client_colour));
+    }
+
+    pub fn set_client_name(&mut self, client_id: ClientID, client_name: &str) {
+        self.state.set_client_name(client_id, client_name);
+        self.pending_delta.names.push((client_id, client_name.to_string()));
+    }
+
+    /// Every claimed hex in the world, for saving it.
+    pub fn get_hex_claims(&self) -> Vec<HexClaim> {
+        self.state.get_chunks().values()
+            .flat_map(|hex_chunk| hex_chunk.get_hexes().iter())
+            .map(|(&(x, y), &owner)| HexClaim { x, y, owner })
+            .collect()
+    }
+
+    /// Puts back the claims from a save, without them ending up in the next delta as nobody is connected yet anyway.
+    pub fn load_hex_claims(&mut self, hex_claims: &[HexClaim]) {
+
+        for hex_claim in hex_claims {
+            self.put_claim_hex(hex_claim.x, hex_claim.y, ClientID { id: hex_claim.owner });
+        }
+
+        self.pending_delta = GameStateDelta::new();
+
     }
 
     pub fn is_claimed_by(&self, x: i32, y: i32, client_id: ClientID) -> bool {
@@ -28,16 +76,51 @@ impl HoxxGameState {
         claim_id == client_id
     }
 
+    pub fn get_number_of_claimed_hexes(&self, client_id: ClientID) -> usize {
+        self.claimed_hex_counts.get(&client_id).copied().unwrap_or(0)
+    }
+
+    pub fn get_claimed_hex_counts(&self) -> &HashMap<ClientID, usize> {
+        &self.claimed_hex_counts
+    }
+
+    /// Every hex held by the client.
+    pub fn get_claimed_hexes(&self, client_id: ClientID) -> Option<&HashSet<Hex>> {
+        self.claimed_hexes.get(&client_id)
+    }
+
     pub fn get_claim_hex(&self, x: i32, y: i32) -> Option<ClientID> {
         self.state.get_hex(x, y).and_then(|id| Some(ClientID { id }))
     }
 
     pub fn put_claim_hex(&mut self, x: i32, y: i32, v: ClientID) {
+
+        // claiming a hex you already own changes nothing, so there is nothing to tell anyone
+        if self.is_claimed_by(x, y, v) {
+            return;
+        }
+
+        if let Some(previous_owner) = self.get_claim_hex(x, y) {
+            if let Some(count) = self.claimed_hex_counts.get_mut(&previous_owner) {
+                *count = count.saturating_sub(1);
+            }
+            if let Some(hexes) = self.claimed_hexes.get_mut(&previous_owner) {
+                hexes.remove(&Hex::new(x, y));
+            }
+            self.shrunk_territories.insert(previous_owner);
+        }
+
+        *self.claimed_hex_counts.entry(v).or_insert(0) += 1;
+        self.claimed_hexes.entry(v).or_default().insert(Hex::new(x, y));
+
         self.state.set_hex(x, y, v.id);
+        self.pending_delta.hexes.push(HexClaim { x, y, owner: v.id });
+
     }
 
     pub fn put_claim_world(&mut self, x: i32, y: i32, v: ClientID) {
-        self.state.set_world(x, y, v.id);
+        let hex_coordinate = self.state.world_to_hex(x, y);
+        self.put_claim_hex(hex_coordinate.x, hex_coordinate.y, v);
     }
 
     pub fn get_claim_world(&mut self, x: i32, y: i32) -> Option<ClientID> {
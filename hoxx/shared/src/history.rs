@@ -0,0 +1,45 @@
+use nanoserde::{DeJson, SerJson};
+
+use crate::{ClientColor, ClientID, HexClaim};
+
+/// What happened in a single entry of the history log.
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub enum HistoryAction {
+    /// Everything already in the world when the log was started, so replaying the log from the start gives the whole world.
+    Initial { hexes: Vec<HexClaim> },
+    /// The colour a player has, written for every player when the server starts and whenever a new player joins.
+    Colour { colour: ClientColor },
+    /// A single accepted claim.
+    Claim { x: i32, y: i32 },
+    /// The hexes filled in after a claim closed a loop, in hex coordinates.
+    Fill { hexes: Vec<(i32, i32)> }
+}
+
+/// A line in the history log, the server appends one for every change to the world.
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct HistoryEvent {
+    /// Milliseconds since the unix epoch.
+    pub time: u64,
+    pub owner: ClientID,
+    pub action: HistoryAction
+}
+
+impl HistoryEvent {
+
+    pub fn now(owner: ClientID, action: HistoryAction) -> HistoryEvent {
+        let time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        HistoryEvent { time, owner, action }
+    }
+
+    /// Parses the history log, one event per line, skipping lines which can't be parsed such as one cut short by the server dying halfway through writing it.
+    pub fn parse_log(log: &str) -> Vec<HistoryEvent> {
+        log.lines()
+            .filter(|line| line.trim().is_empty() == false)
+            .filter_map(|line| HistoryEvent::deserialize_json(line).ok())
+            .collect()
+    }
+
+}
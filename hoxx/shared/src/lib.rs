@@ -1,11 +1,13 @@
 use std::fmt::Display;
 use std::ops::{Add, Deref};
+use std::collections::HashSet;
 use std::{collections::HashMap, ops::AddAssign};
 use glam::{ivec2, vec2, IVec2, Vec2};
 use hexx::{hex, Hex};
 use hexx::HexLayout;
 use nanoserde::{DeJson, SerJson};
 
+pub mod history;
 pub mod utils;
 
 pub const IS_RUNNING_LOCALLY: bool = true;
@@ -16,6 +18,14 @@ pub const HEX_SIZE: f32 = 16.0;
 pub const HEX_IS_VERTICAL: bool = false;
 pub const HEX_CLAIM_COOLDOWN: f32 = 0.1;
 
+/// Width and height of a chunk in hexes, in axial coordinates.
+pub const CHUNK_SIZE: i32 = 32;
+
+pub type ChunkCoordinate = (i32, i32);
+
+/// How many players are sent in the leaderboard, everyone else only gets to see how far they have to go.
+pub const LEADERBOARD_SIZE: usize = 10;
+
 pub enum ClientState {
     Unconnected,
     Connected
@@ -70,9 +80,134 @@ pub struct Client {
 
 #[derive(Debug, Clone, SerJson, DeJson)]
 pub enum ClientMessage {
-    Join { id: ClientID },
+    /// The first thing a client sends, with the token from a previous visit if it has one, so the server can give it back its old id and colour.
+    Identify { token: Option<String>, nickname: String },
+    /// The token is for the client to keep and send back the next time it connects.
+    Join { id: ClientID, token: String },
     Claim { world_x: i32, world_y: i32 },
-    Update { state: GameState }
+    /// Sent by a client which missed a delta, the server answers with a full update.
+    RequestResync,
+    /// Sent by a client whenever the chunks it wants changes, replaces the chunks it was subscribed to before.
+    Subscribe { chunks: Vec<ChunkCoordinate> },
+    /// Snapshots of chunks the client just subscribed to.
+    Chunks { chunks: Vec<(ChunkCoordinate, HexChunk)>, sequence: u64 },
+    /// The full game state for the chunks the client is subscribed to, only sent when joining or resyncing.
+    Update { state: GameState, sequence: u64 },
+    /// Only what changed since the previous sequence number, a client must have applied every message before it to apply this one.
+    Delta { delta: GameStateDelta, sequence: u64 },
+    /// Sent by the server when a claim broke the rules, along with who actually owns the hex so the client can undo its guess.
+    ClaimRejected { world_x: i32, world_y: i32, reason: ClaimRejection, current_owner: Option<i64> },
+    /// The top players by number of hexes, sent to everyone whenever it changes.
+    Leaderboard { entries: Vec<LeaderboardEntry> },
+    /// Sent to everyone when a player closes a loop and the area inside is filled, the position is the claim which closed the loop.
+    Enclosure { id: ClientID, world_x: i32, world_y: i32, hexes: i32 }
+}
+
+#[derive(Debug, Clone, PartialEq, SerJson, DeJson)]
+pub struct LeaderboardEntry {
+    pub id: ClientID,
+    pub name: String,
+    /// Number of hexes held right now.
+    pub hexes: i32,
+    /// Number of hexes in the largest connected area held right now.
+    pub largest_territory: i32,
+    /// Number of loops closed over all time.
+    pub enclosures: i32,
+    /// Number of hexes gained by closing loops over all time.
+    pub enclosed_hexes: i32
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, SerJson, DeJson)]
+pub enum ClaimRejection {
+    /// Claimed again before the cooldown since the last claim was over.
+    Cooldown,
+    /// Claimed too many times in a short while, even if each claim respected the cooldown.
+    RateLimited,
+    /// Only the first claim can be anywhere, after that claims have to border your own territory.
+    NotAdjacent,
+    /// The hex belongs to someone else and the rules don't allow taking it from here.
+    Contested,
+    /// The claim went through, but the area it enclosed was too large to be filled.
+    EnclosureTooLarge
+}
+
+impl Display for ClaimRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ClaimRejection::Cooldown => "claiming too quickly",
+            ClaimRejection::RateLimited => "claiming too much, slow down",
+            ClaimRejection::NotAdjacent => "must claim next to your own hexes",
+            ClaimRejection::Contested => "not enough of your hexes around to take that one",
+            ClaimRejection::EnclosureTooLarge => "area too large to fill"
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, SerJson, DeJson)]
+pub struct HexClaim {
+    pub x: i32,
+    pub y: i32,
+    pub owner: i64
+}
+
+/// The changes to the game state from a single event, a claim along with its flood fill or a client joining or leaving.
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct GameStateDelta {
+    pub hexes: Vec<HexClaim>,
+    pub colours: Vec<(ClientID, ClientColor)>,
+    pub removed_colours: Vec<ClientID>,
+    pub names: Vec<(ClientID, String)>
+}
+
+impl GameStateDelta {
+
+    pub fn new() -> GameStateDelta {
+        GameStateDelta {
+            hexes: Vec::new(),
+            colours: Vec::new(),
+            removed_colours: Vec::new(),
+            names: Vec::new()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hexes.is_empty() && self.colours.is_empty() && self.removed_colours.is_empty() && self.names.is_empty()
+    }
+
+    /// The part of the delta a client subscribed to the given chunks cares about, colours and names are always included.
+    pub fn filtered_to_chunks(&self, chunks: &HashSet<ChunkCoordinate>) -> GameStateDelta {
+        GameStateDelta {
+            hexes: self.hexes.iter().filter(|h| chunks.contains(&hex_to_chunk(h.x, h.y))).copied().collect(),
+            colours: self.colours.clone(),
+            removed_colours: self.removed_colours.clone(),
+            names: self.names.clone()
+        }
+    }
+
+}
+
+pub fn hex_to_chunk(x: i32, y: i32) -> ChunkCoordinate {
+    (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE))
+}
+
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct HexChunk {
+    /// Keyed by the hex coordinate in the world, not within the chunk.
+    hexes: HashMap<(i32, i32), i64>
+}
+
+impl HexChunk {
+
+    pub fn new() -> HexChunk {
+        HexChunk {
+            hexes: HashMap::new()
+        }
+    }
+
+    pub fn get_hexes(&self) -> &HashMap<(i32, i32), i64> {
+        &self.hexes
+    }
+
 }
 
 #[derive(Debug, Copy, Clone, SerJson, DeJson)]
@@ -96,8 +231,9 @@ impl ClientColor {
 
 #[derive(Debug, Clone, SerJson, DeJson)]
 pub struct GameState {
-    hexes: HashMap<(i32, i32), i64>,
+    chunks: HashMap<ChunkCoordinate, HexChunk>,
     colours: HashMap<ClientID, ClientColor>,
+    names: HashMap<ClientID, String>,
     #[nserde(skip)]
     layout: Option<HexLayout>
 }
@@ -117,8 +253,9 @@ impl GameState {
     pub fn new() -> GameState {
         let layout = Self::get_default_hex_layout();
         GameState {
-            hexes: HashMap::new(),
+            chunks: HashMap::new(),
             colours: HashMap::new(),
+            names: HashMap::new(),
             layout: Some(layout)
         }
     }
@@ -128,16 +265,96 @@ impl GameState {
         self.layout = Some(Self::get_default_hex_layout());
     }
 
+    pub fn apply_delta(&mut self, delta: &GameStateDelta) {
+
+        for hex_claim in &delta.hexes {
+            self.set_hex(hex_claim.x, hex_claim.y, hex_claim.owner);
+        }
+
+        for &(client_id, client_colour) in &delta.colours {
+            self.colours.insert(client_id, client_colour);
+        }
+
+        for client_id in &delta.removed_colours {
+            self.colours.remove(client_id);
+        }
+
+        for (client_id, client_name) in &delta.names {
+            self.names.insert(*client_id, client_name.clone());
+        }
+
+    }
+
     pub fn set_client_colour(&mut self, client_id: ClientID, client_colour: ClientColor) {
         self.colours.insert(client_id, client_colour);
     }
 
+    pub fn remove_client_colour(&mut self, client_id: ClientID) {
+        self.colours.remove(&client_id);
+    }
+
     pub fn get_client_colour(&self, client_id: ClientID) -> Option<ClientColor> {
         self.colours.get(&client_id).copied()
     }
 
-    pub fn get_hexes(&self) -> &HashMap<(i32, i32), i64> {
-        &self.hexes
+    pub fn set_client_name(&mut self, client_id: ClientID, client_name: &str) {
+        self.names.insert(client_id, client_name.to_string());
+    }
+
+    pub fn get_client_name(&self, client_id: ClientID) -> Option<&str> {
+        self.names.get(&client_id).map(|name| name.as_str())
+    }
+
+    pub fn get_chunks(&self) -> &HashMap<ChunkCoordinate, HexChunk> {
+        &self.chunks
+    }
+
+    pub fn get_chunk(&self, chunk: ChunkCoordinate) -> Option<&HexChunk> {
+        self.chunks.get(&chunk)
+    }
+
+    pub fn set_chunk(&mut self, chunk: ChunkCoordinate, hex_chunk: HexChunk) {
+        self.chunks.insert(chunk, hex_chunk);
+    }
+
+    pub fn remove_chunk(&mut self, chunk: ChunkCoordinate) {
+        self.chunks.remove(&chunk);
+    }
+
+    /// A copy of the state with only the given chunks in it, but all the colours and names, for sending to a client subscribed to those chunks.
+    pub fn filtered_to_chunks(&self, chunks: &HashSet<ChunkCoordinate>) -> GameState {
+        GameState {
+            chunks: self.chunks.iter().filter(|(c, _)| chunks.contains(c)).map(|(&c, hex_chunk)| (c, hex_chunk.clone())).collect(),
+            colours: self.colours.clone(),
+            names: self.names.clone(),
+            layout: Some(Self::get_default_hex_layout())
+        }
+    }
+
+    /// All the chunks touched by the rectangle in the world, with the given number of chunks of extra margin around it.
+    pub fn chunks_within_world_rect(&self, min: Vec2, max: Vec2, margin: i32) -> HashSet<ChunkCoordinate> {
+
+        // the mapping from world to hex coordinates is linear, so the corners of the rectangle hold the extremes
+        let corners = [vec2(min.x, min.y), vec2(max.x, min.y), vec2(min.x, max.y), vec2(max.x, max.y)];
+        let corner_chunks: Vec<ChunkCoordinate> = corners.iter().map(|c| {
+            let hex_coordinate = self.world_to_hex(c.x as i32, c.y as i32);
+            hex_to_chunk(hex_coordinate.x, hex_coordinate.y)
+        }).collect();
+
+        let min_x = corner_chunks.iter().map(|c| c.0).min().unwrap() - margin;
+        let max_x = corner_chunks.iter().map(|c| c.0).max().unwrap() + margin;
+        let min_y = corner_chunks.iter().map(|c| c.1).min().unwrap() - margin;
+        let max_y = corner_chunks.iter().map(|c| c.1).max().unwrap() + margin;
+
+        let mut chunks = HashSet::new();
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                chunks.insert((x, y));
+            }
+        }
+
+        chunks
+
     }
 
     pub fn get_hex_size(&self) -> Vec2 {
@@ -162,20 +379,26 @@ impl GameState {
 
     pub fn get_world(&self, x: i32, y: i32) -> Option<i64> {
         let hex_coordinate = self.world_to_hex(x, y);
-        self.hexes.get(&(hex_coordinate.x, hex_coordinate.y)).copied()
+        self.get_hex(hex_coordinate.x, hex_coordinate.y)
     }
 
     pub fn set_world(&mut self, x: i32, y: i32, v: i64) {
         let hex_coordinate = self.world_to_hex(x, y);
-        self.hexes.insert((hex_coordinate.x, hex_coordinate.y), v);
+        self.set_hex(hex_coordinate.x, hex_coordinate.y, v);
     }
 
     pub fn set_hex(&mut self, x: i32, y: i32, v: i64) {
-        self.hexes.insert((x, y), v);
+        self.chunks.entry(hex_to_chunk(x, y)).or_insert_with(HexChunk::new).hexes.insert((x, y), v);
+    }
+
+    pub fn remove_hex(&mut self, x: i32, y: i32) {
+        if let Some(chunk) = self.chunks.get_mut(&hex_to_chunk(x, y)) {
+            chunk.hexes.remove(&(x, y));
+        }
     }
 
     pub fn get_hex(&self, x: i32, y: i32) -> Option<i64> {
-        self.hexes.get(&(x, y)).copied()
+        self.chunks.get(&hex_to_chunk(x, y)).and_then(|chunk| chunk.hexes.get(&(x, y)).copied())
     }
 
 }
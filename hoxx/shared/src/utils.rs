@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 
 use hexx::{EdgeDirection, Hex};
 
@@ -133,6 +133,35 @@ pub fn flood_fill_hexes<T>(state: &mut T, start_hex: Hex, inside_fn: impl Fn(&T,
 
 }
 
+/// Counts the hexes a flood fill from the start hex would set, without setting them, returns None as soon as there are more than the limit.
+pub fn count_flood_fill_hexes(start_hex: Hex, inside_fn: impl Fn(Hex) -> bool, limit: i32) -> Option<i32> {
+
+    let mut counted_hexes = 0;
+    let mut visited: HashSet<Hex> = HashSet::new();
+    let mut queue: VecDeque<Hex> = VecDeque::new();
+    queue.push_back(start_hex);
+
+    while let Some(n) = queue.pop_front() {
+
+        if visited.insert(n) == false || inside_fn(n) == false {
+            continue;
+        }
+
+        counted_hexes += 1;
+        if counted_hexes > limit {
+            return None;
+        }
+
+        for n in n.all_neighbors() {
+            queue.push_back(n);
+        }
+
+    }
+
+    Some(counted_hexes)
+
+}
+
 /// Returns the boundary that is formed by walking around in a clockwise fashion, if there is a boundary.
 pub fn trace_hex_boundary(start_hand_hex: Hex, in_boundary_fn: impl Fn(Hex) -> bool) -> Option<HexBoundary> {
 
@@ -0,0 +1,219 @@
+use hexx::Hex;
+use hoxx_shared::{history::{HistoryAction, HistoryEvent}, GameState};
+use raster::HexRaster;
+
+mod raster;
+
+const DEFAULT_HISTORY_LOG_PATH: &str = "save/history.log";
+const DEFAULT_OUTPUT_DIRECTORY: &str = "timelapse";
+const DEFAULT_FRAME_INTERVAL_SECONDS: u64 = 60 * 60;
+const DEFAULT_SCALE: f32 = 0.25;
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+struct TimelapseArguments {
+    history_path: String,
+    output_directory: String,
+    /// Time in the log between two frames of the image sequence.
+    frame_interval_seconds: u64,
+    /// If set, only a single image of the map as it was at this time is exported, in seconds since the unix epoch.
+    snapshot_time_seconds: Option<u64>,
+    /// Pixels per world unit, a hex is HEX_SIZE * 2 world units across.
+    scale: f32
+}
+
+impl TimelapseArguments {
+
+    /// Parses ```[history.log] [--out DIR] [--interval S] [--at UNIX_SECONDS] [--days-ago N] [--scale N]```.
+    fn from_args<I>(args: I) -> Result<TimelapseArguments, String>
+        where I: Iterator<Item = String>
+    {
+
+        let mut arguments = TimelapseArguments {
+            history_path: DEFAULT_HISTORY_LOG_PATH.to_string(),
+            output_directory: DEFAULT_OUTPUT_DIRECTORY.to_string(),
+            frame_interval_seconds: DEFAULT_FRAME_INTERVAL_SECONDS,
+            snapshot_time_seconds: None,
+            scale: DEFAULT_SCALE
+        };
+
+        let args: Vec<String> = args.collect();
+        let mut args_iterator = args.iter().skip(1);
+
+        while let Some(arg) = args_iterator.next() {
+
+            let mut next_value = || args_iterator.next().cloned().ok_or_else(|| format!("missing value for: {}", arg));
+            let parse_error = |value: &str| format!("invalid value for: {}, got: {}", arg, value);
+
+            match arg.as_str() {
+                "--out" => arguments.output_directory = next_value()?,
+                "--interval" => { let v = next_value()?; arguments.frame_interval_seconds = v.parse().map_err(|_| parse_error(&v))?; },
+                "--at" => { let v = next_value()?; arguments.snapshot_time_seconds = Some(v.parse().map_err(|_| parse_error(&v))?); },
+                "--days-ago" => {
+                    let v = next_value()?;
+                    let days_ago: u64 = v.parse().map_err(|_| parse_error(&v))?;
+                    arguments.snapshot_time_seconds = Some(unix_time_seconds().saturating_sub(days_ago * SECONDS_PER_DAY));
+                },
+                "--scale" => { let v = next_value()?; arguments.scale = v.parse().map_err(|_| parse_error(&v))?; },
+                other if other.starts_with("--") => return Err(format!("unknown argument: {}", other)),
+                path => arguments.history_path = path.to_string()
+            }
+
+        }
+
+        if arguments.frame_interval_seconds == 0 {
+            return Err("frame interval must be above zero".to_string());
+        }
+
+        if arguments.scale <= 0.0 {
+            return Err("scale must be above zero".to_string());
+        }
+
+        Ok(arguments)
+
+    }
+
+}
+
+fn unix_time_seconds() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Plays the events of a history log back into a game state, in order.
+struct HistoryReplay {
+    events: Vec<HistoryEvent>,
+    next_event_index: usize,
+    state: GameState
+}
+
+impl HistoryReplay {
+
+    fn new(events: Vec<HistoryEvent>) -> HistoryReplay {
+        HistoryReplay {
+            events,
+            next_event_index: 0,
+            state: GameState::new()
+        }
+    }
+
+    fn first_event_time(&self) -> Option<u64> {
+        self.events.first().map(|e| e.time)
+    }
+
+    fn last_event_time(&self) -> Option<u64> {
+        self.events.last().map(|e| e.time)
+    }
+
+    /// Every hex the log ever touches, so all frames can cover the same area.
+    fn all_hexes(&self) -> Vec<Hex> {
+        self.events.iter().flat_map(|event| match &event.action {
+            HistoryAction::Initial { hexes } => hexes.iter().map(|h| Hex::new(h.x, h.y)).collect(),
+            HistoryAction::Colour { .. } => Vec::new(),
+            HistoryAction::Claim { x, y } => vec![Hex::new(*x, *y)],
+            HistoryAction::Fill { hexes } => hexes.iter().map(|&(x, y)| Hex::new(x, y)).collect()
+        }).collect()
+    }
+
+    fn apply_event(state: &mut GameState, event: &HistoryEvent) {
+        match &event.action {
+            HistoryAction::Initial { hexes } => {
+                for hex_claim in hexes {
+                    state.set_hex(hex_claim.x, hex_claim.y, hex_claim.owner);
+                }
+            },
+            HistoryAction::Colour { colour } => state.set_client_colour(event.owner, *colour),
+            HistoryAction::Claim { x, y } => state.set_hex(*x, *y, event.owner.id),
+            HistoryAction::Fill { hexes } => {
+                for &(x, y) in hexes {
+                    state.set_hex(x, y, event.owner.id);
+                }
+            }
+        }
+    }
+
+    /// Applies every event up to and including the given time, in milliseconds since the unix epoch.
+    fn advance_to(&mut self, time: u64) {
+        while let Some(event) = self.events.get(self.next_event_index) {
+            if event.time > time {
+                break;
+            }
+            Self::apply_event(&mut self.state, event);
+            self.next_event_index += 1;
+        }
+    }
+
+}
+
+fn export_frame(raster: &HexRaster, state: &GameState, path: &str) {
+    match raster.export_png(state, path) {
+        Ok(()) => println!("[hoxx-timelapse] exported: {}", path),
+        Err(error) => println!("[hoxx-timelapse] failed to export: {} with error: {}", path, error)
+    }
+}
+
+fn main() {
+
+    let arguments = match TimelapseArguments::from_args(std::env::args()) {
+        Ok(arguments) => arguments,
+        Err(error) => {
+            println!("[hoxx-timelapse] {}", error);
+            println!("usage: hoxx-timelapse [history.log] [--out DIR] [--interval S] [--at UNIX_SECONDS] [--days-ago N] [--scale N]");
+            return;
+        }
+    };
+
+    let history_log = match std::fs::read_to_string(&arguments.history_path) {
+        Ok(history_log) => history_log,
+        Err(error) => {
+            println!("[hoxx-timelapse] failed to read history log at: {} with error: {}", arguments.history_path, error);
+            return;
+        }
+    };
+
+    let mut replay = HistoryReplay::new(HistoryEvent::parse_log(&history_log));
+    let (Some(first_event_time), Some(last_event_time)) = (replay.first_event_time(), replay.last_event_time()) else {
+        println!("[hoxx-timelapse] history log at: {} has no events, nothing to export", arguments.history_path);
+        return;
+    };
+
+    let Some(raster) = HexRaster::new(&replay.state, &replay.all_hexes(), arguments.scale) else {
+        println!("[hoxx-timelapse] nothing was ever claimed in the history log, nothing to export");
+        return;
+    };
+
+    if let Err(error) = std::fs::create_dir_all(&arguments.output_directory) {
+        println!("[hoxx-timelapse] failed to create output directory: {} with error: {}", arguments.output_directory, error);
+        return;
+    }
+
+    println!("[hoxx-timelapse] replaying {} events into {}x{} images", replay.events.len(), raster.width(), raster.height());
+
+    if let Some(snapshot_time_seconds) = arguments.snapshot_time_seconds {
+        replay.advance_to(snapshot_time_seconds * 1000);
+        export_frame(&raster, &replay.state, &format!("{}/snapshot_{}.png", arguments.output_directory, snapshot_time_seconds));
+        return;
+    }
+
+    let frame_interval = arguments.frame_interval_seconds * 1000;
+    let mut frame_time = first_event_time;
+    let mut frame_index = 0;
+
+    loop {
+
+        replay.advance_to(frame_time);
+        export_frame(&raster, &replay.state, &format!("{}/frame_{:05}.png", arguments.output_directory, frame_index));
+
+        // the last frame always shows the map with every event applied
+        if frame_time >= last_event_time {
+            break;
+        }
+
+        frame_time = (frame_time + frame_interval).min(last_event_time);
+        frame_index += 1;
+
+    }
+
+}
@@ -0,0 +1,103 @@
+use std::{fs::File, io::BufWriter};
+
+use hexx::Hex;
+use hoxx_shared::{ClientColor, ClientID, GameState, HEX_SIZE};
+
+/// Neither side of an exported image gets larger than this, the scale is lowered to fit instead.
+const MAX_IMAGE_SIZE: f32 = 4096.0;
+
+const BACKGROUND_COLOUR: [u8; 3] = [255, 255, 255];
+
+/// Used for hexes whose owner never had a colour written to the log.
+const UNKNOWN_OWNER_COLOUR: [u8; 3] = [160, 160, 160];
+
+/// Maps each pixel of the exported images to the hex under it, worked out once as every frame covers the same area.
+pub struct HexRaster {
+    width: u32,
+    height: u32,
+    pixel_hexes: Vec<Hex>
+}
+
+impl HexRaster {
+
+    /// Covers every given hex, with scale in pixels per world unit.
+    pub fn new(state: &GameState, hexes: &[Hex], scale: f32) -> Option<HexRaster> {
+
+        let world_positions: Vec<_> = hexes.iter().map(|h| state.hex_to_world(h.x, h.y)).collect();
+        let margin = HEX_SIZE * 2.0;
+
+        let min_x = world_positions.iter().map(|p| p.x).reduce(f32::min)? - margin;
+        let min_y = world_positions.iter().map(|p| p.y).reduce(f32::min)? - margin;
+        let max_x = world_positions.iter().map(|p| p.x).reduce(f32::max)? + margin;
+        let max_y = world_positions.iter().map(|p| p.y).reduce(f32::max)? + margin;
+
+        let largest_side = (max_x - min_x).max(max_y - min_y);
+        let scale = if largest_side * scale > MAX_IMAGE_SIZE {
+            let fitted_scale = MAX_IMAGE_SIZE / largest_side;
+            println!("[hoxx-timelapse] map too large for scale: {}, using: {} instead", scale, fitted_scale);
+            fitted_scale
+        } else {
+            scale
+        };
+
+        let width = ((max_x - min_x) * scale).ceil().max(1.0) as u32;
+        let height = ((max_y - min_y) * scale).ceil().max(1.0) as u32;
+
+        let mut pixel_hexes = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let world_x = min_x + (x as f32 + 0.5) / scale;
+                let world_y = min_y + (y as f32 + 0.5) / scale;
+                pixel_hexes.push(state.world_to_hex(world_x as i32, world_y as i32));
+            }
+        }
+
+        Some(HexRaster { width, height, pixel_hexes })
+
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn to_rgb(colour: ClientColor) -> [u8; 3] {
+        [
+            (colour.r.clamp(0.0, 1.0) * 255.0) as u8,
+            (colour.g.clamp(0.0, 1.0) * 255.0) as u8,
+            (colour.b.clamp(0.0, 1.0) * 255.0) as u8
+        ]
+    }
+
+    fn rasterize(&self, state: &GameState) -> Vec<u8> {
+
+        let mut image_data = Vec::with_capacity(self.pixel_hexes.len() * 3);
+
+        for hex in &self.pixel_hexes {
+            let pixel_colour = match state.get_hex(hex.x, hex.y) {
+                Some(owner) => state.get_client_colour(ClientID { id: owner }).map(Self::to_rgb).unwrap_or(UNKNOWN_OWNER_COLOUR),
+                None => BACKGROUND_COLOUR
+            };
+            image_data.extend_from_slice(&pixel_colour);
+        }
+
+        image_data
+
+    }
+
+    pub fn export_png(&self, state: &GameState, path: &str) -> Result<(), String> {
+
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_image_data(&self.rasterize(state)).map_err(|e| e.to_string())
+
+    }
+
+}
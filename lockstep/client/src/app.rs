@@ -355,12 +355,15 @@ impl<GameType> ApplicationState<GameType> where GameType: Game {
             if self.mode == ApplicationMode::Singleplayer {
                 lockstep.handle_generic_messages_with(
                     |peer_id, msg| self.game.handle_generic_message(peer_id, msg),
-                    |_, _| ()
+                    |_, _, _| ()
                 );
             } else if self.mode == ApplicationMode::Multiplayer {
                 lockstep.handle_generic_messages_with(
                     |peer_id, msg| self.game.handle_generic_message(peer_id, msg),
-                    |peer_id, msg| self.net.send_text(RelayMessage::Message(peer_id, msg).serialize_json())
+                    |peer_id, target_peer_id, msg| match target_peer_id {
+                        Some(target_peer_id) => self.net.send_text(RelayMessage::DirectMessage(target_peer_id, msg).serialize_json()),
+                        None => self.net.send_text(RelayMessage::Message(peer_id, msg).serialize_json())
+                    }
                 );
             }
 
@@ -348,6 +348,7 @@ impl RelayClient {
             RelayMessage::PushLobbyData(_) => (),
             RelayMessage::QueryActiveLobbies => (),
             RelayMessage::QueryActivePlayers => (),
+            RelayMessage::DirectMessage(_, _) => (),
             RelayMessage::Register(_) => (),
             RelayMessage::CreateLobby(_) => (),
             RelayMessage::StartLobby => (),
@@ -152,7 +152,8 @@ pub struct LockstepClient {
     turn_delay: i32, // how many turns out should the message be sent by?
 
     generic_command_queue: VecDeque<(PeerID, GenericCommand)>,
-    generic_commands_to_send: Vec<(PeerID, GenericCommand)>
+    /// Commands to send along with the peer it is meant for, or None when it is meant for everyone.
+    generic_commands_to_send: Vec<(Option<PeerID>, GenericCommand)>
 
 }
 
@@ -330,13 +331,29 @@ impl LockstepClient {
         self.generic_command_queue.push_back((self.peer_id, generic_command.clone()));
 
         // multicast to everyone else who is connected
-        self.generic_commands_to_send.push((self.peer_id, generic_command.clone()));
+        self.generic_commands_to_send.push((None, generic_command.clone()));
 
     }
 
     pub fn send_generic_message_to_peer(&mut self, peer_id: PeerID, generic_command_message: &str) {
         let generic_command = GenericCommand::Message(generic_command_message.to_string());
-        self.generic_commands_to_send.push((peer_id, generic_command));
+        self.generic_commands_to_send.push((Some(peer_id), generic_command));
+    }
+
+    /// Sends the message to ourselves and only the given peers, nobody else in the lobby gets to see it.
+    pub fn send_generic_message_to_peers(&mut self, peer_ids: &[PeerID], generic_command_message: &str) {
+
+        let generic_command = GenericCommand::Message(generic_command_message.to_string());
+
+        // send to self
+        self.generic_command_queue.push_back((self.peer_id, generic_command.clone()));
+
+        for &peer_id in peer_ids {
+            if peer_id != self.peer_id {
+                self.generic_commands_to_send.push((Some(peer_id), generic_command.clone()));
+            }
+        }
+
     }
 
     pub fn handle_message(&mut self, peer_id: PeerID, turn_command: TurnCommand) {
@@ -378,7 +395,7 @@ impl LockstepClient {
     pub fn handle_generic_messages_with<F1, F2>(&mut self, mut handle_generic_command_fn: F1, mut send_generic_command_fn: F2)
         where
             F1: FnMut(PeerID, &str),
-            F2: FnMut(PeerID, String)
+            F2: FnMut(PeerID, Option<PeerID>, String)
     {
 
         while self.generic_command_queue.is_empty() == false {
@@ -389,9 +406,9 @@ impl LockstepClient {
 
         }
 
-        for (peer_id, generic_command) in &self.generic_commands_to_send {
+        for (target_peer_id, generic_command) in &self.generic_commands_to_send {
             let application_command = ApplicationCommand::GenericCommand(generic_command.clone());
-            send_generic_command_fn(*peer_id, application_command.serialize_json());
+            send_generic_command_fn(self.peer_id, *target_peer_id, application_command.serialize_json());
         }
 
         self.generic_commands_to_send.clear();
@@ -156,6 +156,7 @@ impl RelayServer {
 
                 // messages for passing game data, external to the relay server (to be forwarded to all in the same lobby)
                 RelayMessage::Message(peer_id, text) => { self.send_message_to_clients_lobby(sender_id, RelayMessage::Message(peer_id, text)); },
+                RelayMessage::DirectMessage(to_client_id, text) => { self.send_direct_message(sender_id, to_client_id, text); },
 
                 // messages for querying relay server/lobby state
                 RelayMessage::QueryActiveLobbies => { self.query_active_lobbies(sender_id); },
@@ -505,6 +506,18 @@ impl RelayServer {
         }
     }
 
+    /// Only passes the message on if both clients are in the same lobby, so nobody can message players in other lobbies.
+    pub fn send_direct_message(&self, from_client_id: LobbyClientID, to_client_id: LobbyClientID, text: String) {
+
+        let from_lobby_id = self.get_client_lobby(from_client_id);
+        if from_lobby_id.is_none() || from_lobby_id != self.get_client_lobby(to_client_id) {
+            return;
+        }
+
+        self.send_message_to_client(to_client_id, RelayMessage::Message(from_client_id, text));
+
+    }
+
     pub fn send_update_data_to_clients_lobby(&mut self, client_id: LobbyClientID, data: String) {
         if let Some(lobby_id) = self.get_client_lobby(client_id) {      
             self.send_update_data_to_lobby(lobby_id, data);
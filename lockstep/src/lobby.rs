@@ -65,6 +65,9 @@ pub enum RelayMessage {
     /// Represents a payload that should be sent through the current active lobby to all other players in the lobby.
     Message(LobbyClientID, String),
 
+    /// Represents a payload that should only be sent to a specific client in the same lobby, which receives it as a regular message from the sender.
+    DirectMessage(LobbyClientID, String),
+
     /// Represents a message notifying that a specific client has become de boss
     Boss(LobbyClientID),
 
@@ -1,25 +1,83 @@
 use lockstep_client::step::LockstepClient;
 use nanoserde::{DeJson, SerJson};
 
+use crate::PlayerID;
+
+/// Who a chat message is meant for, team messages are only shown to allies of the sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SerJson, DeJson)]
+pub enum ChatChannel {
+    All,
+    Team
+}
+
+impl ChatChannel {
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChatChannel::All => "all",
+            ChatChannel::Team => "team"
+        }
+    }
+
+}
 
 #[derive(Debug, SerJson, DeJson)]
 pub enum GameCommand {
-    Message { text: String },
+    Message { text: String, channel: ChatChannel },
+    Ping { x: f32, y: f32 },
+    Marker { start_x: f32, start_y: f32, end_x: f32, end_y: f32 },
     JoinTeam { team_id: i32 },
     LeaveTeam
 }
 
+impl GameCommand {
+
+    /// Pings and markers are always meant for allies only, nobody wants to tell the enemy where the attack is going.
+    pub fn is_team_only(&self) -> bool {
+        match self {
+            GameCommand::Message { channel, .. } => *channel == ChatChannel::Team,
+            GameCommand::Ping { .. } => true,
+            GameCommand::Marker { .. } => true,
+            GameCommand::JoinTeam { .. } => false,
+            GameCommand::LeaveTeam => false
+        }
+    }
+
+}
+
+/// Commands which are team only are passed the allies of the sender, only they are sent the command.
 pub trait CommandsExt {
-    fn send_chat_message(&mut self, message: String);
+    fn send_game_command(&mut self, game_command: GameCommand, allies: &[PlayerID]);
+    fn send_chat_message(&mut self, message: String, channel: ChatChannel, allies: &[PlayerID]);
+    fn send_ping_message(&mut self, x: f32, y: f32, allies: &[PlayerID]);
+    fn send_marker_message(&mut self, start_x: f32, start_y: f32, end_x: f32, end_y: f32, allies: &[PlayerID]);
     fn send_join_team_message(&mut self, team_id: i32);
     fn send_leave_team_message(&mut self);
 }
 
 impl CommandsExt for LockstepClient {
 
-    fn send_chat_message(&mut self, message: String) {
-        let game_command = GameCommand::Message { text: message };
-        self.send_generic_message(&game_command.serialize_json());
+    fn send_game_command(&mut self, game_command: GameCommand, allies: &[PlayerID]) {
+        if game_command.is_team_only() {
+            self.send_generic_message_to_peers(allies, &game_command.serialize_json());
+        } else {
+            self.send_generic_message(&game_command.serialize_json());
+        }
+    }
+
+    fn send_chat_message(&mut self, message: String, channel: ChatChannel, allies: &[PlayerID]) {
+        let game_command = GameCommand::Message { text: message, channel };
+        self.send_game_command(game_command, allies);
+    }
+
+    fn send_ping_message(&mut self, x: f32, y: f32, allies: &[PlayerID]) {
+        let ping_message = GameCommand::Ping { x, y };
+        self.send_game_command(ping_message, allies);
+    }
+
+    fn send_marker_message(&mut self, start_x: f32, start_y: f32, end_x: f32, end_y: f32, allies: &[PlayerID]) {
+        let marker_message = GameCommand::Marker { start_x, start_y, end_x, end_y };
+        self.send_game_command(marker_message, allies);
     }
 
     fn send_join_team_message(&mut self, team_id: i32) {
@@ -32,4 +90,4 @@ impl CommandsExt for LockstepClient {
         self.send_generic_message(&leave_team_message.serialize_json());
     }
     
-}
\ No newline at end of file
+}
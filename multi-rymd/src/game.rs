@@ -1,15 +1,18 @@
 use lockstep_client::game::{GameContext, GameLobbyContext};
 use lockstep_client::{game::Game, step::LockstepClient};
 use lockstep_client::step::PeerID;
+use macroquad::math::{vec2, Vec2};
 use nanoserde::{DeJson, SerJson};
 use puffin_egui::egui;
 use utility::{DebugText, TextPosition};
 
-use crate::commands::{CommandsExt, GameCommand};
+use crate::commands::{ChatChannel, CommandsExt, GameCommand};
 use crate::gamemodes::chickens::RymdGameModeChickens;
 use crate::gamemodes::conquest::RymdGameModeConquest;
 use crate::gamemodes::gamemode::{RymdGameMode, RymdGameModeResult};
-use crate::lobby::LobbyGameState;
+use crate::gamemodes::koth::RymdGameModeKingOfTheHill;
+use crate::gamemodes::mission::RymdGameModeMission;
+use crate::lobby::{LobbyGameState, LobbySettings};
 use crate::PlayerID;
 use crate::measure_scope;
 use crate::model::{GameMessage, RymdGameModel};
@@ -37,6 +40,11 @@ pub struct RymdGame {
     chat: RymdGameChat,
     model: RymdGameModel,
     view: RymdGameView,
+    local_player_id: Option<PlayerID>,
+    /// Remembered from the lobby, the host gets to decide the speed of the game without a vote.
+    lobby_boss_id: Option<PlayerID>,
+    /// Set once the game mode has ended the game, so that nothing more is simulated while waiting for the lobby to stop.
+    has_game_ended: bool,
     is_started: bool,
     is_running: bool,
     is_paused: bool
@@ -64,7 +72,8 @@ impl RymdGameTeam {
 pub struct RymdGameSetup {
     game_modes: Vec<Box<dyn RymdGameMode>>,
     game_mode: Option<Box<dyn RymdGameMode>>,
-    selected_game_mode: String
+    selected_game_mode: String,
+    settings: LobbySettings
 }
 
 impl RymdGameSetup {
@@ -72,7 +81,8 @@ impl RymdGameSetup {
         RymdGameSetup {
             game_modes: Vec::new(),
             game_mode: None,
-            selected_game_mode: String::new()
+            selected_game_mode: String::new(),
+            settings: LobbySettings::new()
         }
     }
 
@@ -101,35 +111,88 @@ impl RymdGameSetup {
     }
 }
 
+pub struct RymdGameChatEntry {
+    pub sender: Option<PeerID>,
+    pub channel: ChatChannel,
+    pub text: String,
+    pub position: Option<Vec2>
+}
+
+impl RymdGameChatEntry {
+
+    pub fn to_display_text(&self) -> String {
+
+        let Some(sender) = self.sender else {
+            return self.text.clone();
+        };
+
+        if self.channel == ChatChannel::Team {
+            format!("[team] [peer {}] {}", sender, self.text)
+        } else {
+            format!("[peer {}] {}", sender, self.text)
+        }
+
+    }
+
+}
+
 pub struct RymdGameChat {
-    pub current_messsage_buffer: String,
+    pub entries: Vec<RymdGameChatEntry>,
     pub current_message: String,
+    pub current_channel: ChatChannel
 }
 
 impl RymdGameChat {
     pub fn new() -> RymdGameChat {
         RymdGameChat {
-            current_messsage_buffer: String::new(),
-            current_message: String::new()
+            entries: Vec::new(),
+            current_message: String::new(),
+            current_channel: ChatChannel::All
         }
     }
 
-    pub fn on_game_command(&mut self, game_command: &GameCommand) {
-        let GameCommand::Message { text } = game_command else { return };
-        self.current_messsage_buffer += text;
+    /// Adds whatever the command should show in the chat log, the caller is expected to have filtered out commands not meant for the local player.
+    pub fn on_game_command(&mut self, peer_id: PeerID, game_command: &GameCommand) {
+
+        let entry = match game_command {
+            GameCommand::Message { text, channel } => RymdGameChatEntry { sender: Some(peer_id), channel: *channel, text: text.clone(), position: None },
+            GameCommand::Ping { x, y } => RymdGameChatEntry { sender: Some(peer_id), channel: ChatChannel::Team, text: "pinged the map".to_string(), position: Some(vec2(*x, *y)) },
+            GameCommand::Marker { end_x, end_y, .. } => RymdGameChatEntry { sender: Some(peer_id), channel: ChatChannel::Team, text: "placed a marker".to_string(), position: Some(vec2(*end_x, *end_y)) },
+            GameCommand::JoinTeam { .. } | GameCommand::LeaveTeam => return
+        };
+
+        self.entries.push(entry);
+
+    }
+
+    fn add_system_message(&mut self, text: String) {
+        self.entries.push(RymdGameChatEntry { sender: None, channel: ChatChannel::All, text, position: None });
     }
 
     pub fn on_client_joined_lobby(&mut self, peer_id: PeerID) {
-        self.current_messsage_buffer += &format!("[peer {}] joined!\n", peer_id);
+        self.add_system_message(format!("[peer {}] joined!", peer_id));
     }
 
     pub fn on_client_left_lobby(&mut self, peer_id: PeerID) {
-        self.current_messsage_buffer += &format!("[peer {}] left!\n", peer_id);
+        self.add_system_message(format!("[peer {}] left!", peer_id));
+    }
+
+    /// Sends the message currently being typed on the currently selected channel, if there is anything to send.
+    pub fn send_current_message(&mut self, lockstep: &mut LockstepClient, allies: &[PlayerID]) {
+
+        if self.current_message.trim().is_empty() {
+            return;
+        }
+
+        lockstep.send_chat_message(self.current_message.trim().to_string(), self.current_channel, allies);
+        self.current_message.clear();
+
     }
 
     pub fn reset(&mut self) {
-        self.current_messsage_buffer.clear();
+        self.entries.clear();
         self.current_message.clear();
+        self.current_channel = ChatChannel::All;
     }
 }
 
@@ -174,12 +237,23 @@ impl Game for RymdGame {
                 game_mode.on_start(&mut self.model, &game_parameters);
             }
 
+            // the ai in singleplayer doesn't get a say in how fast the game goes, or the speed could never change
+            let (game_speed_voters, game_speed_host) = if lockstep.is_singleplayer() {
+                (vec![lockstep.peer_id()], Some(lockstep.peer_id()))
+            } else {
+                (game_parameters.players.iter().map(|player| player.id).collect(), self.lobby_boss_id)
+            };
+
+            self.model.are_cheats_enabled = self.setup.settings.are_cheats_enabled;
             self.model.start(game_parameters.clone());
+            self.model.speed.start(game_speed_voters, game_speed_host);
             self.view.start(game_parameters.clone(), lockstep.peer_id());
+            self.local_player_id = Some(lockstep.peer_id());
             
             self.is_running = true;
             self.is_started = true;
             self.is_paused = false;
+            self.has_game_ended = false;
 
         }
 
@@ -197,6 +271,8 @@ impl Game for RymdGame {
         self.is_running = false;
         self.is_started = false;
         self.is_paused = false;
+        self.local_player_id = None;
+        self.has_game_ended = false;
         self.model = RymdGameModel::new();
     }
 
@@ -205,7 +281,10 @@ impl Game for RymdGame {
         match GameCommand::deserialize_json(message) {
             Ok(ref game_command) => {
 
-                self.chat.on_game_command(game_command);
+                if self.is_game_command_visible(peer_id, game_command) {
+                    self.chat.on_game_command(peer_id, game_command);
+                    self.view.on_game_command(peer_id, game_command);
+                }
 
                 if let Some(game_mode) = &mut self.setup.game_mode {
                     game_mode.on_lobby_command(peer_id, game_command);
@@ -221,8 +300,10 @@ impl Game for RymdGame {
 
     fn handle_lobby_tick(&mut self, ctx: &mut GameLobbyContext) {
 
+        self.lobby_boss_id = ctx.get_lobby_boss_id();
+
         if let Some(game_mode) = &mut self.setup.game_mode {
-            game_mode.handle_lobby_tick(ctx);
+            game_mode.handle_lobby_tick(ctx, &self.setup.settings);
         }
 
     }
@@ -243,22 +324,35 @@ impl Game for RymdGame {
     #[profiling::function]
     fn update(&mut self, ctx: &mut GameContext) {
 
-        if self.is_started == false {
+        if self.is_started == false || self.has_game_ended {
             return;
         }
 
         measure_scope!(self.stats.update_time_ms);
-        self.model.tick();
 
-        if let Some(game_mode) = &mut self.setup.game_mode {
-            let game_mode_result = game_mode.tick(&mut self.model);
-            if game_mode_result == RymdGameModeResult::End {
-                // we do something specific when requesting to end the game, ... return to lobby probably?
-                ctx.stop_lobby();
+        // every client runs the same number of updates per lockstep turn, so the game speed decides how many ticks each of those runs
+        let number_of_ticks = self.model.speed.ticks_for_update();
+
+        for _ in 0..number_of_ticks {
+
+            self.model.tick();
+
+            if let Some(game_mode) = &mut self.setup.game_mode {
+                let game_mode_result = game_mode.tick(&mut self.model);
+                if game_mode_result == RymdGameModeResult::End {
+                    // we do something specific when requesting to end the game, ... return to lobby probably?
+                    self.has_game_ended = true;
+                    ctx.stop_lobby();
+                }
             }
+
+            self.view.update(&mut self.model);
+
+            if self.has_game_ended {
+                break;
+            }
+
         }
-        
-        self.view.update(&mut self.model);
 
     }
 
@@ -288,7 +382,7 @@ impl Game for RymdGame {
             return;
         }
 
-        self.view.draw_ui(ui_ctx, &mut self.model, ctx);
+        self.view.draw_ui(ui_ctx, &mut self.model, &mut self.chat, ctx);
         
         if crate::INGAME_PROFILER_ENABLED {
             puffin_egui::profiler_window(ui_ctx);
@@ -335,18 +429,31 @@ impl Game for RymdGame {
 
         }
 
+        ui.separator();
+
+        // in singleplayer there is nobody to cheat against, so it's always up to the player
+        let can_change_settings = ctx.is_player_boss() || ctx.lockstep().is_singleplayer();
+        let cheats_checkbox = egui::Checkbox::new(&mut self.setup.settings.are_cheats_enabled, "cheats enabled (console, spawning, resources)");
+
+        if ui.add_enabled(can_change_settings, cheats_checkbox).changed() && let Some(game_mode) = &mut self.setup.game_mode {
+            game_mode.force_lobby_update(ctx);
+        }
+
         if ctx.lockstep_mut().is_singleplayer() == false {
 
             ui.separator();
             ui.label("chat");
 
-            ui.label(&self.chat.current_messsage_buffer);
+            for entry in &self.chat.entries {
+                ui.label(entry.to_display_text());
+            }
+
+            // teams are only known once the game has started, so everything in the lobby goes to everyone
+            self.chat.current_channel = ChatChannel::All;
             ui.text_edit_singleline(&mut self.chat.current_message);
     
-            if ui.button("send message").clicked() && self.chat.current_message.is_empty() == false {
-                let chat_message_to_send = format!("[peer {}] {}\n", ctx.lockstep_mut().peer_id(), self.chat.current_message);
-                ctx.lockstep_mut().send_chat_message(chat_message_to_send.to_string());
-                self.chat.current_message.clear();
+            if ui.button("send message").clicked() {
+                self.chat.send_current_message(ctx.lockstep_mut(), &[]);
             }
 
         }
@@ -362,8 +469,9 @@ impl Game for RymdGame {
     }
 
     fn on_enter_lobby(&mut self) {
-        self.setup.game_modes = vec![Box::new(RymdGameModeConquest::new()), Box::new(RymdGameModeChickens::new())];
+        self.setup.game_modes = vec![Box::new(RymdGameModeConquest::new()), Box::new(RymdGameModeKingOfTheHill::new()), Box::new(RymdGameModeChickens::new()), Box::new(RymdGameModeMission::new())];
         self.setup.game_mode = None;
+        self.setup.settings = LobbySettings::new();
         self.chat.reset();
     }
 
@@ -376,6 +484,7 @@ impl Game for RymdGame {
         if let Ok(lobby_game_state) = LobbyGameState::deserialize_json(&new_lobby_data) {
 
             self.setup.selected_game_mode = lobby_game_state.game_mode_name;
+            self.setup.settings = lobby_game_state.settings;
 
             if let Some(game_mode) = &mut self.setup.game_mode && self.is_started == false {
                 game_mode.on_lobby_update(lobby_game_state.game_mode_state);
@@ -415,12 +524,27 @@ impl RymdGame {
             stats: RymdGameFrameStats::new(),
             model: RymdGameModel::new(),
             view: RymdGameView::new(),
+            local_player_id: None,
+            lobby_boss_id: None,
+            has_game_ended: false,
             is_running: false,
             is_started: false,
             is_paused: false
         }
     }
 
+    /// Team messages, pings and markers only reach allies of the sender, which is only known once the game has started.
+    fn is_game_command_visible(&self, peer_id: PeerID, game_command: &GameCommand) -> bool {
+
+        if game_command.is_team_only() == false {
+            return true;
+        }
+
+        let Some(local_player_id) = self.local_player_id else { return false; };
+        peer_id == local_player_id || self.model.is_player_friendly_to(local_player_id, peer_id)
+
+    }
+
     fn draw_frame_stats(&self, debug: &mut DebugText) {
         let fps = 1000.0 / self.stats.main_time_ms;
         debug.draw_text("game update", TextPosition::TopRight, macroquad::color::WHITE);
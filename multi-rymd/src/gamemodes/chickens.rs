@@ -2,7 +2,7 @@ use lockstep_client::game::GameLobbyContext;
 use nanoserde::{DeJson, SerJson};
 use puffin_egui::egui;
 
-use crate::{commands::GameCommand, game::RymdGameParameters, lobby::LobbyGameState, model::{set_player_team_allegiance, RymdGameModel}, utils::helpers::{create_asteroid_clumps, create_player_commander_ships, create_players, is_commander_dead_for_player}, PlayerID};
+use crate::{commands::GameCommand, game::RymdGameParameters, lobby::{LobbyGameState, LobbySettings}, model::{set_player_team_allegiance, RymdGameModel}, utils::helpers::{create_asteroid_clumps, create_player_commander_ships, create_players, is_commander_dead_for_player}, PlayerID};
 
 use super::gamemode::{RymdGameMode, RymdGameModeResult};
 
@@ -126,7 +126,7 @@ impl RymdGameMode for RymdGameModeChickens {
         
     }
     
-    fn handle_lobby_tick(&mut self, ctx: &mut GameLobbyContext) {
+    fn handle_lobby_tick(&mut self, ctx: &mut GameLobbyContext, settings: &LobbySettings) {
 
         if ctx.is_player_boss() && self.data.changed {
             
@@ -135,7 +135,8 @@ impl RymdGameMode for RymdGameModeChickens {
             let chicken_lobby_data = self.data.serialize_json();
             let lobby_game_state = LobbyGameState {
                 game_mode_name: self.name().to_owned(),
-                game_mode_state: chicken_lobby_data
+                game_mode_state: chicken_lobby_data,
+                settings: settings.clone()
             };
             
             ctx.push_new_lobby_data(lobby_game_state.serialize_json());
@@ -2,7 +2,7 @@ use lockstep_client::game::GameLobbyContext;
 use nanoserde::{DeJson, SerJson};
 use puffin_egui::egui;
 
-use crate::{commands::{CommandsExt, GameCommand}, game::{RymdGameParameters, RymdGameTeam}, lobby::LobbyGameState, model::{set_default_energy_pool_size, set_default_metal_pool_size, set_player_team_allegiance, RymdGameModel}, utils::helpers::{create_asteroid_clumps, create_player_commander_ships, create_players, destroy_all_units_controlled_by_team, is_any_commander_still_alive_in_team}, PlayerID};
+use crate::{commands::{CommandsExt, GameCommand}, game::{RymdGameParameters, RymdGameTeam}, lobby::{LobbyGameState, LobbySettings}, model::{set_default_energy_pool_size, set_default_metal_pool_size, set_player_team_allegiance, RymdGameModel}, utils::helpers::{create_asteroid_clumps, create_player_commander_ships, create_players, destroy_all_units_controlled_by_team, is_any_commander_still_alive_in_team}, PlayerID};
 
 use super::gamemode::{RymdGameMode, RymdGameModeResult};
 
@@ -193,7 +193,9 @@ impl RymdGameMode for RymdGameModeConquest {
     fn on_lobby_command(&mut self, client_id: PlayerID, game_command: &GameCommand) {
         
         match game_command {
-            GameCommand::Message { text } => (),
+            GameCommand::Message { .. } => (),
+            GameCommand::Ping { .. } => (),
+            GameCommand::Marker { .. } => (),
             GameCommand::JoinTeam { team_id } => {
                 self.data.move_player_to_team(client_id, *team_id);
                 self.data.changed = true;
@@ -206,7 +208,7 @@ impl RymdGameMode for RymdGameModeConquest {
 
     }
     
-    fn handle_lobby_tick(&mut self, ctx: &mut GameLobbyContext) {
+    fn handle_lobby_tick(&mut self, ctx: &mut GameLobbyContext, settings: &LobbySettings) {
         
         if ctx.is_player_boss() && self.data.changed {
             
@@ -215,7 +217,8 @@ impl RymdGameMode for RymdGameModeConquest {
             let conquest_lobby_data = self.data.serialize_json();
             let lobby_game_state = LobbyGameState {
                 game_mode_name: self.name().to_owned(),
-                game_mode_state: conquest_lobby_data
+                game_mode_state: conquest_lobby_data,
+                settings: settings.clone()
             };
             
             ctx.push_new_lobby_data(lobby_game_state.serialize_json());
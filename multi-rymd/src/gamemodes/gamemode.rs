@@ -1,7 +1,7 @@
 use lockstep_client::game::GameLobbyContext;
 use puffin_egui::egui;
 
-use crate::{commands::GameCommand, game::RymdGameParameters, model::RymdGameModel, PlayerID};
+use crate::{commands::GameCommand, game::RymdGameParameters, lobby::LobbySettings, model::RymdGameModel, PlayerID};
 
 #[derive(PartialEq)]
 pub enum RymdGameModeResult {
@@ -23,7 +23,7 @@ pub trait RymdGameMode {
     fn on_lobby_command(&mut self, client_id: PlayerID, game_command: &GameCommand);
     fn on_lobby_update(&mut self, new_lobby_data: String);
     
-    fn handle_lobby_tick(&mut self, ctx: &mut GameLobbyContext);
+    fn handle_lobby_tick(&mut self, ctx: &mut GameLobbyContext, settings: &LobbySettings);
     fn draw_lobby_ui(&mut self, ui: &mut egui::Ui, ctx: &mut GameLobbyContext);
 
     fn force_lobby_update(&mut self, ctx: &mut GameLobbyContext);
@@ -0,0 +1,285 @@
+use lockstep_client::game::GameLobbyContext;
+use nanoserde::{DeJson, SerJson};
+use macroquad::math::{vec2, Vec2};
+use puffin_egui::egui;
+
+use crate::{commands::{CommandsExt, GameCommand}, game::{RymdGameParameters, RymdGameTeam}, lobby::{LobbyGameState, LobbySettings}, model::{set_default_energy_pool_size, set_default_metal_pool_size, set_player_team_allegiance, CapturePoints, RymdGameModel, CAPTURE_POINT_RADIUS}, utils::helpers::{create_asteroid_clumps, create_player_commander_ships, create_players}, PlayerID};
+
+use super::gamemode::{RymdGameMode, RymdGameModeResult};
+
+#[derive(Clone, Debug, SerJson, DeJson)]
+pub struct RymdGameModeKingOfTheHillData {
+    pub teams: Vec<RymdGameTeam>,
+    pub starting_metal: i32,
+    pub starting_energy: i32,
+    pub number_of_capture_points: i32,
+    pub score_limit: i32,
+    /// Zero means there is no time limit, the match only ends when a team reaches the score limit.
+    pub time_limit_minutes: i32,
+    pub changed: bool
+}
+
+impl RymdGameModeKingOfTheHillData {
+
+    pub fn new() -> RymdGameModeKingOfTheHillData {
+        RymdGameModeKingOfTheHillData {
+            teams: vec![RymdGameTeam::new(0), RymdGameTeam::new(1)],
+            starting_metal: 1000,
+            starting_energy: 1000,
+            number_of_capture_points: 3,
+            score_limit: 500,
+            time_limit_minutes: 20,
+            changed: false
+        }
+    }
+
+    pub fn move_player_to_team(&mut self, player_id: PlayerID, target_team_id: i32) {
+
+        for team in &mut self.teams {
+            team.players.retain(|&p| p != player_id);
+        }
+
+        for team in &mut self.teams {
+            if team.id == target_team_id {
+                team.players.push(player_id);
+                break;
+            }
+        }
+
+    }
+
+    pub fn remove_player_from_teams(&mut self, player_id: PlayerID) {
+
+        for team in &mut self.teams {
+            team.players.retain(|&p| p != player_id);
+        }
+
+    }
+
+}
+
+/// Places one capture point in the middle of the map and spreads the rest out evenly on a ring around it.
+fn generate_capture_point_positions(model: &mut RymdGameModel, number_of_capture_points: i32) -> Vec<Vec2> {
+
+    let capture_point_ring_radius = 2000.0;
+    let mut positions = Vec::new();
+
+    if number_of_capture_points <= 0 {
+        return positions;
+    }
+
+    positions.push(vec2(0.0, 0.0));
+
+    let number_of_ring_points = number_of_capture_points - 1;
+    let ring_rotation = model.random.gen_range(0.0, std::f32::consts::TAU);
+
+    for i in 0..number_of_ring_points {
+        let angle = ring_rotation + (i as f32 / number_of_ring_points as f32) * std::f32::consts::TAU;
+        positions.push(vec2(angle.cos(), angle.sin()) * capture_point_ring_radius);
+    }
+
+    positions
+
+}
+
+#[derive(Clone)]
+pub struct RymdGameModeKingOfTheHill {
+    pub data: RymdGameModeKingOfTheHillData
+}
+
+impl RymdGameModeKingOfTheHill {
+
+    pub fn new() -> RymdGameModeKingOfTheHill {
+        RymdGameModeKingOfTheHill {
+            data: RymdGameModeKingOfTheHillData::new()
+        }
+    }
+
+}
+
+impl RymdGameMode for RymdGameModeKingOfTheHill {
+
+    fn name(&self) -> &str {
+        "King of the Hill"
+    }
+
+    fn on_start(&self, model: &mut RymdGameModel, parameters: &RymdGameParameters) {
+
+        model.random.srand(42);
+
+        let number_of_asteroid_clumps = 10;
+        let number_of_asteroids = 10;
+
+        create_players(model, parameters);
+
+        for team in &self.data.teams {
+            for &player_id in &team.players {
+                let current_team_mask: u64 = 1 << team.id;
+                set_player_team_allegiance(&mut model.world, player_id, current_team_mask);
+            }
+        }
+
+        create_player_commander_ships(model, parameters);
+        create_asteroid_clumps(model, number_of_asteroid_clumps, number_of_asteroids);
+
+        set_default_metal_pool_size(&mut model.world, self.data.starting_metal, self.data.starting_metal);
+        set_default_energy_pool_size(&mut model.world, self.data.starting_energy, self.data.starting_energy);
+
+        let time_limit_ticks = (self.data.time_limit_minutes as f32 * 60.0 / RymdGameModel::TIME_STEP) as u64;
+        let mut capture_points = CapturePoints::new(self.data.teams.clone(), self.data.score_limit as f32, time_limit_ticks);
+
+        for position in generate_capture_point_positions(model, self.data.number_of_capture_points) {
+            capture_points.add_point(position, CAPTURE_POINT_RADIUS);
+        }
+
+        model.capture_points = Some(capture_points);
+
+    }
+
+    fn on_client_joined_lobby(&mut self, client_id: PlayerID, ctx: &mut GameLobbyContext) {
+        
+        if ctx.is_player_boss() {
+            self.data.move_player_to_team(client_id, self.data.teams.first().unwrap().id);
+            self.data.changed = true;
+        }
+
+    }
+
+    fn on_client_left_lobby(&mut self, client_id: PlayerID, ctx: &mut GameLobbyContext) {
+
+        if ctx.is_player_boss() {
+            self.data.remove_player_from_teams(client_id);
+            self.data.changed = true;
+        }
+        
+    }
+
+    fn on_lobby_update(&mut self, new_lobby_data: String) {
+        
+        if let Ok(rymd_game_mode_king_of_the_hill_data) = RymdGameModeKingOfTheHillData::deserialize_json(&new_lobby_data) {
+            self.data = rymd_game_mode_king_of_the_hill_data;
+        }
+
+    }
+
+    fn tick(&self, model: &mut RymdGameModel) -> RymdGameModeResult {
+
+        let Some(capture_points) = &model.capture_points else {
+            return RymdGameModeResult::End;
+        };
+
+        if capture_points.is_finished() {
+            return RymdGameModeResult::End;
+        }
+
+        RymdGameModeResult::Continue
+        
+    }
+    
+    fn draw_lobby_ui(&mut self, ui: &mut egui::Ui, ctx: &mut GameLobbyContext) {
+
+        let old_data = self.data.clone();
+        let mut anything_changed = false;
+
+        ui.vertical_centered(|ui| {
+
+            ui.heading("settings");
+
+            ui.horizontal(|ui| {
+                ui.label("starting metal");
+                let e = ui.add(egui::Slider::new(&mut self.data.starting_metal, 1000..=50000));
+                anything_changed = anything_changed || e.changed();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("starting energy");
+                let e = ui.add(egui::Slider::new(&mut self.data.starting_energy, 1000..=50000));
+                anything_changed = anything_changed || e.changed();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("capture points");
+                let e = ui.add(egui::Slider::new(&mut self.data.number_of_capture_points, 1..=7));
+                anything_changed = anything_changed || e.changed();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("score limit");
+                let e = ui.add(egui::Slider::new(&mut self.data.score_limit, 100..=5000));
+                anything_changed = anything_changed || e.changed();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("time limit (minutes)");
+                let e = ui.add(egui::Slider::new(&mut self.data.time_limit_minutes, 0..=120).custom_formatter(|v, _| if v == 0.0 { "none".to_string() } else { format!("{}", v) }));
+                anything_changed = anything_changed || e.changed();
+            });
+
+            ui.heading("teams");
+
+            for team in &mut self.data.teams.clone() {
+
+                ui.separator();
+                ui.heading(format!("team {}", team.id));
+                for &player_id in &team.players {
+                    ui.label(format!("{} ({})", ctx.get_lobby_client_name(player_id), player_id));
+                }
+
+                if team.players.contains(&ctx.lockstep().peer_id()) == false && ui.button("join").clicked() {
+                    ctx.lockstep_mut().send_join_team_message(team.id);
+                }
+
+            }
+
+        });
+        
+        self.data.changed = self.data.changed || anything_changed;
+
+        if ctx.is_player_boss() == false {
+            self.data = old_data;
+        }
+
+    }
+    
+    fn on_lobby_command(&mut self, client_id: PlayerID, game_command: &GameCommand) {
+        
+        match game_command {
+            GameCommand::Message { .. } => (),
+            GameCommand::Ping { .. } => (),
+            GameCommand::Marker { .. } => (),
+            GameCommand::JoinTeam { team_id } => {
+                self.data.move_player_to_team(client_id, *team_id);
+                self.data.changed = true;
+            }
+            GameCommand::LeaveTeam => {
+                self.data.remove_player_from_teams(client_id);
+                self.data.changed = true;
+            }
+        }
+
+    }
+    
+    fn handle_lobby_tick(&mut self, ctx: &mut GameLobbyContext, settings: &LobbySettings) {
+        
+        if ctx.is_player_boss() && self.data.changed {
+            
+            self.data.changed = false;
+            
+            let king_of_the_hill_lobby_data = self.data.serialize_json();
+            let lobby_game_state = LobbyGameState {
+                game_mode_name: self.name().to_owned(),
+                game_mode_state: king_of_the_hill_lobby_data,
+                settings: settings.clone()
+            };
+            
+            ctx.push_new_lobby_data(lobby_game_state.serialize_json());
+
+        }
+
+    }
+    
+    fn force_lobby_update(&mut self, ctx: &mut GameLobbyContext) {
+        self.data.changed = true;
+    }
+
+}
\ No newline at end of file
@@ -0,0 +1,266 @@
+use lockstep_client::game::GameLobbyContext;
+use nanoserde::{DeJson, SerJson};
+use puffin_egui::egui;
+
+use crate::{commands::GameCommand, game::RymdGameParameters, lobby::{LobbyGameState, LobbySettings}, model::{create_player_entity, set_default_energy_pool_size, set_default_metal_pool_size, set_player_team_allegiance, Mission, RymdGameModel}, utils::helpers::create_players, PlayerID};
+
+use super::gamemode::{RymdGameMode, RymdGameModeResult};
+
+const MISSIONS_DIRECTORY: &str = "missions";
+const MISSION_SCRIPT_EXTENSION: &str = "rhai";
+
+/// Lists the names of all the mission scripts available, sorted so every client sees them in the same order.
+fn find_available_missions() -> Vec<String> {
+
+    let Ok(directory) = std::fs::read_dir(MISSIONS_DIRECTORY) else { return Vec::new(); };
+
+    let mut missions: Vec<String> = directory
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == MISSION_SCRIPT_EXTENSION))
+        .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .collect();
+
+    missions.sort();
+    missions
+
+}
+
+fn mission_path(mission_name: &str) -> String {
+    format!("{}/{}.{}", MISSIONS_DIRECTORY, mission_name, MISSION_SCRIPT_EXTENSION)
+}
+
+#[derive(Clone, Debug, SerJson, DeJson)]
+pub struct RymdGameModeMissionData {
+    pub selected_mission: String,
+    /// The script of the selected mission as read by the host, sent along so every client runs exactly the same script rather than whatever copy it has locally.
+    pub mission_source: String,
+    pub starting_metal: i32,
+    pub starting_energy: i32,
+    pub changed: bool
+}
+
+impl RymdGameModeMissionData {
+    pub fn new() -> RymdGameModeMissionData {
+        RymdGameModeMissionData {
+            selected_mission: String::new(),
+            mission_source: String::new(),
+            starting_metal: 1000,
+            starting_energy: 1000,
+            changed: false
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RymdGameModeMission {
+    pub data: RymdGameModeMissionData,
+    pub available_missions: Vec<String>
+}
+
+impl RymdGameModeMission {
+    pub fn new() -> RymdGameModeMission {
+
+        let available_missions = find_available_missions();
+        let mut data = RymdGameModeMissionData::new();
+
+        if let Some(first_mission) = available_missions.first() {
+            data.selected_mission = first_mission.clone();
+        }
+
+        RymdGameModeMission {
+            data,
+            available_missions
+        }
+
+    }
+}
+
+impl RymdGameMode for RymdGameModeMission {
+
+    fn name(&self) -> &str {
+        "Mission"
+    }
+
+    fn on_start(&self, model: &mut RymdGameModel, parameters: &RymdGameParameters) {
+
+        model.random.srand(42);
+
+        create_players(model, parameters);
+
+        // every player gets their own team, the mission script decides who is fighting who
+        for (i, player) in parameters.players.iter().enumerate() {
+            let current_team_mask: u64 = 1 << i;
+            set_player_team_allegiance(&mut model.world, player.id, current_team_mask);
+        }
+
+        // the enemy the mission spawns its waves for, on a team of its own so it is hostile to everyone in the lobby
+        let enemy_player_id = parameters.players.iter().map(|p| p.id).max().unwrap_or(0) + 1;
+        create_player_entity(&mut model.world, enemy_player_id);
+        set_player_team_allegiance(&mut model.world, enemy_player_id, 1 << parameters.players.len());
+
+        set_default_metal_pool_size(&mut model.world, self.data.starting_metal, self.data.starting_metal);
+        set_default_energy_pool_size(&mut model.world, self.data.starting_energy, self.data.starting_energy);
+
+        if self.data.mission_source.is_empty() {
+            println!("[RymdGameModeMission] failed to start mission: {}, the host did not send a mission script", self.data.selected_mission);
+            return;
+        }
+
+        let mission_players = parameters.players.iter().map(|p| p.id).chain(std::iter::once(enemy_player_id)).collect();
+
+        match Mission::new(&self.data.selected_mission, &self.data.mission_source, mission_players, enemy_player_id) {
+            Ok(mission) => model.mission = Some(mission),
+            Err(error) => println!("[RymdGameModeMission] failed to start mission: {}", error)
+        }
+
+    }
+
+    fn tick(&self, model: &mut RymdGameModel) -> RymdGameModeResult {
+
+        let Some(mission) = &model.mission else {
+            return RymdGameModeResult::End;
+        };
+
+        if mission.is_finished(model.current_tick) {
+            return RymdGameModeResult::End;
+        }
+
+        RymdGameModeResult::Continue
+
+    }
+
+    fn on_client_joined_lobby(&mut self, client_id: PlayerID, ctx: &mut GameLobbyContext) {
+
+    }
+
+    fn on_client_left_lobby(&mut self, client_id: PlayerID, ctx: &mut GameLobbyContext) {
+
+    }
+
+    fn on_lobby_update(&mut self, new_lobby_data: String) {
+
+        if let Ok(rymd_game_mode_mission_data) = RymdGameModeMissionData::deserialize_json(&new_lobby_data) {
+            self.data = rymd_game_mode_mission_data;
+        }
+
+    }
+
+    fn draw_lobby_ui(&mut self, ui: &mut egui::Ui, ctx: &mut GameLobbyContext) {
+
+        let old_data = self.data.clone();
+        let mut anything_changed = false;
+
+        ui.vertical_centered(|ui| {
+
+            ui.heading("mission");
+
+            if self.available_missions.is_empty() {
+                ui.label(format!("no missions found in: {}/", MISSIONS_DIRECTORY));
+            }
+
+            for mission_name in &self.available_missions {
+                let e = ui.selectable_value(&mut self.data.selected_mission, mission_name.clone(), mission_name);
+                anything_changed = anything_changed || e.changed();
+            }
+
+            ui.heading("settings");
+
+            ui.horizontal(|ui| {
+                ui.label("starting metal");
+                let e = ui.add(egui::Slider::new(&mut self.data.starting_metal, 1000..=50000));
+                anything_changed = anything_changed || e.changed();
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("starting energy");
+                let e = ui.add(egui::Slider::new(&mut self.data.starting_energy, 1000..=50000));
+                anything_changed = anything_changed || e.changed();
+            });
+
+        });
+
+        self.data.changed = self.data.changed || anything_changed;
+
+        if ctx.is_player_boss() == false {
+            self.data = old_data;
+        }
+
+    }
+
+    fn on_lobby_command(&mut self, client_id: PlayerID, game_command: &GameCommand) {
+
+    }
+
+    fn handle_lobby_tick(&mut self, ctx: &mut GameLobbyContext, settings: &LobbySettings) {
+
+        if ctx.is_player_boss() && self.data.changed {
+
+            self.data.changed = false;
+
+            let selected_mission_path = mission_path(&self.data.selected_mission);
+            self.data.mission_source = match std::fs::read_to_string(&selected_mission_path) {
+                Ok(mission_source) => mission_source,
+                Err(error) => {
+                    println!("[RymdGameModeMission] could not read mission script: {}, error: {}", selected_mission_path, error);
+                    String::new()
+                }
+            };
+
+            let mission_lobby_data = self.data.serialize_json();
+            let lobby_game_state = LobbyGameState {
+                game_mode_name: self.name().to_owned(),
+                game_mode_state: mission_lobby_data,
+                settings: settings.clone()
+            };
+
+            ctx.push_new_lobby_data(lobby_game_state.serialize_json());
+
+        }
+
+    }
+
+    fn force_lobby_update(&mut self, ctx: &mut GameLobbyContext) {
+        self.data.changed = true;
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{game::RymdGamePlayer, model::{Controller, MissionTag}};
+
+    use super::*;
+
+    #[test]
+    fn test_campaign_waves_spawn_for_enemy_player() {
+
+        let mut game_mode = RymdGameModeMission::new();
+        game_mode.data.selected_mission = "campaign_01".to_string();
+        game_mode.data.mission_source = include_str!("../../missions/campaign_01.rhai").to_string();
+
+        let local_player_id = 0;
+        let parameters = RymdGameParameters { players: vec![RymdGamePlayer { id: local_player_id }] };
+
+        let mut model = RymdGameModel::new();
+        game_mode.on_start(&mut model, &parameters);
+        model.start(parameters.clone());
+
+        // the first wave arrives after 30 seconds
+        for _ in 0..(60 * 30 + 10) {
+            model.tick();
+            assert!(game_mode.tick(&mut model) == RymdGameModeResult::Continue);
+        }
+
+        let first_wave_controllers: Vec<PlayerID> = model.world.query::<(&MissionTag, &Controller)>().iter()
+            .filter(|(_, (mission_tag, _))| mission_tag.name == "first_wave")
+            .map(|(_, (_, controller))| controller.id)
+            .collect();
+
+        assert_eq!(8, first_wave_controllers.len());
+        assert!(first_wave_controllers.iter().all(|&id| model.player_mapping.contains_key(&id) && model.is_player_friendly_to(local_player_id, id) == false));
+
+    }
+
+}
@@ -1,3 +1,5 @@
 pub mod chickens;
 pub mod conquest;
-pub mod gamemode;
\ No newline at end of file
+pub mod gamemode;
+pub mod koth;
+pub mod mission;
\ No newline at end of file
@@ -1,7 +1,20 @@
 use nanoserde::{DeJson, SerJson};
 
+/// Settings for the match which apply no matter what game mode is picked.
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct LobbySettings {
+    pub are_cheats_enabled: bool
+}
+
+impl LobbySettings {
+    pub fn new() -> LobbySettings {
+        LobbySettings { are_cheats_enabled: false }
+    }
+}
+
 #[derive(Debug, Clone, SerJson, DeJson)]
 pub struct LobbyGameState {
     pub game_mode_name: String,
-    pub game_mode_state: String
-}
\ No newline at end of file
+    pub game_mode_state: String,
+    pub settings: LobbySettings
+}
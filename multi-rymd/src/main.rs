@@ -15,6 +15,7 @@ use macroquad::prelude::*;
 
 use lockstep_client::app::ApplicationState;
 use puffin_egui::puffin;
+use utils::headless::{run_headless, HeadlessArguments};
 
 mod commands;
 mod game;
@@ -29,8 +30,26 @@ type PlayerID = lockstep_client::step::PeerID;
 
 pub const INGAME_PROFILER_ENABLED: bool = false;
 
-#[macroquad::main("multi-rymd")]
-async fn main() {
+fn main() {
+
+    match HeadlessArguments::from_args(std::env::args()) {
+        Ok(Some(headless_arguments)) => {
+            run_headless(headless_arguments);
+            return;
+        },
+        Ok(None) => (),
+        Err(error) => {
+            println!("[RymdHeadless] {}", error);
+            println!("usage: multi-rymd --headless [scenario.json] [--ticks N] [--units N] [--serial]");
+            return;
+        }
+    }
+
+    macroquad::Window::new("multi-rymd", run());
+
+}
+
+async fn run() {
 
     let mut main_loop_update_time_ms = 0.0;
     let mut app = ApplicationState::new("multi-rymd", RymdGame::new());
@@ -1,30 +1,41 @@
 mod buildings;
+mod capture;
 mod constants;
 mod components;
 mod environment;
 mod effects;
+mod events;
 mod message;
+mod mission;
 mod model;
 mod orders;
+mod parallel;
 mod physics;
 mod player;
 mod projectiles;
 mod ships;
 mod spatial;
+mod transports;
+mod speed;
 mod resources;
 mod steering;
 
 pub use buildings::*;
+pub use capture::*;
 pub use constants::*;
 pub use components::*;
 pub use effects::*;
 pub use environment::*;
+pub use events::*;
 pub use message::*;
+pub use mission::*;
 pub use model::*;
 pub use orders::*;
 pub use physics::*;
 pub use player::*;
 pub use projectiles::*;
 pub use ships::*;
+pub use speed::*;
 pub use resources::*;
-pub use steering::*;
\ No newline at end of file
+pub use steering::*;
+pub use transports::*;
\ No newline at end of file
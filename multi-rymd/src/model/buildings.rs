@@ -1,13 +1,12 @@
 use hecs::{CommandBuffer, Entity, World};
-use macroquad::{math::{Vec2, Rect, vec2}, miniquad::KeyCode};
+use macroquad::math::{Vec2, Rect, vec2};
 
 use crate::PlayerID;
-use super::{cancel_pending_orders, create_default_kinematic_body, create_explosion_effect_in_buffer, get_entity_position, get_player_team_allegiance, Attackable, Blueprint, BlueprintIdentity, Blueprints, Building, Constructor, Consumer, Controller, Cost, Decayer, DynamicBody, EntityState, Health, MovementTarget, Orderable, PhysicsBody, Powered, Producer, Spawner, Sprite, Storage, Transform};
+use super::{cancel_pending_orders, create_default_kinematic_body, create_explosion_effect_in_buffer, get_entity_position, get_player_team_allegiance, Attackable, Blueprint, BlueprintIdentity, Blueprints, Building, Constructor, Consumer, Controller, Cost, Decayer, DynamicBody, EntityState, Factory, Health, MovementTarget, Orderable, PhysicsBody, Powered, Producer, Spawner, Sprite, Storage, Transform};
 
 pub fn create_solar_collector_blueprint() -> Blueprint {
     Blueprint {
         id: Blueprints::SolarCollector as i32,
-        shortcut: KeyCode::Q,
         name: String::from("Solar Collector"),
         texture: String::from("SOLAR_COLLECTOR"),
         constructor: build_solar_collector,
@@ -19,7 +18,6 @@ pub fn create_solar_collector_blueprint() -> Blueprint {
 pub fn create_shipyard_blueprint() -> Blueprint {
     Blueprint {
         id: Blueprints::Shipyard as i32,
-        shortcut: KeyCode::W,
         name: String::from("Shipyard"),
         texture: String::from("SHIPYARD"),
         constructor: build_shipyard,
@@ -31,7 +29,6 @@ pub fn create_shipyard_blueprint() -> Blueprint {
 pub fn create_energy_storage_blueprint() -> Blueprint {
     Blueprint {
         id: Blueprints::EnergyStorage as i32,
-        shortcut: KeyCode::E,
         name: String::from("Energy Storage"),
         texture: String::from("ENERGY_STORAGE"),
         constructor: build_energy_storage,
@@ -43,7 +40,6 @@ pub fn create_energy_storage_blueprint() -> Blueprint {
 pub fn create_metal_storage_blueprint() -> Blueprint {
     Blueprint {
         id: Blueprints::MetalStorage as i32,
-        shortcut: KeyCode::R,
         name: String::from("Metal Storage"),
         texture: String::from("METAL_STORAGE"),
         constructor: build_metal_storage,
@@ -55,7 +51,6 @@ pub fn create_metal_storage_blueprint() -> Blueprint {
 pub fn create_energy_converter_blueprint() -> Blueprint {
     Blueprint {
         id: Blueprints::EnergyConverter as i32,
-        shortcut: KeyCode::M,
         name: String::from("Energy Converter"),
         texture: String::from("ENERGY_CONVERTER"),
         constructor: build_energy_converter,
@@ -161,7 +156,7 @@ pub fn build_shipyard(world: &mut World, owner: PlayerID, position: Vec2) -> Ent
     let initial_shipyard_health = 10.0;
 
     let shipyard_build_speed = 100;
-    let shipyard_blueprints = vec![Blueprints::Arrowhead as i32, Blueprints::Extractor as i32, Blueprints::Dragonfly as i32];
+    let shipyard_blueprints = vec![Blueprints::Arrowhead as i32, Blueprints::Extractor as i32, Blueprints::Dragonfly as i32, Blueprints::Carrier as i32, Blueprints::Transport as i32];
 
     let shipyard_parameters = BuildingParameters {
         
@@ -180,8 +175,9 @@ pub fn build_shipyard(world: &mut World, owner: PlayerID, position: Vec2) -> Ent
     let constructor = Constructor { current_target: None, constructibles: shipyard_blueprints, build_range: shipyard_size as i32 / 2, build_speed: shipyard_build_speed, beam_offset: -vec2(0.0, 8.0), can_assist: false };
     let movement_target = MovementTarget { target: None };
     let orderable = Orderable::new();
+    let factory = Factory::new();
 
-    let _ = world.insert(shipyard, (spawner, constructor, movement_target, orderable));
+    let _ = world.insert(shipyard, (spawner, constructor, movement_target, orderable, factory));
 
     shipyard
 
@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use hecs::World;
+use macroquad::math::Vec2;
+
+use crate::game::RymdGameTeam;
+use crate::PlayerID;
+
+use super::spatial::SpatialQueryManager;
+use super::{Building, Controller, EntityState, RymdGameModel, Transform};
+
+/// How long a single team needs to hold an uncontested point to take it over from neutral, in seconds.
+pub const CAPTURE_POINT_CAPTURE_SECONDS: f32 = 10.0;
+
+/// Score awarded to a team per second for every point it holds.
+pub const CAPTURE_POINT_SCORE_PER_SECOND: f32 = 1.0;
+
+pub const CAPTURE_POINT_RADIUS: f32 = 384.0;
+
+#[derive(Debug, Clone)]
+pub struct CapturePoint {
+    pub position: Vec2,
+    pub radius: f32,
+    /// The team currently holding the point, only they score from it.
+    pub owner: Option<i32>,
+    /// The team the progress belongs to, which is not necessarily the owner while a point is being taken over.
+    pub progress_team: Option<i32>,
+    pub progress: f32
+}
+
+impl CapturePoint {
+
+    pub fn new(position: Vec2, radius: f32) -> CapturePoint {
+        CapturePoint {
+            position,
+            radius,
+            owner: None,
+            progress_team: None,
+            progress: 0.0
+        }
+    }
+
+    /// Moves the capture progress towards the given team, any progress of another team has to be undone first.
+    fn advance_capture(&mut self, team_id: i32, amount: f32) {
+
+        if self.progress_team.is_some_and(|t| t != team_id) {
+
+            self.progress = (self.progress - amount).max(0.0);
+
+            if self.progress <= 0.0 {
+                self.owner = None;
+                self.progress_team = Some(team_id);
+            }
+
+        } else {
+
+            self.progress_team = Some(team_id);
+            self.progress = (self.progress + amount).min(1.0);
+
+            if self.progress >= 1.0 && self.owner != Some(team_id) {
+                println!("[CapturePoints] team: {} captured the point at: {}", team_id, self.position);
+                self.owner = Some(team_id);
+            }
+
+        }
+
+    }
+
+}
+
+/// State of a king of the hill match, the capture points along with the score of each team.
+pub struct CapturePoints {
+    pub points: Vec<CapturePoint>,
+    pub teams: Vec<RymdGameTeam>,
+    pub scores: BTreeMap<i32, f32>,
+    pub score_limit: f32,
+    /// Zero means the match only ends once a team reaches the score limit.
+    pub time_limit_ticks: u64,
+    pub winner: Option<i32>
+}
+
+impl CapturePoints {
+
+    pub fn new(teams: Vec<RymdGameTeam>, score_limit: f32, time_limit_ticks: u64) -> CapturePoints {
+
+        let scores = teams.iter().map(|team| (team.id, 0.0)).collect();
+
+        CapturePoints {
+            points: Vec::new(),
+            teams,
+            scores,
+            score_limit,
+            time_limit_ticks,
+            winner: None
+        }
+
+    }
+
+    pub fn add_point(&mut self, position: Vec2, radius: f32) {
+        self.points.push(CapturePoint::new(position, radius));
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    pub fn get_team_of_player(&self, player_id: PlayerID) -> Option<i32> {
+        self.teams.iter().find(|team| team.players.contains(&player_id)).map(|team| team.id)
+    }
+
+    /// Once the time runs out while the teams in the lead are tied, the match goes on until one of them pulls ahead.
+    pub fn is_sudden_death(&self, current_tick: u64) -> bool {
+        self.is_finished() == false && self.remaining_ticks(current_tick) == Some(0)
+    }
+
+    /// Returns the one team with the highest score, or nothing if several teams share it.
+    fn sole_leading_team(&self) -> Option<(i32, f32)> {
+
+        let leading_score = self.scores.values().copied().max_by(|a, b| a.total_cmp(b))?;
+        let mut leading_teams = self.scores.iter().filter(|&(_, &score)| score == leading_score);
+
+        match (leading_teams.next(), leading_teams.next()) {
+            (Some((&team_id, _)), None) => Some((team_id, leading_score)),
+            _ => None
+        }
+
+    }
+
+    pub fn remaining_ticks(&self, current_tick: u64) -> Option<u64> {
+        if self.time_limit_ticks == 0 {
+            None
+        } else {
+            Some(self.time_limit_ticks.saturating_sub(current_tick))
+        }
+    }
+
+    /// Returns the teams with constructed units within range of the point, buildings do not count towards capturing.
+    fn teams_present_at_point(&self, point: &CapturePoint, world: &World, spatial_manager: &SpatialQueryManager) -> BTreeSet<i32> {
+
+        let mut teams_present = BTreeSet::new();
+
+        for e in spatial_manager.entities_within_circle_bounds(point.position, point.radius) {
+
+            if world.get::<&Building>(e).is_ok() {
+                continue;
+            }
+
+            let Ok(mut query) = world.query_one::<(&Controller, &Transform, &EntityState)>(e) else { continue };
+            let Some((controller, transform, state)) = query.get() else { continue };
+
+            if *state != EntityState::Constructed || transform.world_position.distance(point.position) > point.radius {
+                continue;
+            }
+
+            if let Some(team_id) = self.get_team_of_player(controller.id) {
+                teams_present.insert(team_id);
+            }
+
+        }
+
+        teams_present
+
+    }
+
+    pub fn tick(&mut self, world: &World, spatial_manager: &SpatialQueryManager, current_tick: u64) {
+
+        if self.is_finished() {
+            return;
+        }
+
+        let capture_amount = RymdGameModel::TIME_STEP / CAPTURE_POINT_CAPTURE_SECONDS;
+
+        for i in 0..self.points.len() {
+
+            let teams_present = self.teams_present_at_point(&self.points[i], world, spatial_manager);
+
+            // contested points stay as they are until only one team is left standing
+            if teams_present.len() == 1 && let Some(&team_id) = teams_present.first() {
+                self.points[i].advance_capture(team_id, capture_amount);
+            }
+
+            if let Some(owner) = self.points[i].owner {
+                *self.scores.entry(owner).or_insert(0.0) += CAPTURE_POINT_SCORE_PER_SECOND * RymdGameModel::TIME_STEP;
+            }
+
+        }
+
+        // a tie is never broken by anything but score, so with several teams in the lead the match just goes on
+        let Some((leading_team_id, leading_score)) = self.sole_leading_team() else { return; };
+        let is_out_of_time = self.remaining_ticks(current_tick) == Some(0);
+
+        if leading_score >= self.score_limit || is_out_of_time {
+            println!("[CapturePoints] team: {} won with a score of: {}", leading_team_id, leading_score as i32);
+            self.winner = Some(leading_team_id);
+        }
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn create_capture_points(time_limit_ticks: u64) -> CapturePoints {
+        let teams = vec![
+            RymdGameTeam { id: 1, players: vec![1] },
+            RymdGameTeam { id: 2, players: vec![2] }
+        ];
+        CapturePoints::new(teams, 100.0, time_limit_ticks)
+    }
+
+    #[test]
+    fn test_tie_at_time_limit_goes_to_sudden_death() {
+
+        let world = World::new();
+        let spatial_manager = SpatialQueryManager::new(64);
+        let mut capture_points = create_capture_points(10);
+
+        capture_points.scores.insert(1, 50.0);
+        capture_points.scores.insert(2, 50.0);
+        capture_points.tick(&world, &spatial_manager, 10);
+
+        assert_eq!(None, capture_points.winner);
+        assert!(capture_points.is_sudden_death(10));
+
+        capture_points.scores.insert(1, 51.0);
+        capture_points.tick(&world, &spatial_manager, 11);
+
+        assert_eq!(Some(1), capture_points.winner);
+        assert!(capture_points.is_sudden_death(11) == false);
+
+    }
+
+    #[test]
+    fn test_tie_is_not_decided_by_team_id() {
+
+        let world = World::new();
+        let spatial_manager = SpatialQueryManager::new(64);
+        let mut capture_points = create_capture_points(0);
+
+        capture_points.scores.insert(1, 100.0);
+        capture_points.scores.insert(2, 100.0);
+        capture_points.tick(&world, &spatial_manager, 1000);
+
+        assert_eq!(None, capture_points.winner);
+
+    }
+
+    #[test]
+    fn test_leader_wins_at_time_limit() {
+
+        let world = World::new();
+        let spatial_manager = SpatialQueryManager::new(64);
+        let mut capture_points = create_capture_points(10);
+
+        capture_points.scores.insert(2, 20.0);
+        capture_points.tick(&world, &spatial_manager, 9);
+        assert_eq!(None, capture_points.winner);
+
+        capture_points.tick(&world, &spatial_manager, 10);
+        assert_eq!(Some(2), capture_points.winner);
+
+    }
+
+}
@@ -1,11 +1,11 @@
 use std::collections::VecDeque;
 use hecs::{CommandBuffer, Entity, World};
-use macroquad::{color::Color, input::KeyCode, math::{Rect, Vec2}};
+use macroquad::{color::Color, math::{Rect, Vec2}};
 use utility::{Kinematic, RotatedBy, SteeringParameters};
 use lockstep_client::step::PeerID;
 
 use crate::PlayerID;
-use super::{BeamParameters, Blueprints, BulletParameters, Cost, GameOrder, GameOrderType, PhysicsBody};
+use super::{BeamParameters, Blueprints, BulletParameters, Cost, GameOrder, GameOrderType, PhysicsBody, RallyPoint, RymdGameEvent};
 
 #[derive(Clone)]
 pub struct Thruster {
@@ -113,7 +113,7 @@ pub struct DynamicBody {
 
 #[derive(Clone)]
 pub struct DynamicBodyCallback {
-    pub on_collision: fn(&World, &mut CommandBuffer, Entity, Entity, &DynamicBody) -> ()
+    pub on_collision: fn(&World, &mut CommandBuffer, &mut Vec<RymdGameEvent>, Entity, Entity, &DynamicBody) -> ()
 }
 
 impl PhysicsBody for DynamicBody {
@@ -218,16 +218,28 @@ pub struct Orderable {
     order_queue: OrderQueue
 }
 
+/// Identifies an order for as long as it is in the queue, so it can be referred to even after the orders before it have moved.
+pub type OrderID = u64;
+
 #[derive(Clone)]
 struct OrderQueue {
     canceled_orders: VecDeque<GameOrder>,
-    orders: VecDeque<GameOrder>
+    orders: VecDeque<GameOrder>,
+    /// The id of each order in orders, at the same position.
+    order_ids: VecDeque<OrderID>,
+    next_order_id: OrderID
 }
 
 impl OrderQueue {
 
     pub fn new() -> OrderQueue {
-        OrderQueue { canceled_orders: VecDeque::new(), orders: VecDeque::new() }
+        OrderQueue { canceled_orders: VecDeque::new(), orders: VecDeque::new(), order_ids: VecDeque::new(), next_order_id: 0 }
+    }
+
+    fn create_order_id(&mut self) -> OrderID {
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+        order_id
     }
 
     /// Returns the order first in the queue, if any.
@@ -237,6 +249,7 @@ impl OrderQueue {
 
     /// Pops and returns the first order in the queue.
     pub fn pop_first_order(&mut self) -> Option<GameOrder> {
+        self.order_ids.pop_front();
         self.orders.pop_front()
     }
     
@@ -250,6 +263,11 @@ impl OrderQueue {
         &self.orders
     }
 
+    /// Returns the ids of the enqueued orders, in the same order as the orders themselves.
+    pub fn order_ids(&self) -> &VecDeque<OrderID> {
+        &self.order_ids
+    }
+
     /// Returns a reference to the collection of canceled orders.
     pub fn canceled_orders(&self) -> &VecDeque<GameOrder> {
         &self.canceled_orders
@@ -262,6 +280,7 @@ impl OrderQueue {
     
     /// Cancel the current order.
     pub fn cancel_order(&mut self) {
+        self.order_ids.pop_front();
         let canceled_order = self.orders.pop_front();
         if let Some(order) = canceled_order {
             self.canceled_orders.push_front(order);
@@ -270,11 +289,15 @@ impl OrderQueue {
 
     /// Push a new order to the front of the queue.
     pub fn push_order(&mut self, order: GameOrder) {
+        let order_id = self.create_order_id();
+        self.order_ids.push_front(order_id);
         self.orders.push_front(order);
     }
 
     /// Enqueues the order at the end of the queue.
     pub fn queue_order(&mut self, order: GameOrder) {
+        let order_id = self.create_order_id();
+        self.order_ids.push_back(order_id);
         self.orders.push_back(order);
     }
 
@@ -282,6 +305,7 @@ impl OrderQueue {
     pub fn cancel_orders(&mut self) {
         self.canceled_orders.extend(self.orders.iter());
         self.orders.clear();
+        self.order_ids.clear();
     }
 
     /// Clear the queue of canceled orders.
@@ -289,6 +313,33 @@ impl OrderQueue {
         self.canceled_orders.clear();
     }
 
+    /// Cancel the order with the given id, if it is still in the queue.
+    pub fn cancel_order_with_id(&mut self, order_id: OrderID) {
+
+        let Some(index) = self.order_ids.iter().position(|&id| id == order_id) else { return; };
+
+        self.order_ids.remove(index);
+        if let Some(order) = self.orders.remove(index) {
+            self.canceled_orders.push_back(order);
+        }
+
+    }
+
+    /// Moves the order at the given position to another position in the queue, the order currently being processed is never moved.
+    pub fn move_order(&mut self, from: usize, to: usize) {
+
+        let is_move_valid = from > 0 && to > 0 && from < self.orders.len() && to < self.orders.len();
+        if is_move_valid == false {
+            return;
+        }
+
+        if let Some(order) = self.orders.remove(from) && let Some(order_id) = self.order_ids.remove(from) {
+            self.orders.insert(to, order);
+            self.order_ids.insert(to, order_id);
+        }
+
+    }
+
 }
 
 impl Orderable {
@@ -332,6 +383,14 @@ impl Orderable {
         }
     }
 
+    /// Returns the ids of the enqueued orders, in the same order as the orders themselves.
+    pub fn order_ids(&self, order_type: GameOrderType) -> &VecDeque<OrderID> {
+        match order_type {
+            GameOrderType::Order => self.order_queue.order_ids(),
+            GameOrderType::Construct => self.build_order_queue.order_ids(),
+        }
+    }
+
     /// Returns a reference to the collection of canceled orders.
     pub fn canceled_orders(&self, order_type: GameOrderType) -> &VecDeque<GameOrder> {
         match order_type {
@@ -388,6 +447,22 @@ impl Orderable {
         }
     }
 
+    /// Cancel the order with the given id, if it is still in the queue.
+    pub fn cancel_order_with_id(&mut self, order_type: GameOrderType, order_id: OrderID) {
+        match order_type {
+            GameOrderType::Order => self.order_queue.cancel_order_with_id(order_id),
+            GameOrderType::Construct => self.build_order_queue.cancel_order_with_id(order_id),
+        }
+    }
+
+    /// Moves the order at the given position to another position in the queue, the order currently being processed is never moved.
+    pub fn move_order(&mut self, order_type: GameOrderType, from: usize, to: usize) {
+        match order_type {
+            GameOrderType::Order => self.order_queue.move_order(from, to),
+            GameOrderType::Construct => self.build_order_queue.move_order(from, to),
+        }
+    }
+
     /// Returns true if there's any pending orders in the given order queue.
     pub fn has_pending_orders(&self, order_type: GameOrderType) -> bool {
         match order_type {
@@ -424,25 +499,28 @@ pub struct Health {
     current_health: f32,
     last_health: f32,
 
+    /// Damage taken since this was last reset, heals don't count.
+    damage_taken: f32,
+
     pub on_death: Option<fn(world: &World, buffer: &mut CommandBuffer, entity: Entity) -> ()>
 
 }
 
 impl Health {
     pub fn new(full_health: f32) -> Health {
-        Health { full_health, current_health: full_health, last_health: full_health, on_death: None }
+        Health { full_health, current_health: full_health, last_health: full_health, damage_taken: 0.0, on_death: None }
     }
 
     pub fn new_with_callback(full_health: f32, on_death_fn: fn(world: &World, buffer: &mut CommandBuffer, entity: Entity) -> ()) -> Health {
-        Health { full_health, current_health: full_health, last_health: full_health, on_death: Some(on_death_fn) }
+        Health { full_health, current_health: full_health, last_health: full_health, damage_taken: 0.0, on_death: Some(on_death_fn) }
     }
 
     pub fn new_with_current_health(full_health: f32, current_health: f32) -> Health {
-        Health { full_health, current_health, last_health: current_health, on_death: None }
+        Health { full_health, current_health, last_health: current_health, damage_taken: 0.0, on_death: None }
     }
 
     pub fn new_with_current_health_and_callback(full_health: f32, current_health: f32, on_death_fn: fn(world: &World, buffer: &mut CommandBuffer, entity: Entity) -> ()) -> Health {
-        Health { full_health, current_health, last_health: current_health, on_death: Some(on_death_fn) }
+        Health { full_health, current_health, last_health: current_health, damage_taken: 0.0, on_death: Some(on_death_fn) }
     }
 
     pub fn heal_to_full_health(&mut self) {
@@ -451,6 +529,9 @@ impl Health {
     }
 
     pub fn damage(&mut self, value: f32) {
+        if value > 0.0 {
+            self.damage_taken += value;
+        }
         self.last_health = self.current_health;
         self.current_health = (self.current_health - value).clamp(0.0, self.full_health); // ensure we don't go below zero or above max health, bad things will probably happen
     }
@@ -460,6 +541,11 @@ impl Health {
         self.damage(value);
     }
 
+    /// Returns the damage taken since the last time this was called.
+    pub fn take_damage_taken(&mut self) -> f32 {
+        std::mem::take(&mut self.damage_taken)
+    }
+
     pub fn kill(&mut self) {
         self.last_health = self.current_health;
         self.current_health = 0.0;
@@ -620,12 +706,77 @@ pub struct Spawner {
     pub position: Vec2
 }
 
+#[derive(Debug, Clone)]
+pub struct Factory {
+    /// The chain of rally points newly constructed units inherit as orders, in order.
+    pub rally_points: Vec<RallyPoint>,
+    /// When set, every completed unit is queued up again at the back of the construction queue.
+    pub is_repeating: bool
+}
+
+impl Factory {
+    pub fn new() -> Factory {
+        Factory { rally_points: Vec::new(), is_repeating: false }
+    }
+}
+
+/// Carries friendly units around, loaded units are taken out of the world until they are unloaded again.
+#[derive(Debug, Clone)]
+pub struct Transport {
+    pub capacity: i32,
+    pub load_range: f32,
+    pub passengers: Vec<Entity>
+}
+
+impl Transport {
+
+    pub fn new(capacity: i32, load_range: f32) -> Transport {
+        Transport { capacity, load_range, passengers: Vec::new() }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.passengers.len() as i32 >= self.capacity
+    }
+
+}
+
+/// Builds drones over time and keeps them docked, launching them when the carrier has something to attack and recalling them after.
+#[derive(Debug, Clone)]
+pub struct Hangar {
+    pub drone_blueprint_id: BlueprintID,
+    pub capacity: i32,
+    /// How long a single drone takes to build, in seconds.
+    pub build_time: f32,
+    pub build_progress: f32,
+    pub launch_range: f32,
+    /// Every drone belonging to this hangar, both the docked and the launched ones.
+    pub drones: Vec<Entity>
+}
+
+impl Hangar {
+    pub fn new(drone_blueprint_id: BlueprintID, capacity: i32, build_time: f32, launch_range: f32) -> Hangar {
+        Hangar { drone_blueprint_id, capacity, build_time, build_progress: 0.0, launch_range, drones: Vec::new() }
+    }
+}
+
+/// Marks a drone as belonging to the hangar of the given carrier.
+#[derive(Debug, Clone)]
+pub struct Drone {
+    pub carrier: Entity
+}
+
+/// An entity stowed away inside a transport or hangar, the body is kept here while loaded so that it takes no part in physics or spatial queries.
+#[derive(Clone)]
+pub struct Passenger {
+    pub transport: Entity,
+    pub body: DynamicBody
+}
+
 #[derive(Debug, Clone)]
 pub struct Blueprint {
     pub id: BlueprintID,
     pub name: String,
     pub texture: String,
-    pub shortcut: KeyCode,
     pub constructor: fn(&mut World, PlayerID, Vec2) -> Entity,
     pub is_building: bool,
     pub cost: Cost
@@ -737,4 +888,38 @@ impl Effect {
     }
 }
 
-pub struct Commander;
\ No newline at end of file
+pub struct Commander;
+
+#[cfg(test)]
+mod tests {
+
+    use crate::model::MoveOrder;
+    use super::*;
+
+    fn order_x_positions(orderable: &Orderable) -> Vec<f32> {
+        orderable.orders(GameOrderType::Order).iter().filter_map(|order| if let GameOrder::Move(MoveOrder { x, .. }) = order { Some(*x) } else { None }).collect()
+    }
+
+    #[test]
+    fn test_cancel_order_by_id_after_move() {
+
+        let mut orderable = Orderable::new();
+
+        for x in [0.0, 1.0, 2.0, 3.0] {
+            orderable.queue_order(GameOrder::Move(MoveOrder { x, y: 0.0 }));
+        }
+
+        let order_id_to_cancel = orderable.order_ids(GameOrderType::Order)[3];
+        orderable.move_order(GameOrderType::Order, 3, 1);
+        orderable.cancel_order_with_id(GameOrderType::Order, order_id_to_cancel);
+
+        assert_eq!(vec![0.0, 1.0, 2.0], order_x_positions(&orderable));
+        assert_eq!(1, orderable.canceled_orders(GameOrderType::Order).len());
+
+        // cancelling an order which is no longer in the queue does nothing
+        orderable.cancel_order_with_id(GameOrderType::Order, order_id_to_cancel);
+        assert_eq!(vec![0.0, 1.0, 2.0], order_x_positions(&orderable));
+
+    }
+
+}
@@ -16,6 +16,8 @@ pub enum Blueprints {
     Arrowhead = 7,
     Dragonfly = 8,
     Extractor = 9,
+    Carrier = 12,
+    Transport = 13,
 
     // green units
     Commissar = 10,
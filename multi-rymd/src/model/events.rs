@@ -0,0 +1,22 @@
+use hecs::Entity;
+use macroquad::math::Vec2;
+
+use crate::PlayerID;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponKind {
+    Projectile,
+    Beam
+}
+
+/// Things that happened during the last tick which the view might want to react to, the model clears these at the start of every tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RymdGameEvent {
+    WeaponFired { owner: PlayerID, position: Vec2, weapon: WeaponKind },
+    Impact { position: Vec2 },
+    ConstructionCompleted { entity: Entity, owner: PlayerID, position: Vec2, is_building: bool },
+    Destroyed { entity: Entity, owner: Option<PlayerID>, position: Vec2, is_building: bool },
+    UnderAttack { entity: Entity, owner: PlayerID, position: Vec2, is_building: bool },
+    ResourceSourceExhausted { entity: Entity, owner: PlayerID, position: Vec2 },
+    CommanderDestroyed { entity: Entity, owner: PlayerID, position: Vec2 }
+}
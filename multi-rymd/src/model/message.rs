@@ -1,9 +1,29 @@
 use nanoserde::{SerJson, DeJson};
 
-use crate::EntityID;
-use crate::model::GameOrder;
+use crate::{EntityID, PlayerID};
+use crate::model::{BlueprintID, GameOrder, GameSpeed, OrderID, RallyPoint, ResourcePriority};
 
 #[derive(Debug, SerJson, DeJson)]
 pub enum GameMessage {
     Order { entity: EntityID, order: GameOrder, add: bool },
+    CancelConstructionOrder { entity: EntityID, order_id: OrderID },
+    MoveConstructionOrder { entity: EntityID, from: i32, to: i32 },
+    QueueProduction { entity: EntityID, blueprint_id: BlueprintID, count: i32 },
+    SetProductionRepeat { entity: EntityID, is_repeating: bool },
+    SetRallyPoint { entity: EntityID, rally_point: RallyPoint, add: bool },
+    ClearRallyPoints { entity: EntityID },
+    SetResourcePriority { entity: EntityID, priority: ResourcePriority },
+    VoteGameSpeed { player: PlayerID, speed: GameSpeed },
+    SetGameSpeed { player: PlayerID, speed: GameSpeed },
+    SetPaused { player: PlayerID, is_paused: bool },
+    Cheat { player: PlayerID, cheat: CheatCommand }
+}
+
+/// Commands from the developer console, only executed when cheats were enabled in the lobby.
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub enum CheatCommand {
+    Spawn { owner: PlayerID, blueprint_id: BlueprintID, count: i32, x: f32, y: f32 },
+    GrantResources { owner: PlayerID, metal: f32, energy: f32 },
+    Kill { entities: Vec<EntityID> },
+    Heal { entities: Vec<EntityID> }
 }
\ No newline at end of file
@@ -0,0 +1,485 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use hecs::Entity;
+use macroquad::math::{vec2, Rect};
+use rhai::{Dynamic, Engine, FnPtr, Module, AST, FLOAT, INT};
+
+use crate::PlayerID;
+
+use super::{AttackMoveOrder, Blueprints, Controller, GameMessage, GameOrder, Health, MoveOrder, Orderable, RymdGameModel};
+
+/// Upper bound on how many operations a single script call may perform, stops a broken mission script from hanging the simulation.
+const MAX_MISSION_SCRIPT_OPERATIONS: u64 = 1_000_000;
+
+/// How long the mission keeps running after victory or defeat was declared, so the player has a chance to see the outcome.
+pub const MISSION_END_DELAY_TICKS: u64 = 60 * 5;
+
+/// Spacing between units spawned in a single batch by a mission script.
+const MISSION_SPAWN_SPACING: f32 = 32.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MissionResult {
+    InProgress,
+    Victory,
+    Defeat
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MissionObjectiveState {
+    Active,
+    Completed,
+    Failed
+}
+
+#[derive(Debug, Clone)]
+pub struct MissionObjective {
+    pub id: String,
+    pub text: String,
+    pub state: MissionObjectiveState
+}
+
+#[derive(Debug, Clone)]
+pub struct MissionMessage {
+    pub text: String,
+    pub tick: u64
+}
+
+/// Marks an entity as spawned by a mission script, scripts refer to groups of units by their tag.
+#[derive(Debug, Clone)]
+pub struct MissionTag {
+    pub name: String
+}
+
+#[derive(Clone)]
+enum MissionTrigger {
+    Tick { tick: u64, callback: FnPtr },
+    Region { player_id: PlayerID, bounds: Rect, callback: FnPtr },
+    TaggedRegion { tag: String, bounds: Rect, callback: FnPtr },
+    Destroyed { tag: String, has_seen_units: bool, callback: FnPtr }
+}
+
+#[derive(Clone)]
+enum MissionCommand {
+    SpawnUnits { player_id: PlayerID, blueprint_id: i32, position_x: f32, position_y: f32, count: i32, tag: String },
+    OrderUnits { tag: String, order: GameOrder },
+    AddTrigger(MissionTrigger),
+    ShowMessage(String),
+    SetObjective { id: String, text: String },
+    SetObjectiveState { id: String, state: MissionObjectiveState },
+    Victory,
+    Defeat
+}
+
+/// State shared between the mission and the functions exposed to the script, the script only ever queues commands which are then applied in order by the mission.
+struct MissionContext {
+    current_tick: u64,
+    players: Vec<PlayerID>,
+    enemy_player_id: PlayerID,
+    tagged_unit_counts: BTreeMap<String, i64>,
+    flags: BTreeMap<String, INT>,
+    commands: Vec<MissionCommand>
+}
+
+impl MissionContext {
+
+    fn new(players: Vec<PlayerID>, enemy_player_id: PlayerID) -> MissionContext {
+        MissionContext {
+            current_tick: 0,
+            players,
+            enemy_player_id,
+            tagged_unit_counts: BTreeMap::new(),
+            flags: BTreeMap::new(),
+            commands: Vec::new()
+        }
+    }
+
+}
+
+pub struct Mission {
+    engine: Engine,
+    script: AST,
+    context: Arc<Mutex<MissionContext>>,
+    triggers: Vec<MissionTrigger>,
+    is_started: bool,
+    pub name: String,
+    pub objectives: Vec<MissionObjective>,
+    pub messages: Vec<MissionMessage>,
+    pub result: MissionResult,
+    pub result_tick: u64
+}
+
+impl Mission {
+
+    pub fn new(name: &str, script_source: &str, players: Vec<PlayerID>, enemy_player_id: PlayerID) -> Result<Mission, String> {
+
+        let context = Arc::new(Mutex::new(MissionContext::new(players, enemy_player_id)));
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_MISSION_SCRIPT_OPERATIONS);
+        register_mission_api(&mut engine, &context);
+
+        let script = engine.compile(script_source).map_err(|e| format!("could not compile mission script: {}, error: {}", name, e))?;
+
+        Ok(Mission {
+            engine,
+            script,
+            context,
+            triggers: Vec::new(),
+            is_started: false,
+            name: name.to_string(),
+            objectives: Vec::new(),
+            messages: Vec::new(),
+            result: MissionResult::InProgress,
+            result_tick: 0
+        })
+
+    }
+
+    pub fn is_finished(&self, current_tick: u64) -> bool {
+        self.result != MissionResult::InProgress && current_tick >= self.result_tick + MISSION_END_DELAY_TICKS
+    }
+
+    fn has_function(&self, name: &str) -> bool {
+        self.script.iter_functions().any(|f| f.name == name)
+    }
+
+    fn update_context(&mut self, model: &RymdGameModel) {
+
+        let mut tagged_unit_counts = BTreeMap::new();
+        for (e, tag) in model.world.query::<&MissionTag>().iter() {
+            *tagged_unit_counts.entry(tag.name.clone()).or_insert(0) += 1;
+        }
+
+        let mut context = self.context.lock().unwrap();
+        context.current_tick = model.current_tick;
+        context.tagged_unit_counts = tagged_unit_counts;
+
+    }
+
+    fn call_script_function(&mut self, model: &mut RymdGameModel, callback: &FnPtr) {
+
+        if let Err(error) = callback.call::<Dynamic>(&self.engine, &self.script, ()) {
+            println!("[Mission] error in mission script: {} when calling: {}, error: {}", self.name, callback.fn_name(), error);
+        }
+
+        self.apply_commands(model);
+
+    }
+
+    fn is_any_unit_of_player_in_region(model: &RymdGameModel, player_id: PlayerID, bounds: Rect) -> bool {
+        model.spatial_manager.entities_within_rect(bounds).any(|e| {
+            model.world.get::<&Controller>(e).map(|c| c.id == player_id).unwrap_or(false)
+        })
+    }
+
+    fn is_any_tagged_unit_in_region(model: &RymdGameModel, tag: &str, bounds: Rect) -> bool {
+        model.spatial_manager.entities_within_rect(bounds).any(|e| {
+            model.world.get::<&MissionTag>(e).map(|t| t.name == tag).unwrap_or(false)
+        })
+    }
+
+    fn should_trigger_fire(trigger: &mut MissionTrigger, model: &RymdGameModel, tagged_unit_counts: &BTreeMap<String, i64>) -> bool {
+        match trigger {
+            MissionTrigger::Tick { tick, .. } => model.current_tick >= *tick,
+            MissionTrigger::Region { player_id, bounds, .. } => Self::is_any_unit_of_player_in_region(model, *player_id, *bounds),
+            MissionTrigger::TaggedRegion { tag, bounds, .. } => Self::is_any_tagged_unit_in_region(model, tag, *bounds),
+            MissionTrigger::Destroyed { tag, has_seen_units, .. } => {
+                let number_of_units = tagged_unit_counts.get(tag).copied().unwrap_or(0);
+                *has_seen_units = *has_seen_units || number_of_units > 0;
+                *has_seen_units && number_of_units == 0
+            }
+        }
+    }
+
+    pub fn tick(&mut self, model: &mut RymdGameModel) {
+
+        if self.result != MissionResult::InProgress {
+            return;
+        }
+
+        self.update_context(model);
+
+        if self.is_started == false {
+
+            self.is_started = true;
+
+            if self.has_function("on_start") {
+                let on_start_callback = FnPtr::new("on_start").expect("on_start is always a valid function name!");
+                self.call_script_function(model, &on_start_callback);
+            }
+
+        }
+
+        let tagged_unit_counts = self.context.lock().unwrap().tagged_unit_counts.clone();
+
+        // all triggers fire at most once, pull the ones that fired out first so callbacks adding new triggers don't disturb the iteration
+        let mut fired_triggers = Vec::new();
+        let mut remaining_triggers = Vec::new();
+
+        for mut trigger in std::mem::take(&mut self.triggers) {
+            if Self::should_trigger_fire(&mut trigger, model, &tagged_unit_counts) {
+                fired_triggers.push(trigger);
+            } else {
+                remaining_triggers.push(trigger);
+            }
+        }
+
+        self.triggers = remaining_triggers;
+
+        for trigger in fired_triggers {
+
+            let callback = match trigger {
+                MissionTrigger::Tick { callback, .. } => callback,
+                MissionTrigger::Region { callback, .. } => callback,
+                MissionTrigger::TaggedRegion { callback, .. } => callback,
+                MissionTrigger::Destroyed { callback, .. } => callback
+            };
+
+            self.call_script_function(model, &callback);
+
+            if self.result != MissionResult::InProgress {
+                break;
+            }
+
+        }
+
+    }
+
+    fn spawn_units(model: &mut RymdGameModel, player_id: PlayerID, blueprint_id: i32, position_x: f32, position_y: f32, count: i32, tag: &str) {
+
+        if model.player_mapping.contains_key(&player_id) == false {
+            println!("[Mission] tried to spawn units for unknown player: {}", player_id);
+            return;
+        }
+
+        let Some(blueprint) = model.blueprint_manager.get_blueprint(blueprint_id) else {
+            println!("[Mission] tried to spawn unknown blueprint: {}", blueprint_id);
+            return;
+        };
+
+        let blueprint_constructor = blueprint.constructor;
+
+        // lay the units out in a square grid centered on the requested position
+        let units_per_row = (count as f32).sqrt().ceil().max(1.0) as i32;
+        let grid_offset = (units_per_row - 1) as f32 * MISSION_SPAWN_SPACING * 0.5;
+
+        for i in 0..count {
+
+            let unit_offset = vec2((i % units_per_row) as f32, (i / units_per_row) as f32) * MISSION_SPAWN_SPACING - vec2(grid_offset, grid_offset);
+            let new_unit = blueprint_constructor(&mut model.world, player_id, vec2(position_x, position_y) + unit_offset);
+
+            if let Ok(mut health) = model.world.get::<&mut Health>(new_unit) {
+                health.heal_to_full_health();
+            }
+
+            if tag.is_empty() == false {
+                let _ = model.world.insert_one(new_unit, MissionTag { name: tag.to_string() });
+            }
+
+        }
+
+    }
+
+    fn order_units(model: &mut RymdGameModel, tag: &str, order: GameOrder) {
+
+        let mut entities_to_order: Vec<Entity> = model.world.query::<(&MissionTag, &Orderable)>().iter()
+            .filter(|(e, (mission_tag, _))| mission_tag.name == tag)
+            .map(|(e, _)| e)
+            .collect();
+
+        entities_to_order.sort();
+
+        for e in entities_to_order {
+            model.handle_message(&GameMessage::Order { entity: e.to_bits().into(), order, add: false });
+        }
+
+    }
+
+    fn set_objective_state(&mut self, id: &str, state: MissionObjectiveState) {
+        if let Some(objective) = self.objectives.iter_mut().find(|o| o.id == id) {
+            objective.state = state;
+        }
+    }
+
+    fn apply_commands(&mut self, model: &mut RymdGameModel) {
+
+        let commands = std::mem::take(&mut self.context.lock().unwrap().commands);
+
+        for command in commands {
+            match command {
+                MissionCommand::SpawnUnits { player_id, blueprint_id, position_x, position_y, count, tag } => {
+                    Self::spawn_units(model, player_id, blueprint_id, position_x, position_y, count, &tag);
+                },
+                MissionCommand::OrderUnits { tag, order } => {
+                    Self::order_units(model, &tag, order);
+                },
+                MissionCommand::AddTrigger(trigger) => {
+                    self.triggers.push(trigger);
+                },
+                MissionCommand::ShowMessage(text) => {
+                    self.messages.push(MissionMessage { text, tick: model.current_tick });
+                },
+                MissionCommand::SetObjective { id, text } => {
+                    if let Some(objective) = self.objectives.iter_mut().find(|o| o.id == id) {
+                        objective.text = text;
+                    } else {
+                        self.objectives.push(MissionObjective { id, text, state: MissionObjectiveState::Active });
+                    }
+                },
+                MissionCommand::SetObjectiveState { id, state } => {
+                    self.set_objective_state(&id, state);
+                },
+                MissionCommand::Victory => {
+                    if self.result == MissionResult::InProgress {
+                        self.result = MissionResult::Victory;
+                        self.result_tick = model.current_tick;
+                    }
+                },
+                MissionCommand::Defeat => {
+                    if self.result == MissionResult::InProgress {
+                        self.result = MissionResult::Defeat;
+                        self.result_tick = model.current_tick;
+                    }
+                }
+            }
+        }
+
+    }
+
+}
+
+fn create_blueprints_module() -> Module {
+
+    let mut module = Module::new();
+
+    module.set_var("SHIPYARD", Blueprints::Shipyard as INT);
+    module.set_var("SOLAR_COLLECTOR", Blueprints::SolarCollector as INT);
+    module.set_var("ENERGY_STORAGE", Blueprints::EnergyStorage as INT);
+    module.set_var("METAL_STORAGE", Blueprints::MetalStorage as INT);
+    module.set_var("ENERGY_CONVERTER", Blueprints::EnergyConverter as INT);
+
+    module.set_var("COMMANDER", Blueprints::Commander as INT);
+    module.set_var("ARROWHEAD", Blueprints::Arrowhead as INT);
+    module.set_var("DRAGONFLY", Blueprints::Dragonfly as INT);
+    module.set_var("EXTRACTOR", Blueprints::Extractor as INT);
+    module.set_var("CARRIER", Blueprints::Carrier as INT);
+    module.set_var("TRANSPORT", Blueprints::Transport as INT);
+
+    module.set_var("COMMISSAR", Blueprints::Commissar as INT);
+    module.set_var("GRUNT", Blueprints::Grunt as INT);
+
+    module
+
+}
+
+fn register_mission_api(engine: &mut Engine, context: &Arc<Mutex<MissionContext>>) {
+
+    engine.register_static_module("blueprints", create_blueprints_module().into());
+
+    // queries
+
+    let ctx = context.clone();
+    engine.register_fn("current_tick", move || ctx.lock().unwrap().current_tick as INT);
+
+    let ctx = context.clone();
+    engine.register_fn("player", move |index: INT| {
+        let context = ctx.lock().unwrap();
+        context.players.get(index as usize).copied().unwrap_or(-1)
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("enemy", move || ctx.lock().unwrap().enemy_player_id);
+
+    let ctx = context.clone();
+    engine.register_fn("count_units", move |tag: &str| ctx.lock().unwrap().tagged_unit_counts.get(tag).copied().unwrap_or(0) as INT);
+
+    let ctx = context.clone();
+    engine.register_fn("get_flag", move |name: &str| ctx.lock().unwrap().flags.get(name).copied().unwrap_or(0));
+
+    let ctx = context.clone();
+    engine.register_fn("set_flag", move |name: &str, value: INT| {
+        ctx.lock().unwrap().flags.insert(name.to_string(), value);
+    });
+
+    // units
+
+    let ctx = context.clone();
+    engine.register_fn("spawn_units", move |player_id: INT, blueprint_id: INT, x: FLOAT, y: FLOAT, count: INT, tag: &str| {
+        let spawn_command = MissionCommand::SpawnUnits { player_id, blueprint_id: blueprint_id as i32, position_x: x, position_y: y, count: count as i32, tag: tag.to_string() };
+        ctx.lock().unwrap().commands.push(spawn_command);
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("move_units", move |tag: &str, x: FLOAT, y: FLOAT| {
+        let move_order = GameOrder::Move(MoveOrder { x, y });
+        ctx.lock().unwrap().commands.push(MissionCommand::OrderUnits { tag: tag.to_string(), order: move_order });
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("attack_move_units", move |tag: &str, x: FLOAT, y: FLOAT| {
+        let attack_move_order = GameOrder::AttackMove(AttackMoveOrder { x, y });
+        ctx.lock().unwrap().commands.push(MissionCommand::OrderUnits { tag: tag.to_string(), order: attack_move_order });
+    });
+
+    // triggers
+
+    let ctx = context.clone();
+    engine.register_fn("on_tick", move |tick: INT, callback: FnPtr| {
+        let tick_trigger = MissionTrigger::Tick { tick: tick.max(0) as u64, callback };
+        ctx.lock().unwrap().commands.push(MissionCommand::AddTrigger(tick_trigger));
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("on_region_entered", move |player_id: INT, x: FLOAT, y: FLOAT, w: FLOAT, h: FLOAT, callback: FnPtr| {
+        let region_trigger = MissionTrigger::Region { player_id, bounds: Rect::new(x, y, w, h), callback };
+        ctx.lock().unwrap().commands.push(MissionCommand::AddTrigger(region_trigger));
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("on_region_entered_by", move |tag: &str, x: FLOAT, y: FLOAT, w: FLOAT, h: FLOAT, callback: FnPtr| {
+        let tagged_region_trigger = MissionTrigger::TaggedRegion { tag: tag.to_string(), bounds: Rect::new(x, y, w, h), callback };
+        ctx.lock().unwrap().commands.push(MissionCommand::AddTrigger(tagged_region_trigger));
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("on_units_destroyed", move |tag: &str, callback: FnPtr| {
+        let destroyed_trigger = MissionTrigger::Destroyed { tag: tag.to_string(), has_seen_units: false, callback };
+        ctx.lock().unwrap().commands.push(MissionCommand::AddTrigger(destroyed_trigger));
+    });
+
+    // objectives and messages
+
+    let ctx = context.clone();
+    engine.register_fn("show_message", move |text: &str| {
+        ctx.lock().unwrap().commands.push(MissionCommand::ShowMessage(text.to_string()));
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("set_objective", move |id: &str, text: &str| {
+        ctx.lock().unwrap().commands.push(MissionCommand::SetObjective { id: id.to_string(), text: text.to_string() });
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("complete_objective", move |id: &str| {
+        ctx.lock().unwrap().commands.push(MissionCommand::SetObjectiveState { id: id.to_string(), state: MissionObjectiveState::Completed });
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("fail_objective", move |id: &str| {
+        ctx.lock().unwrap().commands.push(MissionCommand::SetObjectiveState { id: id.to_string(), state: MissionObjectiveState::Failed });
+    });
+
+    // outcome
+
+    let ctx = context.clone();
+    engine.register_fn("victory", move || {
+        ctx.lock().unwrap().commands.push(MissionCommand::Victory);
+    });
+
+    let ctx = context.clone();
+    engine.register_fn("defeat", move || {
+        ctx.lock().unwrap().commands.push(MissionCommand::Defeat);
+    });
+
+}
@@ -1,9 +1,13 @@
 use core::f32;
 use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
 
-use hecs::{CommandBuffer, Entity, World};
+use deterministic_hash::DeterministicHasher;
+use fnv::FnvHasher;
+
+use hecs::{CommandBuffer, Entity, Or, Without, World};
 use macroquad::*;
-use math::Vec2;
+use math::{vec2, Vec2};
 use rand::RandGenerator;
 use utility::separation;
 use utility::AsVector;
@@ -23,6 +27,8 @@ use super::are_players_allied;
 use super::are_players_hostile;
 use super::create_commissar_ship_blueprint;
 use super::create_dragonfly_ship_blueprint;
+use super::create_carrier_ship_blueprint;
+use super::create_transport_ship_blueprint;
 use super::create_energy_converter_blueprint;
 use super::create_extractor_ship_blueprint;
 use super::create_grunt_ship_blueprint;
@@ -31,14 +37,21 @@ use super::entity_apply_raw_steering;
 use super::is_within_extractor_range_with_extractor;
 use super::spatial::entity_distance_sort_function;
 use super::spatial::SpatialQueryManager;
+use super::parallel::parallel_filter_map;
+use super::calculate_entity_steering_facing_target;
+use super::calculate_entity_steering_towards_target;
+use super::entity_apply_steering_output;
 use super::steer_entity_towards_target;
 use super::AnimatedSprite;
 use super::Beam;
 use super::Building;
+use super::CapturePoints;
 use super::BulletParameters;
 use super::Decayer;
+use super::ConstructOrder;
 use super::ExtractOrder;
 use super::Extractor;
+use super::Factory;
 use super::MovementTarget;
 use super::OrdersExt;
 use super::PreviousTransform;
@@ -51,18 +64,27 @@ use super::point_entity_towards_target;
 use super::Attackable;
 use super::Attacker;
 use super::BlueprintIdentity;
+use super::Commander;
 use super::Constructor;
 use super::Controller;
 use super::Energy;
 use super::EntityState;
 use super::GameOrderType;
+use super::GameSpeedState;
+use super::CheatCommand;
 use super::Health;
 use super::Metal;
+use super::Mission;
+use super::RymdGameEvent;
+use super::WeaponKind;
 use super::PhysicsBody;
 use super::PhysicsManager;
+use super::OrderID;
 use super::Player;
 use super::Consumer;
 use super::Powered;
+use super::ResourceFlow;
+use super::ResourcePriority;
 use super::Producer;
 use super::Projectile;
 use super::Storage;
@@ -74,12 +96,15 @@ use super::create_energy_storage_blueprint;
 use super::create_metal_storage_blueprint;
 use super::create_shipyard_blueprint;
 use super::create_solar_collector_blueprint;
-use super::current_energy;
-use super::current_metal;
 use super::provide_energy;
 use super::provide_metal;
 use super::ProjectileWeapon;
+use super::RallyPoint;
+use super::Spawner;
 use super::{GameOrder, Orderable, Transform, DynamicBody, Blueprint};
+use super::{has_any_pending_orders, is_passenger, release_passenger, stow_passenger, AttackOrder, Drone, Hangar, MoveOrder, Passenger, Transport, HANGAR_DOCKING_RANGE};
+
+pub type RymdGameSystem = fn(&mut RymdGameModel);
 
 pub struct RymdGameModel {
     pub physics_manager: PhysicsManager,
@@ -88,6 +113,13 @@ pub struct RymdGameModel {
     pub player_mapping: BTreeMap<PlayerID, Player>,
     pub random: RandGenerator,
     pub world: World,
+    pub mission: Option<Mission>,
+    pub capture_points: Option<CapturePoints>,
+    pub events: Vec<RymdGameEvent>,
+    pub speed: GameSpeedState,
+    pub are_cheats_enabled: bool,
+    /// Spreads the systems which only read from the world over the worker threads, the results are the same either way.
+    pub is_parallelism_enabled: bool,
     pub current_tick: u64
 }
 
@@ -117,11 +149,15 @@ fn create_blue_side_blueprints() -> BTreeMap<i32, Blueprint> {
     let arrowhead_ship_blueprint = create_arrowhead_ship_blueprint();
     let extractor_ship_blueprint = create_extractor_ship_blueprint();
     let dragonfly_ship_blueprint = create_dragonfly_ship_blueprint();
+    let carrier_ship_blueprint = create_carrier_ship_blueprint();
+    let transport_ship_blueprint = create_transport_ship_blueprint();
 
     blueprints.insert(commander_ship_blueprint.id, commander_ship_blueprint);
     blueprints.insert(arrowhead_ship_blueprint.id, arrowhead_ship_blueprint);
     blueprints.insert(extractor_ship_blueprint.id, extractor_ship_blueprint);
     blueprints.insert(dragonfly_ship_blueprint.id, dragonfly_ship_blueprint);
+    blueprints.insert(carrier_ship_blueprint.id, carrier_ship_blueprint);
+    blueprints.insert(transport_ship_blueprint.id, transport_ship_blueprint);
 
     blueprints
 
@@ -166,6 +202,16 @@ impl BlueprintManager {
         self.blueprints.get(&id)
     }
 
+    pub fn blueprints(&self) -> impl Iterator<Item = &Blueprint> {
+        self.blueprints.values()
+    }
+
+    /// Finds a blueprint by name ignoring case, spaces and underscores are considered the same.
+    pub fn find_blueprint_by_name(&self, name: &str) -> Option<&Blueprint> {
+        let normalized_name = name.to_lowercase().replace('_', " ");
+        self.blueprints.values().find(|blueprint| blueprint.name.to_lowercase() == normalized_name)
+    }
+
 }
 
 trait RandomHelpersExt {
@@ -183,6 +229,38 @@ impl RymdGameModel {
     pub const TIME_STEP: f32 = 1.0 / 60.0;
     pub const SPATIAL_BUCKET_SIZE: i32 = 256;
 
+    /// All the systems that make up a tick of the simulation, in the order they are executed.
+    pub const SYSTEMS: [(&'static str, RymdGameSystem); 28] = [
+        ("constructing_entities", Self::tick_constructing_entities),
+        ("resource_storage", Self::tick_resource_storage),
+        ("orderables", Self::tick_orderables),
+        ("resource_flow", Self::tick_resource_flow),
+        ("transforms", Self::tick_transforms),
+        ("resources", Self::tick_resources),
+        ("resource_sources", Self::tick_resource_sources),
+        ("rotation_targets", Self::tick_rotation_targets),
+        ("movement_targets", Self::tick_movement_targets),
+        ("separation", Self::tick_separation),
+        ("attackers", Self::tick_attackers),
+        ("projectile_weapons", Self::tick_projectile_weapons),
+        ("beam_weapons", Self::tick_beam_weapons),
+        ("projectiles", Self::tick_projectiles),
+        ("beams", Self::tick_beams),
+        ("effects", Self::tick_effects),
+        ("constructors", Self::tick_constructors),
+        ("extractors", Self::tick_extractors),
+        ("decayers", Self::tick_decayers),
+        ("transports", Self::tick_transports),
+        ("hangars", Self::tick_hangars),
+        ("physics_engine", Self::tick_physics_engine),
+        ("spatial_engine", Self::tick_spatial_engine),
+        ("transform_updates", Self::tick_transform_updates),
+        ("damage_events", Self::tick_damage_events),
+        ("lifetimes", Self::tick_lifetimes),
+        ("mission", Self::tick_mission),
+        ("capture_points", Self::tick_capture_points)
+    ];
+
     pub fn new() -> RymdGameModel {
         RymdGameModel {
             physics_manager: PhysicsManager::new(Self::TIME_STEP),
@@ -191,6 +269,12 @@ impl RymdGameModel {
             player_mapping: BTreeMap::new(),
             random: RandGenerator::new(),
             world: World::new(),
+            mission: None,
+            capture_points: None,
+            events: Vec::new(),
+            speed: GameSpeedState::new(),
+            are_cheats_enabled: false,
+            is_parallelism_enabled: true,
             current_tick: 0
         }
     }
@@ -213,6 +297,9 @@ impl RymdGameModel {
     pub fn stop(&mut self) {
         self.physics_manager.clear();
         self.world.clear();
+        self.mission = None;
+        self.capture_points = None;
+        self.events.clear();
     }
 
     fn handle_order(&mut self, entity_id: EntityID, order: GameOrder, should_add: bool) {
@@ -229,12 +316,162 @@ impl RymdGameModel {
         
     }
 
+    fn handle_cancel_construction_order(&mut self, entity_id: EntityID, order_id: OrderID) {
+
+        let Some(entity) = Entity::from_bits(entity_id) else { return };
+        if let Ok(orderable) = self.world.query_one_mut::<&mut Orderable>(entity) {
+            orderable.cancel_order_with_id(GameOrderType::Construct, order_id);
+        }
+
+    }
+
+    fn handle_move_construction_order(&mut self, entity_id: EntityID, from: i32, to: i32) {
+
+        let Some(entity) = Entity::from_bits(entity_id) else { return };
+        if let Ok(orderable) = self.world.query_one_mut::<&mut Orderable>(entity) && from >= 0 && to >= 0 {
+            orderable.move_order(GameOrderType::Construct, from as usize, to as usize);
+        }
+
+    }
+
+    fn handle_queue_production(&mut self, entity_id: EntityID, blueprint_id: BlueprintID, count: i32) {
+
+        let maximum_number_to_queue = 100;
+
+        let Some(entity) = Entity::from_bits(entity_id) else { return };
+        let Ok((transform, spawner, constructor, orderable)) = self.world.query_one_mut::<(&Transform, &Spawner, &Constructor, &mut Orderable)>(entity) else { return };
+
+        if constructor.has_blueprint(blueprint_id) == false {
+            return;
+        }
+
+        let spawn_position = transform.world_position + spawner.position;
+        for _ in 0..count.clamp(0, maximum_number_to_queue) {
+            let construct_order = ConstructOrder { entity_id: None, blueprint_id: Some(blueprint_id), is_self_order: true, x: spawn_position.x, y: spawn_position.y };
+            orderable.queue_order(GameOrder::Construct(construct_order));
+        }
+
+    }
+
+    fn handle_set_production_repeat(&mut self, entity_id: EntityID, is_repeating: bool) {
+
+        let Some(entity) = Entity::from_bits(entity_id) else { return };
+        if let Ok(factory) = self.world.query_one_mut::<&mut Factory>(entity) {
+            factory.is_repeating = is_repeating;
+        }
+
+    }
+
+    fn handle_set_rally_point(&mut self, entity_id: EntityID, rally_point: RallyPoint, should_add: bool) {
+
+        let Some(entity) = Entity::from_bits(entity_id) else { return };
+        if let Ok(factory) = self.world.query_one_mut::<&mut Factory>(entity) {
+            if should_add == false {
+                factory.rally_points.clear();
+            }
+            factory.rally_points.push(rally_point);
+        }
+
+    }
+
+    fn handle_clear_rally_points(&mut self, entity_id: EntityID) {
+
+        let Some(entity) = Entity::from_bits(entity_id) else { return };
+        if let Ok(factory) = self.world.query_one_mut::<&mut Factory>(entity) {
+            factory.rally_points.clear();
+        }
+
+    }
+
+    fn handle_set_resource_priority(&mut self, entity_id: EntityID, priority: ResourcePriority) {
+
+        let Some(entity) = Entity::from_bits(entity_id) else { return };
+        if self.world.satisfies::<Or<&Consumer, &Constructor>>(entity).unwrap_or(false) {
+            let _ = self.world.insert_one(entity, priority);
+        }
+
+    }
+
     pub fn handle_message(&mut self, message: &GameMessage) {
 
         // println!("[RymdGameModel] got message: {:?}", message);
 
         match message {
             GameMessage::Order { entity, order, add } => self.handle_order(*entity, *order, *add),
+            GameMessage::CancelConstructionOrder { entity, order_id } => self.handle_cancel_construction_order(*entity, *order_id),
+            GameMessage::MoveConstructionOrder { entity, from, to } => self.handle_move_construction_order(*entity, *from, *to),
+            GameMessage::QueueProduction { entity, blueprint_id, count } => self.handle_queue_production(*entity, *blueprint_id, *count),
+            GameMessage::SetProductionRepeat { entity, is_repeating } => self.handle_set_production_repeat(*entity, *is_repeating),
+            GameMessage::SetRallyPoint { entity, rally_point, add } => self.handle_set_rally_point(*entity, *rally_point, *add),
+            GameMessage::ClearRallyPoints { entity } => self.handle_clear_rally_points(*entity),
+            GameMessage::SetResourcePriority { entity, priority } => self.handle_set_resource_priority(*entity, *priority),
+            GameMessage::VoteGameSpeed { player, speed } => self.speed.vote(*player, *speed),
+            GameMessage::SetGameSpeed { player, speed } => self.speed.set_speed_as_host(*player, *speed),
+            GameMessage::SetPaused { player, is_paused } => self.speed.set_paused(*player, *is_paused),
+            GameMessage::Cheat { player, cheat } => self.handle_cheat(*player, cheat)
+        }
+
+    }
+
+    fn handle_cheat(&mut self, player_id: PlayerID, cheat: &CheatCommand) {
+
+        if self.are_cheats_enabled == false {
+            println!("[RymdGameModel] player: {} tried to cheat with cheats disabled, ignoring: {:?}", player_id, cheat);
+            return;
+        }
+
+        println!("[RymdGameModel] player: {} cheated: {:?}", player_id, cheat);
+
+        match cheat {
+            CheatCommand::Spawn { owner, blueprint_id, count, x, y } => self.handle_cheat_spawn(*owner, *blueprint_id, *count, vec2(*x, *y)),
+            CheatCommand::GrantResources { owner, metal, energy } => {
+                provide_metal(*owner, &self.world, *metal, 0.0);
+                provide_energy(*owner, &self.world, *energy, 0.0);
+            },
+            CheatCommand::Kill { entities } => {
+                for entity in entities.iter().filter_map(|&entity_id| Entity::from_bits(entity_id)) {
+                    if let Ok(mut health) = self.world.get::<&mut Health>(entity) {
+                        health.kill();
+                    }
+                }
+            },
+            CheatCommand::Heal { entities } => {
+                for entity in entities.iter().filter_map(|&entity_id| Entity::from_bits(entity_id)) {
+                    if let Ok(mut health) = self.world.get::<&mut Health>(entity) {
+                        health.heal_to_full_health();
+                    }
+                }
+            }
+        }
+
+    }
+
+    fn handle_cheat_spawn(&mut self, owner: PlayerID, blueprint_id: BlueprintID, count: i32, position: Vec2) {
+
+        let maximum_number_to_spawn = 100;
+        let spacing_between_spawned = 48.0;
+
+        if self.player_mapping.contains_key(&owner) == false {
+            return;
+        }
+
+        let Some(blueprint) = self.blueprint_manager.get_blueprint(blueprint_id) else { return };
+        let blueprint_constructor = blueprint.constructor;
+
+        // lay them out in a square grid around the position, so a whole army doesn't spawn inside itself
+        let number_to_spawn = count.clamp(0, maximum_number_to_spawn);
+        let grid_size = (number_to_spawn as f32).sqrt().ceil() as i32;
+
+        for i in 0..number_to_spawn {
+
+            let grid_offset = vec2((i % grid_size) as f32, (i / grid_size) as f32) - vec2((grid_size - 1) as f32, (grid_size - 1) as f32) / 2.0;
+            let spawned_entity = blueprint_constructor(&mut self.world, owner, position + grid_offset * spacing_between_spawned);
+
+            // at full health it counts as constructed on the next tick, just like anything built normally
+            if let Ok(mut health) = self.world.get::<&mut Health>(spawned_entity) {
+                health.heal_to_full_health();
+            }
+
         }
 
     }
@@ -242,6 +479,8 @@ impl RymdGameModel {
     //#[profiling::function]
     fn tick_constructing_entities(&mut self) {
 
+        let mut constructed_entities = Vec::new();
+
         for (e, (state, health, body, building)) in self.world.query_mut::<(&mut EntityState, &Health, Option<&mut DynamicBody>, Option<&Building>)>() {
 
             let is_entity_ghost = *state == EntityState::Ghost;
@@ -252,6 +491,7 @@ impl RymdGameModel {
 
             // all entities that are ghosts which reach full health are then considered constructed
             *state = EntityState::Constructed;
+            constructed_entities.push(e);
 
             let Some(body) = body else { continue; };
 
@@ -262,19 +502,41 @@ impl RymdGameModel {
 
         }
 
-    }
+        // factories are still pointing at what they just finished, so this is where we find out which units came out of which factory
+        let mut produced_entities = Vec::new();
+        for (e, (factory, constructor)) in self.world.query::<(&Factory, &Constructor)>().iter() {
+            if let Some(current_target) = constructor.current_target && constructed_entities.contains(&current_target) {
+                produced_entities.push((e, current_target, factory.rally_points.clone(), factory.is_repeating));
+            }
+        }
 
-    //#[profiling::function]
-    fn tick_powered_entities(&mut self) {
+        for (factory_entity, produced_entity, rally_points, is_repeating) in produced_entities {
 
-        for (e, (state, controller, consumer, _powered)) in self.world.query::<(&mut EntityState, &Controller, &Consumer, &Powered)>().iter() {
-            if *state == EntityState::Constructed || *state == EntityState::Inactive {
-                if consumer.energy * Self::TIME_STEP >= current_energy(controller.id, &self.world) {
-                    *state = EntityState::Inactive
-                } else {
-                    *state = EntityState::Constructed;
+            let rally_orders: Vec<GameOrder> = rally_points.iter().filter_map(|rally_point| rally_point.to_order(produced_entity, self)).collect();
+            if let Ok(mut orderable) = self.world.get::<&mut Orderable>(produced_entity) {
+                for order in rally_orders {
+                    orderable.queue_order(order);
                 }
-            }       
+            }
+
+            if is_repeating == false {
+                continue;
+            }
+
+            let Ok(blueprint_id) = self.world.get::<&BlueprintIdentity>(produced_entity).map(|identity| identity.blueprint_id) else { continue; };
+            let Ok((transform, spawner, orderable)) = self.world.query_one_mut::<(&Transform, &Spawner, &mut Orderable)>(factory_entity) else { continue; };
+
+            let spawn_position = transform.world_position + spawner.position;
+            let construct_order = ConstructOrder { entity_id: None, blueprint_id: Some(blueprint_id), is_self_order: true, x: spawn_position.x, y: spawn_position.y };
+            orderable.queue_order(GameOrder::Construct(construct_order));
+
+        }
+
+        for e in constructed_entities {
+            let Ok(controller) = self.world.get::<&Controller>(e) else { continue; };
+            let Some(position) = get_entity_position(&self.world, e) else { continue; };
+            let is_building = self.world.satisfies::<&Building>(e).unwrap_or(false);
+            self.events.push(RymdGameEvent::ConstructionCompleted { entity: e, owner: controller.id, position, is_building });
         }
 
     }
@@ -380,8 +642,15 @@ impl RymdGameModel {
             rotation_targets.push((e, target_position));
         }
 
-        for (e, target_position) in rotation_targets {
-            point_entity_towards_target(&mut self.world, e, target_position.x, target_position.y, Self::TIME_STEP);
+        rotation_targets.sort_by_key(|(e, _)| *e);
+
+        let world = &self.world;
+        let steering_output_to_apply = parallel_filter_map(&rotation_targets, self.is_parallelism_enabled, |&(e, target_position)| {
+            calculate_entity_steering_facing_target(world, e, target_position.x, target_position.y).map(|output| (e, output))
+        });
+
+        for (e, steering_output) in steering_output_to_apply {
+            entity_apply_steering_output(&self.world, e, steering_output, Self::TIME_STEP);
         }
 
     }
@@ -396,15 +665,22 @@ impl RymdGameModel {
             move_targets.push((e, target_position));
         }
 
-        for (e, target_position) in move_targets {
-            steer_entity_towards_target(&mut self.world, e, target_position.x, target_position.y, Self::TIME_STEP);
+        move_targets.sort_by_key(|(e, _)| *e);
+
+        let world = &self.world;
+        let steering_output_to_apply = parallel_filter_map(&move_targets, self.is_parallelism_enabled, |&(e, target_position)| {
+            calculate_entity_steering_towards_target(world, e, target_position.x, target_position.y).map(|output| (e, output))
+        });
+
+        for (e, steering_output) in steering_output_to_apply {
+            entity_apply_steering_output(&self.world, e, steering_output, Self::TIME_STEP);
         }
 
     }
 
     fn tick_separation(&mut self) {
 
-        let mut steering_output_to_apply = Vec::new();
+        let mut separating_entities = Vec::new();
 
         for (e, (dynamic_body, steering)) in self.world.query::<(&DynamicBody, &Steering)>().iter() {
 
@@ -412,12 +688,22 @@ impl RymdGameModel {
                 continue;
             }
 
-            let steering_parameters = steering.parameters;
-            let nearby_entities = self.spatial_manager.entities_within_radius(dynamic_body.position(), steering_parameters.separation_threshold);
-            let nearby_entities_with_dynamic_body = nearby_entities.filter(|o| e != *o).filter_map(|e| self.world.get::<&DynamicBody>(e).map(|b| b.kinematic.clone()).ok());
+            separating_entities.push((e, dynamic_body.kinematic.clone(), steering.parameters));
+
+        }
+
+        separating_entities.sort_by_key(|(e, ..)| *e);
+
+        let world = &self.world;
+        let spatial_manager = &self.spatial_manager;
+
+        let steering_output_to_apply = parallel_filter_map(&separating_entities, self.is_parallelism_enabled, |(e, kinematic, steering_parameters)| {
+
+            let nearby_entities = spatial_manager.entities_within_radius(kinematic.position, steering_parameters.separation_threshold);
+            let nearby_entities_with_dynamic_body = nearby_entities.filter(|o| e != o).filter_map(|o| world.get::<&DynamicBody>(o).map(|b| b.kinematic.clone()).ok());
 
             let steering_output = separation(
-                &dynamic_body.kinematic,
+                kinematic,
                 nearby_entities_with_dynamic_body,
                 steering_parameters.max_acceleration,
                 steering_parameters.separation_threshold,
@@ -425,10 +711,12 @@ impl RymdGameModel {
             );
 
             if steering_output.linear.length() > f32::EPSILON {
-                steering_output_to_apply.push((e, steering_output));
+                Some((*e, steering_output))
+            } else {
+                None
             }
 
-        }
+        });
 
         for (e, steering_output) in steering_output_to_apply {
             let mut dynamic_body = self.world.get::<&mut DynamicBody>(e).expect("must have DynamicBody to get here!");
@@ -440,77 +728,91 @@ impl RymdGameModel {
     //#[profiling::function]
     fn tick_attackers(&mut self) {
 
-        let mut attack_targets = BTreeMap::new();
+        struct AttackerCandidate {
+            entity: Entity,
+            controller_id: PlayerID,
+            position: Vec2,
+            range: f32,
+            can_acquire_targets: bool
+        }
 
-        // search for targets in range and accumulate
+        let mut attacker_candidates = Vec::new();
 
-        for (e, (controller, attacker, transform, orderable, &state)) in self.world.query::<(&Controller, &mut Attacker, &Transform, &Orderable, &EntityState)>().iter() {
+        for (e, (controller, attacker, transform, orderable, &state)) in self.world.query::<(&Controller, &Attacker, &Transform, &Orderable, &EntityState)>().iter() {
 
             if orderable.is_current_order_attack_order() {
                 continue;
             }
 
-            attacker.target = None; // reset current target every time we tick the attackers
+            let is_current_order_queue_empty = orderable.is_queue_empty(GameOrderType::Order);
+            let is_current_order_attack_move = orderable.is_current_order_attack_move_order();
+            let can_acquire_targets = state == EntityState::Constructed && (is_current_order_queue_empty || is_current_order_attack_move);
 
-            for o in self.spatial_manager.entities_within_radius(transform.world_position, attacker.range) {
+            attacker_candidates.push(AttackerCandidate {
+                entity: e,
+                controller_id: controller.id,
+                position: transform.world_position,
+                range: attacker.range,
+                can_acquire_targets
+            });
 
-                if e == o {
-                    continue;
-                }
+        }
+
+        attacker_candidates.sort_by_key(|c| c.entity);
+
+        // search for the closest target in range of each attacker, this only reads from the world so can be done in parallel
 
-                if self.world.contains(o) == false {
+        let world = &self.world;
+        let spatial_manager = &self.spatial_manager;
+        let player_mapping = &self.player_mapping;
+
+        let attack_targets = parallel_filter_map(&attacker_candidates, self.is_parallelism_enabled, |candidate| {
+
+            if candidate.can_acquire_targets == false {
+                return Some((candidate.entity, None));
+            }
+
+            let mut closest_target_distance = f32::MAX;
+            let mut closest_target = None;
+
+            for o in spatial_manager.entities_within_radius(candidate.position, candidate.range) {
+
+                if candidate.entity == o {
                     continue;
                 }
 
-                let mut other_query = self.world.query_one::<(&Controller, &Attackable, &Transform, &EntityState)>(o).unwrap();
+                let Ok(mut other_query) = world.query_one::<(&Controller, &Attackable, &Transform, &EntityState)>(o) else { continue };
                 let Some((other_controller, other_attackable, other_transform, other_state)) = other_query.get() else { continue };
 
-                let can_attack = self.is_controller_attackable_by(controller.id, other_controller);
-                let is_current_order_queue_empty = orderable.is_queue_empty(GameOrderType::Order);
-                let is_current_order_attack_move = orderable.is_current_order_attack_move_order();
-
-                if state != EntityState::Constructed || (is_current_order_queue_empty == false && is_current_order_attack_move == false) || can_attack == false {
-                    continue
+                let can_attack = are_players_hostile(&player_mapping[&candidate.controller_id], &player_mapping[&other_controller.id]);
+                if can_attack == false {
+                    continue;
                 }
 
-                let is_in_attack_range = transform.world_position.distance(other_transform.world_position) <= attacker.range;
-
-                if is_in_attack_range {
-                    let entry = attack_targets.entry(e).or_insert(vec![]);
-                    entry.push(o);
+                let d = candidate.position.distance(other_transform.world_position);
+                if d <= candidate.range && d < closest_target_distance {
+                    closest_target_distance = d;
+                    closest_target = Some((o, other_transform.world_position));
                 }
 
             }
 
-        }
-
-        // now filter targets and pick one unless we have a direct attack order
+            Some((candidate.entity, closest_target))
 
-        for (e, targets) in attack_targets {
+        });
 
-            let attacker_position = get_entity_position(&self.world, e).unwrap();
+        // now apply the targets in entity order, resetting the target of anything that found nothing
 
-            let mut closest_target_distance = f32::MAX;
-            let mut closest_target = Entity::DANGLING;
-            let mut closest_target_position = None;
-
-            for t in targets {
-                let target_position = get_entity_position(&self.world, t).unwrap();
-                let d = attacker_position.distance(target_position);
-                if d < closest_target_distance {
-                    closest_target_position = Some(target_position);
-                    closest_target_distance = d;
-                    closest_target = t;
-                }
-            }
+        for (e, closest_target) in attack_targets {
 
             // #TODO: depending on unit stance, either turn towards the target, or actually pursue it when attacking
 
-            if let Some(position) = closest_target_position {
+            if let Some((target, position)) = closest_target {
                 point_entity_towards_target(&mut self.world, e, position.x, position.y, Self::TIME_STEP);
-                if let Ok(mut attacker) = self.world.get::<&mut Attacker>(e) {
-                    attacker.target = Some(closest_target);
-                }
+            }
+
+            if let Ok(mut attacker) = self.world.get::<&mut Attacker>(e) {
+                attacker.target = closest_target.map(|(target, _)| target);
             }
 
         }
@@ -564,6 +866,7 @@ impl RymdGameModel {
 
         for projectile in queued_projectile_creations {
             create_simple_bullet(&mut self.world, projectile.owner, projectile.position, projectile.direction);
+            self.events.push(RymdGameEvent::WeaponFired { owner: projectile.owner, position: projectile.position, weapon: WeaponKind::Projectile });
         }
 
     }
@@ -614,6 +917,7 @@ impl RymdGameModel {
 
         for projectile in queued_beam_creations {
             create_simple_beam(&mut self.world, projectile.owner, projectile.position, projectile.direction);
+            self.events.push(RymdGameEvent::WeaponFired { owner: projectile.owner, position: projectile.position, weapon: WeaponKind::Beam });
         }  
 
     }
@@ -650,6 +954,7 @@ impl RymdGameModel {
             }
 
             create_impact_effect_in_world(&mut self.world, position, hit_normal);
+            self.events.push(RymdGameEvent::Impact { position });
 
         }
 
@@ -671,57 +976,75 @@ impl RymdGameModel {
 
     }
 
-    //#[profiling::function]
-    fn tick_constructors(&mut self) {
+    /// Returns the metal and energy per second the constructor wants to spend on its current target and the health per second this would build, if it has a target.
+    fn calculate_constructor_demand(&self, constructor: &Constructor) -> Option<(f32, f32, f32)> {
 
-        for (e, (controller, constructor)) in self.world.query::<(&Controller, &mut Constructor)>().iter() {
+        let constructing_entity = constructor.current_target?;
 
-            // we must have a current entity we're constructing for any of this logic to make sense
-            let Some(constructing_entity) = constructor.current_target else { continue; };
+        let entity_health = self.world.get::<&Health>(constructing_entity).expect("entity must have the Health component");
+        let entity_blueprint_identity = self.world.get::<&BlueprintIdentity>(constructing_entity).expect("entity must have blueprint identity to be able to identify cost!");
+        let entity_blueprint = self.blueprint_manager.get_blueprint(entity_blueprint_identity.blueprint_id).expect("entity must have blueprint!");
 
-            let mut entity_health = self.world.get::<&mut Health>(constructing_entity).expect("entity must have the Health component");
-            let entity_blueprint_identity = self.world.get::<&BlueprintIdentity>(constructing_entity).expect("entity must have blueprint identity to be able to identify cost!");
-            let entity_blueprint = self.blueprint_manager.get_blueprint(entity_blueprint_identity.blueprint_id).expect("entity must have blueprint!");
+        let entity_metal_cost = entity_blueprint.cost.metal;
+        let entity_energy_cost = entity_blueprint.cost.energy;
 
-            let entity_metal_cost = entity_blueprint.cost.metal;
-            let entity_energy_cost = entity_blueprint.cost.energy;
+        // #HACK: this is a little hackety sax
+        let entity_remaining_health_fraction = 1.0 - entity_health.current_health_fraction();
+        
+        let entity_remaining_metal_cost = entity_metal_cost * entity_remaining_health_fraction;
+        let entity_remaining_energy_cost = entity_energy_cost * entity_remaining_health_fraction;
 
-            // #HACK: this is a little hackety sax
-            let entity_remaining_health_fraction = 1.0 - entity_health.current_health_fraction();
-            
-            let entity_remaining_metal_cost = entity_metal_cost * entity_remaining_health_fraction;
-            let entity_remaining_energy_cost = entity_energy_cost * entity_remaining_health_fraction;
+        // build power/metal cost should actually be the max of the build speed and remaining cost, this way build speed actually matters
+        let build_power_metal_cost = (constructor.build_speed as f32).max(entity_remaining_metal_cost);
+        let build_power_energy_cost = (constructor.build_speed as f32).max(entity_remaining_energy_cost);
 
-            let available_metal = current_metal(controller.id, &self.world);
-            let available_energy = current_energy(controller.id, &self.world);
+        let build_power_metal_proportion = build_power_metal_cost / entity_remaining_metal_cost;
+        let build_power_energy_proportion = build_power_energy_cost / entity_remaining_energy_cost;
+        let min_available_proportion_with_build_power = build_power_metal_proportion.min(build_power_energy_proportion);
 
-            // build power/metal cost should actually be the max of the build speed and remaining cost, we clamp this later (this way build speed actually matters)
-            let build_power_metal_cost = (constructor.build_speed as f32).max(entity_remaining_metal_cost);
-            let build_power_energy_cost = (constructor.build_speed as f32).max(entity_remaining_energy_cost);
-            
-            let available_metal_proportion = available_metal / entity_remaining_metal_cost;
-            let available_energy_proportion = available_energy / entity_remaining_energy_cost;
-            let min_available_proportion = available_metal_proportion.min(available_energy_proportion);
+        let metal_demand = entity_remaining_metal_cost * min_available_proportion_with_build_power;
+        let energy_demand = entity_remaining_energy_cost * min_available_proportion_with_build_power;
+
+        let entity_health_proportion = entity_remaining_health_fraction * entity_health.full_health();
+        let entity_health_regain_amount = entity_health_proportion * min_available_proportion_with_build_power;
+
+        Some((metal_demand, energy_demand, entity_health_regain_amount))
+
+    }
+
+    fn resource_flows_per_player(&self) -> BTreeMap<PlayerID, ResourceFlow> {
+        self.world.query::<(&Player, &ResourceFlow)>().iter().map(|(e, (player, resource_flow))| (player.id, *resource_flow)).collect()
+    }
+
+    //#[profiling::function]
+    fn tick_constructors(&mut self) {
+
+        let resource_flows_per_player = self.resource_flows_per_player();
 
-            let build_power_metal_proportion = build_power_metal_cost / entity_remaining_metal_cost;
-            let build_power_energy_proportion = build_power_energy_cost / entity_remaining_energy_cost;
-            let min_available_proportion_with_build_power = build_power_metal_proportion.min(build_power_energy_proportion);
+        for (e, (controller, constructor, priority)) in self.world.query::<(&Controller, &Constructor, Option<&ResourcePriority>)>().iter() {
+
+            // we must have a current entity we're constructing for any of this logic to make sense
+            let Some(constructing_entity) = constructor.current_target else { continue; };
+            let Some((metal_demand, energy_demand, entity_health_regain_amount)) = self.calculate_constructor_demand(constructor) else { continue; };
 
-            // here we make sure that if our build speed exceeds our actually available resources, we take the minimum so we can actually use as much as is actually available
-            let clamped_min_available_proportion_with_build_power = min_available_proportion_with_build_power.min(min_available_proportion);
+            // when stalling we only get a fraction of what we asked for, the same fraction as everything else of the same priority
+            let priority = priority.copied().unwrap_or(ResourcePriority::Normal);
+            let resource_flow = resource_flows_per_player.get(&controller.id).copied().unwrap_or_else(ResourceFlow::new);
+            let supplied_fraction = resource_flow.fraction_for(priority, metal_demand > 0.0, energy_demand > 0.0);
 
-            let metal_to_consume = entity_remaining_metal_cost * clamped_min_available_proportion_with_build_power;
-            let energy_to_consume = entity_remaining_energy_cost * clamped_min_available_proportion_with_build_power;
+            let metal_to_consume_this_tick = metal_demand * supplied_fraction * Self::TIME_STEP;
+            let energy_to_consume_this_tick = energy_demand * supplied_fraction * Self::TIME_STEP;
 
-            let metal_to_consume_this_tick = metal_to_consume * Self::TIME_STEP;
-            let energy_to_consume_this_tick = energy_to_consume * Self::TIME_STEP;
+            let metal_consumed = consume_metal(controller.id, &self.world, metal_to_consume_this_tick, Self::TIME_STEP);
+            let energy_consumed = consume_energy(controller.id, &self.world, energy_to_consume_this_tick, Self::TIME_STEP);
 
-            consume_metal(controller.id, &self.world, metal_to_consume_this_tick, Self::TIME_STEP);
-            consume_energy(controller.id, &self.world, energy_to_consume_this_tick, Self::TIME_STEP);
+            // the flow is an estimate made at the start of the tick, so only build as much as we actually managed to pay for
+            let metal_paid_fraction = if metal_to_consume_this_tick > 0.0 { metal_consumed / metal_to_consume_this_tick } else { 1.0 };
+            let energy_paid_fraction = if energy_to_consume_this_tick > 0.0 { energy_consumed / energy_to_consume_this_tick } else { 1.0 };
+            let paid_fraction = metal_paid_fraction.min(energy_paid_fraction);
 
-            let entity_health_proportion = entity_remaining_health_fraction * entity_health.full_health();
-            let entity_health_regain_amount = entity_health_proportion * clamped_min_available_proportion_with_build_power;
-            let entity_repair_health = entity_health_regain_amount * Self::TIME_STEP;
+            let mut entity_health = self.world.get::<&mut Health>(constructing_entity).expect("entity must have the Health component");
+            let entity_repair_health = entity_health_regain_amount * supplied_fraction * paid_fraction * Self::TIME_STEP;
             entity_health.heal(entity_repair_health);
             
         }
@@ -865,54 +1188,112 @@ impl RymdGameModel {
 
     }
     
+    //#[profiling::function]
+    fn tick_resource_flow(&mut self) {
+
+        // per player: (metal demand, energy demand) per priority
+        let mut demand_per_player: BTreeMap<PlayerID, ([f32; 3], [f32; 3])> = BTreeMap::new();
+
+        for (e, (controller, &state, consumer, priority)) in self.world.query::<(&Controller, &EntityState, &Consumer, Option<&ResourcePriority>)>().iter() {
+
+            if state != EntityState::Constructed {
+                continue;
+            }
+
+            let priority = priority.copied().unwrap_or(ResourcePriority::Normal) as usize;
+            let entry = demand_per_player.entry(controller.id).or_insert(([0.0; 3], [0.0; 3]));
+            entry.0[priority] += consumer.metal;
+            entry.1[priority] += consumer.energy;
+
+        }
+
+        for (e, (controller, constructor, priority)) in self.world.query::<(&Controller, &Constructor, Option<&ResourcePriority>)>().iter() {
+
+            let Some((metal_demand, energy_demand, _)) = self.calculate_constructor_demand(constructor) else { continue; };
+
+            let priority = priority.copied().unwrap_or(ResourcePriority::Normal) as usize;
+            let entry = demand_per_player.entry(controller.id).or_insert(([0.0; 3], [0.0; 3]));
+            entry.0[priority] += metal_demand;
+            entry.1[priority] += energy_demand;
+
+        }
+
+        for (e, (player, metal, energy, resource_flow)) in self.world.query_mut::<(&Player, &Metal, &Energy, &mut ResourceFlow)>() {
+
+            let (metal_demand, energy_demand) = demand_per_player.get(&player.id).copied().unwrap_or(([0.0; 3], [0.0; 3]));
+
+            // what can be spent per second this tick is whatever is in the pool plus what came in last tick
+            let metal_supply = (metal.current + metal.income * Self::TIME_STEP) / Self::TIME_STEP;
+            let energy_supply = (energy.current + energy.income * Self::TIME_STEP) / Self::TIME_STEP;
+
+            resource_flow.metal_demand = metal_demand;
+            resource_flow.energy_demand = energy_demand;
+            resource_flow.metal_fraction = ResourceFlow::distribute(&metal_demand, metal_supply);
+            resource_flow.energy_fraction = ResourceFlow::distribute(&energy_demand, energy_supply);
+
+        }
+
+    }
+
     //#[profiling::function]
     fn tick_resources(&mut self) {
 
-        let mut energy_incomes_per_player = BTreeMap::new();
+        let resource_flows_per_player = self.resource_flows_per_player();
 
-        for (e, (controller, &state, consumer, producer)) in self.world.query::<(&Controller, &EntityState, Option<&Consumer>, Option<&Producer>)>().iter() {
+        // per player: (metal income, metal expense, energy income, energy expense)
+        let mut resource_totals_per_player = BTreeMap::new();
 
-            let mut total_metal_income = 0.0;
-            let mut total_energy_income = 0.0;
+        for (e, (controller, &state, consumer, producer, powered, priority)) in self.world.query::<(&Controller, &EntityState, Option<&Consumer>, Option<&Producer>, Option<&Powered>, Option<&ResourcePriority>)>().iter() {
+
+            let entry = resource_totals_per_player.entry(controller.id).or_insert((0.0, 0.0, 0.0, 0.0));
+            let resource_flow = resource_flows_per_player.get(&controller.id).copied().unwrap_or_else(ResourceFlow::new);
+            let priority = priority.copied().unwrap_or(ResourcePriority::Normal);
+
+            // consumers only get the fraction of their upkeep that could be supplied this tick
+            let supplied_fraction = if let Some(consumer) = consumer {
+                resource_flow.fraction_for(priority, consumer.metal > 0.0, consumer.energy > 0.0)
+            } else {
+                1.0
+            };
 
             if let Some(consumer) = consumer && state == EntityState::Constructed {
-                consume_metal(controller.id, &self.world, consumer.metal * Self::TIME_STEP, 0.0);
-                consume_energy(controller.id, &self.world, consumer.energy * Self::TIME_STEP, 0.0);
-                total_metal_income -= consumer.metal;
-                total_energy_income -= consumer.energy;
+                consume_metal(controller.id, &self.world, consumer.metal * supplied_fraction * Self::TIME_STEP, 0.0);
+                consume_energy(controller.id, &self.world, consumer.energy * supplied_fraction * Self::TIME_STEP, 0.0);
+                entry.1 += consumer.metal * supplied_fraction;
+                entry.3 += consumer.energy * supplied_fraction;
             }
 
+            // powered producers only produce as much as they were supplied with
+            let produced_fraction = if powered.is_some() { supplied_fraction } else { 1.0 };
+
             if let Some(producer) = producer && state == EntityState::Constructed {
-                provide_metal(controller.id, &self.world, producer.metal * Self::TIME_STEP, 0.0);
-                provide_energy(controller.id, &self.world, producer.energy * Self::TIME_STEP, 0.0);
-                total_metal_income += producer.metal;
-                total_energy_income += producer.energy;
+                provide_metal(controller.id, &self.world, producer.metal * produced_fraction * Self::TIME_STEP, 0.0);
+                provide_energy(controller.id, &self.world, producer.energy * produced_fraction * Self::TIME_STEP, 0.0);
+                entry.0 += producer.metal * produced_fraction;
+                entry.2 += producer.energy * produced_fraction;
             }
 
-            let entry = energy_incomes_per_player.entry(controller.id).or_insert((0.0, 0.0));
-            entry.0 += total_metal_income;
-            entry.1 += total_energy_income;
-
         }
 
         for (e, (player, metal, energy)) in self.world.query_mut::<(&Player, &mut Metal, &mut Energy)>() {
-            if let Some((metal_income, energy_income)) = energy_incomes_per_player.get(&player.id) {
-                metal.income = *metal_income;
-                energy.income = *energy_income;
-            } else {
-                metal.income = 0.0;
-                energy.income = 0.0;
-            }
+            let (metal_income, metal_expense, energy_income, energy_expense) = resource_totals_per_player.get(&player.id).cloned().unwrap_or((0.0, 0.0, 0.0, 0.0));
+            metal.income = metal_income;
+            metal.expense = metal_expense;
+            energy.income = energy_income;
+            energy.expense = energy_expense;
         }
 
     }
 
     fn tick_resource_sources(&mut self) {
 
+        let mut exhausted_resource_sources = Vec::new();
+
         for (e, (resource_source, health)) in self.world.query::<(&mut ResourceSource, &mut Health)>().iter() {
 
             if resource_source.is_finite {
-                if resource_source.is_exhausted() {
+                if resource_source.is_exhausted() && health.is_at_or_below_zero_health() == false {
+                    exhausted_resource_sources.push(e);
                     health.kill();
                 }
                 continue;
@@ -923,6 +1304,25 @@ impl RymdGameModel {
 
         }
 
+        // let everyone who was extracting from the source know it ran dry, each player only once
+        for e in exhausted_resource_sources {
+
+            let Some(position) = get_entity_position(&self.world, e) else { continue; };
+            let mut owners_extracting = Vec::new();
+
+            for (_, (controller, extractor)) in self.world.query::<(&Controller, &Extractor)>().iter() {
+                let is_extracting_from_source = extractor.current_target == Some(e) || extractor.last_target == Some(e);
+                if is_extracting_from_source && owners_extracting.contains(&controller.id) == false {
+                    owners_extracting.push(controller.id);
+                }
+            }
+
+            for owner in owners_extracting {
+                self.events.push(RymdGameEvent::ResourceSourceExhausted { entity: e, owner, position });
+            }
+
+        }
+
     }
 
     //#[profiling::function]
@@ -983,9 +1383,170 @@ impl RymdGameModel {
 
     }
 
+    //#[profiling::function]
+    fn tick_mission(&mut self) {
+
+        // the mission needs the whole model to do its work, so take it out while it runs
+        let Some(mut mission) = self.mission.take() else { return; };
+        mission.tick(self);
+        self.mission = Some(mission);
+
+    }
+
+    //#[profiling::function]
+    fn tick_transports(&mut self) {
+
+        // whatever is inside a transport or hangar goes down with it
+        let mut stranded_passengers = Vec::new();
+
+        for (e, passenger) in self.world.query::<&Passenger>().iter() {
+            let is_transport_alive = self.world.contains(passenger.transport)
+                && self.world.get::<&Health>(passenger.transport).map_or(true, |health| health.is_at_or_below_zero_health() == false);
+            if is_transport_alive == false {
+                stranded_passengers.push(e);
+            }
+        }
+
+        for e in stranded_passengers {
+            if let Ok(mut health) = self.world.get::<&mut Health>(e) {
+                health.kill();
+            }
+        }
+
+        for (e, transport) in self.world.query::<&mut Transport>().iter() {
+            transport.passengers.retain(|&passenger| self.world.contains(passenger));
+        }
+
+    }
+
+    //#[profiling::function]
+    fn tick_hangars(&mut self) {
+
+        let mut drones_to_build = Vec::new();
+        let mut drones_to_launch = Vec::new();
+        let mut drones_to_recall = Vec::new();
+
+        for (e, (controller, transform, hangar, attacker, &state)) in self.world.query::<(&Controller, &Transform, &mut Hangar, Option<&Attacker>, &EntityState)>().iter() {
+
+            hangar.drones.retain(|&drone| self.world.contains(drone));
+
+            if state != EntityState::Constructed {
+                continue;
+            }
+
+            // drones are only launched at whatever the carrier itself is targeting, and only once it is close enough
+            let launch_target = attacker
+                .and_then(|attacker| attacker.target)
+                .filter(|&target| get_entity_position(&self.world, target).is_some_and(|p| p.distance(transform.world_position) <= hangar.launch_range));
+
+            for &drone in &hangar.drones {
+                let is_docked = is_passenger(&self.world, drone);
+                match launch_target {
+                    Some(target) if is_docked => drones_to_launch.push((drone, e, target)),
+                    None if is_docked == false => drones_to_recall.push((drone, e)),
+                    _ => ()
+                }
+            }
+
+            if (hangar.drones.len() as i32) < hangar.capacity {
+                drones_to_build.push((e, controller.id, hangar.drone_blueprint_id, hangar.build_time));
+            }
+
+        }
+
+        for (e, owner, blueprint_id, build_time) in drones_to_build {
+            self.build_hangar_drone(e, owner, blueprint_id, build_time);
+        }
+
+        for (drone, carrier, target) in drones_to_launch {
+
+            let Some(carrier_position) = get_entity_position(&self.world, carrier) else { continue; };
+            release_passenger(self, drone, carrier_position);
+
+            if let Ok(mut orderable) = self.world.get::<&mut Orderable>(drone) {
+                orderable.queue_order(GameOrder::Attack(AttackOrder::new(target)));
+            }
+
+        }
+
+        for (drone, carrier) in drones_to_recall {
+
+            let is_drone_busy = has_any_pending_orders(&self.world, drone) || self.world.get::<&Attacker>(drone).is_ok_and(|attacker| attacker.target.is_some());
+            if is_drone_busy {
+                continue;
+            }
+
+            let (Some(drone_position), Some(carrier_position)) = (get_entity_position(&self.world, drone), get_entity_position(&self.world, carrier)) else { continue; };
+
+            if drone_position.distance(carrier_position) <= HANGAR_DOCKING_RANGE {
+                stow_passenger(self, carrier, drone);
+            } else if let Ok(mut orderable) = self.world.get::<&mut Orderable>(drone) {
+                orderable.queue_order(GameOrder::Move(MoveOrder { x: carrier_position.x, y: carrier_position.y }));
+            }
+
+        }
+
+    }
+
+    /// Pays for the drone being built by the hangar bit by bit, once it is paid for it is docked in the hangar right away.
+    fn build_hangar_drone(&mut self, carrier: Entity, owner: PlayerID, blueprint_id: BlueprintID, build_time: f32) {
+
+        let Some(blueprint) = self.blueprint_manager.get_blueprint(blueprint_id) else { return; };
+        let drone_constructor = blueprint.constructor;
+        let drone_cost = blueprint.cost.clone();
+
+        let metal_needed = drone_cost.metal * Self::TIME_STEP / build_time;
+        let energy_needed = drone_cost.energy * Self::TIME_STEP / build_time;
+
+        let metal_paid = consume_metal(owner, &self.world, metal_needed, Self::TIME_STEP);
+        let energy_paid = consume_energy(owner, &self.world, energy_needed, Self::TIME_STEP);
+
+        let metal_paid_fraction = if metal_needed > 0.0 { metal_paid / metal_needed } else { 1.0 };
+        let energy_paid_fraction = if energy_needed > 0.0 { energy_paid / energy_needed } else { 1.0 };
+
+        {
+            let Ok(mut hangar) = self.world.get::<&mut Hangar>(carrier) else { return; };
+            hangar.build_progress += Self::TIME_STEP * metal_paid_fraction.min(energy_paid_fraction);
+
+            if hangar.build_progress < build_time {
+                return;
+            }
+
+            hangar.build_progress = 0.0;
+        }
+
+        let Some(carrier_position) = get_entity_position(&self.world, carrier) else { return; };
+        let drone = (drone_constructor)(&mut self.world, owner, carrier_position);
+
+        if let Ok(mut health) = self.world.get::<&mut Health>(drone) {
+            health.heal_to_full_health();
+        }
+
+        if let Ok(mut state) = self.world.get::<&mut EntityState>(drone) {
+            *state = EntityState::Constructed;
+        }
+
+        let _ = self.world.insert_one(drone, Drone { carrier });
+
+        if let Ok(mut hangar) = self.world.get::<&mut Hangar>(carrier) {
+            hangar.drones.push(drone);
+        }
+
+        stow_passenger(self, carrier, drone);
+
+    }
+
+    //#[profiling::function]
+    fn tick_capture_points(&mut self) {
+
+        let Some(capture_points) = &mut self.capture_points else { return; };
+        capture_points.tick(&self.world, &self.spatial_manager, self.current_tick);
+
+    }
+
     //#[profiling::function]
     fn tick_physics_engine(&mut self) {
-        self.physics_manager.tick(&mut self.world);
+        self.physics_manager.tick(&mut self.world, &mut self.events);
     }
 
     //#[profiling::function]
@@ -1004,6 +1565,29 @@ impl RymdGameModel {
     }
 
     //#[profiling::function]
+    fn tick_damage_events(&mut self) {
+
+        let mut damaged_entities = Vec::new();
+
+        for (e, (health, controller, &state)) in self.world.query_mut::<(&mut Health, &Controller, &EntityState)>() {
+
+            // always drain the damage taken, so that damage taken while something was a ghost doesn't show up later
+            let damage_taken = health.take_damage_taken();
+
+            if damage_taken > 0.0 && state == EntityState::Constructed {
+                damaged_entities.push((e, controller.id));
+            }
+
+        }
+
+        for (e, owner) in damaged_entities {
+            let Some(position) = get_entity_position(&self.world, e) else { continue; };
+            let is_building = self.world.satisfies::<&Building>(e).unwrap_or(false);
+            self.events.push(RymdGameEvent::UnderAttack { entity: e, owner, position, is_building });
+        }
+
+    }
+
     fn tick_lifetimes(&mut self) {
 
         let mut destroyed_entities = Vec::new();
@@ -1028,6 +1612,21 @@ impl RymdGameModel {
                 (on_death_fn)(&self.world, &mut command_buffer, e);
             }
 
+            // projectiles have health too, but only units report being destroyed
+            let is_unit = self.world.satisfies::<Without<&Health, &Projectile>>(e).unwrap_or(false);
+
+            if is_unit && let Some(position) = get_entity_position(&self.world, e) {
+
+                let owner = self.world.get::<&Controller>(e).ok().map(|c| c.id);
+                let is_building = self.world.satisfies::<&Building>(e).unwrap_or(false);
+                self.events.push(RymdGameEvent::Destroyed { entity: e, owner, position, is_building });
+
+                if let Some(owner) = owner && self.world.satisfies::<&Commander>(e).unwrap_or(false) {
+                    self.events.push(RymdGameEvent::CommanderDestroyed { entity: e, owner, position });
+                }
+
+            }
+
             let result = self.world.despawn(e);
             
             if let Err(error) = result {
@@ -1072,6 +1671,22 @@ impl RymdGameModel {
         are_players_allied(self.get_player_by_id(controller_id), self.get_player_by_id(target_controller.id))
     }
 
+    /// Returns every other player allied with the given player.
+    pub fn allies_of(&self, player_id: PlayerID) -> Vec<PlayerID> {
+        self.player_mapping.keys()
+            .copied()
+            .filter(|&other_player_id| other_player_id != player_id && self.is_player_friendly_to(player_id, other_player_id))
+            .collect()
+    }
+
+    /// Unlike the other checks this one is fine to call with players not in the game, they are never friendly to anyone.
+    pub fn is_player_friendly_to(&self, player_id: PlayerID, other_player_id: PlayerID) -> bool {
+        match (self.player_mapping.get(&player_id), self.player_mapping.get(&other_player_id)) {
+            (Some(player), Some(other_player)) => are_players_allied(player, other_player),
+            _ => false
+        }
+    }
+
     pub fn is_controller_controllable_by(&self, controller_id: PlayerID, target_controller: &Controller) -> bool {
         controller_id == target_controller.id // #TODO: alliances, teams?
     }
@@ -1084,34 +1699,50 @@ impl RymdGameModel {
         }
     }
 
-    pub fn tick(&mut self) {
-        
-        self.tick_constructing_entities();
-        self.tick_powered_entities();
-        self.tick_resource_storage();
-        self.tick_orderables();
-        self.tick_transforms();
-        self.tick_resources();
-        self.tick_resource_sources();
-        self.tick_rotation_targets();
-        self.tick_movement_targets();
-        self.tick_separation();
-        self.tick_attackers();
-        self.tick_projectile_weapons();
-        self.tick_beam_weapons();
-        self.tick_projectiles();
-        self.tick_beams();
-        self.tick_effects();
-        self.tick_constructors();
-        self.tick_extractors();
-        self.tick_decayers();
-        self.tick_physics_engine();
-        self.tick_spatial_engine();
-        self.tick_transform_updates();
-        self.tick_lifetimes();
+    /// Hashes the state of every entity relevant to the simulation, useful for checking if two instances of the simulation are in sync.
+    pub fn state_hash(&self) -> u64 {
+
+        let mut hasher = DeterministicHasher::new(FnvHasher::default());
+        self.current_tick.hash(&mut hasher);
+
+        let mut entity_states = Vec::new();
+        for (e, (transform, health, state)) in self.world.query::<(&Transform, Option<&Health>, Option<&EntityState>)>().iter() {
+            let current_health = health.map(|h| h.current_health()).unwrap_or(0.0);
+            let current_state = state.map(|s| *s as u8).unwrap_or(u8::MAX);
+            entity_states.push((e.to_bits().get(), transform.world_position.x.to_bits(), transform.world_position.y.to_bits(), transform.world_rotation.to_bits(), current_health.to_bits(), current_state));
+        }
+
+        // query order depends on archetypes, so sort by entity id to make sure we always hash in the same order
+        entity_states.sort_by_key(|(id, ..)| *id);
+        entity_states.hash(&mut hasher);
+
+        for (_, (player, metal, energy)) in self.world.query::<(&Player, &Metal, &Energy)>().iter() {
+            player.id.hash(&mut hasher);
+            metal.current.to_bits().hash(&mut hasher);
+            energy.current.to_bits().hash(&mut hasher);
+        }
+
+        hasher.finish()
+
+    }
+
+    /// Runs every system of a single tick through the passed function, allowing the caller to wrap each system (for example to measure it).
+    pub fn tick_with<F>(&mut self, mut run_system_fn: F)
+        where F: FnMut(&mut RymdGameModel, &'static str, RymdGameSystem)
+    {
+
+        self.events.clear();
+
+        for (system_name, system) in Self::SYSTEMS {
+            run_system_fn(self, system_name, system);
+        }
 
         self.current_tick += 1;
 
     }
 
+    pub fn tick(&mut self) {
+        self.tick_with(|model, _, system| system(model));
+    }
+
 }
\ No newline at end of file
@@ -7,7 +7,7 @@ use lockstep_client::step::LockstepClient;
 use utility::RotatedBy;
 
 use crate::EntityID;
-use crate::model::GameMessage;
+use crate::model::{CheatCommand, GameMessage};
 
 use super::constructible_at_position;
 use super::existing_static_body_at_position;
@@ -18,10 +18,15 @@ use super::Attacker;
 use super::BlueprintID;
 use super::DynamicBody;
 use super::Extractor;
+use super::OrderID;
+use super::Factory;
+use super::GameSpeed;
 use super::PhysicsBody;
+use super::ResourcePriority;
 use super::get_entity_position;
 use super::get_entity_position_from_id;
 use super::get_closest_position_with_entity_bounds;
+use super::{can_load_passenger, load_passenger, unload_all_passengers, Transport};
 use super::{RymdGameModel, Constructor, Controller, Health, Orderable};
 
 pub const ARBITRARY_DISTANCE_THRESHOLD: f32 = 16.0;
@@ -41,7 +46,20 @@ pub trait GameOrdersExt {
     fn send_build_order(&mut self, entity: Entity, target_position: Vec2, blueprint_id: BlueprintID, should_add: bool, is_self: bool);
     fn send_repair_order(&mut self, entity: Entity, target_position: Vec2, target: Entity, should_add: bool);
     fn send_extract_order(&mut self, entity: Entity, target: Entity, should_add: bool);
+    fn send_load_order(&mut self, entity: Entity, target: Entity, should_add: bool);
+    fn send_unload_order(&mut self, entity: Entity, target_position: Vec2, should_add: bool);
     fn cancel_current_orders(&mut self, entity: Entity);
+    fn cancel_construction_order(&mut self, entity: Entity, order_id: OrderID);
+    fn move_construction_order(&mut self, entity: Entity, from: i32, to: i32);
+    fn send_production_order(&mut self, entity: Entity, blueprint_id: BlueprintID, count: i32);
+    fn set_production_repeat(&mut self, entity: Entity, is_repeating: bool);
+    fn send_rally_point(&mut self, entity: Entity, rally_point: RallyPoint, should_add: bool);
+    fn clear_rally_points(&mut self, entity: Entity);
+    fn set_resource_priority(&mut self, entity: Entity, priority: ResourcePriority);
+    fn vote_game_speed(&mut self, speed: GameSpeed);
+    fn set_game_speed(&mut self, speed: GameSpeed);
+    fn set_game_paused(&mut self, is_paused: bool);
+    fn send_cheat(&mut self, cheat: CheatCommand);
 
 }
 
@@ -89,6 +107,73 @@ impl GameOrdersExt for LockstepClient {
         self.send_command(extract_order_message.serialize_json());
     }
 
+    fn send_load_order(&mut self, entity: Entity, target: Entity, should_add: bool) {
+        let load_order = GameOrder::Load(LoadOrder { entity_id: target.to_bits().get() });
+        let load_order_message = GameMessage::Order { entity: entity.to_bits().into(), order: load_order, add: should_add };
+        self.send_command(load_order_message.serialize_json());
+    }
+
+    fn send_unload_order(&mut self, entity: Entity, target_position: Vec2, should_add: bool) {
+        let unload_order = GameOrder::Unload(UnloadOrder { x: target_position.x, y: target_position.y });
+        let unload_order_message = GameMessage::Order { entity: entity.to_bits().into(), order: unload_order, add: should_add };
+        self.send_command(unload_order_message.serialize_json());
+    }
+
+    fn cancel_construction_order(&mut self, entity: Entity, order_id: OrderID) {
+        let cancel_construction_order_message = GameMessage::CancelConstructionOrder { entity: entity.to_bits().into(), order_id };
+        self.send_command(cancel_construction_order_message.serialize_json());
+    }
+
+    fn move_construction_order(&mut self, entity: Entity, from: i32, to: i32) {
+        let move_construction_order_message = GameMessage::MoveConstructionOrder { entity: entity.to_bits().into(), from, to };
+        self.send_command(move_construction_order_message.serialize_json());
+    }
+
+    fn send_production_order(&mut self, entity: Entity, blueprint_id: BlueprintID, count: i32) {
+        let production_order_message = GameMessage::QueueProduction { entity: entity.to_bits().into(), blueprint_id, count };
+        self.send_command(production_order_message.serialize_json());
+    }
+
+    fn set_production_repeat(&mut self, entity: Entity, is_repeating: bool) {
+        let production_repeat_message = GameMessage::SetProductionRepeat { entity: entity.to_bits().into(), is_repeating };
+        self.send_command(production_repeat_message.serialize_json());
+    }
+
+    fn send_rally_point(&mut self, entity: Entity, rally_point: RallyPoint, should_add: bool) {
+        let rally_point_message = GameMessage::SetRallyPoint { entity: entity.to_bits().into(), rally_point, add: should_add };
+        self.send_command(rally_point_message.serialize_json());
+    }
+
+    fn clear_rally_points(&mut self, entity: Entity) {
+        let clear_rally_points_message = GameMessage::ClearRallyPoints { entity: entity.to_bits().into() };
+        self.send_command(clear_rally_points_message.serialize_json());
+    }
+
+    fn set_resource_priority(&mut self, entity: Entity, priority: ResourcePriority) {
+        let resource_priority_message = GameMessage::SetResourcePriority { entity: entity.to_bits().into(), priority };
+        self.send_command(resource_priority_message.serialize_json());
+    }
+
+    fn vote_game_speed(&mut self, speed: GameSpeed) {
+        let game_speed_message = GameMessage::VoteGameSpeed { player: self.peer_id(), speed };
+        self.send_command(game_speed_message.serialize_json());
+    }
+
+    fn set_game_speed(&mut self, speed: GameSpeed) {
+        let game_speed_message = GameMessage::SetGameSpeed { player: self.peer_id(), speed };
+        self.send_command(game_speed_message.serialize_json());
+    }
+
+    fn set_game_paused(&mut self, is_paused: bool) {
+        let paused_message = GameMessage::SetPaused { player: self.peer_id(), is_paused };
+        self.send_command(paused_message.serialize_json());
+    }
+
+    fn send_cheat(&mut self, cheat: CheatCommand) {
+        let cheat_message = GameMessage::Cheat { player: self.peer_id(), cheat };
+        self.send_command(cheat_message.serialize_json());
+    }
+
 }
 
 trait Order {
@@ -113,6 +198,8 @@ impl GameOrder {
             GameOrder::AttackMove(order) => order.is_order_valid(entity, model),
             GameOrder::Construct(order) => order.is_order_valid(entity, model),
             GameOrder::Extract(order) => order.is_order_valid(entity, model),
+            GameOrder::Load(order) => order.is_order_valid(entity, model),
+            GameOrder::Unload(order) => order.is_order_valid(entity, model),
             GameOrder::Cancel(order) => order.is_order_valid(entity, model)
         }
     }
@@ -124,6 +211,8 @@ impl GameOrder {
             GameOrder::AttackMove(order) => order.is_order_completed(entity, model),
             GameOrder::Construct(order) => order.is_order_completed(entity, model),
             GameOrder::Extract(order) => order.is_order_completed(entity, model),
+            GameOrder::Load(order) => order.is_order_completed(entity, model),
+            GameOrder::Unload(order) => order.is_order_completed(entity, model),
             GameOrder::Cancel(order) => order.is_order_completed(entity, model)
         }
     }
@@ -135,6 +224,8 @@ impl GameOrder {
             GameOrder::AttackMove(order) => order.get_target_position(model),
             GameOrder::Construct(order) => order.get_target_position(model),
             GameOrder::Extract(order) => order.get_target_position(model),
+            GameOrder::Load(order) => order.get_target_position(model),
+            GameOrder::Unload(order) => order.get_target_position(model),
             GameOrder::Cancel(order) => order.get_target_position(model)
         }
     }
@@ -146,6 +237,8 @@ impl GameOrder {
             GameOrder::AttackMove(order) => order.tick(entity, model, dt),
             GameOrder::Construct(order) => order.tick(entity, model, dt),
             GameOrder::Extract(order) => order.tick(entity, model, dt),
+            GameOrder::Load(order) => order.tick(entity, model, dt),
+            GameOrder::Unload(order) => order.tick(entity, model, dt),
             GameOrder::Cancel(order) => order.tick(entity, model, dt)
         }
     }
@@ -157,6 +250,8 @@ impl GameOrder {
             GameOrder::AttackMove(order) => order.on_completed(entity, model),
             GameOrder::Construct(order) => order.on_completed(entity, model),
             GameOrder::Extract(order) => order.on_completed(entity, model),
+            GameOrder::Load(order) => order.on_completed(entity, model),
+            GameOrder::Unload(order) => order.on_completed(entity, model),
             GameOrder::Cancel(order) => order.on_completed(entity, model)
         }     
     }
@@ -168,6 +263,8 @@ impl GameOrder {
             GameOrder::AttackMove(_) => GameOrderType::Order,
             GameOrder::Construct(order) => if order.is_self_order { GameOrderType::Construct } else { GameOrderType::Order },
             GameOrder::Extract(order) => GameOrderType::Order,
+            GameOrder::Load(_) => GameOrderType::Order,
+            GameOrder::Unload(_) => GameOrderType::Order,
             GameOrder::Cancel(_) => GameOrderType::Order
         }
     }
@@ -181,13 +278,15 @@ pub enum GameOrder {
     AttackMove(AttackMoveOrder),
     Construct(ConstructOrder),
     Extract(ExtractOrder),
+    Load(LoadOrder),
+    Unload(UnloadOrder),
     Cancel(CancelOrder)
 }
 
 #[derive(Debug, Copy, Clone, SerJson, DeJson)]
 pub struct MoveOrder {
-    x: f32,
-    y: f32
+    pub x: f32,
+    pub y: f32
 }
 
 impl Order for MoveOrder {
@@ -215,6 +314,10 @@ pub struct AttackOrder {
 }
 
 impl AttackOrder {
+    pub fn new(target: Entity) -> AttackOrder {
+        AttackOrder { entity_id: target.to_bits().into() }
+    }
+
     pub fn entity(&self) -> Option<Entity> {
         Entity::from_bits(self.entity_id)
     }
@@ -256,8 +359,8 @@ impl Order for AttackOrder {
 
 #[derive(Debug, Copy, Clone, SerJson, DeJson)]
 pub struct AttackMoveOrder {
-    x: f32,
-    y: f32
+    pub x: f32,
+    pub y: f32
 }
 
 impl Order for AttackMoveOrder {
@@ -502,8 +605,12 @@ impl ConstructOrder {
             let mut orderable_view = orderable_query.view();
             let [constructor_orderable, new_orderable] = orderable_view.get_many_mut([constructor_entity, new_entity]);
 
+            // factories hand out their rally points instead, see tick_constructing_entities
+            let is_constructor_a_factory = model.world.satisfies::<&Factory>(constructor_entity).unwrap_or(false);
+
             if let Some(constructor_orderable) = constructor_orderable
                 && let Some(new_orderable) = new_orderable
+                && is_constructor_a_factory == false
                 && constructor_orderable.is_queue_empty(GameOrderType::Order) == false
                 && new_orderable.is_queue_empty(GameOrderType::Order)
             {
@@ -559,6 +666,51 @@ impl ConstructOrder {
 
 }
 
+/// Where units produced by a factory should head once they are constructed, factories keep a chain of these which is handed to each new unit as orders.
+#[derive(Debug, Copy, Clone, SerJson, DeJson)]
+pub enum RallyPoint {
+    Move { x: f32, y: f32 },
+    AttackMove { x: f32, y: f32 },
+    Entity { entity_id: EntityID }
+}
+
+impl RallyPoint {
+
+    pub fn get_target_position(&self, model: &RymdGameModel) -> Option<Vec2> {
+        match self {
+            RallyPoint::Move { x, y } => Some(vec2(*x, *y)),
+            RallyPoint::AttackMove { x, y } => Some(vec2(*x, *y)),
+            RallyPoint::Entity { entity_id } => {
+                let target = Entity::from_bits(*entity_id)?;
+                get_entity_position(&model.world, target)
+            }
+        }
+    }
+
+    /// Turns the rally point into an order for the given newly constructed unit, rally points on entities that are gone by now produce no order.
+    pub fn to_order(&self, entity: Entity, model: &RymdGameModel) -> Option<GameOrder> {
+        match self {
+            RallyPoint::Move { x, y } => Some(GameOrder::Move(MoveOrder { x: *x, y: *y })),
+            RallyPoint::AttackMove { x, y } => Some(GameOrder::AttackMove(AttackMoveOrder { x: *x, y: *y })),
+            RallyPoint::Entity { entity_id } => {
+
+                let target = Entity::from_bits(*entity_id)?;
+                let target_position = get_entity_position(&model.world, target)?;
+                let controller_id = model.world.get::<&Controller>(entity).ok()?.id;
+                let is_attacker = model.world.satisfies::<&Attacker>(entity).unwrap_or(false);
+
+                if is_attacker && model.is_entity_attackable_by(controller_id, target) {
+                    Some(GameOrder::Attack(AttackOrder { entity_id: *entity_id }))
+                } else {
+                    Some(GameOrder::Move(MoveOrder { x: target_position.x, y: target_position.y }))
+                }
+
+            }
+        }
+    }
+
+}
+
 #[derive(Debug, Copy, Clone, SerJson, DeJson)]
 pub struct CancelOrder {
 
@@ -630,6 +782,91 @@ impl Order for ExtractOrder {
     }
 }
 
+/// Given to a transport, which moves to the unit and loads it once in range.
+#[derive(Debug, Copy, Clone, SerJson, DeJson)]
+pub struct LoadOrder {
+    pub entity_id: EntityID
+}
+
+impl LoadOrder {
+
+    pub fn entity(&self) -> Option<Entity> {
+        Entity::from_bits(self.entity_id)
+    }
+
+}
+
+impl Order for LoadOrder {
+
+    fn is_order_completed(&self, entity: Entity, model: &RymdGameModel) -> bool {
+        let Some(target) = self.entity() else { return true; };
+        model.world.contains(target) == false || can_load_passenger(model, entity, target) == false
+    }
+
+    fn get_target_position(&self, model: &RymdGameModel) -> Option<Vec2> {
+        get_entity_position(&model.world, self.entity()?)
+    }
+
+    fn tick(&self, entity: Entity, model: &mut RymdGameModel, dt: f32) {
+
+        let Some(target) = self.entity() else { return; };
+        let Some(target_position) = self.get_target_position(model) else { return; };
+        let Some(transport_position) = get_entity_position(&model.world, entity) else { return; };
+
+        let load_range = model.world.get::<&Transport>(entity).expect("can't issue a load order to something without a Transport component!").load_range;
+
+        if transport_position.distance(target_position) > load_range {
+            set_movement_target_to_position(&model.world, entity, Some(target_position));
+        } else {
+            load_passenger(model, entity, target);
+        }
+
+    }
+
+    fn on_completed(&self, entity: Entity, model: &mut RymdGameModel) {
+        set_movement_target_to_position(&model.world, entity, None);
+    }
+
+}
+
+/// Given to a transport, which moves to the position and unloads everything it carries there.
+#[derive(Debug, Copy, Clone, SerJson, DeJson)]
+pub struct UnloadOrder {
+    pub x: f32,
+    pub y: f32
+}
+
+impl Order for UnloadOrder {
+
+    fn is_order_completed(&self, entity: Entity, model: &RymdGameModel) -> bool {
+        model.world.get::<&Transport>(entity).map_or(true, |transport| transport.passengers.is_empty())
+    }
+
+    fn get_target_position(&self, model: &RymdGameModel) -> Option<Vec2> {
+        Some(vec2(self.x, self.y))
+    }
+
+    fn tick(&self, entity: Entity, model: &mut RymdGameModel, dt: f32) {
+
+        let target_position = vec2(self.x, self.y);
+        let Some(transport_position) = get_entity_position(&model.world, entity) else { return; };
+
+        let load_range = model.world.get::<&Transport>(entity).expect("can't issue an unload order to something without a Transport component!").load_range;
+
+        if transport_position.distance(target_position) > load_range {
+            set_movement_target_to_position(&model.world, entity, Some(target_position));
+        } else {
+            unload_all_passengers(model, entity, target_position);
+        }
+
+    }
+
+    fn on_completed(&self, entity: Entity, model: &mut RymdGameModel) {
+        set_movement_target_to_position(&model.world, entity, None);
+    }
+
+}
+
 pub fn is_within_extractor_range(entity: Entity, world: &World, target: Vec2) -> bool {
 
     let extractor = world.get::<&Extractor>(entity).expect("must have extractor to be asking about if within extraction range!");
@@ -0,0 +1,59 @@
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+
+/// Work is never split into pieces smaller than this, below it the cost of handing work to the pool outweighs doing it on the calling thread.
+const MIN_ITEMS_PER_TASK: usize = 128;
+
+/// Maps the items in chunks spread over the worker threads of the global pool, the results are always returned in the same order as the items that were passed in.
+/// This means that as long as the map function only reads shared state, the result is the same as if it had run serially, which keeps the simulation deterministic.
+/// Passing false for is_parallel runs everything on the calling thread instead.
+pub fn parallel_filter_map<T, R, F>(items: &[T], is_parallel: bool, map_fn: F) -> Vec<R>
+    where
+        T: Sync,
+        R: Send,
+        F: Fn(&T) -> Option<R> + Sync
+{
+
+    if is_parallel == false || items.len() < MIN_ITEMS_PER_TASK * 2 {
+        return items.iter().filter_map(&map_fn).collect();
+    }
+
+    // no threads on the web (yet), just run everything on the main thread
+    #[cfg(target_arch = "wasm32")]
+    return items.iter().filter_map(&map_fn).collect();
+
+    // the threads of the pool are kept around between calls, collecting into a vec keeps the order of the items
+    #[cfg(not(target_arch = "wasm32"))]
+    items.par_iter().with_min_len(MIN_ITEMS_PER_TASK).filter_map(&map_fn).collect()
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parallel_filter_map_matches_serial_in_order() {
+
+        let items: Vec<u64> = (0..100_000).collect();
+        let map_fn = |v: &u64| if v % 3 == 0 { None } else { Some(v.wrapping_mul(2654435761) ^ (v >> 3)) };
+
+        let serial_results: Vec<u64> = items.iter().filter_map(map_fn).collect();
+        let parallel_results = parallel_filter_map(&items, true, map_fn);
+
+        assert_eq!(serial_results, parallel_results);
+
+    }
+
+    #[test]
+    fn test_parallel_filter_map_few_items() {
+
+        let items = [1, 2, 3, 4, 5];
+        let results = parallel_filter_map(&items, true, |v| if v % 2 == 1 { Some(v * 10) } else { None });
+
+        assert_eq!(vec![10, 30, 50], results);
+
+    }
+
+}
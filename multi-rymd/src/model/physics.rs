@@ -5,7 +5,7 @@ use macroquad::prelude::*;
 use hecs::{CommandBuffer, Entity, Without, World};
 use rapier2d::{crossbeam, prelude::*};
 use utility::line_segment_rect_intersection;
-use super::{spatial::{entity_distance_sort_function, SpatialQueryManager}, DynamicBody, DynamicBodyCallback};
+use super::{spatial::{entity_distance_sort_function, SpatialQueryManager}, DynamicBody, DynamicBodyCallback, RymdGameEvent};
 
 const COLLISION_ELASTICITY: f32 = 1.0;
 
@@ -182,7 +182,8 @@ impl PhysicsManager {
 
     }
 
-    pub fn tick(&mut self, world: &mut World) {
+    /// Any events caused by collisions, like projectile impacts, are pushed onto the passed events.
+    pub fn tick(&mut self, world: &mut World, events: &mut Vec<RymdGameEvent>) {
 
         self.handle_created_entities(world);
         self.handle_destroyed_entities(world);
@@ -216,13 +217,13 @@ impl PhysicsManager {
 
             if let Ok(a_callback) = world.get::<&DynamicBodyCallback>(a_entity) {
                 if let Ok(other_body) = world.get::<&DynamicBody>(b_entity) {
-                    (a_callback.on_collision)(world, &mut command_buffer, a_entity, b_entity, &other_body);
+                    (a_callback.on_collision)(world, &mut command_buffer, events, a_entity, b_entity, &other_body);
                 }
             }
 
             if let Ok(b_callback)= world.get::<&DynamicBodyCallback>(b_entity) {
                 if let Ok(other_body) = world.get::<&DynamicBody>(a_entity) {
-                    (b_callback.on_collision)(world, &mut command_buffer, b_entity, a_entity, &other_body);
+                    (b_callback.on_collision)(world, &mut command_buffer, events, b_entity, a_entity, &other_body);
                 }
             }
 
@@ -306,16 +307,17 @@ impl PhysicsManager {
 
     fn handle_destroyed_entities(&mut self, world: &mut World) {
 
-        let mut destroyed_bodies: Vec<RigidBodyHandle> = Vec::new();
+        let mut destroyed_bodies: Vec<(RigidBodyHandle, Entity)> = Vec::new();
 
         for (&rigid_body, &e) in &self.rigid_body_handle_to_entity {
-            if world.contains(e) == false {
-                destroyed_bodies.push(rigid_body);
+            // entities can also lose their body without being destroyed, like units being loaded into a transport
+            if world.contains(e) == false || world.satisfies::<&DynamicBody>(e).unwrap_or(false) == false {
+                destroyed_bodies.push((rigid_body, e));
             }
         }
 
         // clean up all destroyed entities and their bodies/colliders!
-        for rigid_body_handle in destroyed_bodies {
+        for (rigid_body_handle, e) in destroyed_bodies {
 
             let should_remove_attached_colliders = true;
 
@@ -330,6 +332,11 @@ impl PhysicsManager {
 
             self.rigid_body_handle_to_entity.remove(&rigid_body_handle);
 
+            // if it is still around, it gets a new rigid body whenever it gets its body back
+            if world.contains(e) {
+                let _ = world.remove_one::<PhysicsBodyHandle>(e);
+            }
+
         }
 
     }
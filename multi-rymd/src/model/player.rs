@@ -3,7 +3,7 @@ use lockstep_client::step::PeerID;
 
 use crate::PlayerID;
 
-use super::{Metal, Energy};
+use super::{Metal, Energy, ResourceFlow};
 
 #[derive(Debug, Clone)]
 pub struct Player {
@@ -19,12 +19,13 @@ pub fn create_player_entity(world: &mut World, id: PeerID) -> Entity {
     let default_metal_pool_size = 1000.0;
     let default_energy_pool_size = 1000.0;
 
-    let metal = Metal { current: default_metal, income: 0.0, base_size: default_metal_pool_size, pool_size: 0.0 };
-    let energy = Energy { current: default_energy, income: 0.0, base_size: default_energy_pool_size, pool_size: 0.0 };
+    let metal = Metal { current: default_metal, income: 0.0, expense: 0.0, base_size: default_metal_pool_size, pool_size: 0.0 };
+    let energy = Energy { current: default_energy, income: 0.0, expense: 0.0, base_size: default_energy_pool_size, pool_size: 0.0 };
+    let resource_flow = ResourceFlow::new();
     let player = Player { id, team_mask: 0 };
 
     
-    world.spawn((player, metal, energy))
+    world.spawn((player, metal, energy, resource_flow))
 
 }
 
@@ -3,7 +3,7 @@ use macroquad::{color::Color, math::Rect, prelude::Vec2};
 use utility::{AsAngle, Kinematic};
 use crate::PlayerID;
 
-use super::{create_default_kinematic_body, create_impact_effect_in_buffer, create_muzzle_flash_effect_in_world, get_entity_physics_position, get_player_team_allegiance, Beam, Controller, DynamicBody, DynamicBodyCallback, Effect, Health, PhysicsBody, Projectile, Sprite, Transform, SIMPLE_BEAM_PARAMETERS, SIMPLE_BULLET_PARAMETERS};
+use super::{create_default_kinematic_body, create_impact_effect_in_buffer, create_muzzle_flash_effect_in_world, get_entity_physics_position, get_player_team_allegiance, Beam, Controller, DynamicBody, DynamicBodyCallback, Effect, Health, PhysicsBody, Projectile, RymdGameEvent, Sprite, Transform, SIMPLE_BEAM_PARAMETERS, SIMPLE_BULLET_PARAMETERS};
 
 #[derive(Clone, Copy, Debug)]
 pub struct BulletParameters {
@@ -29,7 +29,7 @@ pub struct BeamParameters {
 
 }
 
-fn on_bullet_impact(world: &World, buffer: &mut CommandBuffer, a: Entity, b: Entity, b_body: &DynamicBody) {
+fn on_bullet_impact(world: &World, buffer: &mut CommandBuffer, events: &mut Vec<RymdGameEvent>, a: Entity, b: Entity, b_body: &DynamicBody) {
     
     if let Ok(mut bullet_health) = world.get::<&mut Health>(a) {
         bullet_health.kill();
@@ -42,6 +42,7 @@ fn on_bullet_impact(world: &World, buffer: &mut CommandBuffer, a: Entity, b: Ent
     let entity_a_physics_position = get_entity_physics_position(world, a).unwrap();
     let (position_on_target_radius, normal_on_targeted_entity) = get_position_and_normal_on_targeted_entity_relative_to(world, b_body, entity_a_physics_position);
     create_impact_effect_in_buffer(buffer, position_on_target_radius, normal_on_targeted_entity);
+    events.push(RymdGameEvent::Impact { position: position_on_target_radius });
 
 }
 
@@ -2,6 +2,7 @@ use super::Player;
 
 use hecs::World;
 use lockstep_client::step::PeerID;
+use nanoserde::{SerJson, DeJson};
 
 pub trait Resource {
     fn capacity(&self) -> i64;
@@ -32,12 +33,113 @@ pub struct Storage {
     pub energy: f32
 }
 
+/// Marks that the output of the entity's producer depends on its consumer being supplied, so it only produces as much as it is supplied with.
 #[derive(Clone)]
 pub struct Powered;
 
+/// When there isn't enough to go around, demand of a higher priority is supplied in full before any lower priority demand is, entities without one are of normal priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, SerJson, DeJson)]
+pub enum ResourcePriority {
+    High,
+    Normal,
+    Low
+}
+
+impl ResourcePriority {
+
+    pub const ALL: [ResourcePriority; 3] = [ResourcePriority::High, ResourcePriority::Normal, ResourcePriority::Low];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResourcePriority::High => "high",
+            ResourcePriority::Normal => "normal",
+            ResourcePriority::Low => "low"
+        }
+    }
+
+}
+
+/// Per player summary of how much everything wanted to consume in the current tick and how much of that could be supplied, indexed by priority.
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceFlow {
+    pub metal_demand: [f32; 3],
+    pub energy_demand: [f32; 3],
+    pub metal_fraction: [f32; 3],
+    pub energy_fraction: [f32; 3]
+}
+
+impl ResourceFlow {
+
+    pub fn new() -> ResourceFlow {
+        ResourceFlow {
+            metal_demand: [0.0; 3],
+            energy_demand: [0.0; 3],
+            metal_fraction: [1.0; 3],
+            energy_fraction: [1.0; 3]
+        }
+    }
+
+    /// Returns the fraction of its demand a consumer of the given priority gets, only taking into account the resources it actually uses.
+    pub fn fraction_for(&self, priority: ResourcePriority, uses_metal: bool, uses_energy: bool) -> f32 {
+        let metal_fraction = if uses_metal { self.metal_fraction[priority as usize] } else { 1.0 };
+        let energy_fraction = if uses_energy { self.energy_fraction[priority as usize] } else { 1.0 };
+        metal_fraction.min(energy_fraction)
+    }
+
+    pub fn total_metal_demand(&self) -> f32 {
+        self.metal_demand.iter().sum()
+    }
+
+    pub fn total_energy_demand(&self) -> f32 {
+        self.energy_demand.iter().sum()
+    }
+
+    /// Returns the fraction of all the metal demand which was supplied.
+    pub fn metal_supplied_fraction(&self) -> f32 {
+        Self::supplied_fraction(&self.metal_demand, &self.metal_fraction)
+    }
+
+    /// Returns the fraction of all the energy demand which was supplied.
+    pub fn energy_supplied_fraction(&self) -> f32 {
+        Self::supplied_fraction(&self.energy_demand, &self.energy_fraction)
+    }
+
+    pub fn is_stalling(&self) -> bool {
+        self.metal_supplied_fraction() < 1.0 || self.energy_supplied_fraction() < 1.0
+    }
+
+    fn supplied_fraction(demand: &[f32; 3], fraction: &[f32; 3]) -> f32 {
+        let total_demand: f32 = demand.iter().sum();
+        if total_demand <= 0.0 {
+            return 1.0;
+        }
+        demand.iter().zip(fraction.iter()).map(|(d, f)| d * f).sum::<f32>() / total_demand
+    }
+
+    /// Hands out the supply to each priority in turn, every consumer of the same priority getting the same fraction of its demand.
+    pub fn distribute(demand: &[f32; 3], supply: f32) -> [f32; 3] {
+
+        let mut remaining_supply = supply.max(0.0);
+        let mut fractions = [1.0; 3];
+
+        for (fraction, &priority_demand) in fractions.iter_mut().zip(demand.iter()) {
+            if priority_demand <= 0.0 {
+                continue;
+            }
+            *fraction = (remaining_supply / priority_demand).min(1.0);
+            remaining_supply -= priority_demand * *fraction;
+        }
+
+        fractions
+
+    }
+
+}
+
 pub struct Energy {
     pub current: f32,
     pub income: f32,
+    pub expense: f32,
     pub base_size: f32,
     pub pool_size: f32
 }
@@ -45,56 +147,65 @@ pub struct Energy {
 pub struct Metal {
     pub current: f32,
     pub income: f32,
+    pub expense: f32,
     pub base_size: f32,
     pub pool_size: f32,
 }
 
-/// Attempts to provide this amount of metal to the given player's energy pool.
-pub fn provide_metal(player_id: PeerID, world: &World, amount: f32, dt: f32) -> bool {
-    consume_metal(player_id, world, -amount, dt)
+/// Provides this amount of metal to the given player's metal pool.
+pub fn provide_metal(player_id: PeerID, world: &World, amount: f32, dt: f32) {
+    consume_metal(player_id, world, -amount, dt);
 }
 
-/// Attempts to provide this amount of energy to the given player's energy pool.
-pub fn provide_energy(player_id: PeerID, world: &World, amount: f32, dt: f32) -> bool {
-    consume_energy(player_id, world, -amount, dt)
+/// Provides this amount of energy to the given player's energy pool.
+pub fn provide_energy(player_id: PeerID, world: &World, amount: f32, dt: f32) {
+    consume_energy(player_id, world, -amount, dt);
 }
 
-/// Attempts to consume the specific amount of metal from this player's resources, returns true if successful.
-pub fn consume_metal(player_id: PeerID, world: &World, amount: f32, dt: f32) -> bool {
+/// Consumes up to the given amount of metal from this player's resources, returns the amount that was actually consumed.
+pub fn consume_metal(player_id: PeerID, world: &World, amount: f32, dt: f32) -> f32 {
 
-    if let Some((current_player_entity, current_player)) = world.query::<&Player>().iter().find(|(e, p)| p.id == player_id) {
-        if let Ok(mut metal) = world.get::<&mut Metal>(current_player_entity) && metal.current >= amount {
-            if dt != 0.0 {
-                metal.income -= amount / dt;
-            }
-            metal.current = (metal.current - amount).clamp(0.0, metal.base_size + metal.pool_size); // #TODO: specifically distribute excess/overflow to other allied players maybe?
-            true
-        } else {
-            false
-        }
-    } else {
-        false
+    let Some((current_player_entity, current_player)) = world.query::<&Player>().iter().find(|(e, p)| p.id == player_id) else { return 0.0 };
+    let Ok(mut metal) = world.get::<&mut Metal>(current_player_entity) else { return 0.0 };
+
+    let amount_consumed = amount.min(metal.current);
+
+    if dt != 0.0 && amount_consumed >= 0.0 {
+        metal.expense += amount_consumed / dt;
+    } else if dt != 0.0 {
+        metal.income -= amount_consumed / dt;
     }
 
+    metal.current = (metal.current - amount_consumed).clamp(0.0, metal.base_size + metal.pool_size); // #TODO: specifically distribute excess/overflow to other allied players maybe?
+    amount_consumed
+
 }
 
-/// Attempts to consume the specific amount of energy from this player's resources, returns true if successful.
-pub fn consume_energy(player_id: PeerID, world: &World, amount: f32, dt: f32) -> bool {
+/// Consumes up to the given amount of energy from this player's resources, returns the amount that was actually consumed.
+pub fn consume_energy(player_id: PeerID, world: &World, amount: f32, dt: f32) -> f32 {
 
-    if let Some((current_player_entity, current_player)) = world.query::<&Player>().iter().find(|(e, p)| p.id == player_id) {
-        if let Ok(mut energy) = world.get::<&mut Energy>(current_player_entity) && energy.current >= amount {
-            if dt != 0.0 {
-                energy.income -= amount / dt;
-            }
-            energy.current = (energy.current - amount).clamp(0.0, energy.base_size + energy.pool_size);
-            true
-        } else {
-            false
-        }
-    } else {
-        false
+    let Some((current_player_entity, current_player)) = world.query::<&Player>().iter().find(|(e, p)| p.id == player_id) else { return 0.0 };
+    let Ok(mut energy) = world.get::<&mut Energy>(current_player_entity) else { return 0.0 };
+
+    let amount_consumed = amount.min(energy.current);
+
+    if dt != 0.0 && amount_consumed >= 0.0 {
+        energy.expense += amount_consumed / dt;
+    } else if dt != 0.0 {
+        energy.income -= amount_consumed / dt;
     }
 
+    energy.current = (energy.current - amount_consumed).clamp(0.0, energy.base_size + energy.pool_size);
+    amount_consumed
+
+}
+
+/// Returns the state of the resource flow of the given player last tick, if the player exists.
+pub fn current_resource_flow(player_id: PeerID, world: &World) -> Option<ResourceFlow> {
+
+    let (current_player_entity, current_player) = world.query::<&Player>().iter().find(|(e, p)| p.id == player_id)?;
+    world.get::<&ResourceFlow>(current_player_entity).ok().map(|flow| *flow)
+
 }
 
 /// Returns the current amount of metal in the given player's resource pool.
@@ -185,4 +296,92 @@ pub fn current_energy_income(player_id: PeerID, world: &World) -> f32 {
         0.0
     }
 
-}
\ No newline at end of file
+}
+
+/// Returns the amount of metal spent per second last tick for the given player's resource pool.
+pub fn current_metal_expense(player_id: PeerID, world: &World) -> f32 {
+
+    if let Some((current_player_entity, current_player)) = world.query::<&Player>().iter().find(|(e, p)| p.id == player_id) {
+        if let Ok(metal) = world.get::<&Metal>(current_player_entity) {
+            metal.expense
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    }
+
+}
+
+/// Returns the amount of energy spent per second last tick for the given player's resource pool.
+pub fn current_energy_expense(player_id: PeerID, world: &World) -> f32 {
+
+    if let Some((current_player_entity, current_player)) = world.query::<&Player>().iter().find(|(e, p)| p.id == player_id) {
+        if let Ok(energy) = world.get::<&Energy>(current_player_entity) {
+            energy.expense
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_distribute_with_enough_supply() {
+        assert_eq!([1.0, 1.0, 1.0], ResourceFlow::distribute(&[10.0, 20.0, 30.0], 100.0));
+        assert_eq!([1.0, 1.0, 1.0], ResourceFlow::distribute(&[10.0, 20.0, 30.0], 60.0));
+    }
+
+    #[test]
+    fn test_distribute_shortage_goes_to_higher_priorities_first() {
+
+        let fractions = ResourceFlow::distribute(&[10.0, 20.0, 30.0], 20.0);
+
+        assert_eq!(1.0, fractions[ResourcePriority::High as usize]);
+        assert_eq!(0.5, fractions[ResourcePriority::Normal as usize]);
+        assert_eq!(0.0, fractions[ResourcePriority::Low as usize]);
+
+    }
+
+    #[test]
+    fn test_distribute_shortage_within_a_priority() {
+
+        let fractions = ResourceFlow::distribute(&[40.0, 0.0, 10.0], 10.0);
+
+        assert_eq!(0.25, fractions[ResourcePriority::High as usize]);
+        assert_eq!(0.0, fractions[ResourcePriority::Low as usize]);
+
+    }
+
+    #[test]
+    fn test_distribute_without_demand_is_fully_supplied() {
+        assert_eq!([1.0, 1.0, 1.0], ResourceFlow::distribute(&[0.0, 0.0, 0.0], 0.0));
+        assert_eq!([1.0, 0.0, 1.0], ResourceFlow::distribute(&[0.0, 10.0, 0.0], 0.0));
+    }
+
+    #[test]
+    fn test_distribute_zero_or_negative_supply() {
+        assert_eq!([0.0, 0.0, 0.0], ResourceFlow::distribute(&[10.0, 20.0, 30.0], 0.0));
+        assert_eq!([0.0, 0.0, 0.0], ResourceFlow::distribute(&[10.0, 20.0, 30.0], -5.0));
+    }
+
+    #[test]
+    fn test_supplied_fraction_is_weighted_by_demand() {
+
+        let mut resource_flow = ResourceFlow::new();
+        resource_flow.metal_demand = [10.0, 20.0, 30.0];
+        resource_flow.metal_fraction = ResourceFlow::distribute(&resource_flow.metal_demand, 20.0);
+
+        assert!((resource_flow.metal_supplied_fraction() - 20.0 / 60.0).abs() < 1e-6);
+        assert!(resource_flow.is_stalling());
+
+    }
+
+}
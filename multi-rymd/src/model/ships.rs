@@ -6,7 +6,7 @@ use utility::{AsAngle, RotatedBy, SteeringParameters};
 
 use crate::PlayerID;
 use crate::model::{Transform, Orderable, AnimatedSprite, Thruster, DynamicBody, Ship, ThrusterKind};
-use super::{cancel_pending_orders, create_default_kinematic_body, create_explosion_effect_in_buffer, get_entity_position, get_player_team_allegiance, Attackable, Attacker, BeamParameters, BeamWeapon, Blueprint, BlueprintIdentity, Blueprints, Commander, Constructor, Controller, Cost, EntityState, Extractor, Health, MovementTarget, Producer, ProjectileWeapon, RotationTarget, Steering, ARROWHEAD_STEERING_PARAMETERS, COMMANDER_STEERING_PARAMETERS, DEFAULT_STEERING_PARAMETERS, DRAGONFLY_STEERING_PARAMETERS, EXTRACTOR_STEERING_PARAMETERS, SIMPLE_BEAM_PARAMETERS, SIMPLE_BULLET_PARAMETERS};
+use super::{cancel_pending_orders, create_default_kinematic_body, create_explosion_effect_in_buffer, get_entity_position, get_player_team_allegiance, Attackable, Attacker, BeamParameters, BeamWeapon, Blueprint, BlueprintIdentity, Blueprints, Commander, Constructor, Controller, Cost, EntityState, Extractor, Hangar, Health, MovementTarget, Producer, ProjectileWeapon, RotationTarget, Steering, ARROWHEAD_STEERING_PARAMETERS, COMMANDER_STEERING_PARAMETERS, DEFAULT_STEERING_PARAMETERS, DRAGONFLY_STEERING_PARAMETERS, EXTRACTOR_STEERING_PARAMETERS, SIMPLE_BEAM_PARAMETERS, SIMPLE_BULLET_PARAMETERS, Transport};
 
 #[derive(Bundle)]
 pub struct ShipThruster {
@@ -26,7 +26,6 @@ impl ShipThruster {
 pub fn create_commander_ship_blueprint() -> Blueprint {
     Blueprint {
         id: Blueprints::Commander as i32,
-        shortcut: KeyCode::J,
         name: String::from("Commander Ship"),
         texture: String::from("PLAYER_SHIP"),
         constructor: build_commander_ship,
@@ -37,8 +36,7 @@ pub fn create_commander_ship_blueprint() -> Blueprint {
 
 pub fn create_commissar_ship_blueprint() -> Blueprint {
     Blueprint {
-        id: Blueprints::Commander as i32,
-        shortcut: KeyCode::K,
+        id: Blueprints::Commissar as i32,
         name: String::from("Commissar Ship"),
         texture: String::from("ENEMY_SHIP"),
         constructor: build_commissar_ship,
@@ -50,7 +48,6 @@ pub fn create_commissar_ship_blueprint() -> Blueprint {
 pub fn create_arrowhead_ship_blueprint() -> Blueprint {
     Blueprint {
         id: Blueprints::Arrowhead as i32,
-        shortcut: KeyCode::Y,
         name: String::from("Arrowhead (Fighter)"),
         texture: String::from("ARROWHEAD"),
         constructor: build_arrowhead_ship,
@@ -62,7 +59,6 @@ pub fn create_arrowhead_ship_blueprint() -> Blueprint {
 pub fn create_dragonfly_ship_blueprint() -> Blueprint {
         Blueprint {
         id: Blueprints::Dragonfly as i32,
-        shortcut: KeyCode::D,
         name: String::from("Dragonfly (Drone)"),
         texture: String::from("DRAGONFLY"),
         constructor: build_dragonfly_ship,
@@ -71,10 +67,31 @@ pub fn create_dragonfly_ship_blueprint() -> Blueprint {
     }
 }
 
+pub fn create_carrier_ship_blueprint() -> Blueprint {
+    Blueprint {
+        id: Blueprints::Carrier as i32,
+        name: String::from("Carrier (Hangar)"),
+        texture: String::from("PLAYER_SHIP"),
+        constructor: build_carrier_ship,
+        cost: Cost { metal: 400.0, energy: 300.0 },
+        is_building: false
+    }
+}
+
+pub fn create_transport_ship_blueprint() -> Blueprint {
+    Blueprint {
+        id: Blueprints::Transport as i32,
+        name: String::from("Transport (Assist)"),
+        texture: String::from("EXTRACTOR"),
+        constructor: build_transport_ship,
+        cost: Cost { metal: 150.0, energy: 100.0 },
+        is_building: false
+    }
+}
+
 pub fn create_extractor_ship_blueprint() -> Blueprint {
     Blueprint {
         id: Blueprints::Extractor as i32,
-        shortcut: KeyCode::U,
         name: String::from("Extractor (Assist)"),
         texture: String::from("EXTRACTOR"),
         constructor: build_extractor_ship,
@@ -86,7 +103,6 @@ pub fn create_extractor_ship_blueprint() -> Blueprint {
 pub fn create_grunt_ship_blueprint() -> Blueprint {
     Blueprint {
         id: Blueprints::Grunt as i32,
-        shortcut: KeyCode::I,
         name: String::from("Grunt (Fighter)"),
         texture: String::from("ENEMY_GRUNT"),
         constructor: build_grunt_ship,
@@ -306,7 +322,7 @@ pub fn build_commissar_ship(world: &mut World, owner: PlayerID, position: Vec2)
 
         initial_health: initial_commissar_health,
         maximum_health: maximum_commissar_health,
-        blueprint: Blueprints::Commander,
+        blueprint: Blueprints::Commissar,
 
         bounds: commissar_bounds,
         texture: "PLAYER_SHIP".to_string(),
@@ -494,6 +510,108 @@ pub fn build_dragonfly_ship(world: &mut World, owner: PlayerID, position: Vec2)
 
 }
 
+pub fn build_carrier_ship(world: &mut World, owner: PlayerID, position: Vec2) -> Entity {
+
+    let carrier_ship_size = 32.0;
+    let carrier_bounds = Rect { x: 0.0, y: 0.0, w: carrier_ship_size, h: carrier_ship_size };
+
+    let initial_carrier_health = 1.0;
+    let maximum_carrier_health = 750.0;
+
+    let carrier_thruster_power = 48.0;
+    let carrier_turn_thruster_power = 16.0;
+
+    // the carrier has no weapons of its own, its range is only used to pick targets for its drones
+    let carrier_range = 384.0;
+    let carrier_drone_capacity = 4;
+    let carrier_drone_build_time = 8.0;
+
+    let carrier_steering_parameters = DEFAULT_STEERING_PARAMETERS;
+
+    let carrier_ship_parameters = ShipParameters {
+
+        initial_health: initial_carrier_health,
+        maximum_health: maximum_carrier_health,
+        blueprint: Blueprints::Carrier,
+
+        bounds: carrier_bounds,
+        texture: "PLAYER_SHIP".to_string(),
+        texture_h_frames: 3,
+
+        steering_parameters: carrier_steering_parameters
+
+    };
+
+    let carrier_ship_body = create_ship(world, owner, position, carrier_ship_parameters);
+
+    let hangar = Hangar::new(Blueprints::Dragonfly as i32, carrier_drone_capacity, carrier_drone_build_time, carrier_range);
+    let attacker = Attacker::new(carrier_range);
+
+    let _ = world.insert(carrier_ship_body, (hangar, attacker));
+
+    // add ship thrusters
+    let carrier_ship_thruster_left = world.spawn(ShipThruster::new(vec2(-14.0, 4.0).rotated_by(PI/ 2.0), (-Vec2::X).rotated_by(PI/ 2.0), -(PI / 2.0), carrier_turn_thruster_power, carrier_turn_thruster_power, ThrusterKind::Attitude, carrier_ship_body));
+    let carrier_ship_thruster_right = world.spawn(ShipThruster::new(vec2(14.0, 4.0).rotated_by(PI/ 2.0), Vec2::X.rotated_by(PI/ 2.0), PI / 2.0, carrier_turn_thruster_power, carrier_turn_thruster_power, ThrusterKind::Attitude, carrier_ship_body));
+    let carrier_ship_thruster_main = world.spawn(ShipThruster::new(vec2(0.0, 10.0).rotated_by(PI/ 2.0), Vec2::Y.rotated_by(PI/ 2.0), 0.0, carrier_thruster_power, carrier_thruster_power, ThrusterKind::Main, carrier_ship_body));
+
+    let mut carrier_ship = world.get::<&mut Ship>(carrier_ship_body).unwrap();
+    carrier_ship.thrusters.push(carrier_ship_thruster_left);
+    carrier_ship.thrusters.push(carrier_ship_thruster_right);
+    carrier_ship.thrusters.push(carrier_ship_thruster_main);
+
+    carrier_ship_body
+
+}
+
+pub fn build_transport_ship(world: &mut World, owner: PlayerID, position: Vec2) -> Entity {
+
+    let transport_ship_size = 32.0;
+    let transport_bounds = Rect { x: 0.0, y: 0.0, w: transport_ship_size, h: transport_ship_size };
+
+    let initial_transport_health = 1.0;
+    let maximum_transport_health = 400.0;
+
+    let transport_thruster_power = 64.0;
+    let transport_turn_thruster_power = 16.0;
+
+    let transport_capacity = 8;
+    let transport_load_range = 64.0;
+
+    let transport_steering_parameters = DEFAULT_STEERING_PARAMETERS;
+
+    let transport_ship_parameters = ShipParameters {
+
+        initial_health: initial_transport_health,
+        maximum_health: maximum_transport_health,
+        blueprint: Blueprints::Transport,
+
+        bounds: transport_bounds,
+        texture: "EXTRACTOR".to_string(),
+        texture_h_frames: 1,
+
+        steering_parameters: transport_steering_parameters
+
+    };
+
+    let transport_ship_body = create_ship(world, owner, position, transport_ship_parameters);
+
+    let transport = Transport::new(transport_capacity, transport_load_range);
+    let _ = world.insert(transport_ship_body, (transport,));
+
+    // add ship thrusters
+    let transport_ship_thruster_left = world.spawn(ShipThruster::new(vec2(-14.0, 4.0).rotated_by(PI/ 2.0), (-Vec2::X).rotated_by(PI/ 2.0), -(PI / 2.0), transport_turn_thruster_power, transport_turn_thruster_power, ThrusterKind::Attitude, transport_ship_body));
+    let transport_ship_thruster_right = world.spawn(ShipThruster::new(vec2(14.0, 4.0).rotated_by(PI/ 2.0), Vec2::X.rotated_by(PI/ 2.0), PI / 2.0, transport_turn_thruster_power, transport_turn_thruster_power, ThrusterKind::Attitude, transport_ship_body));
+    let transport_ship_thruster_main = world.spawn(ShipThruster::new(vec2(0.0, 10.0).rotated_by(PI/ 2.0), Vec2::Y.rotated_by(PI/ 2.0), 0.0, transport_thruster_power, transport_thruster_power, ThrusterKind::Main, transport_ship_body));
+
+    let mut transport_ship = world.get::<&mut Ship>(transport_ship_body).unwrap();
+    transport_ship.thrusters.push(transport_ship_thruster_left);
+    transport_ship.thrusters.push(transport_ship_thruster_right);
+    transport_ship.thrusters.push(transport_ship_thruster_main);
+
+    transport_ship_body
+
+}
+
 pub fn build_extractor_ship(world: &mut World, owner: PlayerID, position: Vec2) -> Entity {
 
     let extractor_ship_size = 32.0;
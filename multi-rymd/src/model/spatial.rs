@@ -220,6 +220,12 @@ impl SpatialQueryManager {
 
     }
 
+    /// Returns the entities in the square centered on the position which bounds the circle, anything using this should still check the actual distance.
+    pub fn entities_within_circle_bounds(&self, position: Vec2, radius: f32) -> impl Iterator::<Item = Entity> + '_  {
+        let position_bounds = Rect::new(position.x - radius, position.y - radius, radius * 2.0, radius * 2.0);
+        self.entities_within_rect(position_bounds)
+    }
+
     pub fn buckets(&self) -> impl Iterator::<Item = (&IVec2, &Vec<Entity>)> {
         self.buckets.iter()
     }
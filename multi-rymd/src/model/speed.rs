@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+
+use nanoserde::{DeJson, SerJson};
+
+use crate::PlayerID;
+
+use super::RymdGameModel;
+
+/// How long each player may keep the game paused in total over a match, in seconds.
+pub const PAUSE_BUDGET_SECONDS: f32 = 60.0;
+
+/// Simulation speed, as a multiple of the normal speed of one tick per update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, SerJson, DeJson)]
+pub enum GameSpeed {
+    Half,
+    Normal,
+    Double,
+    Triple,
+    Quadruple
+}
+
+impl GameSpeed {
+
+    pub const ALL: [GameSpeed; 5] = [GameSpeed::Half, GameSpeed::Normal, GameSpeed::Double, GameSpeed::Triple, GameSpeed::Quadruple];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameSpeed::Half => "0.5x",
+            GameSpeed::Normal => "1x",
+            GameSpeed::Double => "2x",
+            GameSpeed::Triple => "3x",
+            GameSpeed::Quadruple => "4x"
+        }
+    }
+
+    /// Counted in half ticks so that half speed can be accumulated exactly, floats would risk clients disagreeing on when to tick.
+    fn half_ticks_per_update(&self) -> u32 {
+        match self {
+            GameSpeed::Half => 1,
+            GameSpeed::Normal => 2,
+            GameSpeed::Double => 4,
+            GameSpeed::Triple => 6,
+            GameSpeed::Quadruple => 8
+        }
+    }
+
+    pub fn faster(&self) -> GameSpeed {
+        let index = GameSpeed::ALL.iter().position(|s| s == self).unwrap_or(1);
+        GameSpeed::ALL[(index + 1).min(GameSpeed::ALL.len() - 1)]
+    }
+
+    pub fn slower(&self) -> GameSpeed {
+        let index = GameSpeed::ALL.iter().position(|s| s == self).unwrap_or(1);
+        GameSpeed::ALL[index.saturating_sub(1)]
+    }
+
+}
+
+/// Speed and pause state of the simulation, only ever changed through game messages so that every client changes it on the same turn.
+pub struct GameSpeedState {
+    pub speed: GameSpeed,
+    pub votes: BTreeMap<PlayerID, GameSpeed>,
+    pub voters: Vec<PlayerID>,
+    /// Can change the speed straight away without waiting for everyone else to vote for it.
+    pub host: Option<PlayerID>,
+    pub paused_by: Option<PlayerID>,
+    pause_budgets: BTreeMap<PlayerID, u32>,
+    half_tick_accumulator: u32
+}
+
+impl GameSpeedState {
+
+    pub fn new() -> GameSpeedState {
+        GameSpeedState {
+            speed: GameSpeed::Normal,
+            votes: BTreeMap::new(),
+            voters: Vec::new(),
+            host: None,
+            paused_by: None,
+            pause_budgets: BTreeMap::new(),
+            half_tick_accumulator: 0
+        }
+    }
+
+    /// Sets up who gets a say in the speed of the game and a pause budget, usually just the human players.
+    pub fn start(&mut self, voters: Vec<PlayerID>, host: Option<PlayerID>) {
+
+        let pause_budget_updates = (PAUSE_BUDGET_SECONDS / RymdGameModel::TIME_STEP) as u32;
+
+        *self = GameSpeedState::new();
+        self.pause_budgets = voters.iter().map(|&voter| (voter, pause_budget_updates)).collect();
+        self.voters = voters;
+        self.host = host;
+
+    }
+
+    pub fn is_host(&self, player_id: PlayerID) -> bool {
+        self.host == Some(player_id)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_by.is_some()
+    }
+
+    pub fn remaining_pause_seconds(&self, player_id: PlayerID) -> f32 {
+        self.pause_budgets.get(&player_id).copied().unwrap_or(0) as f32 * RymdGameModel::TIME_STEP
+    }
+
+    /// The speed only changes once every voter has asked for the same speed, with a single voter that is immediately.
+    pub fn vote(&mut self, player_id: PlayerID, speed: GameSpeed) {
+
+        if self.voters.contains(&player_id) == false {
+            return;
+        }
+
+        self.votes.insert(player_id, speed);
+
+        let is_unanimous = self.voters.iter().all(|voter| self.votes.get(voter) == Some(&speed));
+        if is_unanimous {
+            self.speed = speed;
+            self.votes.clear();
+        }
+
+    }
+
+    /// Lets the host decide the speed without a vote, for when someone is away or just won't agree.
+    pub fn set_speed_as_host(&mut self, player_id: PlayerID, speed: GameSpeed) {
+
+        if self.is_host(player_id) == false {
+            return;
+        }
+
+        self.speed = speed;
+        self.votes.clear();
+
+    }
+
+    /// Anyone can resume the game, but pausing it is only possible with some pause budget left.
+    pub fn set_paused(&mut self, player_id: PlayerID, is_paused: bool) {
+
+        if is_paused == false {
+            self.paused_by = None;
+            return;
+        }
+
+        let has_pause_budget_left = self.pause_budgets.get(&player_id).is_some_and(|&budget| budget > 0);
+        if self.paused_by.is_none() && has_pause_budget_left {
+            self.paused_by = Some(player_id);
+        }
+
+    }
+
+    /// Called once per update of the game, returns how many ticks of the simulation should be run for it.
+    pub fn ticks_for_update(&mut self) -> u32 {
+
+        if let Some(paused_by) = self.paused_by {
+
+            let pause_budget = self.pause_budgets.entry(paused_by).or_insert(0);
+            *pause_budget = pause_budget.saturating_sub(1);
+
+            if *pause_budget == 0 {
+                self.paused_by = None;
+            }
+
+            return 0;
+
+        }
+
+        self.half_tick_accumulator += self.speed.half_ticks_per_update();
+        let ticks_to_run = self.half_tick_accumulator / 2;
+        self.half_tick_accumulator %= 2;
+
+        ticks_to_run
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_speed_changes_only_when_vote_is_unanimous() {
+
+        let mut speed_state = GameSpeedState::new();
+        speed_state.start(vec![1, 2], Some(1));
+
+        speed_state.vote(1, GameSpeed::Double);
+        assert_eq!(GameSpeed::Normal, speed_state.speed);
+
+        speed_state.vote(2, GameSpeed::Double);
+        assert_eq!(GameSpeed::Double, speed_state.speed);
+        assert!(speed_state.votes.is_empty());
+
+    }
+
+    #[test]
+    fn test_host_sets_speed_without_vote() {
+
+        let mut speed_state = GameSpeedState::new();
+        speed_state.start(vec![1, 2], Some(1));
+
+        speed_state.vote(2, GameSpeed::Half);
+        speed_state.set_speed_as_host(2, GameSpeed::Quadruple);
+        assert_eq!(GameSpeed::Normal, speed_state.speed);
+
+        speed_state.set_speed_as_host(1, GameSpeed::Triple);
+        assert_eq!(GameSpeed::Triple, speed_state.speed);
+        assert!(speed_state.votes.is_empty());
+
+    }
+
+}
@@ -1,7 +1,7 @@
 use macroquad::prelude::*;
 use hecs::{World, Entity};
 
-use utility::{arrive_ex, face_ex, SteeringOutput, Kinematic, AsVector};
+use utility::{arrive_ex, face_ex, SteeringOutput, SteeringParameters, Kinematic, AsVector};
 use super::{DynamicBody, MovementTarget, PhysicsBody, RotationTarget, Steering, Transform, DEFAULT_STEERING_PARAMETERS};
 
 pub fn get_entity_physics_position(world: &World, entity: Entity) -> Option<Vec2> {
@@ -68,64 +68,77 @@ pub fn entity_apply_raw_steering(kinematic: &mut Kinematic, steering_maybe: Opti
 
 }
 
-pub fn steer_entity_towards_target(world: &mut World, entity: Entity, x: f32, y: f32, dt: f32) {
-
-    if let Ok(mut dynamic_body) = world.get::<&mut DynamicBody>(entity) {
+fn get_entity_steering_parameters(world: &World, entity: Entity) -> SteeringParameters {
+    if let Ok(steering) = world.get::<&Steering>(entity) {
+        steering.parameters
+    } else {
+        DEFAULT_STEERING_PARAMETERS
+    }
+}
 
-        let parameters = if let Ok(steering) = world.get::<&Steering>(entity) {
-            steering.parameters
-        } else {
-            DEFAULT_STEERING_PARAMETERS
-        };
+/// Calculates the steering needed for the entity to arrive at and face the target, only reads from the world so it is safe to call from multiple threads.
+pub fn calculate_entity_steering_towards_target(world: &World, entity: Entity, x: f32, y: f32) -> Option<SteeringOutput> {
 
-        let target_kinematic = Kinematic { position: vec2(x, y), ..Default::default() };
-        let time_to_target = 1.0;
+    let dynamic_body = world.get::<&DynamicBody>(entity).ok()?;
+    let parameters = get_entity_steering_parameters(world, entity);
 
-        let arrive_steering_output = arrive_ex(
-            &dynamic_body.kinematic,
-            &target_kinematic,
-            parameters,
-            time_to_target
-        ).unwrap_or_default();
+    let target_kinematic = Kinematic { position: vec2(x, y), ..Default::default() };
+    let time_to_target = 1.0;
 
-        let face_steering_output = face_ex(
-            &dynamic_body.kinematic,
-            &target_kinematic,
-            parameters,
-            time_to_target
-        ).unwrap_or_default();
+    let arrive_steering_output = arrive_ex(
+        &dynamic_body.kinematic,
+        &target_kinematic,
+        parameters,
+        time_to_target
+    ).unwrap_or_default();
 
-        let final_steering_output = arrive_steering_output + face_steering_output;
-        entity_apply_steering(&mut dynamic_body.kinematic, Some(final_steering_output), dt);
+    let face_steering_output = face_ex(
+        &dynamic_body.kinematic,
+        &target_kinematic,
+        parameters,
+        time_to_target
+    ).unwrap_or_default();
 
-    }
+    Some(arrive_steering_output + face_steering_output)
 
 }
 
-pub fn point_entity_towards_target(world: &mut World, entity: Entity, x: f32, y: f32, dt: f32) {
+/// Calculates the steering needed for the entity to face the target, only reads from the world so it is safe to call from multiple threads.
+pub fn calculate_entity_steering_facing_target(world: &World, entity: Entity, x: f32, y: f32) -> Option<SteeringOutput> {
 
-    if let Ok(mut dynamic_body) = world.get::<&mut DynamicBody>(entity) {
+    let dynamic_body = world.get::<&DynamicBody>(entity).ok()?;
+    let parameters = get_entity_steering_parameters(world, entity);
 
-        let parameters = if let Ok(steering) = world.get::<&Steering>(entity) {
-            steering.parameters
-        } else {
-            DEFAULT_STEERING_PARAMETERS
-        };
+    let target_kinematic = Kinematic { position: vec2(x, y), ..Default::default() };
+    let time_to_target = 1.0;
 
-        let target_kinematic = Kinematic { position: vec2(x, y), ..Default::default() };
-        let time_to_target = 1.0;
+    let face_steering_output = face_ex(
+        &dynamic_body.kinematic,
+        &target_kinematic,
+        parameters,
+        time_to_target
+    ).unwrap_or_default();
 
-        let face_steering_output = face_ex(
-            &dynamic_body.kinematic,
-            &target_kinematic,
-            parameters,
-            time_to_target
-        ).unwrap_or_default();
+    Some(face_steering_output)
 
-        entity_apply_steering(&mut dynamic_body.kinematic, Some(face_steering_output), dt);
+}
 
+pub fn entity_apply_steering_output(world: &World, entity: Entity, steering_output: SteeringOutput, dt: f32) {
+    if let Ok(mut dynamic_body) = world.get::<&mut DynamicBody>(entity) {
+        entity_apply_steering(&mut dynamic_body.kinematic, Some(steering_output), dt);
+    }
+}
+
+pub fn steer_entity_towards_target(world: &mut World, entity: Entity, x: f32, y: f32, dt: f32) {
+    if let Some(steering_output) = calculate_entity_steering_towards_target(world, entity, x, y) {
+        entity_apply_steering_output(world, entity, steering_output, dt);
+    }
+}
+
+pub fn point_entity_towards_target(world: &mut World, entity: Entity, x: f32, y: f32, dt: f32) {
+    if let Some(steering_output) = calculate_entity_steering_facing_target(world, entity, x, y) {
+        entity_apply_steering_output(world, entity, steering_output, dt);
     }
-    
 }
 
 pub fn set_movement_target_to_entity(world: &mut World, entity: Entity, target_entity: Entity) {
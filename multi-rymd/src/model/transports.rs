@@ -0,0 +1,169 @@
+use std::f32::consts::PI;
+
+use hecs::{Entity, Or, World};
+use macroquad::math::{vec2, Vec2};
+use utility::RotatedBy;
+
+use super::{cancel_pending_orders, Attacker, Building, Controller, DynamicBody, EntityState, Extractor, Hangar, MovementTarget, Passenger, PreviousTransform, RotationTarget, RymdGameModel, Transform, Transport};
+
+/// Spacing between units unloaded from a transport at the same time.
+const TRANSPORT_UNLOAD_SPACING: f32 = 32.0;
+
+/// How close a recalled drone has to get to its carrier before it docks.
+pub const HANGAR_DOCKING_RANGE: f32 = 48.0;
+
+pub fn is_passenger(world: &World, entity: Entity) -> bool {
+    world.satisfies::<&Passenger>(entity).unwrap_or(false)
+}
+
+/// Only constructed friendly units can be loaded, buildings and anything carrying units itself can not.
+pub fn can_load_passenger(model: &RymdGameModel, transport: Entity, passenger: Entity) -> bool {
+
+    if transport == passenger || is_passenger(&model.world, passenger) {
+        return false;
+    }
+
+    let Ok(transport_component) = model.world.get::<&Transport>(transport) else { return false; };
+    if transport_component.is_full() {
+        return false;
+    }
+
+    let is_carrying_units = model.world.satisfies::<Or<&Transport, &Hangar>>(passenger).unwrap_or(false);
+    let is_building = model.world.satisfies::<&Building>(passenger).unwrap_or(false);
+    let is_constructed = model.world.get::<&EntityState>(passenger).is_ok_and(|state| *state == EntityState::Constructed);
+
+    if is_carrying_units || is_building || is_constructed == false {
+        return false;
+    }
+
+    match (model.world.get::<&Controller>(transport), model.world.get::<&Controller>(passenger)) {
+        (Ok(transport_controller), Ok(passenger_controller)) => model.is_controller_friendly_to(transport_controller.id, &passenger_controller),
+        _ => false
+    }
+
+}
+
+/// Loads the passenger into the transport if there is room for it, returns if it was loaded.
+pub fn load_passenger(model: &mut RymdGameModel, transport: Entity, passenger: Entity) -> bool {
+
+    if can_load_passenger(model, transport, passenger) == false {
+        return false;
+    }
+
+    if stow_passenger(model, transport, passenger) == false {
+        return false;
+    }
+
+    if let Ok(mut transport_component) = model.world.get::<&mut Transport>(transport) {
+        transport_component.passengers.push(passenger);
+    }
+
+    true
+
+}
+
+/// Unloads every passenger of the transport, spread out around the given position.
+pub fn unload_all_passengers(model: &mut RymdGameModel, transport: Entity, position: Vec2) {
+
+    let passengers = match model.world.get::<&mut Transport>(transport) {
+        Ok(mut transport_component) => std::mem::take(&mut transport_component.passengers),
+        Err(_) => return
+    };
+
+    for (i, passenger) in passengers.into_iter().enumerate() {
+        release_passenger(model, passenger, position + unload_offset(i));
+    }
+
+}
+
+/// Offsets spiral outwards from the unload position, so a full transport does not unload everything on top of itself.
+fn unload_offset(index: usize) -> Vec2 {
+
+    if index == 0 {
+        return Vec2::ZERO;
+    }
+
+    let angle = index as f32 * (PI * (3.0 - 5.0_f32.sqrt()));
+    let distance = TRANSPORT_UNLOAD_SPACING * (index as f32).sqrt();
+    vec2(distance, 0.0).rotated_by(angle)
+
+}
+
+/// Takes the entity out of the world and attaches it to the transport, the transport or hangar is responsible for keeping track of it.
+pub fn stow_passenger(model: &mut RymdGameModel, transport: Entity, passenger: Entity) -> bool {
+
+    cancel_pending_orders(&model.world, passenger);
+
+    // without a body the physics engine drops the entity, and without a previous transform it is added to the spatial engine again once unloaded
+    let Ok((body,)) = model.world.remove::<(DynamicBody,)>(passenger) else { return false; };
+    let _ = model.world.remove_one::<PreviousTransform>(passenger);
+
+    if let Ok(mut transform) = model.world.get::<&mut Transform>(passenger) {
+        model.spatial_manager.remove_entity(passenger, transform.world_position);
+        transform.parent = Some(transport);
+        transform.local_position = Vec2::ZERO;
+        transform.local_rotation = 0.0;
+    }
+
+    if let Ok(mut state) = model.world.get::<&mut EntityState>(passenger) {
+        *state = EntityState::Inactive;
+    }
+
+    clear_passenger_targets(&model.world, passenger);
+
+    let _ = model.world.insert_one(passenger, Passenger { transport, body });
+
+    true
+
+}
+
+/// Puts a stowed entity back into the world at the given position.
+pub fn release_passenger(model: &mut RymdGameModel, passenger: Entity, position: Vec2) {
+
+    let Ok((passenger_component,)) = model.world.remove::<(Passenger,)>(passenger) else { return; };
+    let transport = passenger_component.transport;
+    let mut body = passenger_component.body;
+
+    // passengers are never buildings, and drones are docked before they have had the chance to be made non-static
+    body.is_static = false;
+    body.kinematic.position = position;
+    body.kinematic.velocity = Vec2::ZERO;
+    body.kinematic.angular_velocity = 0.0;
+
+    if let Ok(mut transform) = model.world.get::<&mut Transform>(passenger) {
+        body.kinematic.orientation = transform.world_rotation;
+        *transform = Transform::new(position, transform.world_rotation, None);
+    }
+
+    if let Ok(mut state) = model.world.get::<&mut EntityState>(passenger) {
+        *state = EntityState::Constructed;
+    }
+
+    let _ = model.world.insert_one(passenger, body);
+
+    if let Ok(mut transport_component) = model.world.get::<&mut Transport>(transport) {
+        transport_component.passengers.retain(|&p| p != passenger);
+    }
+
+}
+
+fn clear_passenger_targets(world: &World, passenger: Entity) {
+
+    if let Ok(mut attacker) = world.get::<&mut Attacker>(passenger) {
+        attacker.target = None;
+    }
+
+    if let Ok(mut extractor) = world.get::<&mut Extractor>(passenger) {
+        extractor.current_target = None;
+        extractor.is_searching = false;
+    }
+
+    if let Ok(mut movement_target) = world.get::<&mut MovementTarget>(passenger) {
+        movement_target.target = None;
+    }
+
+    if let Ok(mut rotation_target) = world.get::<&mut RotationTarget>(passenger) {
+        rotation_target.target = None;
+    }
+
+}
@@ -0,0 +1,391 @@
+use std::time::Instant;
+
+use hecs::Entity;
+use macroquad::math::vec2;
+use nanoserde::{DeJson, SerJson};
+
+use crate::game::{RymdGameParameters, RymdGamePlayer};
+use crate::model::{AttackMoveOrder, Blueprints, Controller, GameMessage, GameOrder, Health, Orderable, RymdGameModel, set_default_energy_pool_size, set_default_metal_pool_size, set_player_team_allegiance};
+use crate::PlayerID;
+
+use super::helpers::{create_asteroid_clumps, create_players};
+
+const DEFAULT_NUMBER_OF_TICKS: u64 = 60 * 60;
+const DEFAULT_NUMBER_OF_UNITS: i32 = 2000;
+
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct RymdScenarioPlayer {
+    pub id: PlayerID,
+    pub team_id: u64
+}
+
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct RymdScenarioUnits {
+    pub player_id: PlayerID,
+    pub blueprint_id: i32,
+    pub count: i32,
+    pub x: f32,
+    pub y: f32,
+    pub spread: i32
+}
+
+/// An order issued at a given tick to every orderable entity controlled by the given player.
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct RymdScenarioOrder {
+    pub tick: u64,
+    pub player_id: PlayerID,
+    pub order: GameOrder,
+    pub add: bool
+}
+
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct RymdScenario {
+    pub name: String,
+    pub seed: u64,
+    pub starting_metal: i32,
+    pub starting_energy: i32,
+    pub number_of_asteroid_clumps: i32,
+    pub number_of_asteroids: i32,
+    pub players: Vec<RymdScenarioPlayer>,
+    pub units: Vec<RymdScenarioUnits>,
+    pub orders: Vec<RymdScenarioOrder>
+}
+
+impl RymdScenario {
+
+    pub fn load(path: &str) -> Result<RymdScenario, String> {
+        let scenario_data = std::fs::read_to_string(path).map_err(|e| format!("could not read scenario: {}, error: {}", path, e))?;
+        RymdScenario::deserialize_json(&scenario_data).map_err(|e| format!("could not parse scenario: {}, error: {}", path, e))
+    }
+
+    /// Two opposing armies which start on opposite sides of the map and then attack move into eachother.
+    pub fn stress(number_of_units: i32) -> RymdScenario {
+
+        let number_of_units_per_player = number_of_units / 2;
+        let army_distance = 1500.0;
+
+        RymdScenario {
+            name: format!("stress ({} units)", number_of_units_per_player * 2),
+            seed: 42,
+            starting_metal: 1000,
+            starting_energy: 1000,
+            number_of_asteroid_clumps: 10,
+            number_of_asteroids: 10,
+            players: vec![
+                RymdScenarioPlayer { id: 1, team_id: 0 },
+                RymdScenarioPlayer { id: 2, team_id: 1 }
+            ],
+            units: vec![
+                RymdScenarioUnits { player_id: 1, blueprint_id: Blueprints::Arrowhead as i32, count: number_of_units_per_player, x: -army_distance, y: 0.0, spread: 800 },
+                RymdScenarioUnits { player_id: 2, blueprint_id: Blueprints::Grunt as i32, count: number_of_units_per_player, x: army_distance, y: 0.0, spread: 800 }
+            ],
+            orders: vec![
+                RymdScenarioOrder { tick: 10, player_id: 1, order: GameOrder::AttackMove(AttackMoveOrder { x: army_distance, y: 0.0 }), add: false },
+                RymdScenarioOrder { tick: 10, player_id: 2, order: GameOrder::AttackMove(AttackMoveOrder { x: -army_distance, y: 0.0 }), add: false }
+            ]
+        }
+
+    }
+
+    fn parameters(&self) -> RymdGameParameters {
+        RymdGameParameters { players: self.players.iter().map(|p| RymdGamePlayer { id: p.id }).collect() }
+    }
+
+    fn setup(&self, model: &mut RymdGameModel) {
+
+        model.random.srand(self.seed);
+
+        create_players(model, &self.parameters());
+
+        for player in &self.players {
+            let current_team_mask: u64 = 1 << player.team_id;
+            set_player_team_allegiance(&mut model.world, player.id, current_team_mask);
+        }
+
+        create_asteroid_clumps(model, self.number_of_asteroid_clumps, self.number_of_asteroids);
+
+        for units in &self.units {
+
+            let Some(blueprint) = model.blueprint_manager.get_blueprint(units.blueprint_id) else {
+                println!("[RymdHeadless] scenario referenced unknown blueprint: {}, skipping!", units.blueprint_id);
+                continue;
+            };
+
+            let blueprint_constructor = blueprint.constructor;
+
+            for i in 0..units.count {
+
+                let random_x = model.random.gen_range(-units.spread, units.spread) as f32;
+                let random_y = model.random.gen_range(-units.spread, units.spread) as f32;
+
+                let new_unit = blueprint_constructor(&mut model.world, units.player_id, vec2(units.x + random_x, units.y + random_y));
+                if let Ok(mut health) = model.world.get::<&mut Health>(new_unit) {
+                    health.heal_to_full_health();
+                }
+
+            }
+
+        }
+
+        set_default_metal_pool_size(&mut model.world, self.starting_metal, self.starting_metal);
+        set_default_energy_pool_size(&mut model.world, self.starting_energy, self.starting_energy);
+
+    }
+
+    fn issue_orders_for_tick(&self, model: &mut RymdGameModel) {
+
+        for scenario_order in &self.orders {
+
+            if scenario_order.tick != model.current_tick {
+                continue;
+            }
+
+            let mut entities_to_order: Vec<Entity> = model.world.query::<(&Controller, &Orderable)>().iter()
+                .filter(|(_, (controller, _))| controller.id == scenario_order.player_id)
+                .map(|(e, _)| e)
+                .collect();
+
+            // sort so orders are always issued in the same order, regardless of how the world happens to be laid out
+            entities_to_order.sort();
+
+            for e in entities_to_order {
+                let order_message = GameMessage::Order { entity: e.to_bits().into(), order: scenario_order.order, add: scenario_order.add };
+                model.handle_message(&order_message);
+            }
+
+        }
+
+    }
+
+}
+
+pub struct HeadlessArguments {
+    pub scenario_path: Option<String>,
+    pub number_of_ticks: u64,
+    pub number_of_units: i32,
+    /// Runs the systems that would otherwise be spread over the worker threads on the main thread, to compare tick times against.
+    pub is_serial: bool
+}
+
+impl HeadlessArguments {
+
+    /// Parses ```--headless [scenario.json] [--ticks N] [--units N] [--serial]```, returns None if the game should start as usual.
+    pub fn from_args<I>(args: I) -> Result<Option<HeadlessArguments>, String>
+        where I: Iterator<Item = String>
+    {
+
+        let args: Vec<String> = args.collect();
+        if args.iter().any(|a| a == "--headless") == false {
+            return Ok(None);
+        }
+
+        let mut arguments = HeadlessArguments {
+            scenario_path: None,
+            number_of_ticks: DEFAULT_NUMBER_OF_TICKS,
+            number_of_units: DEFAULT_NUMBER_OF_UNITS,
+            is_serial: false
+        };
+
+        let mut args_iterator = args.iter().skip(1);
+        while let Some(arg) = args_iterator.next() {
+
+            let mut next_value = || args_iterator.next().cloned().ok_or_else(|| format!("missing value for: {}", arg));
+            let parse_error = |value: &str| format!("invalid value for: {}, got: {}", arg, value);
+
+            match arg.as_str() {
+                "--headless" => (),
+                "--serial" => arguments.is_serial = true,
+                "--ticks" => { let v = next_value()?; arguments.number_of_ticks = v.parse().map_err(|_| parse_error(&v))?; },
+                "--units" => { let v = next_value()?; arguments.number_of_units = v.parse().map_err(|_| parse_error(&v))?; },
+                other if other.starts_with("--") => return Err(format!("unknown argument: {}", other)),
+                path if arguments.scenario_path.is_some() => return Err(format!("more than one scenario passed, got: {}", path)),
+                path => arguments.scenario_path = Some(path.to_string())
+            }
+
+        }
+
+        Ok(Some(arguments))
+
+    }
+
+}
+
+struct SystemMeasurements {
+    name: &'static str,
+    samples_ms: Vec<f32>
+}
+
+impl SystemMeasurements {
+
+    fn percentile(sorted_samples_ms: &[f32], percentile: f32) -> f32 {
+        if sorted_samples_ms.is_empty() {
+            return 0.0;
+        }
+        let index = ((sorted_samples_ms.len() - 1) as f32 * percentile).round() as usize;
+        sorted_samples_ms[index]
+    }
+
+    fn print(&self) {
+
+        let mut sorted_samples_ms = self.samples_ms.clone();
+        sorted_samples_ms.sort_by(|a, b| a.total_cmp(b));
+
+        let mean_ms = sorted_samples_ms.iter().sum::<f32>() / (sorted_samples_ms.len().max(1) as f32);
+
+        println!(
+            "{:<24} mean: {:>8.3} ms  p50: {:>8.3} ms  p95: {:>8.3} ms  p99: {:>8.3} ms  max: {:>8.3} ms",
+            self.name,
+            mean_ms,
+            Self::percentile(&sorted_samples_ms, 0.50),
+            Self::percentile(&sorted_samples_ms, 0.95),
+            Self::percentile(&sorted_samples_ms, 0.99),
+            Self::percentile(&sorted_samples_ms, 1.0)
+        );
+
+    }
+
+}
+
+/// Runs the given scenario without a window, reporting per system tick times and the final state hash at the end.
+pub fn run_headless(arguments: HeadlessArguments) {
+
+    let scenario = match &arguments.scenario_path {
+        Some(path) => match RymdScenario::load(path) {
+            Ok(scenario) => scenario,
+            Err(error) => {
+                println!("[RymdHeadless] {}", error);
+                return;
+            }
+        },
+        None => RymdScenario::stress(arguments.number_of_units)
+    };
+
+    let mut model = RymdGameModel::new();
+    model.is_parallelism_enabled = arguments.is_serial == false;
+    scenario.setup(&mut model);
+    model.start(scenario.parameters());
+
+    let number_of_entities = model.world.len();
+    let execution_mode = if arguments.is_serial { "serially" } else { "in parallel" };
+    println!("[RymdHeadless] running scenario: {} with {} entities for {} ticks {}", scenario.name, number_of_entities, arguments.number_of_ticks, execution_mode);
+
+    let mut system_measurements: Vec<SystemMeasurements> = RymdGameModel::SYSTEMS.iter()
+        .map(|(name, _)| SystemMeasurements { name, samples_ms: Vec::with_capacity(arguments.number_of_ticks as usize) })
+        .collect();
+
+    let mut tick_measurements = SystemMeasurements { name: "tick", samples_ms: Vec::with_capacity(arguments.number_of_ticks as usize) };
+
+    for _ in 0..arguments.number_of_ticks {
+
+        scenario.issue_orders_for_tick(&mut model);
+
+        let tick_start = Instant::now();
+        let mut current_system_index = 0;
+
+        model.tick_with(|model, _, system| {
+            let system_start = Instant::now();
+            system(model);
+            system_measurements[current_system_index].samples_ms.push(system_start.elapsed().as_secs_f32() * 1000.0);
+            current_system_index += 1;
+        });
+
+        tick_measurements.samples_ms.push(tick_start.elapsed().as_secs_f32() * 1000.0);
+
+    }
+
+    println!("[RymdHeadless] finished at tick: {} with {} entities remaining", model.current_tick, model.world.len());
+
+    for measurements in &system_measurements {
+        measurements.print();
+    }
+
+    tick_measurements.print();
+
+    println!("[RymdHeadless] final state hash: {:016x}", model.state_hash());
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn to_args(args: &[&str]) -> impl Iterator<Item = String> {
+        std::iter::once("multi-rymd").chain(args.iter().copied()).map(String::from).collect::<Vec<_>>().into_iter()
+    }
+
+    /// Sets up and runs the scenario from scratch, returning the state hash after every tick.
+    fn run_scenario_state_hashes(scenario: &RymdScenario, number_of_ticks: u64, is_parallel: bool) -> Vec<u64> {
+
+        let mut model = RymdGameModel::new();
+        model.is_parallelism_enabled = is_parallel;
+        scenario.setup(&mut model);
+        model.start(scenario.parameters());
+
+        let mut state_hashes = Vec::with_capacity(number_of_ticks as usize);
+
+        for _ in 0..number_of_ticks {
+            scenario.issue_orders_for_tick(&mut model);
+            model.tick();
+            state_hashes.push(model.state_hash());
+        }
+
+        state_hashes
+
+    }
+
+    #[test]
+    fn test_same_scenario_gives_same_state_hashes() {
+
+        let scenario = RymdScenario::stress(64);
+        let number_of_ticks = 600;
+
+        let first_run = run_scenario_state_hashes(&scenario, number_of_ticks, true);
+        let second_run = run_scenario_state_hashes(&scenario, number_of_ticks, true);
+
+        let first_diverging_tick = first_run.iter().zip(&second_run).position(|(a, b)| a != b);
+        assert_eq!(None, first_diverging_tick, "state hashes diverged between two runs of the same scenario");
+
+    }
+
+    #[test]
+    fn test_serial_and_parallel_give_same_state_hashes() {
+
+        // enough units on each side that the systems actually get split up over the worker threads
+        let scenario = RymdScenario::stress(800);
+        let number_of_ticks = 300;
+
+        let serial_run = run_scenario_state_hashes(&scenario, number_of_ticks, false);
+        let parallel_run = run_scenario_state_hashes(&scenario, number_of_ticks, true);
+
+        let first_diverging_tick = serial_run.iter().zip(&parallel_run).position(|(a, b)| a != b);
+        assert_eq!(None, first_diverging_tick, "state hashes diverged between running serially and in parallel");
+
+    }
+
+    #[test]
+    fn test_from_args_without_headless_starts_game() {
+        assert!(matches!(HeadlessArguments::from_args(to_args(&["--ticks", "10"])), Ok(None)));
+    }
+
+    #[test]
+    fn test_from_args_parses_scenario_and_values() {
+
+        let arguments = HeadlessArguments::from_args(to_args(&["--headless", "scenario.json", "--ticks", "10", "--units", "20"])).unwrap().unwrap();
+
+        assert_eq!(Some("scenario.json".to_string()), arguments.scenario_path);
+        assert_eq!(10, arguments.number_of_ticks);
+        assert_eq!(20, arguments.number_of_units);
+        assert!(arguments.is_serial == false);
+
+    }
+
+    #[test]
+    fn test_from_args_rejects_unknown_and_invalid_arguments() {
+        assert!(HeadlessArguments::from_args(to_args(&["--headless", "--tick", "10"])).is_err());
+        assert!(HeadlessArguments::from_args(to_args(&["--headless", "--ticks", "ten"])).is_err());
+        assert!(HeadlessArguments::from_args(to_args(&["--headless", "--units"])).is_err());
+        assert!(HeadlessArguments::from_args(to_args(&["--headless", "a.json", "b.json"])).is_err());
+    }
+
+}
@@ -1,3 +1,4 @@
 pub mod measure;
 pub mod hecs;
-pub mod helpers;
\ No newline at end of file
+pub mod helpers;
+pub mod headless;
\ No newline at end of file
@@ -1,7 +1,19 @@
+mod alerts;
+mod audio;
 mod camera;
+mod console;
+mod input;
+mod markers;
+mod minimap;
 mod sprite;
 mod view;
 
+pub use alerts::*;
+pub use audio::*;
 pub use camera::*;
+pub use console::*;
+pub use input::*;
+pub use markers::*;
+pub use minimap::*;
 pub use sprite::*;
 pub use view::*;
\ No newline at end of file
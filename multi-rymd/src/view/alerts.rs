@@ -0,0 +1,221 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+use puffin_egui::egui::{self, Align2};
+
+use crate::PlayerID;
+use crate::model::RymdGameEvent;
+
+use super::GameCamera2D;
+
+/// How long an alert stays in the list on screen, in seconds.
+const ALERT_DURATION: f64 = 6.0;
+
+/// How long the ping of an alert stays on the minimap, in seconds.
+const PING_DURATION: f64 = 3.0;
+
+/// Alerts of the same kind closer than this to a recent one are dropped, a battle should not produce an alert for every ship hit.
+const ALERT_THROTTLE_DISTANCE: f32 = 768.0;
+
+/// Alerts are remembered for this long so they can throttle new ones, must be at least as long as the longest throttle duration.
+const ALERT_MEMORY_DURATION: f64 = 15.0;
+
+const MAXIMUM_NUMBER_OF_ALERTS_SHOWN: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    UnitUnderAttack,
+    BuildingUnderAttack,
+    ConstructionCompleted,
+    ResourceSourceExhausted,
+    UnitLost,
+    BuildingLost,
+    CommanderLost
+}
+
+impl AlertKind {
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            AlertKind::UnitUnderAttack => "unit under attack",
+            AlertKind::BuildingUnderAttack => "building under attack",
+            AlertKind::ConstructionCompleted => "construction completed",
+            AlertKind::ResourceSourceExhausted => "resource source exhausted",
+            AlertKind::UnitLost => "unit lost",
+            AlertKind::BuildingLost => "building lost",
+            AlertKind::CommanderLost => "commander lost!"
+        }
+    }
+
+    pub fn colour(&self) -> egui::Color32 {
+        match self {
+            AlertKind::UnitUnderAttack => egui::Color32::from_rgb(255, 160, 64),
+            AlertKind::BuildingUnderAttack => egui::Color32::from_rgb(255, 160, 64),
+            AlertKind::ConstructionCompleted => egui::Color32::from_rgb(96, 224, 96),
+            AlertKind::ResourceSourceExhausted => egui::Color32::from_rgb(224, 224, 96),
+            AlertKind::UnitLost => egui::Color32::from_rgb(224, 64, 64),
+            AlertKind::BuildingLost => egui::Color32::from_rgb(224, 64, 64),
+            AlertKind::CommanderLost => egui::Color32::from_rgb(255, 32, 32)
+        }
+    }
+
+    /// Seconds before another alert of this kind can be raised in the same area.
+    fn throttle_duration(&self) -> f64 {
+        match self {
+            AlertKind::UnitUnderAttack => 15.0,
+            AlertKind::BuildingUnderAttack => 15.0,
+            AlertKind::ConstructionCompleted => 5.0,
+            AlertKind::ResourceSourceExhausted => 5.0,
+            AlertKind::UnitLost => 10.0,
+            AlertKind::BuildingLost => 5.0,
+            AlertKind::CommanderLost => 0.0
+        }
+    }
+
+    /// Being attacked where the player is already looking is not news, everything else is.
+    fn is_suppressed_when_visible(&self) -> bool {
+        *self == AlertKind::UnitUnderAttack || *self == AlertKind::BuildingUnderAttack
+    }
+
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub position: Vec2,
+    pub time: f64
+}
+
+impl Alert {
+
+    /// Returns how far along the ping of this alert is, from 0 to 1, or None when it is done.
+    pub fn ping_progress(&self, now: f64) -> Option<f32> {
+        let progress = (now - self.time) / PING_DURATION;
+        if progress < 1.0 { Some(progress as f32) } else { None }
+    }
+
+}
+
+pub struct AlertManager {
+    alerts: VecDeque<Alert>,
+    last_alert_position: Option<Vec2>
+}
+
+impl AlertManager {
+
+    pub fn new() -> AlertManager {
+        AlertManager {
+            alerts: VecDeque::new(),
+            last_alert_position: None
+        }
+    }
+
+    pub fn last_alert_position(&self) -> Option<Vec2> {
+        self.last_alert_position
+    }
+
+    /// Returns the alerts which should still be pinging on the minimap.
+    pub fn pings(&self) -> impl Iterator<Item = &Alert> {
+        let now = get_time();
+        self.alerts.iter().filter(move |alert| alert.ping_progress(now).is_some())
+    }
+
+    fn alert_for_event(event: &RymdGameEvent, player_id: PlayerID) -> Option<(AlertKind, Vec2)> {
+        match *event {
+            RymdGameEvent::UnderAttack { owner, position, is_building, .. } if owner == player_id => {
+                Some((if is_building { AlertKind::BuildingUnderAttack } else { AlertKind::UnitUnderAttack }, position))
+            },
+            RymdGameEvent::ConstructionCompleted { owner, position, .. } if owner == player_id => Some((AlertKind::ConstructionCompleted, position)),
+            RymdGameEvent::ResourceSourceExhausted { owner, position, .. } if owner == player_id => Some((AlertKind::ResourceSourceExhausted, position)),
+            RymdGameEvent::CommanderDestroyed { owner, position, .. } if owner == player_id => Some((AlertKind::CommanderLost, position)),
+            RymdGameEvent::Destroyed { owner: Some(owner), position, is_building, .. } if owner == player_id => {
+                Some((if is_building { AlertKind::BuildingLost } else { AlertKind::UnitLost }, position))
+            },
+            _ => None
+        }
+    }
+
+    fn is_throttled(&self, kind: AlertKind, position: Vec2, now: f64) -> bool {
+        self.alerts.iter().any(|alert| {
+            alert.kind == kind
+                && now - alert.time < kind.throttle_duration()
+                && alert.position.distance(position) < ALERT_THROTTLE_DISTANCE
+        })
+    }
+
+    /// Turns the events of the last tick into alerts for the given player, returns true if any new alerts were raised.
+    pub fn handle_events(&mut self, events: &[RymdGameEvent], player_id: PlayerID, camera: &GameCamera2D) -> bool {
+
+        let now = get_time();
+        let visible_world_rect = camera.screen_to_world_rect(Rect { x: 0.0, y: 0.0, w: screen_width(), h: screen_height() });
+        let mut raised_any_alert = false;
+
+        for event in events {
+
+            let Some((kind, position)) = Self::alert_for_event(event, player_id) else { continue; };
+
+            if kind.is_suppressed_when_visible() && visible_world_rect.contains(position) {
+                continue;
+            }
+
+            if self.is_throttled(kind, position, now) {
+                continue;
+            }
+
+            self.alerts.push_back(Alert { kind, position, time: now });
+            self.last_alert_position = Some(position);
+            raised_any_alert = true;
+
+        }
+
+        while let Some(alert) = self.alerts.front() && now - alert.time > ALERT_MEMORY_DURATION {
+            self.alerts.pop_front();
+        }
+
+        raised_any_alert
+
+    }
+
+    /// Draws the most recent alerts, returns the position of the alert clicked on, if any.
+    pub fn draw_alerts_ui(&self, ui_ctx: &egui::Context) -> Option<Vec2> {
+
+        let now = get_time();
+        let recent_alerts: Vec<&Alert> = self.alerts.iter()
+            .rev()
+            .filter(|alert| now - alert.time < ALERT_DURATION)
+            .take(MAXIMUM_NUMBER_OF_ALERTS_SHOWN)
+            .collect();
+
+        if recent_alerts.is_empty() {
+            return None;
+        }
+
+        let mut clicked_alert_position = None;
+
+        egui::Area::new(egui::Id::new("alerts"))
+            .anchor(Align2::CENTER_TOP, (0.0, 96.0))
+            .show(ui_ctx, |ui| {
+
+                for alert in recent_alerts {
+
+                    // fade out over the last second
+                    let alpha = ((ALERT_DURATION - (now - alert.time)).min(1.0) * 255.0) as u8;
+                    let colour = alert.kind.colour();
+                    let faded_colour = egui::Color32::from_rgba_unmultiplied(colour.r(), colour.g(), colour.b(), alpha);
+
+                    let alert_response = ui.add(egui::Label::new(egui::RichText::new(alert.kind.message()).color(faded_colour).strong()).sense(egui::Sense::click()))
+                        .on_hover_text("click to jump to the alert");
+
+                    if alert_response.clicked() {
+                        clicked_alert_position = Some(alert.position);
+                    }
+
+                }
+
+        });
+
+        clicked_alert_position
+
+    }
+
+}
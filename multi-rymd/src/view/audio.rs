@@ -0,0 +1,563 @@
+use std::f32::consts::PI;
+
+use fnv::FnvHashMap;
+use macroquad::audio::{load_sound_from_bytes, play_sound, PlaySoundParams, Sound};
+use macroquad::prelude::*;
+use nanoserde::{DeJson, SerJson};
+use puffin_egui::egui;
+use utility::screen_dimensions;
+
+use crate::model::{RymdGameEvent, WeaponKind};
+
+use super::GameCamera2D;
+
+pub const AUDIO_SETTINGS_PATH: &str = "config/audio.json";
+
+const SOUNDS_DIRECTORY: &str = "raw/sounds";
+const SYNTHESIZED_SAMPLE_RATE: u32 = 22050;
+
+/// Every sound is prepared at this many fixed pan positions, as macroquad can only set the volume of a sound when playing it.
+const NUMBER_OF_PAN_STEPS: usize = 5;
+
+/// Sounds within this distance from the center of the view (in view radii) are played at full volume, beyond it they fade out.
+const FULL_VOLUME_VIEW_RADII: f32 = 0.5;
+
+/// Sounds further away than this from the center of the view (in view radii) are not played at all.
+const AUDIBLE_VIEW_RADII: f32 = 2.0;
+
+/// The same sound started again within this many seconds is dropped, a hundred ships firing in the same tick should not be a hundred times as loud.
+const MIN_SECONDS_BETWEEN_SAME_SOUND: f64 = 0.04;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundEffect {
+    ProjectileFired,
+    BeamFired,
+    Impact,
+    ShipDestroyed,
+    BuildingDestroyed,
+    ConstructionCompleted,
+    OrderAcknowledged,
+    Alert
+}
+
+impl SoundEffect {
+
+    const ALL: [SoundEffect; 8] = [
+        SoundEffect::ProjectileFired,
+        SoundEffect::BeamFired,
+        SoundEffect::Impact,
+        SoundEffect::ShipDestroyed,
+        SoundEffect::BuildingDestroyed,
+        SoundEffect::ConstructionCompleted,
+        SoundEffect::OrderAcknowledged,
+        SoundEffect::Alert
+    ];
+
+    /// The name of the file (without extension) in the sounds directory which replaces the synthesized placeholder.
+    fn name(&self) -> &'static str {
+        match self {
+            SoundEffect::ProjectileFired => "projectile_fired",
+            SoundEffect::BeamFired => "beam_fired",
+            SoundEffect::Impact => "impact",
+            SoundEffect::ShipDestroyed => "ship_destroyed",
+            SoundEffect::BuildingDestroyed => "building_destroyed",
+            SoundEffect::ConstructionCompleted => "construction_completed",
+            SoundEffect::OrderAcknowledged => "order_acknowledged",
+            SoundEffect::Alert => "alert"
+        }
+    }
+
+    /// Relative loudness of the effect in the mix, weapons are frequent so they are kept quiet.
+    fn base_volume(&self) -> f32 {
+        match self {
+            SoundEffect::ProjectileFired => 0.3,
+            SoundEffect::BeamFired => 0.35,
+            SoundEffect::Impact => 0.25,
+            SoundEffect::ShipDestroyed => 0.8,
+            SoundEffect::BuildingDestroyed => 1.0,
+            SoundEffect::ConstructionCompleted => 0.6,
+            SoundEffect::OrderAcknowledged => 0.5,
+            SoundEffect::Alert => 0.7
+        }
+    }
+
+    fn is_interface_sound(&self) -> bool {
+        *self == SoundEffect::OrderAcknowledged || *self == SoundEffect::Alert
+    }
+
+}
+
+#[derive(Debug, Clone, SerJson, DeJson)]
+pub struct AudioSettings {
+    pub is_muted: bool,
+    pub master_volume: f32,
+    pub effects_volume: f32,
+    pub interface_volume: f32,
+    pub max_voices: i32
+}
+
+impl AudioSettings {
+    pub fn new() -> AudioSettings {
+        AudioSettings {
+            is_muted: false,
+            master_volume: 0.8,
+            effects_volume: 1.0,
+            interface_volume: 1.0,
+            max_voices: 24
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Waveform {
+    Sine,
+    Square,
+    Noise
+}
+
+/// Placeholder sound description, used to synthesize a sound when there is no sound file for an effect.
+struct Tone {
+    duration: f32,
+    start_frequency: f32,
+    end_frequency: f32,
+    waveform: Waveform,
+    smoothing: f32
+}
+
+fn synthesize_tone(tone: &Tone, samples: &mut Vec<f32>) {
+
+    let number_of_samples = (tone.duration * SYNTHESIZED_SAMPLE_RATE as f32) as usize;
+    let attack_samples = (SYNTHESIZED_SAMPLE_RATE as f32 * 0.005) as usize;
+
+    let mut noise_state: u32 = 0x9E3779B9;
+    let mut phase = 0.0;
+    let mut filtered_sample = 0.0;
+
+    for i in 0..number_of_samples {
+
+        let t = i as f32 / number_of_samples as f32;
+        let frequency = tone.start_frequency + (tone.end_frequency - tone.start_frequency) * t;
+        phase = (phase + frequency / SYNTHESIZED_SAMPLE_RATE as f32).fract();
+
+        let raw_sample = match tone.waveform {
+            Waveform::Sine => (phase * 2.0 * PI).sin(),
+            Waveform::Square => if phase < 0.5 { 0.6 } else { -0.6 },
+            Waveform::Noise => {
+                // xorshift, so the placeholder sounds are always the same
+                noise_state ^= noise_state << 13;
+                noise_state ^= noise_state >> 17;
+                noise_state ^= noise_state << 5;
+                (noise_state as f32 / u32::MAX as f32) * 2.0 - 1.0
+            }
+        };
+
+        // one pole low pass, takes the edge off the noise and square waves
+        filtered_sample += (raw_sample - filtered_sample) * (1.0 - tone.smoothing);
+
+        let attack = (i as f32 / attack_samples as f32).min(1.0);
+        let decay = (1.0 - t).powi(2);
+        samples.push(filtered_sample * attack * decay);
+
+    }
+
+}
+
+fn synthesize_sound_effect(effect: SoundEffect) -> Vec<f32> {
+
+    let tones: &[Tone] = match effect {
+        SoundEffect::ProjectileFired => &[Tone { duration: 0.08, start_frequency: 880.0, end_frequency: 220.0, waveform: Waveform::Square, smoothing: 0.5 }],
+        SoundEffect::BeamFired => &[Tone { duration: 0.15, start_frequency: 1400.0, end_frequency: 600.0, waveform: Waveform::Sine, smoothing: 0.0 }],
+        SoundEffect::Impact => &[Tone { duration: 0.06, start_frequency: 0.0, end_frequency: 0.0, waveform: Waveform::Noise, smoothing: 0.6 }],
+        SoundEffect::ShipDestroyed => &[Tone { duration: 0.5, start_frequency: 0.0, end_frequency: 0.0, waveform: Waveform::Noise, smoothing: 0.92 }],
+        SoundEffect::BuildingDestroyed => &[Tone { duration: 0.9, start_frequency: 0.0, end_frequency: 0.0, waveform: Waveform::Noise, smoothing: 0.96 }],
+        SoundEffect::ConstructionCompleted => &[
+            Tone { duration: 0.12, start_frequency: 660.0, end_frequency: 660.0, waveform: Waveform::Sine, smoothing: 0.0 },
+            Tone { duration: 0.2, start_frequency: 990.0, end_frequency: 990.0, waveform: Waveform::Sine, smoothing: 0.0 }
+        ],
+        SoundEffect::OrderAcknowledged => &[Tone { duration: 0.05, start_frequency: 1320.0, end_frequency: 1100.0, waveform: Waveform::Sine, smoothing: 0.0 }],
+        SoundEffect::Alert => &[
+            Tone { duration: 0.1, start_frequency: 880.0, end_frequency: 880.0, waveform: Waveform::Square, smoothing: 0.7 },
+            Tone { duration: 0.1, start_frequency: 880.0, end_frequency: 880.0, waveform: Waveform::Square, smoothing: 0.7 }
+        ]
+    };
+
+    let mut samples = Vec::new();
+
+    for tone in tones {
+        synthesize_tone(tone, &mut samples);
+    }
+
+    samples
+
+}
+
+/// Reads a 16 bit PCM wav file and mixes it down to mono, anything else is not supported and returns None.
+fn decode_wav(bytes: &[u8]) -> Option<(u32, Vec<f32>)> {
+
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+
+    while offset + 8 <= bytes.len() {
+
+        let chunk_id = &bytes[offset..offset + 4];
+        let chunk_size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let chunk_start = offset + 8;
+        let chunk_end = (chunk_start + chunk_size).min(bytes.len());
+        let chunk = &bytes[chunk_start..chunk_end];
+
+        match chunk_id {
+            b"fmt " if chunk.len() >= 16 => {
+                let audio_format = u16::from_le_bytes([chunk[0], chunk[1]]);
+                let number_of_channels = u16::from_le_bytes([chunk[2], chunk[3]]);
+                let sample_rate = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+                let bits_per_sample = u16::from_le_bytes([chunk[14], chunk[15]]);
+                format = Some((audio_format, number_of_channels, sample_rate, bits_per_sample));
+            },
+            b"data" => data = Some(chunk),
+            _ => ()
+        }
+
+        // chunks are always padded to an even number of bytes
+        offset = chunk_start + chunk_size + (chunk_size & 1);
+
+    }
+
+    let (audio_format, number_of_channels, sample_rate, bits_per_sample) = format?;
+    let data = data?;
+
+    if audio_format != 1 || bits_per_sample != 16 || number_of_channels == 0 {
+        return None;
+    }
+
+    let number_of_channels = number_of_channels as usize;
+    let samples = data.chunks_exact(2 * number_of_channels).map(|frame| {
+        let frame_sum: f32 = frame.chunks_exact(2).map(|s| i16::from_le_bytes([s[0], s[1]]) as f32 / i16::MAX as f32).sum();
+        frame_sum / number_of_channels as f32
+    }).collect();
+
+    Some((sample_rate, samples))
+
+}
+
+fn encode_stereo_wav(sample_rate: u32, samples: &[f32], left_gain: f32, right_gain: f32) -> Vec<u8> {
+
+    let number_of_channels: u16 = 2;
+    let bytes_per_sample: u16 = 2;
+    let block_align = number_of_channels * bytes_per_sample;
+    let data_size = (samples.len() * block_align as usize) as u32;
+
+    let mut bytes = Vec::with_capacity(44 + data_size as usize);
+
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&number_of_channels.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    bytes.extend_from_slice(&block_align.to_le_bytes());
+    bytes.extend_from_slice(&(bytes_per_sample * 8).to_le_bytes());
+
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+
+    for &sample in samples {
+        for gain in [left_gain, right_gain] {
+            let value = (sample * gain).clamp(-1.0, 1.0) * i16::MAX as f32;
+            bytes.extend_from_slice(&(value as i16).to_le_bytes());
+        }
+    }
+
+    bytes
+
+}
+
+fn pan_step_to_pan(pan_step: usize) -> f32 {
+    (pan_step as f32 / (NUMBER_OF_PAN_STEPS - 1) as f32) * 2.0 - 1.0
+}
+
+fn pan_to_pan_step(pan: f32) -> usize {
+    (((pan.clamp(-1.0, 1.0) + 1.0) / 2.0) * (NUMBER_OF_PAN_STEPS - 1) as f32).round() as usize
+}
+
+/// Equal power panning, so a sound does not get quieter as it moves across the screen.
+fn pan_to_gains(pan: f32) -> (f32, f32) {
+    let angle = (pan + 1.0) * (PI / 4.0);
+    (angle.cos(), angle.sin())
+}
+
+struct PannedSound {
+    pan_variants: Vec<Sound>,
+    duration: f64
+}
+
+struct PendingSound {
+    effect: SoundEffect,
+    volume: f32,
+    pan: f32
+}
+
+pub struct AudioManager {
+    settings: AudioSettings,
+    sounds: FnvHashMap<SoundEffect, PannedSound>,
+    pending_sounds: Vec<PendingSound>,
+    voice_end_times: Vec<f64>,
+    last_played_times: FnvHashMap<SoundEffect, f64>,
+    is_settings_open: bool,
+    status_message: String
+}
+
+impl AudioManager {
+
+    pub fn new() -> AudioManager {
+        AudioManager {
+            settings: AudioSettings::new(),
+            sounds: FnvHashMap::default(),
+            pending_sounds: Vec::new(),
+            voice_end_times: Vec::new(),
+            last_played_times: FnvHashMap::default(),
+            is_settings_open: false,
+            status_message: String::new()
+        }
+    }
+
+    pub async fn load(&mut self) {
+
+        if let Ok(audio_settings_data) = load_string(AUDIO_SETTINGS_PATH).await {
+            match AudioSettings::deserialize_json(&audio_settings_data) {
+                Ok(audio_settings) => self.settings = audio_settings,
+                Err(error) => println!("[AudioManager] failed to load audio settings: {}, using defaults", error)
+            }
+        }
+
+        for effect in SoundEffect::ALL {
+            self.load_sound_or_synthesized(effect).await;
+        }
+
+    }
+
+    async fn load_sound_or_synthesized(&mut self, effect: SoundEffect) {
+
+        let sound_path = format!("{}/{}.wav", SOUNDS_DIRECTORY, effect.name());
+        let decoded_sound = load_file(&sound_path).await.ok().and_then(|bytes| decode_wav(&bytes));
+
+        let (sample_rate, samples) = match decoded_sound {
+            Some(decoded_sound) => decoded_sound,
+            None => (SYNTHESIZED_SAMPLE_RATE, synthesize_sound_effect(effect))
+        };
+
+        let mut pan_variants = Vec::with_capacity(NUMBER_OF_PAN_STEPS);
+
+        for pan_step in 0..NUMBER_OF_PAN_STEPS {
+
+            let (left_gain, right_gain) = pan_to_gains(pan_step_to_pan(pan_step));
+            let wav_bytes = encode_stereo_wav(sample_rate, &samples, left_gain, right_gain);
+
+            match load_sound_from_bytes(&wav_bytes).await {
+                Ok(sound) => pan_variants.push(sound),
+                Err(error) => {
+                    println!("[AudioManager] failed to load sound: {}, error: {}", effect.name(), error);
+                    return;
+                }
+            }
+
+        }
+
+        let duration = samples.len() as f64 / sample_rate as f64;
+        self.sounds.insert(effect, PannedSound { pan_variants, duration });
+
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+
+        if let Some(audio_settings_directory) = std::path::Path::new(AUDIO_SETTINGS_PATH).parent() {
+            std::fs::create_dir_all(audio_settings_directory).map_err(|e| e.to_string())?;
+        }
+
+        std::fs::write(AUDIO_SETTINGS_PATH, self.settings.serialize_json()).map_err(|e| e.to_string())
+
+    }
+
+    /// Returns the volume and pan of a sound at the given world position, relative to what the camera is currently looking at.
+    fn calculate_volume_and_pan(camera: &GameCamera2D, world_position: Vec2) -> (f32, f32) {
+
+        let view_half_size = camera.screen_to_world_scale(screen_dimensions() / 2.0);
+        let view_radius = view_half_size.length().max(1.0);
+
+        let delta_from_view_center = world_position - camera.world_position();
+        let distance_in_view_radii = delta_from_view_center.length() / view_radius;
+
+        let attenuation = 1.0 - ((distance_in_view_radii - FULL_VOLUME_VIEW_RADII).max(0.0) / (AUDIBLE_VIEW_RADII - FULL_VOLUME_VIEW_RADII));
+        let volume = attenuation.clamp(0.0, 1.0).powi(2);
+        let pan = (delta_from_view_center.x / view_half_size.x.max(1.0)).clamp(-1.0, 1.0);
+
+        (volume, pan)
+
+    }
+
+    /// Queues a sound that comes from somewhere in the world, it is panned and attenuated depending on where the camera is.
+    pub fn play_at(&mut self, effect: SoundEffect, world_position: Vec2, camera: &GameCamera2D) {
+
+        let (volume, pan) = Self::calculate_volume_and_pan(camera, world_position);
+        if volume <= 0.0 {
+            return;
+        }
+
+        self.pending_sounds.push(PendingSound { effect, volume, pan });
+
+    }
+
+    /// Queues a sound that is not in the world, like user interface feedback.
+    pub fn play(&mut self, effect: SoundEffect) {
+        self.pending_sounds.push(PendingSound { effect, volume: 1.0, pan: 0.0 });
+    }
+
+    pub fn handle_events(&mut self, events: &[RymdGameEvent], camera: &GameCamera2D) {
+
+        for event in events {
+
+            let (effect, position) = match *event {
+                RymdGameEvent::WeaponFired { position, weapon: WeaponKind::Projectile, .. } => (SoundEffect::ProjectileFired, position),
+                RymdGameEvent::WeaponFired { position, weapon: WeaponKind::Beam, .. } => (SoundEffect::BeamFired, position),
+                RymdGameEvent::Impact { position } => (SoundEffect::Impact, position),
+                RymdGameEvent::ConstructionCompleted { position, .. } => (SoundEffect::ConstructionCompleted, position),
+                RymdGameEvent::Destroyed { position, is_building: true, .. } => (SoundEffect::BuildingDestroyed, position),
+                RymdGameEvent::Destroyed { position, is_building: false, .. } => (SoundEffect::ShipDestroyed, position),
+                // these are reported to the player through alerts instead
+                RymdGameEvent::UnderAttack { .. } | RymdGameEvent::ResourceSourceExhausted { .. } | RymdGameEvent::CommanderDestroyed { .. } => continue
+            };
+
+            self.play_at(effect, position, camera);
+
+        }
+
+    }
+
+    fn effective_volume(&self, pending_sound: &PendingSound) -> f32 {
+
+        let category_volume = if pending_sound.effect.is_interface_sound() {
+            self.settings.interface_volume
+        } else {
+            self.settings.effects_volume
+        };
+
+        pending_sound.volume * pending_sound.effect.base_volume() * category_volume * self.settings.master_volume
+
+    }
+
+    /// Plays everything queued since the last frame, loudest first, until the voice limit is reached, the rest is dropped.
+    pub fn tick(&mut self) {
+
+        let current_time = get_time();
+        self.voice_end_times.retain(|&end_time| end_time > current_time);
+
+        let mut pending_sounds = std::mem::take(&mut self.pending_sounds);
+        if self.settings.is_muted {
+            return;
+        }
+
+        pending_sounds.sort_by(|a, b| self.effective_volume(b).total_cmp(&self.effective_volume(a)));
+
+        for pending_sound in pending_sounds {
+
+            if self.voice_end_times.len() >= self.settings.max_voices.max(0) as usize {
+                break;
+            }
+
+            let Some(sound) = self.sounds.get(&pending_sound.effect) else { continue; };
+
+            let was_recently_played = self.last_played_times.get(&pending_sound.effect).is_some_and(|&t| current_time - t < MIN_SECONDS_BETWEEN_SAME_SOUND);
+            if was_recently_played {
+                continue;
+            }
+
+            let volume = self.effective_volume(&pending_sound);
+            if volume <= 0.0 {
+                continue;
+            }
+
+            let pan_variant = &sound.pan_variants[pan_to_pan_step(pending_sound.pan)];
+            play_sound(pan_variant, PlaySoundParams { looped: false, volume });
+
+            self.voice_end_times.push(current_time + sound.duration);
+            self.last_played_times.insert(pending_sound.effect, current_time);
+
+        }
+
+    }
+
+    pub fn toggle_settings_window(&mut self) {
+        self.is_settings_open = !self.is_settings_open;
+    }
+
+    pub fn draw_settings_ui(&mut self, ui_ctx: &egui::Context) {
+
+        if self.is_settings_open == false {
+            return;
+        }
+
+        let mut is_settings_open = self.is_settings_open;
+
+        egui::Window::new("audio")
+            .open(&mut is_settings_open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ui_ctx, |ui| {
+
+                ui.checkbox(&mut self.settings.is_muted, "muted");
+
+                egui::Grid::new("audio_settings").show(ui, |ui| {
+
+                    ui.label("master volume");
+                    ui.add(egui::Slider::new(&mut self.settings.master_volume, 0.0..=1.0));
+                    ui.end_row();
+
+                    ui.label("effects volume");
+                    ui.add(egui::Slider::new(&mut self.settings.effects_volume, 0.0..=1.0));
+                    ui.end_row();
+
+                    ui.label("interface volume");
+                    ui.add(egui::Slider::new(&mut self.settings.interface_volume, 0.0..=1.0));
+                    ui.end_row();
+
+                    ui.label("max voices");
+                    ui.add(egui::Slider::new(&mut self.settings.max_voices, 1..=64));
+                    ui.end_row();
+
+                });
+
+                ui.horizontal(|ui| {
+
+                    if ui.button("save").clicked() {
+                        self.status_message = match self.save() {
+                            Ok(()) => format!("saved to: {}", AUDIO_SETTINGS_PATH),
+                            Err(error) => format!("failed to save: {}", error)
+                        };
+                    }
+
+                    if ui.button("reset to defaults").clicked() {
+                        self.settings = AudioSettings::new();
+                        self.status_message = "reset all audio settings to their defaults".to_string();
+                    }
+
+                });
+
+                if self.status_message.is_empty() == false {
+                    ui.label(&self.status_message);
+                }
+
+        });
+
+        self.is_settings_open = is_settings_open;
+
+    }
+
+}
@@ -0,0 +1,168 @@
+use puffin_egui::egui::{self, Align2};
+
+use crate::PlayerID;
+use crate::model::{BlueprintID, BlueprintManager};
+
+/// How many lines of output the console keeps around.
+const MAXIMUM_NUMBER_OF_CONSOLE_LINES: usize = 128;
+
+const CONSOLE_HELP_TEXT: &str = "\
+help - shows this text
+blueprints - lists everything that can be spawned
+spawn <blueprint> [count] [player] - spawns at the center of the screen, for you unless a player is given
+resources <metal> <energy> [player] - grants resources, for you unless a player is given
+kill - destroys the current selection
+heal - heals the current selection to full health
+player [id] - switches the player you control, to the next one if no id is given";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleCommand {
+    Help,
+    Blueprints,
+    Spawn { blueprint_id: BlueprintID, count: i32, owner: Option<PlayerID> },
+    Resources { metal: f32, energy: f32, owner: Option<PlayerID> },
+    Kill,
+    Heal,
+    SwitchPlayer { player: Option<PlayerID> }
+}
+
+fn parse_argument<T: std::str::FromStr>(argument: Option<&str>, name: &str) -> Result<Option<T>, String> {
+    match argument {
+        Some(argument) => argument.parse::<T>().map(Some).map_err(|_| format!("invalid {}: {}", name, argument)),
+        None => Ok(None)
+    }
+}
+
+/// Parses a line typed into the console, blueprints can be given either by name (with underscores for spaces) or by id.
+pub fn parse_console_command(line: &str, blueprint_manager: &BlueprintManager) -> Result<ConsoleCommand, String> {
+
+    let mut arguments = line.split_whitespace();
+    let Some(command_name) = arguments.next() else { return Err("no command given, try: help".to_string()) };
+
+    let console_command = match command_name {
+        "help" => ConsoleCommand::Help,
+        "blueprints" => ConsoleCommand::Blueprints,
+        "spawn" => {
+
+            let Some(blueprint_name) = arguments.next() else { return Err("usage: spawn <blueprint> [count] [player]".to_string()) };
+
+            let blueprint = blueprint_name.parse::<BlueprintID>().ok()
+                .and_then(|blueprint_id| blueprint_manager.get_blueprint(blueprint_id))
+                .or_else(|| blueprint_manager.find_blueprint_by_name(blueprint_name))
+                .ok_or_else(|| format!("no blueprint named: {}, try: blueprints", blueprint_name))?;
+
+            let count = parse_argument(arguments.next(), "count")?.unwrap_or(1);
+            let owner = parse_argument(arguments.next(), "player")?;
+            ConsoleCommand::Spawn { blueprint_id: blueprint.id, count, owner }
+
+        },
+        "resources" => {
+            let metal = parse_argument(arguments.next(), "metal")?.ok_or("usage: resources <metal> <energy> [player]")?;
+            let energy = parse_argument(arguments.next(), "energy")?.ok_or("usage: resources <metal> <energy> [player]")?;
+            let owner = parse_argument(arguments.next(), "player")?;
+            ConsoleCommand::Resources { metal, energy, owner }
+        },
+        "kill" => ConsoleCommand::Kill,
+        "heal" => ConsoleCommand::Heal,
+        "player" => ConsoleCommand::SwitchPlayer { player: parse_argument(arguments.next(), "player")? },
+        _ => return Err(format!("unknown command: {}, try: help", command_name))
+    };
+
+    Ok(console_command)
+
+}
+
+pub struct DeveloperConsole {
+    is_open: bool,
+    current_line: String,
+    output: Vec<String>
+}
+
+impl DeveloperConsole {
+
+    pub fn new() -> DeveloperConsole {
+        DeveloperConsole {
+            is_open: false,
+            current_line: String::new(),
+            output: Vec::new()
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    pub fn toggle(&mut self) {
+        self.is_open = !self.is_open;
+    }
+
+    pub fn print(&mut self, line: impl Into<String>) {
+
+        self.output.push(line.into());
+
+        if self.output.len() > MAXIMUM_NUMBER_OF_CONSOLE_LINES {
+            let number_of_lines_to_remove = self.output.len() - MAXIMUM_NUMBER_OF_CONSOLE_LINES;
+            self.output.drain(0..number_of_lines_to_remove);
+        }
+
+    }
+
+    pub fn print_help(&mut self) {
+        for line in CONSOLE_HELP_TEXT.lines() {
+            self.print(line);
+        }
+    }
+
+    /// Draws the console if it is open, returns the line entered if one was submitted this frame.
+    pub fn draw_ui(&mut self, ui_ctx: &egui::Context, are_cheats_enabled: bool) -> Option<String> {
+
+        if self.is_open == false {
+            return None;
+        }
+
+        let mut is_open = self.is_open;
+        let mut submitted_line = None;
+
+        egui::Window::new("console")
+            .open(&mut is_open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(480.0)
+            .anchor(Align2::CENTER_CENTER, (0.0, 0.0))
+            .show(ui_ctx, |ui| {
+
+                if are_cheats_enabled == false {
+                    ui.colored_label(egui::Color32::RED, "cheats are not enabled for this match, enable them in the lobby!");
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(240.0)
+                    .stick_to_bottom(true)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        for line in &self.output {
+                            ui.monospace(line);
+                        }
+                });
+
+                let line_response = ui.add(egui::TextEdit::singleline(&mut self.current_line).desired_width(f32::INFINITY).code_editor());
+                line_response.request_focus();
+
+                if line_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && self.current_line.trim().is_empty() == false {
+                    submitted_line = Some(self.current_line.trim().to_string());
+                    self.current_line.clear();
+                }
+
+        });
+
+        self.is_open = is_open;
+
+        if let Some(line) = &submitted_line {
+            self.print(format!("> {}", line));
+        }
+
+        submitted_line
+
+    }
+
+}
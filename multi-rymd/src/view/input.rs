@@ -0,0 +1,795 @@
+use macroquad::prelude::*;
+use nanoserde::{DeJson, SerJson};
+use puffin_egui::egui;
+
+use crate::model::{BlueprintID, BlueprintManager, Blueprints};
+
+use super::{CameraSettings, NUMBER_OF_CAMERA_BOOKMARKS};
+
+pub const INPUT_SETTINGS_PATH: &str = "config/input.json";
+pub const NUMBER_OF_CONTROL_GROUPS: i32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputAction {
+
+    // held down while performing other actions, changes what they do
+    QueueModifier,
+    AlternateModifier,
+
+    // selection
+    SelectAll,
+    SelectNextIdleConstructor,
+    SelectNextCommander,
+    SelectAllOfSameKind,
+    SelectControlGroup(i32),
+    AssignControlGroup(i32),
+
+    // orders
+    AttackMove,
+    CancelOrders,
+    Unload,
+    Build(BlueprintID),
+
+    // camera
+    JumpToLastAlert,
+    ToggleFollowSelection,
+    SaveCameraBookmark(i32),
+    RecallCameraBookmark(i32),
+
+    // game speed
+    TogglePause,
+    IncreaseGameSpeed,
+    DecreaseGameSpeed,
+
+    // communication
+    PlaceMarker,
+    FocusChat,
+
+    // misc
+    SwitchPlayer,
+    ToggleConsole,
+    ToggleInputSettings,
+    ToggleAudioSettings,
+    ToggleDebugBounds,
+    ToggleDebugKinematics,
+    ToggleDebugSpatial,
+    ToggleDebugStates
+
+}
+
+impl InputAction {
+
+    /// The name used for the action in the settings file.
+    pub fn name(&self) -> String {
+        match self {
+            InputAction::QueueModifier => "queue_modifier".to_string(),
+            InputAction::AlternateModifier => "alternate_modifier".to_string(),
+            InputAction::SelectAll => "select_all".to_string(),
+            InputAction::SelectNextIdleConstructor => "select_next_idle_constructor".to_string(),
+            InputAction::SelectNextCommander => "select_next_commander".to_string(),
+            InputAction::SelectAllOfSameKind => "select_all_of_same_kind".to_string(),
+            InputAction::SelectControlGroup(id) => format!("select_control_group_{}", id),
+            InputAction::AssignControlGroup(id) => format!("assign_control_group_{}", id),
+            InputAction::AttackMove => "attack_move".to_string(),
+            InputAction::CancelOrders => "cancel_orders".to_string(),
+            InputAction::Unload => "unload".to_string(),
+            InputAction::Build(blueprint_id) => format!("build_{}", blueprint_id),
+            InputAction::JumpToLastAlert => "jump_to_last_alert".to_string(),
+            InputAction::ToggleFollowSelection => "toggle_follow_selection".to_string(),
+            InputAction::SaveCameraBookmark(id) => format!("save_camera_bookmark_{}", id),
+            InputAction::RecallCameraBookmark(id) => format!("recall_camera_bookmark_{}", id),
+            InputAction::TogglePause => "toggle_pause".to_string(),
+            InputAction::IncreaseGameSpeed => "increase_game_speed".to_string(),
+            InputAction::DecreaseGameSpeed => "decrease_game_speed".to_string(),
+            InputAction::PlaceMarker => "place_marker".to_string(),
+            InputAction::FocusChat => "focus_chat".to_string(),
+            InputAction::SwitchPlayer => "switch_player".to_string(),
+            InputAction::ToggleConsole => "toggle_console".to_string(),
+            InputAction::ToggleInputSettings => "toggle_input_settings".to_string(),
+            InputAction::ToggleAudioSettings => "toggle_audio_settings".to_string(),
+            InputAction::ToggleDebugBounds => "toggle_debug_bounds".to_string(),
+            InputAction::ToggleDebugKinematics => "toggle_debug_kinematics".to_string(),
+            InputAction::ToggleDebugSpatial => "toggle_debug_spatial".to_string(),
+            InputAction::ToggleDebugStates => "toggle_debug_states".to_string()
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<InputAction> {
+
+        let parse_suffix = |prefix: &str| name.strip_prefix(prefix).and_then(|id| id.parse::<i32>().ok());
+
+        if let Some(id) = parse_suffix("select_control_group_") {
+            return Some(InputAction::SelectControlGroup(id));
+        }
+
+        if let Some(id) = parse_suffix("assign_control_group_") {
+            return Some(InputAction::AssignControlGroup(id));
+        }
+
+        if let Some(id) = parse_suffix("save_camera_bookmark_") {
+            return Some(InputAction::SaveCameraBookmark(id));
+        }
+
+        if let Some(id) = parse_suffix("recall_camera_bookmark_") {
+            return Some(InputAction::RecallCameraBookmark(id));
+        }
+
+        if let Some(blueprint_id) = parse_suffix("build_") {
+            return Some(InputAction::Build(blueprint_id));
+        }
+
+        match name {
+            "queue_modifier" => Some(InputAction::QueueModifier),
+            "alternate_modifier" => Some(InputAction::AlternateModifier),
+            "select_all" => Some(InputAction::SelectAll),
+            "select_next_idle_constructor" => Some(InputAction::SelectNextIdleConstructor),
+            "select_next_commander" => Some(InputAction::SelectNextCommander),
+            "select_all_of_same_kind" => Some(InputAction::SelectAllOfSameKind),
+            "attack_move" => Some(InputAction::AttackMove),
+            "cancel_orders" => Some(InputAction::CancelOrders),
+            "unload" => Some(InputAction::Unload),
+            "jump_to_last_alert" => Some(InputAction::JumpToLastAlert),
+            "toggle_follow_selection" => Some(InputAction::ToggleFollowSelection),
+            "toggle_pause" => Some(InputAction::TogglePause),
+            "increase_game_speed" => Some(InputAction::IncreaseGameSpeed),
+            "decrease_game_speed" => Some(InputAction::DecreaseGameSpeed),
+            "place_marker" => Some(InputAction::PlaceMarker),
+            "focus_chat" => Some(InputAction::FocusChat),
+            "switch_player" => Some(InputAction::SwitchPlayer),
+            "toggle_console" => Some(InputAction::ToggleConsole),
+            "toggle_input_settings" => Some(InputAction::ToggleInputSettings),
+            "toggle_audio_settings" => Some(InputAction::ToggleAudioSettings),
+            "toggle_debug_bounds" => Some(InputAction::ToggleDebugBounds),
+            "toggle_debug_kinematics" => Some(InputAction::ToggleDebugKinematics),
+            "toggle_debug_spatial" => Some(InputAction::ToggleDebugSpatial),
+            "toggle_debug_states" => Some(InputAction::ToggleDebugStates),
+            _ => None
+        }
+
+    }
+
+    /// Human readable description of the action, for the rebinding screen.
+    pub fn description(&self, blueprint_manager: &BlueprintManager) -> String {
+        match self {
+            InputAction::QueueModifier => "queue / add to selection (hold)".to_string(),
+            InputAction::AlternateModifier => "group / remove from selection (hold)".to_string(),
+            InputAction::SelectAll => "select all units".to_string(),
+            InputAction::SelectNextIdleConstructor => "select next idle constructor".to_string(),
+            InputAction::SelectNextCommander => "select next commander".to_string(),
+            InputAction::SelectAllOfSameKind => "select all units of the same kind".to_string(),
+            InputAction::SelectControlGroup(id) => format!("select control group {}", id),
+            InputAction::AssignControlGroup(id) => format!("assign control group {}", id),
+            InputAction::AttackMove => "attack move".to_string(),
+            InputAction::CancelOrders => "cancel orders".to_string(),
+            InputAction::Unload => "unload selected transports at the cursor".to_string(),
+            InputAction::Build(blueprint_id) => match blueprint_manager.get_blueprint(*blueprint_id) {
+                Some(blueprint) => format!("build {}", blueprint.name),
+                None => format!("build blueprint {}", blueprint_id)
+            },
+            InputAction::JumpToLastAlert => "jump to the last alert".to_string(),
+            InputAction::ToggleFollowSelection => "follow / stop following the selected unit".to_string(),
+            InputAction::SaveCameraBookmark(id) => format!("save camera bookmark {}", id + 1),
+            InputAction::RecallCameraBookmark(id) => format!("jump to camera bookmark {}", id + 1),
+            InputAction::TogglePause => "pause / resume the game".to_string(),
+            InputAction::IncreaseGameSpeed => "increase game speed (vote in multiplayer)".to_string(),
+            InputAction::DecreaseGameSpeed => "decrease game speed (vote in multiplayer)".to_string(),
+            InputAction::PlaceMarker => "ping (click) or draw a marker (drag) for allies (hold)".to_string(),
+            InputAction::FocusChat => "open the chat".to_string(),
+            InputAction::SwitchPlayer => "switch player (singleplayer)".to_string(),
+            InputAction::ToggleConsole => "toggle the developer console".to_string(),
+            InputAction::ToggleInputSettings => "toggle the hotkey settings".to_string(),
+            InputAction::ToggleAudioSettings => "toggle the audio settings".to_string(),
+            InputAction::ToggleDebugBounds => "toggle bounds debug".to_string(),
+            InputAction::ToggleDebugKinematics => "toggle kinematics debug".to_string(),
+            InputAction::ToggleDebugSpatial => "toggle spatial debug".to_string(),
+            InputAction::ToggleDebugStates => "toggle state debug".to_string()
+        }
+    }
+
+}
+
+macro_rules! key_names {
+    ($($key:ident),*) => {
+        &[$((KeyCode::$key, stringify!($key))),*]
+    };
+}
+
+/// All the keys that can be bound, along with the names they are stored as in the settings file.
+const KEY_NAMES: &[(KeyCode, &str)] = key_names!(
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Kp0, Kp1, Kp2, Kp3, Kp4, Kp5, Kp6, Kp7, Kp8, Kp9,
+    Space, Tab, Escape, Enter, Backspace, Delete, Insert, Home, End, PageUp, PageDown,
+    Up, Down, Left, Right,
+    LeftShift, RightShift, LeftControl, RightControl, LeftAlt, RightAlt,
+    Minus, Equal, LeftBracket, RightBracket, Semicolon, Apostrophe, Comma, Period, Slash, Backslash, GraveAccent
+);
+
+fn key_code_to_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|(k, _)| *k == key).map(|(_, name)| *name)
+}
+
+fn key_code_from_name(name: &str) -> Option<KeyCode> {
+    KEY_NAMES.iter().find(|(_, n)| *n == name).map(|(key, _)| *key)
+}
+
+fn is_ctrl_key(key: KeyCode) -> bool {
+    key == KeyCode::LeftControl || key == KeyCode::RightControl
+}
+
+fn is_shift_key(key: KeyCode) -> bool {
+    key == KeyCode::LeftShift || key == KeyCode::RightShift
+}
+
+fn is_alt_key(key: KeyCode) -> bool {
+    key == KeyCode::LeftAlt || key == KeyCode::RightAlt
+}
+
+fn is_ctrl_down() -> bool {
+    is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)
+}
+
+fn is_shift_down() -> bool {
+    is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift)
+}
+
+fn is_alt_down() -> bool {
+    is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool
+}
+
+impl KeyChord {
+
+    pub fn key(key: KeyCode) -> KeyChord {
+        KeyChord { key, ctrl: false, shift: false, alt: false }
+    }
+
+    pub fn ctrl(key: KeyCode) -> KeyChord {
+        KeyChord { key, ctrl: true, shift: false, alt: false }
+    }
+
+    pub fn shift(key: KeyCode) -> KeyChord {
+        KeyChord { key, ctrl: false, shift: true, alt: false }
+    }
+
+    /// Creates a chord from the key that was just pressed and whatever modifiers are currently held, the key itself is never counted as a modifier.
+    pub fn from_current_modifiers(key: KeyCode) -> KeyChord {
+        KeyChord {
+            key,
+            ctrl: is_ctrl_down() && is_ctrl_key(key) == false,
+            shift: is_shift_down() && is_shift_key(key) == false,
+            alt: is_alt_down() && is_alt_key(key) == false
+        }
+    }
+
+    fn number_of_modifiers(&self) -> i32 {
+        self.ctrl as i32 + self.shift as i32 + self.alt as i32
+    }
+
+    fn are_modifiers_held(&self) -> bool {
+        (self.ctrl == false || is_ctrl_down()) && (self.shift == false || is_shift_down()) && (self.alt == false || is_alt_down())
+    }
+
+    pub fn name(&self) -> String {
+
+        let mut name = String::new();
+
+        if self.ctrl {
+            name += "Ctrl+";
+        }
+
+        if self.shift {
+            name += "Shift+";
+        }
+
+        if self.alt {
+            name += "Alt+";
+        }
+
+        name += key_code_to_name(self.key).unwrap_or("?");
+        name
+
+    }
+
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct InputBinding {
+    pub action: InputAction,
+    pub chord: KeyChord
+}
+
+#[derive(Debug, Clone, SerJson, DeJson)]
+struct InputBindingData {
+    action: String,
+    key: String,
+    ctrl: bool,
+    shift: bool,
+    alt: bool
+}
+
+#[derive(Debug, Clone, SerJson, DeJson)]
+struct InputSettingsData {
+    bindings: Vec<InputBindingData>,
+    /// Missing from settings saved before the camera could be configured.
+    camera: Option<CameraSettings>
+}
+
+fn create_default_bindings() -> Vec<InputBinding> {
+
+    let mut bindings = vec![
+        InputBinding { action: InputAction::QueueModifier, chord: KeyChord::key(KeyCode::LeftShift) },
+        InputBinding { action: InputAction::AlternateModifier, chord: KeyChord::key(KeyCode::LeftControl) },
+        InputBinding { action: InputAction::SelectAll, chord: KeyChord::ctrl(KeyCode::A) },
+        InputBinding { action: InputAction::SelectNextIdleConstructor, chord: KeyChord::ctrl(KeyCode::B) },
+        InputBinding { action: InputAction::SelectNextCommander, chord: KeyChord::ctrl(KeyCode::C) },
+        InputBinding { action: InputAction::SelectAllOfSameKind, chord: KeyChord::ctrl(KeyCode::Z) },
+        InputBinding { action: InputAction::AttackMove, chord: KeyChord::key(KeyCode::A) },
+        InputBinding { action: InputAction::CancelOrders, chord: KeyChord::key(KeyCode::S) },
+        InputBinding { action: InputAction::Unload, chord: KeyChord::key(KeyCode::L) }
+    ];
+
+    let number_keys = [
+        KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+        KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9
+    ];
+
+    for (id, &key) in number_keys.iter().enumerate() {
+        bindings.push(InputBinding { action: InputAction::SelectControlGroup(id as i32), chord: KeyChord::key(key) });
+        bindings.push(InputBinding { action: InputAction::AssignControlGroup(id as i32), chord: KeyChord::ctrl(key) });
+    }
+
+    let blueprint_keys = [
+        (Blueprints::SolarCollector, KeyCode::Q),
+        (Blueprints::Shipyard, KeyCode::W),
+        (Blueprints::EnergyStorage, KeyCode::E),
+        (Blueprints::MetalStorage, KeyCode::R),
+        (Blueprints::EnergyConverter, KeyCode::M),
+        (Blueprints::Commander, KeyCode::J),
+        (Blueprints::Commissar, KeyCode::K),
+        (Blueprints::Arrowhead, KeyCode::Y),
+        (Blueprints::Dragonfly, KeyCode::D),
+        (Blueprints::Extractor, KeyCode::U),
+        (Blueprints::Carrier, KeyCode::O),
+        (Blueprints::Transport, KeyCode::T),
+        (Blueprints::Grunt, KeyCode::I)
+    ];
+
+    for (blueprint, key) in blueprint_keys {
+        bindings.push(InputBinding { action: InputAction::Build(blueprint as i32), chord: KeyChord::key(key) });
+    }
+
+    let bookmark_keys = [KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4];
+
+    for (id, &key) in bookmark_keys.iter().enumerate().take(NUMBER_OF_CAMERA_BOOKMARKS as usize) {
+        bindings.push(InputBinding { action: InputAction::RecallCameraBookmark(id as i32), chord: KeyChord::key(key) });
+        bindings.push(InputBinding { action: InputAction::SaveCameraBookmark(id as i32), chord: KeyChord::ctrl(key) });
+    }
+
+    bindings.extend([
+        InputBinding { action: InputAction::JumpToLastAlert, chord: KeyChord::key(KeyCode::Backspace) },
+        InputBinding { action: InputAction::ToggleFollowSelection, chord: KeyChord::key(KeyCode::F) },
+        InputBinding { action: InputAction::TogglePause, chord: KeyChord::key(KeyCode::P) },
+        InputBinding { action: InputAction::IncreaseGameSpeed, chord: KeyChord::key(KeyCode::Equal) },
+        InputBinding { action: InputAction::DecreaseGameSpeed, chord: KeyChord::key(KeyCode::Minus) },
+        InputBinding { action: InputAction::PlaceMarker, chord: KeyChord::key(KeyCode::G) },
+        InputBinding { action: InputAction::FocusChat, chord: KeyChord::key(KeyCode::Enter) },
+        InputBinding { action: InputAction::SwitchPlayer, chord: KeyChord::key(KeyCode::Tab) },
+        InputBinding { action: InputAction::ToggleConsole, chord: KeyChord::key(KeyCode::GraveAccent) },
+        InputBinding { action: InputAction::ToggleInputSettings, chord: KeyChord::key(KeyCode::F10) },
+        InputBinding { action: InputAction::ToggleAudioSettings, chord: KeyChord::key(KeyCode::F9) },
+        InputBinding { action: InputAction::ToggleDebugBounds, chord: KeyChord::shift(KeyCode::C) },
+        InputBinding { action: InputAction::ToggleDebugKinematics, chord: KeyChord::shift(KeyCode::K) },
+        InputBinding { action: InputAction::ToggleDebugSpatial, chord: KeyChord::shift(KeyCode::S) },
+        InputBinding { action: InputAction::ToggleDebugStates, chord: KeyChord::shift(KeyCode::E) }
+    ]);
+
+    bindings
+
+}
+
+/// Maps input actions to key chords, when several bound chords share a key the one with the most modifiers held wins, so Ctrl+A does not also trigger A.
+pub struct InputManager {
+    bindings: Vec<InputBinding>,
+    camera_settings: CameraSettings,
+    is_rebinding_open: bool,
+    rebinding_action: Option<InputAction>,
+    is_typing: bool,
+    status_message: String
+}
+
+impl InputManager {
+
+    pub fn new() -> InputManager {
+        InputManager {
+            bindings: create_default_bindings(),
+            camera_settings: CameraSettings::new(),
+            is_rebinding_open: false,
+            rebinding_action: None,
+            is_typing: false,
+            status_message: String::new()
+        }
+    }
+
+    pub async fn load(&mut self) {
+
+        let Ok(input_settings_data) = load_string(INPUT_SETTINGS_PATH).await else {
+            println!("[InputManager] no input settings found at: {}, using defaults", INPUT_SETTINGS_PATH);
+            return;
+        };
+
+        if let Err(error) = self.apply_settings(&input_settings_data) {
+            println!("[InputManager] failed to load input settings: {}, using defaults", error);
+        }
+
+    }
+
+    /// Applies the bindings in the settings on top of the current bindings, so that any action missing from the file keeps its default.
+    pub fn apply_settings(&mut self, input_settings_data: &str) -> Result<(), String> {
+
+        let input_settings = InputSettingsData::deserialize_json(input_settings_data).map_err(|e| e.to_string())?;
+
+        for binding_data in input_settings.bindings {
+
+            let Some(action) = InputAction::from_name(&binding_data.action) else {
+                println!("[InputManager] unknown action in input settings: {}, skipping!", binding_data.action);
+                continue;
+            };
+
+            let Some(key) = key_code_from_name(&binding_data.key) else {
+                println!("[InputManager] unknown key in input settings: {}, skipping!", binding_data.key);
+                continue;
+            };
+
+            self.set_chord(action, KeyChord { key, ctrl: binding_data.ctrl, shift: binding_data.shift, alt: binding_data.alt });
+
+        }
+
+        if let Some(camera_settings) = input_settings.camera {
+            self.camera_settings = camera_settings;
+        }
+
+        Ok(())
+
+    }
+
+    pub fn settings_to_json(&self) -> String {
+
+        let bindings = self.bindings.iter().map(|binding| InputBindingData {
+            action: binding.action.name(),
+            key: key_code_to_name(binding.chord.key).unwrap_or("?").to_string(),
+            ctrl: binding.chord.ctrl,
+            shift: binding.chord.shift,
+            alt: binding.chord.alt
+        }).collect();
+
+        InputSettingsData { bindings, camera: Some(self.camera_settings) }.serialize_json()
+
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+
+        if let Some(input_settings_directory) = std::path::Path::new(INPUT_SETTINGS_PATH).parent() {
+            std::fs::create_dir_all(input_settings_directory).map_err(|e| e.to_string())?;
+        }
+
+        std::fs::write(INPUT_SETTINGS_PATH, self.settings_to_json()).map_err(|e| e.to_string())
+
+    }
+
+    pub fn reset_to_defaults(&mut self) {
+        self.bindings = create_default_bindings();
+        self.camera_settings = CameraSettings::new();
+    }
+
+    pub fn camera_settings(&self) -> CameraSettings {
+        self.camera_settings
+    }
+
+    pub fn chord(&self, action: InputAction) -> Option<KeyChord> {
+        self.bindings.iter().find(|b| b.action == action).map(|b| b.chord)
+    }
+
+    pub fn chord_name(&self, action: InputAction) -> String {
+        self.chord(action).map(|c| c.name()).unwrap_or_else(|| "unbound".to_string())
+    }
+
+    pub fn set_chord(&mut self, action: InputAction, chord: KeyChord) {
+        if let Some(binding) = self.bindings.iter_mut().find(|b| b.action == action) {
+            binding.chord = chord;
+        } else {
+            self.bindings.push(InputBinding { action, chord });
+        }
+    }
+
+    /// Returns all the actions which are bound to the exact same chord as the given action.
+    pub fn conflicts(&self, action: InputAction) -> Vec<InputAction> {
+        let Some(chord) = self.chord(action) else { return Vec::new(); };
+        self.bindings.iter()
+            .filter(|b| b.action != action && b.chord == chord)
+            .map(|b| b.action)
+            .collect()
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        self.bindings.iter().any(|b| self.conflicts(b.action).is_empty() == false)
+    }
+
+    /// True while waiting for the player to press the new key for an action, nothing else should react to input meanwhile.
+    pub fn is_capturing(&self) -> bool {
+        self.rebinding_action.is_some()
+    }
+
+    /// Set while a text field has keyboard focus, so typing in the chat doesn't also issue orders and build things.
+    pub fn set_typing(&mut self, is_typing: bool) {
+        self.is_typing = is_typing;
+    }
+
+    pub fn is_typing(&self) -> bool {
+        self.is_typing
+    }
+
+    fn is_chord_shadowed(&self, chord: &KeyChord) -> bool {
+        self.bindings.iter().any(|b| b.chord.key == chord.key && b.chord.number_of_modifiers() > chord.number_of_modifiers() && b.chord.are_modifiers_held())
+    }
+
+    fn is_action_triggered_with<F>(&self, action: InputAction, key_state_fn: F) -> bool
+        where F: Fn(KeyCode) -> bool
+    {
+
+        if self.is_capturing() || self.is_typing {
+            return false;
+        }
+
+        let Some(chord) = self.chord(action) else { return false; };
+        key_state_fn(chord.key) && chord.are_modifiers_held() && self.is_chord_shadowed(&chord) == false
+
+    }
+
+    pub fn is_action_pressed(&self, action: InputAction) -> bool {
+        self.is_action_triggered_with(action, is_key_pressed)
+    }
+
+    pub fn is_action_released(&self, action: InputAction) -> bool {
+        self.is_action_triggered_with(action, is_key_released)
+    }
+
+    pub fn is_action_down(&self, action: InputAction) -> bool {
+        self.is_action_triggered_with(action, is_key_down)
+    }
+
+    /// Like is_action_pressed but also works while typing, for the few actions that have to work from inside a text field, like closing the console.
+    pub fn is_action_pressed_while_typing(&self, action: InputAction) -> bool {
+        let Some(chord) = self.chord(action) else { return false; };
+        self.is_capturing() == false && is_key_pressed(chord.key) && chord.are_modifiers_held()
+    }
+
+    fn first_control_group_matching<F>(&self, action_fn: F) -> Option<i32>
+        where F: Fn(i32) -> InputAction
+    {
+        (0..NUMBER_OF_CONTROL_GROUPS).find(|&id| self.is_action_pressed(action_fn(id)))
+    }
+
+    pub fn control_group_to_select(&self) -> Option<i32> {
+        self.first_control_group_matching(InputAction::SelectControlGroup)
+    }
+
+    pub fn control_group_to_assign(&self) -> Option<i32> {
+        self.first_control_group_matching(InputAction::AssignControlGroup)
+    }
+
+    pub fn camera_bookmark_to_save(&self) -> Option<i32> {
+        (0..NUMBER_OF_CAMERA_BOOKMARKS).find(|&id| self.is_action_pressed(InputAction::SaveCameraBookmark(id)))
+    }
+
+    pub fn camera_bookmark_to_recall(&self) -> Option<i32> {
+        (0..NUMBER_OF_CAMERA_BOOKMARKS).find(|&id| self.is_action_pressed(InputAction::RecallCameraBookmark(id)))
+    }
+
+    fn tick_rebinding(&mut self) {
+
+        let Some(action) = self.rebinding_action else { return; };
+        let Some(key) = get_last_key_pressed() else { return; };
+
+        if key == KeyCode::Escape {
+            self.rebinding_action = None;
+            return;
+        }
+
+        // wait for the actual key if a modifier was pressed, unless the action itself is meant to be held like a modifier
+        let is_modifier_action = action == InputAction::QueueModifier || action == InputAction::AlternateModifier;
+        let is_modifier_key = is_ctrl_key(key) || is_shift_key(key) || is_alt_key(key);
+        if is_modifier_key && is_modifier_action == false {
+            return;
+        }
+
+        if key_code_to_name(key).is_none() {
+            self.status_message = format!("{:?} can not be bound, try another key", key);
+            return;
+        }
+
+        self.set_chord(action, KeyChord::from_current_modifiers(key));
+        self.rebinding_action = None;
+
+    }
+
+    pub fn draw_rebinding_ui(&mut self, ui_ctx: &egui::Context, blueprint_manager: &BlueprintManager) {
+
+        if self.is_capturing() == false && self.is_action_pressed(InputAction::ToggleInputSettings) {
+            self.is_rebinding_open = !self.is_rebinding_open;
+        }
+
+        if self.is_rebinding_open == false {
+            self.rebinding_action = None;
+            return;
+        }
+
+        self.tick_rebinding();
+
+        let mut is_rebinding_open = self.is_rebinding_open;
+
+        egui::Window::new("hotkeys")
+            .open(&mut is_rebinding_open)
+            .collapsible(false)
+            .resizable(true)
+            .default_height(480.0)
+            .show(ui_ctx, |ui| {
+
+                ui.horizontal(|ui| {
+
+                    if ui.button("save").clicked() {
+                        self.status_message = match self.save() {
+                            Ok(()) => format!("saved to: {}", INPUT_SETTINGS_PATH),
+                            Err(error) => format!("failed to save: {}", error)
+                        };
+                    }
+
+                    if ui.button("reset to defaults").clicked() {
+                        self.reset_to_defaults();
+                        self.status_message = "reset all hotkeys to their defaults".to_string();
+                    }
+
+                });
+
+                if self.status_message.is_empty() == false {
+                    ui.label(&self.status_message);
+                }
+
+                if self.has_conflicts() {
+                    ui.colored_label(egui::Color32::RED, "some hotkeys are bound to the same keys!");
+                }
+
+                ui.separator();
+
+                ui.checkbox(&mut self.camera_settings.is_edge_scrolling_enabled, "scroll the camera at the edge of the screen");
+                ui.add_enabled(
+                    self.camera_settings.is_edge_scrolling_enabled,
+                    egui::Slider::new(&mut self.camera_settings.edge_scroll_speed, CameraSettings::MIN_EDGE_SCROLL_SPEED..=CameraSettings::MAX_EDGE_SCROLL_SPEED).text("edge scroll speed")
+                );
+
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("hotkey_bindings").striped(true).show(ui, |ui| {
+
+                        for binding in self.bindings.clone() {
+
+                            ui.label(binding.action.description(blueprint_manager));
+
+                            let is_capturing_this_action = self.rebinding_action == Some(binding.action);
+                            let chord_button_text = if is_capturing_this_action { "press a key... (esc to cancel)".to_string() } else { binding.chord.name() };
+
+                            if ui.button(chord_button_text).clicked() {
+                                self.rebinding_action = Some(binding.action);
+                                self.status_message.clear();
+                            }
+
+                            let conflicting_actions = self.conflicts(binding.action);
+                            if conflicting_actions.is_empty() == false {
+                                let conflicting_descriptions: Vec<String> = conflicting_actions.iter().map(|a| a.description(blueprint_manager)).collect();
+                                ui.colored_label(egui::Color32::RED, format!("conflicts with: {}", conflicting_descriptions.join(", ")));
+                            } else {
+                                ui.label("");
+                            }
+
+                            ui.end_row();
+
+                        }
+
+                    });
+                });
+
+        });
+
+        self.is_rebinding_open = is_rebinding_open;
+
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_action_names_round_trip() {
+
+        for binding in create_default_bindings() {
+            let name = binding.action.name();
+            assert_eq!(Some(binding.action), InputAction::from_name(&name), "action did not round trip: {}", name);
+        }
+
+    }
+
+    #[test]
+    fn test_unknown_action_names_are_rejected() {
+
+        assert_eq!(None, InputAction::from_name(""));
+        assert_eq!(None, InputAction::from_name("fire_everything"));
+        assert_eq!(None, InputAction::from_name("select_control_group_"));
+        assert_eq!(None, InputAction::from_name("build_commander"));
+
+    }
+
+    #[test]
+    fn test_default_bindings_have_no_conflicts() {
+
+        let input = InputManager::new();
+
+        for binding in &input.bindings {
+            assert!(input.conflicts(binding.action).is_empty(), "default binding conflicts: {}", binding.action.name());
+        }
+
+        assert!(input.has_conflicts() == false);
+
+    }
+
+    #[test]
+    fn test_same_chord_is_a_conflict() {
+
+        let mut input = InputManager::new();
+        input.set_chord(InputAction::TogglePause, KeyChord::key(KeyCode::A));
+
+        assert_eq!(vec![InputAction::TogglePause], input.conflicts(InputAction::AttackMove));
+        assert_eq!(vec![InputAction::AttackMove], input.conflicts(InputAction::TogglePause));
+        assert!(input.has_conflicts());
+
+    }
+
+    #[test]
+    fn test_different_modifiers_are_not_a_conflict() {
+
+        let mut input = InputManager::new();
+        input.set_chord(InputAction::TogglePause, KeyChord::shift(KeyCode::A));
+
+        assert!(input.conflicts(InputAction::AttackMove).is_empty());
+        assert!(input.conflicts(InputAction::SelectAll).is_empty());
+        assert!(input.has_conflicts() == false);
+
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+
+        let mut input = InputManager::new();
+        input.set_chord(InputAction::TogglePause, KeyChord::ctrl(KeyCode::F12));
+
+        let mut loaded_input = InputManager::new();
+        loaded_input.apply_settings(&input.settings_to_json()).unwrap();
+
+        for binding in &input.bindings {
+            assert_eq!(Some(binding.chord), loaded_input.chord(binding.action));
+        }
+
+    }
+
+}
@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+use puffin_egui::egui;
+use utility::{draw_arrow, WithAlpha};
+
+use crate::PlayerID;
+
+use super::GameCamera2D;
+
+/// How long a ping stays visible in the world and on the minimap, in seconds.
+const MAP_PING_DURATION: f64 = 4.0;
+
+/// How long a drawn marker stays visible in the world and on the minimap, in seconds.
+const MAP_MARKER_DURATION: f64 = 12.0;
+
+/// Markers shorter than this (in screen space) are treated as pings instead, so a slightly shaky click still pings.
+pub const MAP_MARKER_MINIMUM_LENGTH: f32 = 16.0;
+
+/// Keeps a single player from filling the whole screen with markers.
+const MAXIMUM_NUMBER_OF_MAP_MARKERS: usize = 32;
+
+#[derive(Debug, Clone, Copy)]
+pub enum MapMarkerShape {
+    Ping { position: Vec2 },
+    Arrow { start: Vec2, end: Vec2 }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MapMarker {
+    pub sender: PlayerID,
+    pub shape: MapMarkerShape,
+    pub time: f64
+}
+
+impl MapMarker {
+
+    fn duration(&self) -> f64 {
+        match self.shape {
+            MapMarkerShape::Ping { .. } => MAP_PING_DURATION,
+            MapMarkerShape::Arrow { .. } => MAP_MARKER_DURATION
+        }
+    }
+
+    /// Returns how far along this marker is towards fading out completely, from 0 to 1, or None when it is gone.
+    pub fn progress(&self, now: f64) -> Option<f32> {
+        let progress = (now - self.time) / self.duration();
+        if progress < 1.0 { Some(progress as f32) } else { None }
+    }
+
+    /// Markers stay solid for most of their lifetime and only fade out at the end.
+    pub fn alpha(&self, now: f64) -> f32 {
+        let Some(progress) = self.progress(now) else { return 0.0; };
+        ((1.0 - progress) * 4.0).min(1.0)
+    }
+
+}
+
+/// Pings and arrows placed in the world by the local player and their allies, drawn both in the world and on the minimap.
+pub struct MapMarkers {
+    markers: VecDeque<MapMarker>,
+    drag_start: Option<Vec2>
+}
+
+impl MapMarkers {
+
+    pub fn new() -> MapMarkers {
+        MapMarkers {
+            markers: VecDeque::new(),
+            drag_start: None
+        }
+    }
+
+    pub fn add_ping(&mut self, sender: PlayerID, position: Vec2) {
+        self.add_marker(MapMarker { sender, shape: MapMarkerShape::Ping { position }, time: get_time() });
+    }
+
+    pub fn add_arrow(&mut self, sender: PlayerID, start: Vec2, end: Vec2) {
+        self.add_marker(MapMarker { sender, shape: MapMarkerShape::Arrow { start, end }, time: get_time() });
+    }
+
+    fn add_marker(&mut self, marker: MapMarker) {
+
+        self.markers.push_back(marker);
+
+        while self.markers.len() > MAXIMUM_NUMBER_OF_MAP_MARKERS {
+            self.markers.pop_front();
+        }
+
+    }
+
+    /// Returns the markers which have not yet faded out completely.
+    pub fn visible_markers(&self) -> impl Iterator<Item = &MapMarker> {
+        let now = get_time();
+        self.markers.iter().filter(move |marker| marker.progress(now).is_some())
+    }
+
+    pub fn is_drawing(&self) -> bool {
+        self.drag_start.is_some()
+    }
+
+    pub fn start_drawing(&mut self, world_position: Vec2) {
+        self.drag_start = Some(world_position);
+    }
+
+    /// Finishes the marker being drawn, returns where it started so the caller can decide between a ping and an arrow.
+    pub fn finish_drawing(&mut self) -> Option<Vec2> {
+        self.drag_start.take()
+    }
+
+    pub fn cancel_drawing(&mut self) {
+        self.drag_start = None;
+    }
+
+    pub fn tick(&mut self) {
+
+        let now = get_time();
+
+        // pings and markers last for different amounts of time, so the oldest is not necessarily the first to go
+        self.markers.retain(|marker| marker.progress(now).is_some());
+
+    }
+
+    /// Draws all the markers in screen space, along with the one currently being drawn by the local player.
+    pub fn draw(&self, camera: &GameCamera2D) {
+
+        let now = get_time();
+        let marker_colour = Color::from_rgba(255, 224, 64, 255);
+
+        for marker in self.visible_markers() {
+
+            let Some(progress) = marker.progress(now) else { continue; };
+            let colour = marker_colour.with_alpha(marker.alpha(now));
+
+            match marker.shape {
+                MapMarkerShape::Ping { position } => {
+
+                    let screen_position = camera.world_to_screen(position);
+
+                    // a few expanding rings at the start, so it catches the eye, then just a dot until it fades
+                    let ring_progress = (progress * 4.0).fract();
+                    if progress < 0.75 {
+                        draw_circle_lines(screen_position.x, screen_position.y, 8.0 + ring_progress * 32.0, 2.0, marker_colour.with_alpha(1.0 - ring_progress));
+                    }
+
+                    draw_circle(screen_position.x, screen_position.y, 4.0, colour);
+
+                },
+                MapMarkerShape::Arrow { start, end } => {
+                    let start_screen_position = camera.world_to_screen(start);
+                    let end_screen_position = camera.world_to_screen(end);
+                    draw_arrow(start_screen_position.x, start_screen_position.y, end_screen_position.x, end_screen_position.y, 2.0, 16.0, colour);
+                }
+            }
+
+        }
+
+        if let Some(drag_start) = self.drag_start {
+            let start_screen_position = camera.world_to_screen(drag_start);
+            let end_screen_position = camera.mouse_screen_position();
+            draw_arrow(start_screen_position.x, start_screen_position.y, end_screen_position.x, end_screen_position.y, 2.0, 16.0, marker_colour.with_alpha(0.5));
+        }
+
+    }
+
+    /// Draws all the markers on the minimap, given a function mapping from world positions to positions on the minimap.
+    pub fn draw_on_minimap<F>(&self, painter: &egui::Painter, world_to_minimap: F)
+        where F: Fn(Vec2) -> egui::Pos2
+    {
+
+        let now = get_time();
+
+        for marker in self.visible_markers() {
+
+            let alpha = marker.alpha(now);
+            let colour = egui::Color32::from_rgb(255, 224, 64).gamma_multiply(alpha);
+
+            match marker.shape {
+                MapMarkerShape::Ping { position } => {
+                    painter.circle_stroke(world_to_minimap(position), 4.0, egui::Stroke::new(1.5, colour));
+                },
+                MapMarkerShape::Arrow { start, end } => {
+                    let start_minimap_position = world_to_minimap(start);
+                    let end_minimap_position = world_to_minimap(end);
+                    painter.arrow(start_minimap_position, end_minimap_position - start_minimap_position, egui::Stroke::new(1.5, colour));
+                }
+            }
+
+        }
+
+    }
+
+}
@@ -0,0 +1,148 @@
+use hecs::{Or, Without};
+use macroquad::prelude::*;
+use puffin_egui::egui::{self, Align2};
+
+use crate::PlayerID;
+use crate::model::{Building, Controller, Orderable, Passenger, ResourceSource, RymdGameModel, Transform};
+
+use super::{Alert, GameCamera2D, MapMarkers};
+
+/// Size of the minimap on screen, in points.
+pub const MINIMAP_SIZE: f32 = 192.0;
+
+/// Space left around everything in the world when figuring out the area the minimap covers.
+const MINIMAP_WORLD_PADDING: f32 = 512.0;
+
+pub struct Minimap {
+    extents: Option<Rect>
+}
+
+impl Minimap {
+
+    pub fn new() -> Minimap {
+        Minimap {
+            extents: None
+        }
+    }
+
+    /// Grows the area shown to include everything in the world, it never shrinks so the map doesn't jump around as things die.
+    fn update_extents(&mut self, model: &RymdGameModel) {
+
+        let mut minimum = Vec2::splat(f32::MAX);
+        let mut maximum = Vec2::splat(f32::MIN);
+
+        for (_, (transform, _)) in model.world.query::<(&Transform, Or<&Controller, &ResourceSource>)>().iter() {
+            minimum = minimum.min(transform.world_position);
+            maximum = maximum.max(transform.world_position);
+        }
+
+        if minimum.x > maximum.x {
+            return;
+        }
+
+        if let Some(extents) = self.extents && extents.contains(minimum) && extents.contains(maximum) {
+            return;
+        }
+
+        if let Some(extents) = self.extents {
+            minimum = minimum.min(extents.point());
+            maximum = maximum.max(extents.point() + extents.size());
+        }
+
+        // keep it square, so the minimap doesn't stretch the world
+        let center = (minimum + maximum) / 2.0;
+        let half_size = ((maximum - minimum).max_element() / 2.0) + MINIMAP_WORLD_PADDING;
+        self.extents = Some(Rect { x: center.x - half_size, y: center.y - half_size, w: half_size * 2.0, h: half_size * 2.0 });
+
+    }
+
+    /// Draws the minimap along with any pings from alerts and markers from allies, returns the world position clicked on, if any.
+    pub fn draw_ui<'a>(&mut self, ui_ctx: &egui::Context, model: &RymdGameModel, camera: &GameCamera2D, game_player_id: PlayerID, pings: impl Iterator<Item = &'a Alert>, markers: &MapMarkers) -> Option<Vec2> {
+
+        self.update_extents(model);
+
+        let Some(extents) = self.extents else { return None };
+        let mut clicked_world_position = None;
+
+        egui::Window::new("minimap")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::RIGHT_TOP, (-16.0, 16.0))
+            .show(ui_ctx, |ui| {
+
+                let (response, painter) = ui.allocate_painter(egui::vec2(MINIMAP_SIZE, MINIMAP_SIZE), egui::Sense::click_and_drag());
+                let minimap_rect = response.rect;
+
+                let world_to_minimap = |world_position: Vec2| -> egui::Pos2 {
+                    let relative_position = (world_position - extents.point()) / extents.size();
+                    minimap_rect.min + egui::vec2(relative_position.x, relative_position.y) * MINIMAP_SIZE
+                };
+
+                painter.rect_filled(minimap_rect, 0.0, egui::Color32::from_black_alpha(192));
+
+                if let Some(capture_points) = &model.capture_points {
+
+                    let local_team_id = capture_points.get_team_of_player(game_player_id);
+
+                    for point in &capture_points.points {
+
+                        let colour = match point.owner {
+                            Some(owner) if Some(owner) == local_team_id => egui::Color32::from_rgb(64, 224, 64),
+                            Some(_) => egui::Color32::from_rgb(224, 64, 64),
+                            None => egui::Color32::LIGHT_GRAY
+                        };
+
+                        let minimap_radius = (point.radius / extents.w * MINIMAP_SIZE).max(3.0);
+                        painter.circle_stroke(world_to_minimap(point.position), minimap_radius, egui::Stroke::new(1.5, colour));
+
+                    }
+
+                }
+
+                for (_, (transform, _)) in model.world.query::<(&Transform, &ResourceSource)>().iter() {
+                    painter.circle_filled(world_to_minimap(transform.world_position), 1.5, egui::Color32::from_rgb(224, 192, 64));
+                }
+
+                // the same things which are selectable, so no projectiles, effects or units carried by others
+                for (_, (transform, controller, _)) in model.world.query::<Without<(&Transform, &Controller, Or<&Orderable, &Building>), &Passenger>>().iter() {
+
+                    let colour = if controller.id == game_player_id {
+                        egui::Color32::from_rgb(64, 224, 64)
+                    } else if model.is_controller_friendly_to(game_player_id, controller) {
+                        egui::Color32::from_rgb(64, 160, 255)
+                    } else {
+                        egui::Color32::from_rgb(224, 64, 64)
+                    };
+
+                    let is_building = matches!(orderable_or_building, Or::Right(_) | Or::Both(_, _));
+                    let dot_size = if is_building { 2.5 } else { 1.5 };
+                    painter.circle_filled(world_to_minimap(transform.world_position), dot_size, colour);
+
+                }
+
+                let now = get_time();
+                for ping in pings {
+                    let Some(progress) = ping.ping_progress(now) else { continue; };
+                    let colour = ping.kind.colour().gamma_multiply(1.0 - progress);
+                    painter.circle_stroke(world_to_minimap(ping.position), 4.0 + progress * 12.0, egui::Stroke::new(1.5, colour));
+                }
+
+                markers.draw_on_minimap(&painter, world_to_minimap);
+
+                let view_world_rect = camera.screen_to_world_rect(Rect { x: 0.0, y: 0.0, w: screen_width(), h: screen_height() });
+                let view_minimap_rect = egui::Rect::from_two_pos(world_to_minimap(view_world_rect.point()), world_to_minimap(view_world_rect.point() + view_world_rect.size()));
+                painter.rect_stroke(view_minimap_rect.intersect(minimap_rect), 0.0, egui::Stroke::new(1.0, egui::Color32::WHITE));
+
+                if (response.clicked() || response.dragged()) && let Some(pointer_position) = response.interact_pointer_pos() {
+                    let relative_position = (pointer_position - minimap_rect.min) / MINIMAP_SIZE;
+                    clicked_world_position = Some(extents.point() + vec2(relative_position.x, relative_position.y) * extents.size());
+                }
+
+        });
+
+        clicked_world_position
+
+    }
+
+}
@@ -1,21 +1,23 @@
+use std::collections::BTreeMap;
 use std::f32::consts::PI;
 
 use fnv::FnvHashMap;
 use lockstep_client::game::GameContext;
 use macroquad_particles::{EmitterConfig, Emitter};
 use puffin_egui::egui::{self, Align2};
-use utility::{draw_arrow, draw_rectangle_lines_centered_with_rotation, draw_text_centered, draw_texture_centered, draw_texture_centered_with_rotation, draw_texture_centered_with_rotation_frame, is_point_inside_rect, AsPerpendicular, AsVector, AverageLine2D, DebugText, RotatedBy, TextPosition, WithAlpha};
+use utility::{draw_arrow, draw_rectangle_lines_centered_with_rotation, draw_text_centered, draw_texture_centered, draw_texture_centered_with_rotation, draw_texture_centered_with_rotation_frame, is_point_inside_rect, AsPerpendicular, AsVector, AverageLine2D, RotatedBy, TextPosition, WithAlpha};
 use lockstep_client::step::LockstepClient;
 use macroquad_particles::*;
 use macroquad::prelude::*;
 use hecs::*;
 
-use crate::PlayerID;
-use crate::game::RymdGameParameters;
-use crate::model::{current_energy, current_energy_income, current_metal, current_metal_income, existing_static_body_within_bounds, max_energy, max_metal, Attacker, Beam, Blueprint, BlueprintID, BlueprintIdentity, Blueprints, Building, Commander, Effect, EntityState, Extractor, GameOrder, GameOrderType, Impact, PhysicsBody, ResourceSource, Spawner};
-use crate::model::{RymdGameModel, Orderable, Transform, Sprite, AnimatedSprite, GameOrdersExt, DynamicBody, Thruster, Ship, ThrusterKind, Constructor, Controller, Health, get_entity_position};
+use crate::{EntityID, PlayerID};
+use crate::commands::{ChatChannel, CommandsExt, GameCommand};
+use crate::game::{RymdGameChat, RymdGameParameters};
+use crate::model::{MissionObjectiveState, MissionResult, current_energy, current_energy_expense, current_energy_income, current_metal, current_metal_expense, current_metal_income, current_resource_flow, existing_static_body_within_bounds, max_energy, max_metal, Attacker, Beam, Blueprint, BlueprintID, BlueprintIdentity, Building, CapturePoints, CheatCommand, Commander, Consumer, Effect, EntityState, Extractor, Factory, GameOrder, GameOrderType, GameSpeed, OrderID, Impact, PhysicsBody, RallyPoint, ResourceFlow, ResourcePriority, ResourceSource, Spawner};
+use crate::model::{RymdGameModel, Orderable, Transform, Sprite, AnimatedSprite, GameOrdersExt, DynamicBody, Thruster, Ship, ThrusterKind, Constructor, Controller, Health, Passenger, Transport, Hangar, can_load_passenger, get_entity_position};
 
-use super::{calculate_sprite_bounds, GameCamera2D};
+use super::{calculate_sprite_bounds, parse_console_command, AlertManager, AudioManager, ConsoleCommand, DeveloperConsole, GameCamera2D, InputAction, InputManager, MapMarkers, Minimap, SoundEffect, MAP_MARKER_MINIMUM_LENGTH, MINIMAP_SIZE};
 
 fn entity_state_to_alpha(state: Option<&EntityState>) -> f32 {
     if let Some(state) = state {
@@ -30,6 +32,19 @@ fn entity_state_to_alpha(state: Option<&EntityState>) -> f32 {
     }
 }
 
+/// egui can draw macroquad textures directly, as long as it is given the underlying GL texture.
+fn texture_to_egui_texture_id(texture: &Texture2D) -> egui::TextureId {
+
+    let raw_texture_id = unsafe { get_internal_gl().quad_context.texture_raw_id(texture.raw_miniquad_id()) };
+
+    match raw_texture_id {
+        macroquad::miniquad::RawId::OpenGl(gl_texture_id) => egui::TextureId::User(gl_texture_id as u64),
+        #[allow(unreachable_patterns)]
+        _ => egui::TextureId::default()
+    }
+
+}
+
 fn get_blueprint_bounds(resources: &Resources, blueprint: &Blueprint) -> Rect {
     let texture = resources.get_texture_by_name(&blueprint.texture);
     Rect {
@@ -63,10 +78,10 @@ impl ConstructionState {
         self.current_blueprint_id = None
     }
     
-    fn get_number_to_build() -> i32 {
-        let should_build_five = is_key_down(KeyCode::LeftShift);
-        let should_build_twenty = is_key_down(KeyCode::LeftControl);
-        let should_build_one_hundred = is_key_down(KeyCode::LeftShift) && is_key_down(KeyCode::LeftControl);
+    fn get_number_to_build(input: &InputManager) -> i32 {
+        let should_build_five = input.is_action_down(InputAction::QueueModifier);
+        let should_build_twenty = input.is_action_down(InputAction::AlternateModifier);
+        let should_build_one_hundred = should_build_five && should_build_twenty;
         if should_build_one_hundred {
             100
         } else if should_build_twenty {
@@ -78,22 +93,25 @@ impl ConstructionState {
         }
     }
 
-    fn finalize_blueprint(&mut self, model: &RymdGameModel, camera: &GameCamera2D, lockstep: &mut LockstepClient) {
+    fn finalize_blueprint(&mut self, model: &RymdGameModel, camera: &GameCamera2D, input: &InputManager, lockstep: &mut LockstepClient) {
         
         if let Some(blueprint_id) = self.current_blueprint_id {
 
-            let should_add_to_queue = is_key_down(KeyCode::LeftShift);
+            let should_add_to_queue = input.is_action_down(InputAction::QueueModifier);
     
-            for (e, (transform, _o, selectable, constructor, spawner)) in model.world.query::<(&Transform, &Orderable, &Selectable, &Constructor, Option<&Spawner>)>().iter() {
+            for (e, (transform, _o, selectable, constructor, spawner, factory)) in model.world.query::<(&Transform, &Orderable, &Selectable, &Constructor, Option<&Spawner>, Option<&Factory>)>().iter() {
 
                 if selectable.is_selected == false || constructor.has_blueprint(blueprint_id) == false {
                     continue;
                 }
 
-                if let Some(spawner) = spawner {
+                if factory.is_some() {
+                    let number_to_build = Self::get_number_to_build(input);
+                    lockstep.send_production_order(e, blueprint_id, number_to_build);
+                } else if let Some(spawner) = spawner {
                     let is_self_order = true;
                     let current_build_position: Vec2 = transform.world_position + spawner.position;                
-                    for i in 0..Self::get_number_to_build() {
+                    for i in 0..Self::get_number_to_build(input) {
                         lockstep.send_build_order(e, current_build_position, blueprint_id, should_add_to_queue, is_self_order);
                         println!("[RymdGameView] attempted to send build order for unit at position: {} and blueprint: {}", current_build_position, blueprint_id);
                     }
@@ -112,16 +130,16 @@ impl ConstructionState {
 
     }
 
-    fn tick_and_draw(&mut self, model: &RymdGameModel, camera: &GameCamera2D, resources: &Resources, lockstep: &mut LockstepClient) {
+    fn tick_and_draw(&mut self, model: &RymdGameModel, camera: &GameCamera2D, resources: &Resources, input: &InputManager, lockstep: &mut LockstepClient) {
 
         if let Some(blueprint_id) = self.current_blueprint_id {
 
             let blueprint = model.blueprint_manager.get_blueprint(blueprint_id).expect("could not find the blueprint in the manager somehow, should be impossible!");
 
             if blueprint.is_building {
-                self.preview_building(resources, blueprint, model, camera, lockstep);
+                self.preview_building(resources, blueprint, model, camera, input, lockstep);
             } else {
-                self.finalize_blueprint(model, camera, lockstep);
+                self.finalize_blueprint(model, camera, input, lockstep);
             }
             
         }
@@ -145,7 +163,7 @@ impl ConstructionState {
         
     }
 
-    fn preview_building(&mut self, resources: &Resources, blueprint: &Blueprint, model: &RymdGameModel, camera: &GameCamera2D, lockstep: &mut LockstepClient) {
+    fn preview_building(&mut self, resources: &Resources, blueprint: &Blueprint, model: &RymdGameModel, camera: &GameCamera2D, input: &InputManager, lockstep: &mut LockstepClient) {
 
         let mouse_world_position: Vec2 = camera.mouse_world_position();
         let blueprint_preview_position = mouse_world_position;
@@ -159,7 +177,7 @@ impl ConstructionState {
         Self::draw_building(resources, blueprint, blueprint_preview_position, is_build_position_blocked);
 
         if should_build {
-            self.finalize_blueprint(model, camera, lockstep);
+            self.finalize_blueprint(model, camera, input, lockstep);
         }
 
         if should_cancel {
@@ -550,19 +568,38 @@ struct ControlGroup {
     entities: Vec<Entity>
 }
 
+/// Selecting the same control group again within this many seconds centres the camera on it.
+const CONTROL_GROUP_DOUBLE_TAP_SECONDS: f64 = 0.3;
+
 #[derive(Debug)]
 struct ControlGroupState {
-    groups: Vec<ControlGroup>
+    groups: Vec<ControlGroup>,
+    /// The last control group selected, along with when.
+    last_selected: Option<(i32, f64)>
 }
 
 impl ControlGroupState {
 
     pub fn new() -> ControlGroupState {
         ControlGroupState {
-            groups: Vec::new()
+            groups: Vec::new(),
+            last_selected: None
         }
     }
 
+    /// Returns true if the same control group was also selected just before, a double tap.
+    pub fn register_selection(&mut self, id: i32) -> bool {
+
+        let current_time = get_time();
+        let is_double_tap = self.last_selected.is_some_and(|(last_id, last_time)| last_id == id && current_time - last_time <= CONTROL_GROUP_DOUBLE_TAP_SECONDS);
+
+        // a third tap starts over instead of counting as another double tap
+        self.last_selected = if is_double_tap { None } else { Some((id, current_time)) };
+
+        is_double_tap
+
+    }
+
     pub fn set(&mut self, id: i32, entities: &[Entity]) {
 
         self.groups.retain(|g| g.id != id);
@@ -595,6 +632,14 @@ pub struct RymdGameView {
     selection: SelectionState,
     ordering: OrderingState,
     resources: Resources,
+    input: InputManager,
+    audio: AudioManager,
+    alerts: AlertManager,
+    markers: MapMarkers,
+    minimap: Minimap,
+    console: DeveloperConsole,
+    is_pointer_over_ui: bool,
+    followed_entity: Option<Entity>,
     
     debug: RymdGameDebug
 
@@ -630,6 +675,14 @@ impl RymdGameView {
             ordering: OrderingState::new(),
             selection: SelectionState::new(),
             resources: Resources::new(),
+            input: InputManager::new(),
+            audio: AudioManager::new(),
+            alerts: AlertManager::new(),
+            markers: MapMarkers::new(),
+            minimap: Minimap::new(),
+            console: DeveloperConsole::new(),
+            is_pointer_over_ui: false,
+            followed_entity: None,
             debug: RymdGameDebug::new()
         }
     }
@@ -671,12 +724,18 @@ impl RymdGameView {
     pub fn start(&mut self, game_parameters: RymdGameParameters, game_player_id: PlayerID) {
         self.construction = ConstructionState::new();
         self.camera = GameCamera2D::new();
+        self.alerts = AlertManager::new();
+        self.markers = MapMarkers::new();
+        self.minimap = Minimap::new();
+        self.followed_entity = None;
         self.game_player_id = game_player_id;
         self.game_parameters = game_parameters;
     }
 
     pub async fn load_resources(&mut self) {
         self.resources.load().await;
+        self.input.load().await;
+        self.audio.load().await;
     }
 
     pub fn unload_resources(&mut self) {
@@ -697,6 +756,15 @@ impl RymdGameView {
 
     }
 
+    /// Units can be loaded while selected, or picked up by selecting everything, but anything inside a transport can't be ordered around.
+    fn perform_unselect_all_passengers(&mut self, world: &mut World) {
+
+        for (entity, (passenger, selectable)) in world.query_mut::<(&Passenger, &mut Selectable)>() {
+            selectable.is_selected = false;
+        }
+
+    }
+
     fn perform_unselect_all_units_without_blueprint(&mut self, world: &mut World, blueprint_id: BlueprintID) {
 
         for (entity, (blueprint_identity, controller, selectable)) in world.query_mut::<(&BlueprintIdentity, &Controller, &mut Selectable)>() {
@@ -713,12 +781,25 @@ impl RymdGameView {
 
     }
 
+    fn perform_unselect_all_non_commander_units(&mut self, world: &mut World) {
+
+        for (entity, (controller, selectable)) in world.query_mut::<Without<(&Controller, &mut Selectable), &Commander>>() {
+
+            if self.can_select_unit(controller) == false {
+                continue;
+            }
+
+            selectable.is_selected = false;
+
+        }
+
+    }
+
     fn perform_select_next_commander(&mut self, world: &mut World) {
 
-        // #FIXME: each faction will probably have unique commander blueprints, this shouldn't be hardcoded really
-        self.perform_unselect_all_units_without_blueprint(world, Blueprints::Commander as i32);
+        self.perform_unselect_all_non_commander_units(world);
 
-        for (entity, (blueprint_identity, controller, constructor, selectable, orderable)) in world.query_mut::<(&BlueprintIdentity, &Controller, &Constructor, &mut Selectable, &Orderable)>() {
+        for (entity, (commander, controller, selectable)) in world.query_mut::<(&Commander, &Controller, &mut Selectable)>() {
 
             if self.can_select_unit(controller) == false {
                 continue;
@@ -729,10 +810,8 @@ impl RymdGameView {
                 continue;
             }
 
-            if blueprint_identity.blueprint_id == (Blueprints::Commander as i32) {
-                selectable.is_selected = true;
-                return;
-            }
+            selectable.is_selected = true;
+            return;
 
         }
 
@@ -764,65 +843,6 @@ impl RymdGameView {
 
     }
 
-    fn is_any_number_key_pressed() -> bool {
-        is_key_pressed(KeyCode::Key0)
-            || is_key_pressed(KeyCode::Key1)
-            || is_key_pressed(KeyCode::Key2)
-            || is_key_pressed(KeyCode::Key3)
-            || is_key_pressed(KeyCode::Key4)
-            || is_key_pressed(KeyCode::Key5)
-            || is_key_pressed(KeyCode::Key6)
-            || is_key_pressed(KeyCode::Key7)
-            || is_key_pressed(KeyCode::Key8)
-            || is_key_pressed(KeyCode::Key9)
-    }
-
-    fn get_first_number_key_pressed() -> Option<i32> {
-
-        if is_key_pressed(KeyCode::Key0) {
-            return Some(0);
-        }
-        
-        if is_key_pressed(KeyCode::Key1) {
-            return Some(1);
-        }
-
-        if is_key_pressed(KeyCode::Key2) {
-            return Some(2);
-        }
-
-        if is_key_pressed(KeyCode::Key3) {
-            return Some(3);
-        }
-
-        if is_key_pressed(KeyCode::Key4) {
-            return Some(4);
-        }
-
-        if is_key_pressed(KeyCode::Key5) {
-            return Some(5);
-        }
-
-        if is_key_pressed(KeyCode::Key6) {
-            return Some(6);
-        }
-
-        if is_key_pressed(KeyCode::Key7) {
-            return Some(7);
-        }
-
-        if is_key_pressed(KeyCode::Key8) {
-            return Some(8);
-        }
-
-        if is_key_pressed(KeyCode::Key9) {
-            return Some(9);
-        }
-
-        None
-
-    }
-
     fn get_entity_control_group(&self, entity: Entity) -> Option<i32> {
         for control_group in &self.control_groups.groups {
             if control_group.entities.contains(&entity) {
@@ -841,16 +861,15 @@ impl RymdGameView {
         false
     }
 
-    fn perform_retrieve_and_select_control_group(&mut self, world: &mut World) {
+    fn perform_retrieve_and_select_control_group(&mut self, world: &mut World, control_group_id: i32) {
 
-        if is_key_down(KeyCode::LeftShift) == false {
+        if self.input.is_action_down(InputAction::QueueModifier) == false {
             self.perform_unselect_all(world);
         }
 
-        let control_group_id = Self::get_first_number_key_pressed().expect("there must be a number key pressed when calling this function, there was none!");
         let control_group_entities: Vec<Entity> = self.control_groups.get(control_group_id).to_vec();
 
-        for e in control_group_entities {
+        for &e in &control_group_entities {
             if let Ok((controller, selectable)) = world.query_one_mut::<(&Controller, &mut Selectable)>(e) {
                 if self.can_select_unit(controller) {
                     selectable.is_selected = true;
@@ -858,11 +877,30 @@ impl RymdGameView {
             }
         }
 
+        if self.control_groups.register_selection(control_group_id) {
+            self.move_camera_to_centre_of_entities(world, &control_group_entities);
+        }
+
     }
 
-    fn perform_assign_control_group(&mut self, world: &mut World) {
+    fn move_camera_to_centre_of_entities(&mut self, world: &World, entities: &[Entity]) {
+
+        let positions: Vec<Vec2> = entities.iter()
+            .filter(|&&e| world.satisfies::<&Passenger>(e).unwrap_or(false) == false)
+            .filter_map(|&e| world.get::<&Transform>(e).ok().map(|transform| transform.world_position))
+            .collect();
+
+        if positions.is_empty() {
+            return;
+        }
+
+        let centre = positions.iter().sum::<Vec2>() / positions.len() as f32;
+        self.followed_entity = None;
+        self.camera.move_camera_to_position(centre);
+
+    }
 
-        let control_group_id = Self::get_first_number_key_pressed().expect("there must be a number key pressed when calling this function, there was none!");
+    fn perform_assign_control_group(&mut self, world: &mut World, control_group_id: i32) {
 
         let mut collected_entities = Vec::new();
         for (e, selectable) in world.query_mut::<&Selectable>() {
@@ -881,56 +919,64 @@ impl RymdGameView {
             return;
         }
 
-        // 0-9 keys allow you to retrieve previously set control groups
-        let is_retrieving_and_selecting_control_group = Self::is_any_number_key_pressed() && is_key_down(KeyCode::LeftControl) == false;
-        if is_retrieving_and_selecting_control_group {
-            self.perform_retrieve_and_select_control_group(world);
+        // 0-9 keys (by default) allow you to retrieve previously set control groups
+        if let Some(control_group_id) = self.input.control_group_to_select() {
+            self.perform_retrieve_and_select_control_group(world, control_group_id);
             return;
         }
 
-        // CTRL+0-9 allows you to group units into control groups that you can summon again by pressing 0-9
-        let is_assigning_control_group = is_key_down(KeyCode::LeftControl) && Self::is_any_number_key_pressed();
-        if is_assigning_control_group {
-            self.perform_assign_control_group(world);
+        // CTRL+0-9 (by default) allows you to group units into control groups that you can summon again
+        if let Some(control_group_id) = self.input.control_group_to_assign() {
+            self.perform_assign_control_group(world, control_group_id);
             return;
         }
 
         // CTRL+A should select all units
-        let is_selecting_all = is_key_down(KeyCode::LeftControl) && is_key_pressed(KeyCode::A);
+        let is_selecting_all = self.input.is_action_pressed(InputAction::SelectAll);
         if is_selecting_all {
             self.perform_select_all(world);
             return;
         }
 
         // CTRL+B selects the next idle constructor
-        let is_finding_next_idle_constructor = is_key_down(KeyCode::LeftControl) && is_key_pressed(KeyCode::B);
+        let is_finding_next_idle_constructor = self.input.is_action_pressed(InputAction::SelectNextIdleConstructor);
         if is_finding_next_idle_constructor {
             self.perform_select_next_idle_constructor(world);
             return;
         }
 
         // CTRL+C selects your next commander unit
-        let is_finding_next_commander = is_key_down(KeyCode::LeftControl) && is_key_pressed(KeyCode::C);
+        let is_finding_next_commander = self.input.is_action_pressed(InputAction::SelectNextCommander);
         if is_finding_next_commander {
             self.perform_select_next_commander(world);
             return;
         }
 
         // CTRL+Z allows you to select all units of the same kind as in the selection
-        let is_selecting_all_units_of_same_kind = is_key_down(KeyCode::LeftControl) && is_key_pressed(KeyCode::Z);
+        let is_selecting_all_units_of_same_kind = self.input.is_action_pressed(InputAction::SelectAllOfSameKind);
         if is_selecting_all_units_of_same_kind {
             let all_selected_units = self.get_all_currently_selected_units(world);
             self.perform_selection_of_all_units_matching_type(all_selected_units, world);
             return;
         }
 
+        // clicks on the hud should not also start selecting things in the world behind it
+        if self.is_pointer_over_ui && self.selection.is_active == false {
+            return;
+        }
+
+        // while placing markers the left mouse button is used for drawing them instead
+        if (self.input.is_action_down(InputAction::PlaceMarker) || self.markers.is_drawing()) && self.selection.is_active == false {
+            return;
+        }
+
         let mouse_position: Vec2 = self.camera.mouse_screen_position();
-        let is_adding_to_selection: bool = is_key_down(KeyCode::LeftShift);
-        let is_removing_from_selection = is_key_down(KeyCode::LeftControl);
+        let is_adding_to_selection: bool = self.input.is_action_down(InputAction::QueueModifier);
+        let is_removing_from_selection = self.input.is_action_down(InputAction::AlternateModifier);
         let mut selection_turned_inactive = false;
 
         // allow to select all units under the mouse of the given type with Shift + Left Mouse x2
-        let is_selecting_all_of_type = is_key_down(KeyCode::LeftShift) && is_mouse_button_released(MouseButton::Left) && self.selection.was_double_click();
+        let is_selecting_all_of_type = self.input.is_action_down(InputAction::QueueModifier) && is_mouse_button_released(MouseButton::Left) && self.selection.was_double_click();
 
         if is_mouse_button_pressed(MouseButton::Left) {
             self.selection.start = mouse_position;
@@ -1026,27 +1072,36 @@ impl RymdGameView {
     fn handle_order(&mut self, model: &mut RymdGameModel, lockstep: &mut LockstepClient) {
 
         let mouse_position: Vec2 = self.camera.mouse_world_position();
-        let should_cancel_current_orders: bool = is_key_released(KeyCode::S);
+        let should_cancel_current_orders: bool = self.input.is_action_released(InputAction::CancelOrders);
 
-        let about_to_issue_attack_move_order = is_key_down(KeyCode::LeftControl) == false && is_key_released(KeyCode::LeftControl) == false && is_key_down(KeyCode::A);
-        let about_to_issue_order = is_mouse_button_down(MouseButton::Right);
+        let about_to_issue_attack_move_order = self.is_currently_issuing_an_attack_order();
+        let about_to_issue_order = is_mouse_button_down(MouseButton::Right) && self.is_pointer_over_ui == false;
         let about_to_issue_any_order = about_to_issue_order || about_to_issue_attack_move_order;
 
-        let should_issue_attack_move_order = is_key_down(KeyCode::LeftControl) == false && is_key_released(KeyCode::LeftControl) == false && is_key_released(KeyCode::A);
-        let should_issue_order = is_mouse_button_released(MouseButton::Right);
+        let should_issue_attack_move_order = self.input.is_action_released(InputAction::AttackMove) && self.input.is_action_released(InputAction::AlternateModifier) == false;
+        let should_issue_order = is_mouse_button_released(MouseButton::Right) && self.is_pointer_over_ui == false;
         let should_issue_any_order = should_issue_order || should_issue_attack_move_order;
 
         if should_issue_any_order {
 
-            let should_add = is_key_down(KeyCode::LeftShift);
-            let should_group = is_key_down(KeyCode::LeftControl);
+            if self.get_all_currently_selected_units(&mut model.world).is_empty() == false {
+                self.audio.play(SoundEffect::OrderAcknowledged);
+            }
+
+            let should_add = self.input.is_action_down(InputAction::QueueModifier);
+            let should_group = self.input.is_action_down(InputAction::AlternateModifier);
             let current_selection_end_point = self.ordering.points()[0];
             let entity_under_cursor = self.get_entity_under_cursor(&model.world);
 
+            // factories can't go anywhere themselves, so any order given to them sets where the units they produce go instead
+            self.handle_rally_point_order(&mut model.world, entity_under_cursor, mouse_position, lockstep, should_add, should_issue_attack_move_order);
+
             if let Some(target_entity) = entity_under_cursor {
 
                 if self.is_entity_extractable(target_entity, &model.world) {
                     self.handle_extract_order(&mut model.world, target_entity, lockstep, should_add);
+                } else if self.is_entity_friendly(target_entity, model) && self.handle_load_order(model, target_entity, lockstep, should_add) {
+                    // selected units board the transport, or selected transports pick up the unit
                 } else if self.is_entity_friendly(target_entity, model) {
                     self.handle_repair_order(&mut model.world, target_entity, lockstep, should_add);
                 } else if self.is_entity_attackable(target_entity, model) {
@@ -1067,6 +1122,61 @@ impl RymdGameView {
             self.cancel_current_orders(model, lockstep);
         }
 
+        if self.input.is_action_pressed(InputAction::Unload) && self.is_pointer_over_ui == false {
+            self.handle_unload_order(model, mouse_position, lockstep);
+        }
+
+    }
+
+    /// Returns true if any load order was issued, in which case the right click should not be treated as a repair order.
+    fn handle_load_order(&mut self, model: &RymdGameModel, target_entity: Entity, lockstep: &mut LockstepClient, should_add: bool) -> bool {
+
+        let mut has_issued_load_order = false;
+        let is_target_transport = model.world.satisfies::<&Transport>(target_entity).unwrap_or(false);
+
+        if is_target_transport {
+
+            for (e, (orderable, selectable)) in model.world.query::<Without<(&Orderable, &Selectable), &Transport>>().iter() {
+
+                if selectable.is_selected && can_load_passenger(model, target_entity, e) {
+                    lockstep.send_load_order(target_entity, e, should_add);
+                    println!("[RymdGameView] ordered: {:?} to load: {:?}", target_entity, e);
+                    has_issued_load_order = true;
+                }
+
+            }
+
+            return has_issued_load_order;
+
+        }
+
+        for (e, (orderable, selectable, transport)) in model.world.query::<(&Orderable, &Selectable, &Transport)>().iter() {
+
+            if selectable.is_selected && can_load_passenger(model, e, target_entity) {
+                lockstep.send_load_order(e, target_entity, should_add);
+                println!("[RymdGameView] ordered: {:?} to load: {:?}", e, target_entity);
+                has_issued_load_order = true;
+            }
+
+        }
+
+        has_issued_load_order
+
+    }
+
+    fn handle_unload_order(&mut self, model: &RymdGameModel, target_position: Vec2, lockstep: &mut LockstepClient) {
+
+        let should_add = self.input.is_action_down(InputAction::QueueModifier);
+
+        for (e, (orderable, selectable, transport)) in model.world.query::<(&Orderable, &Selectable, &Transport)>().iter() {
+
+            if selectable.is_selected && transport.passengers.is_empty() == false && self.is_entity_controllable(e, model) {
+                lockstep.send_unload_order(e, target_position, should_add);
+                println!("[RymdGameView] ordered: {:?} to unload at: {}", e, target_position);
+            }
+
+        }
+
     }
 
     fn cancel_current_orders(&self, model: &mut RymdGameModel, lockstep: &mut LockstepClient,) {
@@ -1130,13 +1240,35 @@ impl RymdGameView {
 
     }
 
+    fn handle_rally_point_order(&mut self, world: &mut World, target_entity: Option<Entity>, target_position: Vec2, lockstep: &mut LockstepClient, should_add: bool, should_attack: bool) {
+
+        for (e, (orderable, selectable, factory)) in world.query_mut::<(&Orderable, &Selectable, &Factory)>() {
+
+            if selectable.is_selected == false || target_entity == Some(e) {
+                continue;
+            }
+
+            let rally_point = if let Some(target_entity) = target_entity {
+                RallyPoint::Entity { entity_id: target_entity.to_bits().get() }
+            } else if should_attack {
+                RallyPoint::AttackMove { x: target_position.x, y: target_position.y }
+            } else {
+                RallyPoint::Move { x: target_position.x, y: target_position.y }
+            };
+
+            lockstep.send_rally_point(e, rally_point, should_add);
+
+        }
+
+    }
+
     fn handle_move_order(&mut self, world: &mut World, current_selection_end_point: Vec2, current_mouse_world_position: Vec2, lockstep: &mut LockstepClient, should_group: bool, should_add: bool, should_attack: bool) {
         
         // we need to know the number of selected orderables so that we can distribute units along the line we draw for movement
-        let number_of_selected_orderables = world.query_mut::<(&Orderable, &Selectable)>().into_iter().filter(|e| e.1.1.is_selected).count();
+        let number_of_selected_orderables = world.query_mut::<Without<(&Orderable, &Selectable), &Factory>>().into_iter().filter(|e| e.1.1.is_selected).count();
 
         // order the selectables by their distance from the current selection end point, this way we mostly retain the current arrangement the units are in and they hopefully make sorta-optimal moves
-        let mut selectables_ordered_by_distance_to_end_point: Vec<(Entity, (&Transform, &Orderable, &Selectable))> = world.query_mut::<(&Transform, &Orderable, &Selectable)>().into_iter().filter(|e| e.1.2.is_selected).collect();
+        let mut selectables_ordered_by_distance_to_end_point: Vec<(Entity, (&Transform, &Orderable, &Selectable))> = world.query_mut::<Without<(&Transform, &Orderable, &Selectable), &Factory>>().into_iter().filter(|e| e.1.2.is_selected).collect();
         selectables_ordered_by_distance_to_end_point.sort_by(|a, b| a.1.0.world_position.distance(current_selection_end_point).total_cmp(&b.1.0.world_position.distance(current_selection_end_point)));
 
         // calculate the centroid so that we can use it to figure out where units should go when moving as a group
@@ -1219,7 +1351,7 @@ impl RymdGameView {
         println!("[RymdGameView] attempted to select entities inside: {:?}", selection_rectangle);
 
         let mut now_selected_units = Vec::new();
-        for (e, (transform, controller, bounds, body, selectable)) in world.query_mut::<(&Transform, &Controller, Option<&Bounds>, Option<&DynamicBody>, &mut Selectable)>() {
+        for (e, (transform, controller, bounds, body, selectable)) in world.query_mut::<Without<(&Transform, &Controller, Option<&Bounds>, Option<&DynamicBody>, &mut Selectable), &Passenger>>() {
 
             if self.can_select_unit(controller) == false {
                 continue;
@@ -1289,7 +1421,8 @@ impl RymdGameView {
     }
 
     fn is_currently_issuing_an_attack_order(&self) -> bool {
-        is_key_down(KeyCode::LeftControl) == false && is_key_released(KeyCode::LeftControl) == false && is_key_down(KeyCode::A)
+        // releasing the alternate modifier just before the attack move key should not count, otherwise letting go of a chord like CTRL+A would also issue an attack move
+        self.input.is_action_down(InputAction::AttackMove) && self.input.is_action_released(InputAction::AlternateModifier) == false
     }
     
     fn draw_ordering(&self, world: &World) {
@@ -1401,6 +1534,79 @@ impl RymdGameView {
         
     }
 
+    fn capture_point_colour(&self, capture_points: &CapturePoints, team_id: Option<i32>) -> Color {
+        match team_id {
+            Some(team_id) if capture_points.get_team_of_player(self.game_player_id) == Some(team_id) => Color::from_rgba(64, 224, 64, 255),
+            Some(_) => Color::from_rgba(224, 64, 64, 255),
+            None => LIGHTGRAY
+        }
+    }
+
+    fn draw_capture_points(&self, model: &RymdGameModel) {
+
+        let Some(capture_points) = &model.capture_points else { return; };
+
+        for point in &capture_points.points {
+
+            let screen_position = self.camera.world_to_screen(point.position);
+            let screen_radius = self.camera.world_to_screen_scale_v(point.radius);
+
+            let owner_colour = self.capture_point_colour(capture_points, point.owner);
+            let progress_colour = self.capture_point_colour(capture_points, point.progress_team);
+
+            draw_circle(screen_position.x, screen_position.y, screen_radius, owner_colour.with_alpha(0.05));
+            draw_circle_lines(screen_position.x, screen_position.y, screen_radius, 2.0, owner_colour.with_alpha(0.5));
+
+            // the progress of whoever is currently capturing the point, drawn as an arc just inside the edge
+            if point.progress > 0.0 {
+                draw_arc(screen_position.x, screen_position.y, 64, screen_radius - 6.0, -90.0, 4.0, point.progress * 360.0, progress_colour.with_alpha(0.75));
+            }
+
+        }
+
+    }
+
+    fn draw_rally_points(&self, model: &RymdGameModel) {
+
+        for (e, (transform, spawner, factory, selectable)) in model.world.query::<(&Transform, &Spawner, &Factory, &Selectable)>().iter() {
+
+            if selectable.is_selected == false {
+                continue;
+            }
+
+            let mut current_line_start = transform.world_position + spawner.position;
+
+            let rally_line_thickness = 1.0;
+            let rally_line_head_size = self.camera.world_to_screen_scale_v(8.0);
+
+            let rally_line_colour_attack_move = ORANGE.with_alpha(0.5);
+            let rally_line_colour_move = SKYBLUE.with_alpha(0.5);
+
+            for (i, rally_point) in factory.rally_points.iter().enumerate() {
+
+                let rally_line_colour = if let RallyPoint::AttackMove { .. } = rally_point {
+                    rally_line_colour_attack_move
+                } else {
+                    rally_line_colour_move
+                };
+
+                if let Some(target_position) = rally_point.get_target_position(model) {
+                    let current_screen_position = self.camera.world_to_screen(current_line_start);
+                    let target_screen_position = self.camera.world_to_screen(target_position);
+                    if i == factory.rally_points.len() - 1 {
+                        draw_arrow(current_screen_position.x, current_screen_position.y, target_screen_position.x, target_screen_position.y, rally_line_thickness, rally_line_head_size, rally_line_colour);
+                    } else {
+                        draw_line(current_screen_position.x, current_screen_position.y, target_screen_position.x, target_screen_position.y, rally_line_thickness, rally_line_colour);
+                    }
+                    current_line_start = target_position;
+                }
+
+            }
+
+        }
+
+    }
+
     pub fn move_camera_to_first_unselected_commander(&mut self, model: &mut RymdGameModel) {
 
         for (e, (commander, transform, selectable)) in model.world.query_mut::<(&Commander, &Transform, &Selectable)>() {
@@ -1423,6 +1629,12 @@ impl RymdGameView {
             self.move_camera_to_first_unselected_commander(model);
         }
 
+        self.audio.handle_events(&model.events, &self.camera);
+
+        if self.alerts.handle_events(&model.events, self.game_player_id, &self.camera) {
+            self.audio.play(SoundEffect::Alert);
+        }
+
         let mut beam_components_to_add = Vec::new();
         let mut selectable_components_to_add = Vec::new();
         let mut thruster_components_to_add = Vec::new();
@@ -1494,9 +1706,138 @@ impl RymdGameView {
 
     pub fn tick(&mut self, model: &mut RymdGameModel, ctx: &mut GameContext, dt: f32) {
 
+        // while a hotkey is being rebound the key pressed is meant for the hotkey screen only
+        if self.input.is_capturing() {
+            return;
+        }
+
+        let allies = model.allies_of(self.game_player_id);
+        self.handle_marker_placement(ctx.lockstep_mut(), &allies);
+        self.handle_game_speed(model, ctx.lockstep_mut());
         self.handle_selection(&mut model.world);
+        self.perform_unselect_all_passengers(&mut model.world);
         self.handle_order(model, ctx.lockstep_mut());
 
+        self.handle_camera_hotkeys(&model.world);
+
+    }
+
+    fn handle_camera_hotkeys(&mut self, world: &World) {
+
+        if self.input.is_action_pressed(InputAction::JumpToLastAlert) && let Some(alert_position) = self.alerts.last_alert_position() {
+            self.followed_entity = None;
+            self.camera.move_camera_to_position(alert_position);
+        }
+
+        if let Some(bookmark_id) = self.input.camera_bookmark_to_save() {
+            self.camera.save_bookmark(bookmark_id);
+            println!("[RymdGameView] saved camera bookmark: {} at: {}", bookmark_id, self.camera.world_position());
+        }
+
+        if let Some(bookmark_id) = self.input.camera_bookmark_to_recall() && self.camera.recall_bookmark(bookmark_id) {
+            self.followed_entity = None;
+        }
+
+        if self.input.is_action_pressed(InputAction::ToggleFollowSelection) {
+            self.followed_entity = if self.followed_entity.is_some() {
+                None
+            } else {
+                self.get_first_selected_unit_to_follow(world)
+            };
+        }
+
+    }
+
+    /// Buildings don't go anywhere, so there's no point following them.
+    fn get_first_selected_unit_to_follow(&self, world: &World) -> Option<Entity> {
+        world.query::<Without<(&Transform, &Orderable, &Selectable), &Building>>().iter()
+            .find(|(_, (_, _, selectable))| selectable.is_selected)
+            .map(|(e, _)| e)
+    }
+
+    /// Stops following once the player moves the camera themselves, or the unit is destroyed or loaded into a transport.
+    fn update_camera_follow(&mut self, world: &World) {
+
+        let Some(followed_entity) = self.followed_entity else { return; };
+
+        if self.camera.is_moved_by_player() || world.satisfies::<&Passenger>(followed_entity).unwrap_or(true) {
+            self.followed_entity = None;
+            return;
+        }
+
+        if let Ok(transform) = world.get::<&Transform>(followed_entity) {
+            self.camera.smooth_move_camera_to_position(transform.world_position);
+        }
+
+    }
+
+    /// Pings and markers from the local player and their allies, anything else has already been filtered out by the game.
+    pub fn on_game_command(&mut self, peer_id: PlayerID, game_command: &GameCommand) {
+
+        match *game_command {
+            GameCommand::Ping { x, y } => self.markers.add_ping(peer_id, vec2(x, y)),
+            GameCommand::Marker { start_x, start_y, end_x, end_y } => self.markers.add_arrow(peer_id, vec2(start_x, start_y), vec2(end_x, end_y)),
+            _ => return
+        }
+
+        if peer_id != self.game_player_id {
+            self.audio.play(SoundEffect::Alert);
+        }
+
+    }
+
+    fn handle_game_speed(&mut self, model: &RymdGameModel, lockstep: &mut LockstepClient) {
+
+        if self.input.is_action_pressed(InputAction::TogglePause) {
+            lockstep.set_game_paused(model.speed.is_paused() == false);
+        }
+
+        let is_host = model.speed.is_host(lockstep.peer_id());
+
+        if self.input.is_action_pressed(InputAction::IncreaseGameSpeed) {
+            Self::request_game_speed(lockstep, model.speed.speed.faster(), is_host);
+        }
+
+        if self.input.is_action_pressed(InputAction::DecreaseGameSpeed) {
+            Self::request_game_speed(lockstep, model.speed.speed.slower(), is_host);
+        }
+
+    }
+
+    /// The host sets the speed directly, everyone else votes for it.
+    fn request_game_speed(lockstep: &mut LockstepClient, speed: GameSpeed, is_host: bool) {
+        if is_host {
+            lockstep.set_game_speed(speed);
+        } else {
+            lockstep.vote_game_speed(speed);
+        }
+
+    }
+
+    fn handle_marker_placement(&mut self, lockstep: &mut LockstepClient, allies: &[PlayerID]) {
+
+        if self.input.is_action_down(InputAction::PlaceMarker) && is_mouse_button_pressed(MouseButton::Left) && self.is_pointer_over_ui == false {
+            self.markers.start_drawing(self.camera.mouse_world_position());
+        }
+
+        if is_mouse_button_pressed(MouseButton::Right) {
+            self.markers.cancel_drawing();
+        }
+
+        if is_mouse_button_released(MouseButton::Left) && let Some(marker_start) = self.markers.finish_drawing() {
+
+            let marker_end = self.camera.mouse_world_position();
+            let marker_screen_length = self.camera.world_to_screen(marker_start).distance(self.camera.world_to_screen(marker_end));
+
+            // a click, or close enough to one, is a ping rather than a very short arrow
+            if marker_screen_length < MAP_MARKER_MINIMUM_LENGTH {
+                lockstep.send_ping_message(marker_end.x, marker_end.y, allies);
+            } else {
+                lockstep.send_marker_message(marker_start.x, marker_start.y, marker_end.x, marker_end.y, allies);
+            }
+
+        }
+
     }
 
     fn draw_build_queue(&self, model: &RymdGameModel) {
@@ -1545,7 +1886,8 @@ impl RymdGameView {
 
     fn draw_sprites(&self, world: &World) {
 
-        for (e, (transform, sprite, state)) in world.query::<(&Transform, Or<&Sprite, &AnimatedSprite>, Option<&EntityState>)>().iter() {
+        // passengers are carried inside their transport, so they are not drawn until unloaded
+        for (e, (transform, sprite, state)) in world.query::<Without<(&Transform, Or<&Sprite, &AnimatedSprite>, Option<&EntityState>), &Passenger>>().iter() {
             match sprite {
                 Or::Left(sprite) => self.draw_sprite(state, sprite, transform),
                 Or::Right(animated_sprite) => self.draw_animated_sprite(state, animated_sprite, transform),
@@ -1755,34 +2097,39 @@ impl RymdGameView {
         ctx.debug_text().draw_text(format!("mouse (world) position: ({:.1}, {:.1})", mouse_world_position.x, mouse_world_position.y), TextPosition::TopLeft, WHITE);
         ctx.debug_text().draw_text(format!("number of entities: {}", model.world.len()), TextPosition::TopLeft, WHITE);
         ctx.debug_text().draw_text(format!("collision responses: {}", model.physics_manager.number_of_active_collision_responses()), TextPosition::TopLeft, WHITE);
-        ctx.debug_text().draw_text(format!(" - shift+c to toggle bounds debug (enabled: {})", self.debug.render_bounds), TextPosition::TopLeft, WHITE);
-        ctx.debug_text().draw_text(format!(" - shift+k to toggle kinematics debug (enabled: {})", self.debug.render_kinematic), TextPosition::TopLeft, WHITE);
-        ctx.debug_text().draw_text(format!(" - shift+s to toggle spatial debug (enabled: {})", self.debug.render_spatial), TextPosition::TopLeft, WHITE);
-        ctx.debug_text().draw_text(format!(" - shift+e to toggle state debug (enabled: {})", self.debug.render_states), TextPosition::TopLeft, WHITE);
+        ctx.debug_text().draw_text(format!(" - {} to toggle bounds debug (enabled: {})", self.input.chord_name(InputAction::ToggleDebugBounds), self.debug.render_bounds), TextPosition::TopLeft, WHITE);
+        ctx.debug_text().draw_text(format!(" - {} to toggle kinematics debug (enabled: {})", self.input.chord_name(InputAction::ToggleDebugKinematics), self.debug.render_kinematic), TextPosition::TopLeft, WHITE);
+        ctx.debug_text().draw_text(format!(" - {} to toggle spatial debug (enabled: {})", self.input.chord_name(InputAction::ToggleDebugSpatial), self.debug.render_spatial), TextPosition::TopLeft, WHITE);
+        ctx.debug_text().draw_text(format!(" - {} to toggle state debug (enabled: {})", self.input.chord_name(InputAction::ToggleDebugStates), self.debug.render_states), TextPosition::TopLeft, WHITE);
+        ctx.debug_text().draw_text(format!(" - {} to change hotkeys", self.input.chord_name(InputAction::ToggleInputSettings)), TextPosition::TopLeft, WHITE);
+        ctx.debug_text().draw_text(format!(" - {} to change audio settings", self.input.chord_name(InputAction::ToggleAudioSettings)), TextPosition::TopLeft, WHITE);
+        ctx.debug_text().draw_text(format!(" - {} to jump to the last alert", self.input.chord_name(InputAction::JumpToLastAlert)), TextPosition::TopLeft, WHITE);
+        ctx.debug_text().draw_text(format!(" - {} to follow the selected unit (following: {})", self.input.chord_name(InputAction::ToggleFollowSelection), self.followed_entity.is_some()), TextPosition::TopLeft, WHITE);
+        ctx.debug_text().draw_text(format!(" - {} to save a camera bookmark, {} to jump to it", self.input.chord_name(InputAction::SaveCameraBookmark(0)), self.input.chord_name(InputAction::RecallCameraBookmark(0))), TextPosition::TopLeft, WHITE);
 
         if ctx.lockstep_mut().is_singleplayer() {
-            ctx.debug_text().draw_text("press tab to switch the current player!", TextPosition::TopLeft, WHITE);
-            if is_key_pressed(KeyCode::Tab) {
+            ctx.debug_text().draw_text(format!("press {} to switch the current player!", self.input.chord_name(InputAction::SwitchPlayer)), TextPosition::TopLeft, WHITE);
+            if self.input.is_action_pressed(InputAction::SwitchPlayer) {
                 self.switch_player_id_to_next(&mut model.world);
             }
         }
 
-        let should_toggle_debug_bounds = is_key_down(KeyCode::LeftShift) && is_key_released(KeyCode::C);
+        let should_toggle_debug_bounds = self.input.is_action_released(InputAction::ToggleDebugBounds);
         if should_toggle_debug_bounds {
             self.debug.render_bounds = !self.debug.render_bounds;
         }
 
-        let should_toggle_kinematic_debug = is_key_down(KeyCode::LeftShift) && is_key_released(KeyCode::K);
+        let should_toggle_kinematic_debug = self.input.is_action_released(InputAction::ToggleDebugKinematics);
         if should_toggle_kinematic_debug {
             self.debug.render_kinematic = !self.debug.render_kinematic
         }
 
-        let should_toggle_spatial_debug = is_key_down(KeyCode::LeftShift) && is_key_released(KeyCode::S);
+        let should_toggle_spatial_debug = self.input.is_action_released(InputAction::ToggleDebugSpatial);
         if should_toggle_spatial_debug {
             self.debug.render_spatial = !self.debug.render_spatial
         }
 
-        let should_toggle_state_debug = is_key_down(KeyCode::LeftShift) && is_key_released(KeyCode::E);
+        let should_toggle_state_debug = self.input.is_action_released(InputAction::ToggleDebugStates);
         if should_toggle_state_debug {
             self.debug.render_states = !self.debug.render_states;
         }
@@ -1849,121 +2196,583 @@ impl RymdGameView {
 
     }
 
-    fn draw_text_construction_ui(&mut self, model: &mut RymdGameModel, debug: &mut DebugText) {
+    fn get_blueprint_icon(&self, blueprint: &Blueprint, size: f32) -> egui::Image<'static> {
 
-        let available_blueprints = self.get_available_blueprints_from_current_selection(&model.world);
-
-        debug.skip_line(TextPosition::BottomRight);
+        let texture = self.resources.get_texture_by_name(&blueprint.texture);
 
-        for id in available_blueprints {
-            let blueprint = model.blueprint_manager.get_blueprint(id).unwrap();
-            debug.draw_text(format!(" > {} ({:?}) - ({} M, {} E)", blueprint.name, blueprint.shortcut, blueprint.cost.metal, blueprint.cost.energy), TextPosition::BottomRight, WHITE);
-            if is_key_released(blueprint.shortcut) {
-                self.construction.preview_blueprint(id);
-            }
-        }
+        // ship textures are horizontal sprite sheets of square frames, only show the first frame
+        let number_of_frames = (texture.width() / texture.height().max(1.0)).round().max(1.0);
+        let first_frame_uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0 / number_of_frames, 1.0));
 
-        debug.draw_text("constructibles", TextPosition::BottomRight, WHITE);
+        egui::Image::new(egui::load::SizedTexture::new(texture_to_egui_texture_id(&texture), egui::vec2(size, size))).uv(first_frame_uv)
 
     }
 
-    fn draw_text_construction_queue_ui(&mut self, model: &mut RymdGameModel, debug: &mut DebugText) {
-
-        for (e, (constructor, orderable, selectable)) in model.world.query::<(&Constructor, &Orderable, &Selectable)>().iter() {
+    fn draw_resource_bars_ui(&self, ui_ctx: &egui::Context, model: &RymdGameModel) {
 
-            if orderable.is_queue_empty(GameOrderType::Construct) {
-                continue
-            }
+        egui::Window::new("")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, (0.0, 16.0))
+            .frame(egui::Frame::default())
+            .show(ui_ctx, |ui| {
 
-            if selectable.is_selected == false {
-                continue;
-            }
+                let current_metal = current_metal(self.game_player_id, &model.world);
+                let maximum_metal = max_metal(self.game_player_id, &model.world);
+                let current_metal_income = current_metal_income(self.game_player_id, &model.world);
+                let current_metal_expense = current_metal_expense(self.game_player_id, &model.world);
 
-            debug.skip_line(TextPosition::BottomRight);
+                let current_energy = current_energy(self.game_player_id, &model.world);
+                let maximum_energy = max_energy(self.game_player_id, &model.world);
+                let current_energy_income = current_energy_income(self.game_player_id, &model.world);
+                let current_energy_expense = current_energy_expense(self.game_player_id, &model.world);
 
-            let mut blueprints_in_construction = Vec::new();
+                let current_metal_proportion = current_metal / maximum_metal;
+                let current_energy_proportion = current_energy / maximum_energy;
 
-            for order in orderable.orders(GameOrderType::Construct) {
-                if let GameOrder::Construct(construct_order) = order {
-                    
-                    let current_blueprint_id = if let Some(blueprint_id) = construct_order.blueprint_id {
-                        Some(blueprint_id)
-                    } else if let Some(entity) = construct_order.entity() && let Ok(blueprint_identity) = model.world.get::<&BlueprintIdentity>(entity) {
-                        Some(blueprint_identity.blueprint_id)
-                    } else {
-                        None
-                    };
+                let metal_progress_bar = egui::ProgressBar::new(current_metal_proportion)
+                    .desired_width(320.0)
+                    .text(format!("Metal = {:.0} / {:.0} (+{:.1} / -{:.1})", current_metal, maximum_metal, current_metal_income, current_metal_expense));
 
-                    if let Some(current_blueprint_id) = current_blueprint_id {
+                let energy_progress_bar = egui::ProgressBar::new(current_energy_proportion)
+                    .desired_width(320.0)
+                    .text(format!("Energy = {:.0} / {:.0} (+{:.1} / -{:.1})", current_energy, maximum_energy, current_energy_income, current_energy_expense));
 
-                        if let Some((last_id, last_count)) = blueprints_in_construction.last().cloned() {
-                            if last_id == current_blueprint_id {
-                                blueprints_in_construction.pop();
-                                blueprints_in_construction.push((current_blueprint_id, last_count + 1));
-                            } else {
-                                blueprints_in_construction.push((current_blueprint_id, 1));
+                // show the bars in red whenever demand for them can't be met in full, that's when construction starts slowing down
+                let resource_flow = current_resource_flow(self.game_player_id, &model.world).unwrap_or_else(ResourceFlow::new);
+                let metal_supplied_fraction = resource_flow.metal_supplied_fraction();
+                let energy_supplied_fraction = resource_flow.energy_supplied_fraction();
+                let stall_colour = egui::Color32::from_rgb(192, 48, 48);
+
+                let metal_progress_bar = if metal_supplied_fraction < 1.0 { metal_progress_bar.fill(stall_colour) } else { metal_progress_bar };
+                let energy_progress_bar = if energy_supplied_fraction < 1.0 { energy_progress_bar.fill(stall_colour) } else { energy_progress_bar };
+
+                ui.add(metal_progress_bar);
+                ui.add(energy_progress_bar);
+
+                if resource_flow.is_stalling() {
+                    ui.colored_label(stall_colour, format!(
+                        "Stalling - metal: {:.0}% of {:.1}/s, energy: {:.0}% of {:.1}/s",
+                        metal_supplied_fraction * 100.0, resource_flow.total_metal_demand(),
+                        energy_supplied_fraction * 100.0, resource_flow.total_energy_demand()
+                    ));
+                }
+
+        });
+
+    }
+
+    fn draw_selection_panel_ui(&mut self, ui_ctx: &egui::Context, model: &mut RymdGameModel, lockstep: &mut LockstepClient) {
+
+        // per blueprint: (count, sum of health fractions)
+        let mut selected_units_per_blueprint: BTreeMap<BlueprintID, (i32, f32)> = BTreeMap::new();
+
+        // selected entities which take part in the economy, along with their current priority
+        let mut selected_resource_consumers: Vec<(Entity, ResourcePriority)> = Vec::new();
+
+        for (e, (selectable, controller, blueprint_identity, health, consumer_or_constructor, priority)) in model.world.query::<(&Selectable, &Controller, &BlueprintIdentity, &Health, Option<Or<&Consumer, &Constructor>>, Option<&ResourcePriority>)>().iter() {
+
+            if selectable.is_selected == false || self.can_select_unit(controller) == false {
+                continue;
+            }
+
+            let entry = selected_units_per_blueprint.entry(blueprint_identity.blueprint_id).or_insert((0, 0.0));
+            entry.0 += 1;
+            entry.1 += health.current_health_fraction();
+
+            if consumer_or_constructor.is_some() {
+                selected_resource_consumers.push((e, priority.copied().unwrap_or(ResourcePriority::Normal)));
+            }
+
+        }
+
+        if selected_units_per_blueprint.is_empty() {
+            return;
+        }
+
+        // (passengers, capacity) and (drones, capacity) summed over the selected transports and carriers
+        let mut selected_transport_cargo: Option<(usize, i32)> = None;
+        let mut selected_hangar_drones: Option<(usize, i32)> = None;
+
+        for (e, (selectable, transport_or_hangar)) in model.world.query::<(&Selectable, Or<&Transport, &Hangar>)>().iter() {
+
+            if selectable.is_selected == false {
+                continue;
+            }
+
+            match transport_or_hangar {
+                Or::Left(transport) | Or::Both(transport, _) => {
+                    let cargo = selected_transport_cargo.get_or_insert((0, 0));
+                    cargo.0 += transport.passengers.len();
+                    cargo.1 += transport.capacity;
+                },
+                Or::Right(hangar) => {
+                    let drones = selected_hangar_drones.get_or_insert((0, 0));
+                    drones.0 += hangar.drones.len();
+                    drones.1 += hangar.capacity;
+                }
+            }
+
+        }
+
+        let total_number_of_selected_units: i32 = selected_units_per_blueprint.values().map(|(count, _)| count).sum();
+        let mut blueprint_to_narrow_selection_to = None;
+        let mut priority_to_set = None;
+
+        egui::Window::new("selection")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::LEFT_BOTTOM, (16.0, -16.0))
+            .show(ui_ctx, |ui| {
+
+                ui.label(format!("{} selected", total_number_of_selected_units));
+
+                if let Some((passengers, capacity)) = selected_transport_cargo {
+                    ui.label(format!("carrying: {}/{} (unload with {})", passengers, capacity, self.input.chord_name(InputAction::Unload)));
+                }
+
+                if let Some((drones, capacity)) = selected_hangar_drones {
+                    ui.label(format!("drones: {}/{}", drones, capacity));
+                }
+
+                if selected_resource_consumers.is_empty() == false {
+                    ui.horizontal(|ui| {
+
+                        ui.label("priority:");
+
+                        for priority in ResourcePriority::ALL {
+                            let is_current_priority = selected_resource_consumers.iter().all(|(_, p)| *p == priority);
+                            if ui.selectable_label(is_current_priority, priority.name()).clicked() {
+                                priority_to_set = Some(priority);
                             }
-                        } else {
-                            blueprints_in_construction.push((current_blueprint_id, 1));
                         }
 
+                    });
+                }
+
+                egui::Grid::new("selection_panel").show(ui, |ui| {
+
+                    for (&blueprint_id, &(count, health_fraction_sum)) in &selected_units_per_blueprint {
+
+                        let Some(blueprint) = model.blueprint_manager.get_blueprint(blueprint_id) else { continue; };
+                        let average_health_fraction = health_fraction_sum / count as f32;
+
+                        let icon_response = ui.add(egui::ImageButton::new(self.get_blueprint_icon(blueprint, 32.0)))
+                            .on_hover_text(format!("select only {}", blueprint.name));
+
+                        if icon_response.clicked() {
+                            blueprint_to_narrow_selection_to = Some(blueprint_id);
+                        }
+
+                        ui.label(format!("{}x {}", count, blueprint.name));
+                        ui.add(egui::ProgressBar::new(average_health_fraction).desired_width(96.0).text(format!("{:.0}%", average_health_fraction * 100.0)));
+                        ui.end_row();
+
                     }
 
-                }
+                });
+
+        });
+
+        if let Some(priority) = priority_to_set {
+            for (e, _) in selected_resource_consumers {
+                lockstep.set_resource_priority(e, priority);
             }
+        }
 
-            for (blueprint_id, blueprint_count) in blueprints_in_construction {
-                let Some(blueprint) = model.blueprint_manager.get_blueprint(blueprint_id) else { continue };
-                debug.draw_text(format!(" - {}x {}", blueprint_count, blueprint.name), TextPosition::BottomRight, WHITE);
+        if let Some(blueprint_id) = blueprint_to_narrow_selection_to {
+            self.perform_unselect_all_units_without_blueprint(&mut model.world, blueprint_id);
+        }
+
+    }
+
+    fn draw_command_card_ui(&mut self, ui_ctx: &egui::Context, model: &RymdGameModel) {
+
+        let available_blueprints = self.get_available_blueprints_from_current_selection(&model.world);
+
+        for &id in &available_blueprints {
+            if self.input.is_action_released(InputAction::Build(id)) {
+                self.construction.preview_blueprint(id);
             }
+        }
+
+        let current_metal = current_metal(self.game_player_id, &model.world);
+        let current_energy = current_energy(self.game_player_id, &model.world);
 
-            debug.draw_text("construction queue", TextPosition::BottomRight, WHITE);
+        let command_card_columns = 4;
+        let mut blueprint_to_preview = None;
 
+        egui::Window::new("build")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::RIGHT_BOTTOM, (-16.0, -16.0))
+            .show(ui_ctx, |ui| {
+
+                egui::Grid::new("command_card").spacing(egui::vec2(4.0, 4.0)).show(ui, |ui| {
+
+                    for (i, &id) in available_blueprints.iter().enumerate() {
+
+                        let Some(blueprint) = model.blueprint_manager.get_blueprint(id) else { continue; };
+
+                        // construction drains resources over time, so it can be started anyway, but it will take longer
+                        let is_affordable = current_metal >= blueprint.cost.metal && current_energy >= blueprint.cost.energy;
+                        let blueprint_tint = if is_affordable { egui::Color32::WHITE } else { egui::Color32::from_gray(96) };
+                        let blueprint_text_colour = if is_affordable { ui.visuals().text_color() } else { egui::Color32::from_gray(128) };
+
+                        let is_currently_previewed = self.construction.current_blueprint_id == Some(id);
+                        let blueprint_button = egui::ImageButton::new(self.get_blueprint_icon(blueprint, 48.0).tint(blueprint_tint)).selected(is_currently_previewed);
+
+                        ui.vertical_centered(|ui| {
+
+                            let blueprint_hover_text = format!(
+                                "{} ({})\n{:.0} metal, {:.0} energy{}",
+                                blueprint.name,
+                                self.input.chord_name(InputAction::Build(id)),
+                                blueprint.cost.metal,
+                                blueprint.cost.energy,
+                                if is_affordable { "" } else { "\nnot enough resources, will build slowly" }
+                            );
+
+                            if ui.add(blueprint_button).on_hover_text(blueprint_hover_text).clicked() {
+                                blueprint_to_preview = Some(id);
+                            }
+
+                            ui.label(egui::RichText::new(&blueprint.name).small().color(blueprint_text_colour));
+                            ui.label(egui::RichText::new(format!("{:.0} M / {:.0} E", blueprint.cost.metal, blueprint.cost.energy)).small().color(blueprint_text_colour));
+
+                        });
+
+                        if (i + 1) % command_card_columns == 0 {
+                            ui.end_row();
+                        }
+
+                    }
+
+                });
+
+        });
+
+        if let Some(id) = blueprint_to_preview {
+            self.construction.preview_blueprint(id);
         }
 
     }
 
-    fn draw_game_ui(&mut self, ui_ctx: &egui::Context, model: &mut RymdGameModel, ctx: &mut GameContext) {
+    /// Splits the construction queue of an entity into runs of the same blueprint, as (blueprint, index of the first order, number of orders).
+    fn get_construction_queue_runs(world: &World, orderable: &Orderable) -> Vec<(BlueprintID, usize, usize)> {
 
-        egui::Window::new("")
-            .title_bar(false)
-            .collapsible(false)
+        let mut construction_queue_runs: Vec<(BlueprintID, usize, usize)> = Vec::new();
+
+        for (index, order) in orderable.orders(GameOrderType::Construct).iter().enumerate() {
+
+            let GameOrder::Construct(construct_order) = order else { continue; };
+
+            let current_blueprint_id = if let Some(blueprint_id) = construct_order.blueprint_id {
+                Some(blueprint_id)
+            } else if let Some(entity) = construct_order.entity() && let Ok(blueprint_identity) = world.get::<&BlueprintIdentity>(entity) {
+                Some(blueprint_identity.blueprint_id)
+            } else {
+                None
+            };
+
+            let Some(current_blueprint_id) = current_blueprint_id else { continue; };
+
+            if let Some((last_id, last_start, last_count)) = construction_queue_runs.last_mut() && *last_id == current_blueprint_id && *last_start + *last_count == index {
+                *last_count += 1;
+            } else {
+                construction_queue_runs.push((current_blueprint_id, index, 1));
+            }
+
+        }
+
+        construction_queue_runs
+
+    }
+
+    fn draw_construction_queue_ui(&mut self, ui_ctx: &egui::Context, model: &RymdGameModel, lockstep: &mut LockstepClient) {
+
+        let maximum_number_of_queues_shown = 4;
+        let mut construction_queues = Vec::new();
+
+        for (e, (constructor, orderable, selectable, controller, factory)) in model.world.query::<(&Constructor, &Orderable, &Selectable, &Controller, Option<&Factory>)>().iter() {
+
+            // factories are always shown, so that repeat and rally points can be managed while nothing is queued
+            if selectable.is_selected == false || (orderable.is_queue_empty(GameOrderType::Construct) && factory.is_none()) || self.can_select_unit(controller) == false {
+                continue;
+            }
+
+            let entity_name = model.world.get::<&BlueprintIdentity>(e).ok()
+                .and_then(|identity| model.blueprint_manager.get_blueprint(identity.blueprint_id))
+                .map(|blueprint| blueprint.name.clone())
+                .unwrap_or_else(|| "constructor".to_string());
+
+            let factory_state = factory.map(|factory| (factory.is_repeating, factory.rally_points.len()));
+            let order_ids: Vec<OrderID> = orderable.order_ids(GameOrderType::Construct).iter().copied().collect();
+            construction_queues.push((e, entity_name, factory_state, Self::get_construction_queue_runs(&model.world, &orderable), order_ids));
+
+        }
+
+        if construction_queues.is_empty() {
+            return;
+        }
+
+        // keep the order stable between frames, otherwise the queues jump around
+        construction_queues.sort_by_key(|(e, _, _, _, _)| *e);
+
+        let should_cancel_whole_run = self.input.is_action_down(InputAction::QueueModifier);
+        let mut cancelled_orders: Vec<(Entity, OrderID)> = Vec::new();
+        let mut moved_orders: Vec<(Entity, usize, usize)> = Vec::new();
+        let mut repeat_toggles: Vec<(Entity, bool)> = Vec::new();
+        let mut cleared_rally_points: Vec<Entity> = Vec::new();
+
+        egui::Window::new("construction queue")
+            .collapsible(true)
             .resizable(false)
-            .anchor(Align2::CENTER_TOP, (0.0, 16.0))
-            .frame(egui::Frame::default())
+            .anchor(Align2::RIGHT_BOTTOM, (-16.0, -200.0))
             .show(ui_ctx, |ui| {
 
-                let current_metal = current_metal(self.game_player_id, &model.world);
-                let maximum_metal = max_metal(self.game_player_id, &model.world);
-                let current_metal_income = current_metal_income(self.game_player_id, &model.world);
-                let current_metal_excess = 0;
+                for (e, entity_name, factory_state, construction_queue_runs, order_ids) in construction_queues.iter().take(maximum_number_of_queues_shown) {
 
-                let current_energy = current_energy(self.game_player_id, &model.world);
-                let maximum_energy = max_energy(self.game_player_id, &model.world);
-                let current_energy_income = current_energy_income(self.game_player_id, &model.world);
-                let current_energy_excess = 0;
+                    ui.label(entity_name);
 
-                let current_metal_proportion = current_metal / maximum_metal;
-                let current_energy_proportion = current_energy / maximum_energy;
+                    if let Some((is_repeating, number_of_rally_points)) = *factory_state {
+                        ui.horizontal(|ui| {
 
-                let metal_progress_bar = egui::ProgressBar::new(current_metal_proportion).text(format!("Metal = {:.0} / {:.0} ({:.0})", current_metal, maximum_metal, current_metal_income));
-                let energy_progress_bar = egui::ProgressBar::new(current_energy_proportion).text(format!("Energy = {:.0} / {:.0} ({:.0})", current_energy, maximum_energy, current_energy_income));
+                            let mut should_repeat = is_repeating;
+                            if ui.checkbox(&mut should_repeat, "repeat").changed() {
+                                repeat_toggles.push((*e, should_repeat));
+                            }
 
-                ui.add(metal_progress_bar);
-                ui.add(energy_progress_bar);
+                            if ui.add_enabled(number_of_rally_points > 0, egui::Button::new("clear rally").small()).clicked() {
+                                cleared_rally_points.push(*e);
+                            }
+
+                        });
+                    }
+
+                    egui::Grid::new(("construction_queue", *e)).show(ui, |ui| {
+
+                        for (run_index, &(blueprint_id, run_start, run_count)) in construction_queue_runs.iter().enumerate() {
+
+                            let Some(blueprint) = model.blueprint_manager.get_blueprint(blueprint_id) else { continue; };
+
+                            // the order at the front is being built right now, so it can be cancelled but never moved
+                            let previous_run = if run_index > 0 { construction_queue_runs.get(run_index - 1) } else { None };
+                            let next_run = construction_queue_runs.get(run_index + 1);
+                            let can_move_up = run_start > 0 && previous_run.is_some_and(|&(_, previous_start, _)| previous_start > 0);
+                            let can_move_down = run_start > 0 && next_run.is_some();
+
+                            ui.add(self.get_blueprint_icon(blueprint, 24.0));
+                            ui.label(format!("{}x {}", run_count, blueprint.name));
+
+                            if ui.add_enabled(can_move_up, egui::Button::new("^").small()).clicked() && let Some(&(_, previous_start, _)) = previous_run {
+                                for k in 0..run_count {
+                                    moved_orders.push((*e, run_start + k, previous_start + k));
+                                }
+                            }
+
+                            if ui.add_enabled(can_move_down, egui::Button::new("v").small()).clicked() && let Some(&(_, next_start, next_count)) = next_run {
+                                // moving this run down is the same as moving the next run up in front of it
+                                for k in 0..next_count {
+                                    moved_orders.push((*e, next_start + k, run_start + k));
+                                }
+                            }
+
+                            let cancel_response = ui.add(egui::Button::new("x").small())
+                                .on_hover_text(format!("cancel one, hold {} to cancel all", self.input.chord_name(InputAction::QueueModifier)));
+
+                            if cancel_response.clicked() {
+                                let number_to_cancel = if should_cancel_whole_run { run_count } else { 1 };
+                                // cancelled by id rather than position, as any orders moved this frame will have shifted the positions by the time the cancel is applied
+                                for k in (run_count - number_to_cancel..run_count).rev() {
+                                    if let Some(&order_id) = order_ids.get(run_start + k) {
+                                        cancelled_orders.push((*e, order_id));
+                                    }
+                                }
+                            }
+
+                            ui.end_row();
+
+                        }
+
+                    });
+
+                }
+
+                if construction_queues.len() > maximum_number_of_queues_shown {
+                    ui.label(format!("... and {} more", construction_queues.len() - maximum_number_of_queues_shown));
+                }
 
         });
 
+        for (e, from, to) in moved_orders {
+            lockstep.move_construction_order(e, from as i32, to as i32);
+        }
+
+        for (e, order_id) in cancelled_orders {
+            lockstep.cancel_construction_order(e, order_id);
+        }
+
+        for (e, is_repeating) in repeat_toggles {
+            lockstep.set_production_repeat(e, is_repeating);
+        }
+
+        for e in cleared_rally_points {
+            lockstep.clear_rally_points(e);
+        }
+
+    }
+
+    fn draw_game_ui(&mut self, ui_ctx: &egui::Context, model: &mut RymdGameModel, chat: &mut RymdGameChat, ctx: &mut GameContext) {
+
+        self.is_pointer_over_ui = ui_ctx.is_pointer_over_area() || ui_ctx.wants_pointer_input();
+        self.input.set_typing(ui_ctx.wants_keyboard_input());
+
+        self.draw_resource_bars_ui(ui_ctx, model);
+
+        let clicked_alert_position = self.alerts.draw_alerts_ui(ui_ctx);
+        let clicked_minimap_position = self.minimap.draw_ui(ui_ctx, model, &self.camera, self.game_player_id, self.alerts.pings(), &self.markers);
+        let allies = model.allies_of(self.game_player_id);
+        let clicked_chat_position = self.draw_chat_ui(ui_ctx, chat, ctx.lockstep_mut(), &allies);
+        self.draw_game_speed_ui(ui_ctx, model, ctx.lockstep_mut());
+
+        if let Some(world_position) = clicked_alert_position.or(clicked_minimap_position).or(clicked_chat_position) {
+            self.camera.move_camera_to_position(world_position);
+        }
+
+        self.draw_selection_panel_ui(ui_ctx, model, ctx.lockstep_mut());
+
         if self.current_selection_has_constructor_unit(&model.world) {
-            self.draw_text_construction_ui(model, ctx.debug_text());
-            self.draw_text_construction_queue_ui(model, ctx.debug_text());
+            self.draw_command_card_ui(ui_ctx, model);
+            self.draw_construction_queue_ui(ui_ctx, model, ctx.lockstep_mut());
         }
 
     }
 
+    fn draw_game_speed_ui(&self, ui_ctx: &egui::Context, model: &RymdGameModel, lockstep: &mut LockstepClient) {
+
+        let local_player_id = lockstep.peer_id();
+        let is_voting = model.speed.voters.len() > 1;
+        let is_host = model.speed.is_host(local_player_id);
+
+        egui::Window::new("game speed")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::RIGHT_TOP, (-(MINIMAP_SIZE + 48.0), 16.0))
+            .show(ui_ctx, |ui| {
+
+                ui.horizontal(|ui| {
+
+                    ui.label(if is_voting && is_host == false { "speed (vote):" } else { "speed:" });
+
+                    for speed in GameSpeed::ALL {
+
+                        let is_current_speed = model.speed.speed == speed;
+                        let number_of_votes = model.speed.votes.values().filter(|&&vote| vote == speed).count();
+                        let speed_text = if number_of_votes > 0 { format!("{} ({}/{})", speed.name(), number_of_votes, model.speed.voters.len()) } else { speed.name().to_string() };
+
+                        if ui.selectable_label(is_current_speed, speed_text).clicked() && is_current_speed == false {
+                            Self::request_game_speed(lockstep, speed, is_host);
+                        }
+
+                    }
+
+                });
+
+                ui.horizontal(|ui| {
+
+                    let remaining_pause_seconds = model.speed.remaining_pause_seconds(local_player_id);
+
+                    if model.speed.is_paused() {
+                        if ui.button("resume").clicked() {
+                            lockstep.set_game_paused(false);
+                        }
+                    } else if ui.add_enabled(remaining_pause_seconds > 0.0, egui::Button::new("pause")).clicked() {
+                        lockstep.set_game_paused(true);
+                    }
+
+                    ui.label(format!("pause time left: {:.0}s", remaining_pause_seconds));
+
+                });
+
+                if let Some(paused_by) = model.speed.paused_by {
+                    let paused_by_text = if paused_by == local_player_id { "paused by you".to_string() } else { format!("paused by peer {}", paused_by) };
+                    ui.colored_label(egui::Color32::from_rgb(255, 224, 64), egui::RichText::new(format!("{} ({:.0}s left)", paused_by_text, model.speed.remaining_pause_seconds(paused_by))).strong());
+                }
+
+        });
+
+    }
+
+    /// Draws the chat log along with the message being typed, returns the position of the ping or marker clicked on in the log, if any.
+    fn draw_chat_ui(&mut self, ui_ctx: &egui::Context, chat: &mut RymdGameChat, lockstep: &mut LockstepClient, allies: &[PlayerID]) -> Option<Vec2> {
+
+        let should_focus_chat = self.input.is_action_pressed(InputAction::FocusChat);
+        let mut clicked_entry_position = None;
+
+        egui::Window::new("chat")
+            .collapsible(true)
+            .resizable(false)
+            .default_width(320.0)
+            .anchor(Align2::LEFT_CENTER, (16.0, 0.0))
+            .show(ui_ctx, |ui| {
+
+                egui::ScrollArea::vertical()
+                    .max_height(160.0)
+                    .stick_to_bottom(true)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+
+                        for entry in &chat.entries {
+
+                            let entry_colour = if entry.channel == ChatChannel::Team { egui::Color32::from_rgb(96, 224, 96) } else { egui::Color32::LIGHT_GRAY };
+                            let entry_label = egui::Label::new(egui::RichText::new(entry.to_display_text()).color(entry_colour));
+
+                            if let Some(entry_position) = entry.position {
+                                if ui.add(entry_label.sense(egui::Sense::click())).on_hover_text("click to jump to it").clicked() {
+                                    clicked_entry_position = Some(entry_position);
+                                }
+                            } else {
+                                ui.add(entry_label);
+                            }
+
+                        }
+
+                });
+
+                ui.horizontal(|ui| {
+
+                    for channel in [ChatChannel::All, ChatChannel::Team] {
+                        if ui.selectable_label(chat.current_channel == channel, channel.name()).clicked() {
+                            chat.current_channel = channel;
+                        }
+                    }
+
+                    let message_response = ui.text_edit_singleline(&mut chat.current_message);
+
+                    if should_focus_chat {
+                        message_response.request_focus();
+                    }
+
+                    if message_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        chat.send_current_message(lockstep, allies);
+                    }
+
+                });
+
+                ui.label(format!("hold {} and click to ping, or drag to draw a marker for your allies", self.input.chord_name(InputAction::PlaceMarker)));
+
+        });
+
+        clicked_entry_position
+
+    }
+
     fn draw_health_labels(&self, world: &World) {
 
-        for (e, (transform, health)) in world.query::<(&Transform, &Health)>().iter() {
+        for (e, (transform, health)) in world.query::<Without<(&Transform, &Health), &Passenger>>().iter() {
             let health_label_position = transform.world_position + vec2(0.0, -32.0);
             draw_text_centered(&format!("{}/{}", health.current_health(), health.full_health()), health_label_position.x, health_label_position.y, 24.0, WHITE);
         }
@@ -2129,7 +2938,11 @@ impl RymdGameView {
 
     pub fn draw(&mut self, model: &mut RymdGameModel, ctx: &mut GameContext, dt: f32) {
 
-        self.camera.tick(dt);
+        self.camera.set_settings(self.input.camera_settings());
+        self.camera.tick(dt, self.is_pointer_over_ui);
+        self.update_camera_follow(&model.world);
+        self.audio.tick();
+        self.markers.tick();
 
         self.update_constructor_beams(model);
         self.update_extractor_beams(model);
@@ -2140,6 +2953,9 @@ impl RymdGameView {
 
         self.draw_pending_orders(model);
         self.draw_orders(model);
+        self.draw_rally_points(model);
+        self.draw_capture_points(model);
+        self.markers.draw(&self.camera);
 
         self.draw_selection();
         self.draw_selectables(&mut model.world);
@@ -2161,7 +2977,7 @@ impl RymdGameView {
 
         self.draw_build_queue(model);
 
-        self.construction.tick_and_draw(model, &self.camera, &self.resources, ctx.lockstep_mut());
+        self.construction.tick_and_draw(model, &self.camera, &self.resources, &self.input, ctx.lockstep_mut());
 
         self.draw_particles(&mut model.world);
         self.draw_beam_weapons(&model.world);
@@ -2179,10 +2995,187 @@ impl RymdGameView {
 
     }
 
-    pub fn draw_ui(&mut self, ui_ctx: &egui::Context, model: &mut RymdGameModel, ctx: &mut GameContext) {
+    /// Cheats go through the lockstep like any other order so every client applies them on the same tick, only switching player is local.
+    fn execute_console_command(&mut self, line: &str, model: &mut RymdGameModel, lockstep: &mut LockstepClient) {
+
+        let console_command = match parse_console_command(line, &model.blueprint_manager) {
+            Ok(console_command) => console_command,
+            Err(error) => {
+                self.console.print(error);
+                return;
+            }
+        };
+
+        let is_cheat = matches!(console_command, ConsoleCommand::Help | ConsoleCommand::Blueprints) == false;
+        if is_cheat && model.are_cheats_enabled == false {
+            self.console.print("cheats are not enabled for this match!");
+            return;
+        }
+
+        match console_command {
+            ConsoleCommand::Help => self.console.print_help(),
+            ConsoleCommand::Blueprints => {
+                for blueprint in model.blueprint_manager.blueprints() {
+                    self.console.print(format!("{} - {}", blueprint.id, blueprint.name.to_lowercase().replace(' ', "_")));
+                }
+            },
+            ConsoleCommand::Spawn { blueprint_id, count, owner } => {
+                let owner = owner.unwrap_or(self.game_player_id);
+                let spawn_position = self.camera.world_position();
+                lockstep.send_cheat(CheatCommand::Spawn { owner, blueprint_id, count, x: spawn_position.x, y: spawn_position.y });
+                self.console.print(format!("spawning {} of blueprint {} for player {}", count, blueprint_id, owner));
+            },
+            ConsoleCommand::Resources { metal, energy, owner } => {
+                let owner = owner.unwrap_or(self.game_player_id);
+                lockstep.send_cheat(CheatCommand::GrantResources { owner, metal, energy });
+                self.console.print(format!("granting {} metal and {} energy to player {}", metal, energy, owner));
+            },
+            ConsoleCommand::Kill | ConsoleCommand::Heal => {
+
+                let selected_entities: Vec<EntityID> = self.get_all_currently_selected_units(&mut model.world).iter().map(|e| e.to_bits().into()).collect();
+                let number_of_selected_entities = selected_entities.len();
+
+                if console_command == ConsoleCommand::Kill {
+                    lockstep.send_cheat(CheatCommand::Kill { entities: selected_entities });
+                    self.console.print(format!("killing {} selected", number_of_selected_entities));
+                } else {
+                    lockstep.send_cheat(CheatCommand::Heal { entities: selected_entities });
+                    self.console.print(format!("healing {} selected", number_of_selected_entities));
+                }
+
+            },
+            ConsoleCommand::SwitchPlayer { player: Some(player_id) } => {
+                if self.game_parameters.players.iter().any(|player| player.id == player_id) {
+                    self.perform_unselect_all(&mut model.world);
+                    self.game_player_id = player_id;
+                    self.console.print(format!("now controlling player {}", player_id));
+                } else {
+                    self.console.print(format!("no player with id: {} in this match", player_id));
+                }
+            },
+            ConsoleCommand::SwitchPlayer { player: None } => {
+                self.switch_player_id_to_next(&mut model.world);
+                self.console.print(format!("now controlling player {}", self.game_player_id));
+            }
+        }
+
+    }
+
+    fn draw_mission_ui(&mut self, ui_ctx: &egui::Context, model: &RymdGameModel) {
+
+        let Some(mission) = &model.mission else { return; };
+
+        // how long messages from the mission script stay on screen
+        let mission_message_duration_ticks = 60 * 10;
+
+        egui::Window::new("objectives")
+            .collapsible(true)
+            .resizable(false)
+            .anchor(Align2::RIGHT_TOP, (-16.0, MINIMAP_SIZE + 48.0))
+            .show(ui_ctx, |ui| {
+
+                for objective in &mission.objectives {
+                    let objective_marker = match objective.state {
+                        MissionObjectiveState::Active => "[ ]",
+                        MissionObjectiveState::Completed => "[x]",
+                        MissionObjectiveState::Failed => "[-]"
+                    };
+                    ui.label(format!("{} {}", objective_marker, objective.text));
+                }
+
+        });
+
+        let recent_mission_messages: Vec<_> = mission.messages.iter()
+            .filter(|m| model.current_tick < m.tick + mission_message_duration_ticks)
+            .collect();
+
+        if recent_mission_messages.is_empty() == false || mission.result != MissionResult::InProgress {
+
+            egui::Window::new("mission")
+                .title_bar(false)
+                .collapsible(false)
+                .resizable(false)
+                .anchor(Align2::CENTER_BOTTOM, (0.0, -128.0))
+                .show(ui_ctx, |ui| {
+
+                    for message in recent_mission_messages {
+                        ui.label(&message.text);
+                    }
+
+                    match mission.result {
+                        MissionResult::InProgress => (),
+                        MissionResult::Victory => { ui.heading("victory!"); },
+                        MissionResult::Defeat => { ui.heading("defeat!"); }
+                    }
+
+            });
+
+        }
+
+    }
+
+    fn draw_capture_points_ui(&mut self, ui_ctx: &egui::Context, model: &RymdGameModel) {
+
+        let Some(capture_points) = &model.capture_points else { return; };
+        let local_team_id = capture_points.get_team_of_player(self.game_player_id);
+
+        egui::Window::new("score")
+            .collapsible(true)
+            .resizable(false)
+            .anchor(Align2::RIGHT_TOP, (-16.0, MINIMAP_SIZE + 48.0))
+            .show(ui_ctx, |ui| {
+
+                ui.label(format!("score limit: {}", capture_points.score_limit as i32));
+
+                if capture_points.is_sudden_death(model.current_tick) {
+                    ui.label("sudden death, the next team to pull ahead wins!");
+                } else if let Some(remaining_ticks) = capture_points.remaining_ticks(model.current_tick) {
+                    let remaining_seconds = (remaining_ticks as f32 * RymdGameModel::TIME_STEP) as i32;
+                    ui.label(format!("time left: {}:{:02}", remaining_seconds / 60, remaining_seconds % 60));
+                }
 
-        self.draw_game_ui(ui_ctx, model, ctx);
+                ui.separator();
+
+                for (&team_id, &score) in &capture_points.scores {
+                    let number_of_points_held = capture_points.points.iter().filter(|p| p.owner == Some(team_id)).count();
+                    let team_suffix = if local_team_id == Some(team_id) { " (you)" } else { "" };
+                    ui.label(format!("team {}{} - {} ({} held)", team_id, team_suffix, score as i32, number_of_points_held));
+                }
+
+                if let Some(winner) = capture_points.winner {
+                    ui.separator();
+                    if local_team_id == Some(winner) {
+                        ui.heading("victory!");
+                    } else {
+                        ui.heading("defeat!");
+                    }
+                }
+
+        });
+
+    }
+
+    pub fn draw_ui(&mut self, ui_ctx: &egui::Context, model: &mut RymdGameModel, chat: &mut RymdGameChat, ctx: &mut GameContext) {
+
+        if self.input.is_action_pressed_while_typing(InputAction::ToggleConsole) {
+            self.console.toggle();
+        }
+
+        if let Some(console_line) = self.console.draw_ui(ui_ctx, model.are_cheats_enabled) {
+            self.execute_console_command(&console_line, model, ctx.lockstep_mut());
+        }
+
+        self.draw_game_ui(ui_ctx, model, chat, ctx);
+        self.draw_mission_ui(ui_ctx, model);
+        self.draw_capture_points_ui(ui_ctx, model);
         self.draw_debug_ui(model, ctx);
+        self.input.draw_rebinding_ui(ui_ctx, &model.blueprint_manager);
+
+        if self.input.is_action_pressed(InputAction::ToggleAudioSettings) {
+            self.audio.toggle_settings_window();
+        }
+
+        self.audio.draw_settings_ui(ui_ctx);
 
     }
 
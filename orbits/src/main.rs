@@ -2,9 +2,8 @@ use core::num;
 use std::f32::consts::PI;
 
 use macroquad::prelude::*;
-use utility::{GameCamera, create_camera_from_game_camera, AsAngle, DebugText, draw_circle_lines_3d, draw_with_transformation, TextPosition, WithAlpha, WithY};
+use utility::{GameCamera, GameCameraController, GameCameraMode, create_camera_from_game_camera, AsAngle, DebugText, draw_circle_lines_3d, draw_with_transformation, TextPosition, WithAlpha, WithY};
 
-const WORLD_UP: Vec3 = Vec3::Y;
 const PLANET_RADIUS: f32 = 4.0;
 const MOON_SIZE: f32 = 0.5;
 
@@ -52,6 +51,7 @@ fn create_planet(position: Vec3, min_number_of_moons: i32, max_number_of_moons:
 
 struct Game {
     camera: GameCamera,
+    camera_controller: GameCameraController,
     debug: DebugText,
     planets: Vec<Planet>
 }
@@ -61,6 +61,7 @@ impl Game {
     fn new() -> Game {
         Game {
             camera: GameCamera::new(),
+            camera_controller: GameCameraController::new(GameCameraMode::Fly),
             debug: DebugText::new(),
             planets: Vec::new()
         }
@@ -194,51 +195,6 @@ fn infer_orbital_plane(position: Vec3) -> Vec3 {
 
 }
 
-fn handle_camera_input(active: &mut GameCamera, dt: f32) {
-
-    let is_forwards_pressed = is_key_down(KeyCode::W);
-    let is_backwards_pressed = is_key_down(KeyCode::S);
-    let is_left_pressed = is_key_down(KeyCode::A);
-    let is_right_pressed = is_key_down(KeyCode::D);
-
-    let is_up_pressed = is_key_down(KeyCode::Space);
-    let is_down_pressed = is_key_down(KeyCode::LeftControl);
-
-    let mut camera_movement_delta = Vec3::ZERO;
-
-    let forward_in_plane = vec3(active.forward().x, 0.0, active.forward().z);
-    let left_in_plane = vec3(active.left().x, 0.0, active.left().z);
-    let up_in_plane = WORLD_UP;
-
-    if is_forwards_pressed {
-        camera_movement_delta += forward_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_backwards_pressed {
-        camera_movement_delta -= forward_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_left_pressed {
-        camera_movement_delta += left_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_right_pressed {
-        camera_movement_delta -= left_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_up_pressed {
-        camera_movement_delta += up_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_down_pressed {
-        camera_movement_delta -= up_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    active.position += camera_movement_delta;
-    active.target += camera_movement_delta;
-
-}
-
 
 #[macroquad::main("orbits")]
 async fn main() {
@@ -250,6 +206,7 @@ async fn main() {
         if is_key_released(KeyCode::R) {
             let old_game = std::mem::replace(&mut game, create_game());
             game.camera = old_game.camera;
+            game.camera_controller = old_game.camera_controller;
         }
 
         let dt = get_frame_time();
@@ -258,7 +215,7 @@ async fn main() {
         game.debug.new_frame();
         clear_background(WHITE);
 
-        handle_camera_input(&mut game.camera, dt);
+        game.camera_controller.tick(&mut game.camera, dt);
 
         set_camera(&create_camera_from_game_camera(&game.camera));
         draw_game(&game);
@@ -4,7 +4,7 @@ use std::sync::Arc;
 
 use hecs::{World, Bundle};
 use macroquad::prelude::*;
-use utility::{GameCamera, DebugText, create_camera_from_game_camera, TextPosition, intersect_ray_with_plane, draw_cube_ex, draw_cube_wires_ex, rotate_relative_to_origin, AdjustHue, draw_with_transformation, AsAngle, RotatedBy, normalize, is_point_inside_screen, is_point_inside_rect, draw_circle_lines_3d, FromRotationArcAround, BenchmarkWithDebugText, benchmark_execution};
+use utility::{GameCamera, GameCameraController, GameCameraMode, DebugText, create_camera_from_game_camera, TextPosition, intersect_ray_with_plane, draw_cube_ex, draw_cube_wires_ex, AdjustHue, draw_with_transformation, AsAngle, RotatedBy, normalize, is_point_inside_screen, is_point_inside_rect, draw_circle_lines_3d, FromRotationArcAround, BenchmarkWithDebugText, benchmark_execution};
 use rhai::{Engine, EvalAltResult, AST, NativeCallContext, Scope, OptimizationLevel};
 
 const WORLD_UP: Vec3 = Vec3::Y;
@@ -112,9 +112,25 @@ impl Character {
     }
 }
 
+/// The left mouse button selects and the right gives orders, so looking around is on the middle mouse button and the arrow keys move as well.
+fn create_camera_controller() -> GameCameraController {
+
+    let mut camera_controller = GameCameraController::new(GameCameraMode::Fly);
+    camera_controller.world_up = WORLD_UP;
+    camera_controller.bindings.move_forward.push(KeyCode::Up);
+    camera_controller.bindings.move_backward.push(KeyCode::Down);
+    camera_controller.bindings.move_left.push(KeyCode::Left);
+    camera_controller.bindings.move_right.push(KeyCode::Right);
+    camera_controller.bindings.rotate_button = Some(MouseButton::Middle);
+
+    camera_controller
+
+}
+
 struct Game {
 
     camera: GameCamera,
+    camera_controller: GameCameraController,
     debug_text: DebugText,
     engine: Engine,
     world: World,
@@ -129,6 +145,7 @@ impl Game {
     pub fn new() -> Game {
 
         let camera = GameCamera::new();
+        let camera_controller = create_camera_controller();
         let debug_text = DebugText::new();
 
         let mut engine =  Engine::new();
@@ -142,6 +159,7 @@ impl Game {
 
         Game {
             camera,
+            camera_controller,
             debug_text,
             engine,
             world,
@@ -153,70 +171,6 @@ impl Game {
 
 }
 
-fn handle_camera_input(active: &mut GameCamera, dt: f32) {
-
-    let is_turn_left_pressed = is_key_down(KeyCode::Q);
-    let is_turn_right_pressed = is_key_down(KeyCode::E);
-
-    let is_forwards_pressed = is_key_down(KeyCode::W) || is_key_down(KeyCode::Up);
-    let is_backwards_pressed = is_key_down(KeyCode::S) || is_key_down(KeyCode::Down);
-    let is_left_pressed = is_key_down(KeyCode::A) || is_key_down(KeyCode::Left);
-    let is_right_pressed = is_key_down(KeyCode::D) || is_key_down(KeyCode::Right);
-
-    let is_up_pressed = is_key_down(KeyCode::Space);
-    let is_down_pressed = is_key_down(KeyCode::LeftControl);
-
-    let mut camera_movement_delta = Vec3::ZERO;
-    let mut camera_rotation_delta = Quat::IDENTITY;
-
-    let forward_in_plane = vec3(active.forward().x, 0.0, active.forward().z);
-    let left_in_plane = vec3(active.left().x, 0.0, active.left().z);
-    let up_in_plane = WORLD_UP;
-
-    if is_forwards_pressed {
-        camera_movement_delta += forward_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_backwards_pressed {
-        camera_movement_delta -= forward_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_left_pressed {
-        camera_movement_delta += left_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_right_pressed {
-        camera_movement_delta -= left_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_up_pressed {
-        camera_movement_delta += up_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_down_pressed {
-        camera_movement_delta -= up_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_turn_left_pressed {
-        let current_direction = (active.target - active.position).normalize();
-        let new_desired_direction = Quat::from_rotation_y(active.parameters.rotation_speed * dt) * current_direction;
-        camera_rotation_delta = Quat::from_rotation_arc(current_direction, new_desired_direction);
-    }
-
-    if is_turn_right_pressed {
-        let current_direction = (active.target - active.position).normalize();
-        let new_desired_direction = Quat::from_rotation_y(-(active.parameters.rotation_speed * dt)) * current_direction;
-        camera_rotation_delta = Quat::from_rotation_arc(current_direction, new_desired_direction);
-    }
-
-    active.position += camera_movement_delta;
-    active.target += camera_movement_delta;
-    
-    // #NOTE: position and target can be flipped here for basically orbit camera
-    active.target = rotate_relative_to_origin(active.position, active.target, camera_rotation_delta);
-
-}
-
 fn draw_debug_text(game: &mut Game) {
 
     let dt = get_frame_time() * 1000.0;
@@ -554,7 +508,7 @@ fn update_scene(game: &mut Game, dt: f32) {
 
 fn update_camera(game: &mut Game, dt: f32) {
 
-    handle_camera_input(&mut game.camera, dt);
+    game.camera_controller.tick(&mut game.camera, dt);
 
 }
 
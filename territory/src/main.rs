@@ -4,7 +4,7 @@ use noise::{
     utils::{PlaneMapBuilder, NoiseMapBuilder}, Add, Perlin, ScaleBias, Turbulence
 };
 
-use utility::{exponential_decay, screen_dimensions, AdjustHue, Camera2DExt, DebugText, TextPosition, WithAlpha};
+use utility::{AdjustHue, DebugText, GameCamera2D, GameCamera2DBindings, GameCamera2DParameters, TextPosition, WithAlpha};
 
 const TILE_PADDING: i32 = 2;
 const REAL_TILE_SIZE: i32 = 32;
@@ -520,7 +520,7 @@ fn is_tile_in_view(x: f32, y: f32) -> bool {
     
 }
 
-fn draw_height_field_layer(active: &GameCamera, map: &Heightmap, atlas: &Texture2D, isovalue: u8) {
+fn draw_height_field_layer(active: &GameCamera2D, map: &Heightmap, atlas: &Texture2D, isovalue: u8) {
 
     let height_field_offset = vec2(
         REAL_TILE_SIZE as f32 / 2.0,
@@ -529,7 +529,7 @@ fn draw_height_field_layer(active: &GameCamera, map: &Heightmap, atlas: &Texture
 
     for x in 0..map.width() {
 
-        let view_pos = active.camera.world_to_screen(
+        let view_pos = active.world_to_screen(
             height_field_offset + vec2(REAL_TILE_SIZE as f32 * x as f32, 0.0)
         );
 
@@ -578,7 +578,7 @@ fn draw_height_field_layer(active: &GameCamera, map: &Heightmap, atlas: &Texture
 
 }
 
-fn draw_height_field(active: &GameCamera, map: &Heightmap, atlas: &Texture2D, render_debug_text: bool) {
+fn draw_height_field(active: &GameCamera2D, map: &Heightmap, atlas: &Texture2D, render_debug_text: bool) {
 
     for &isolevel in &map.isolevels {
         draw_height_field_layer(active, map, atlas, isolevel);
@@ -606,128 +606,14 @@ fn draw_height_field(active: &GameCamera, map: &Heightmap, atlas: &Texture2D, re
 
 }
 
-struct GameCamera {
-    size: Vec2,
-    camera_zoom_velocity: f32,
-    camera_target_velocity: Vec2,
-    camera_zoom: f32,
-    camera: Camera2D,
-}
-
-impl GameCamera {
-
-    pub fn new(size: Vec2) -> GameCamera {
-
-        let camera = Camera2D::from_display_rect_fixed(
-            Rect { x: 0.0, y: 0.0, w: size.x, h: size.y }
-        );
-
-        GameCamera {
-            size: size,
-            camera: camera,
-            camera_zoom: 1.0,
-            camera_zoom_velocity: 0.0,
-            camera_target_velocity: Vec2::ZERO
-        }
-
-    }
-
-}
-
-fn handle_camera_input(active: &mut GameCamera, last_mouse_position: Vec2, dt: f32) -> bool {
-
-    handle_camera_movement(active, dt);
-    let zoom_changed = handle_camera_zoom(active, dt);
-    handle_camera_panning(active, last_mouse_position, dt);
-
-    zoom_changed
-
-}
-
-fn handle_camera_movement(active: &mut GameCamera, dt: f32) {
-
-    let camera_speed = 128.0 * active.camera_zoom;
-
-    let is_up_pressed = is_key_down(KeyCode::Up) || is_key_down(KeyCode::W);
-    let is_down_pressed = is_key_down(KeyCode::Down) || is_key_down(KeyCode::S);
-    let is_left_pressed = is_key_down(KeyCode::Left) || is_key_down(KeyCode::A);
-    let is_right_pressed = is_key_down(KeyCode::Right) || is_key_down(KeyCode::D);
-
-    let mut camera_delta = Vec2::ZERO;
-
-    if is_up_pressed {
-        camera_delta += vec2(0.0, -1.0);
-    }
-
-    if is_down_pressed {
-        camera_delta += vec2(0.0, 1.0);
-    }
-
-    if is_left_pressed {
-        camera_delta += vec2(-1.0, 0.0);
-    }
-
-    if is_right_pressed {
-        camera_delta += vec2(1.0, 0.0);
-    }
-
-    active.camera_target_velocity += camera_delta * camera_speed * dt;
-    active.camera.target += active.camera_target_velocity;
-
-    let clamped_target_velocity = exponential_decay(active.camera_target_velocity.length(), 25.0, dt);
-    active.camera_target_velocity = active.camera_target_velocity.clamp_length_max(clamped_target_velocity);
-
-}
-
-fn handle_camera_zoom(active: &mut GameCamera, dt: f32) -> bool {
-
-    let camera_speed = 128.0 * active.camera_zoom;
-
-    let mouse_wheel_delta = mouse_wheel();
-
-    let min_zoom = 0.5;
-    let max_zoom = 4.0;
-
-    let new_zoom_velocity = active.camera_zoom_velocity + mouse_wheel_delta.1 * dt;
-    let new_zoom = (active.camera_zoom - new_zoom_velocity).clamp(min_zoom, max_zoom);
-    let new_size = active.size * new_zoom;
-
-    let new_camera = Camera2D::from_display_rect_fixed(
-        Rect {
-            x: active.camera.target.x - (new_size.x / 2.0),
-            y: active.camera.target.y - (new_size.y / 2.0),
-            w: new_size.x,
-            h: new_size.y
-        }
-    );
-
-    active.camera_zoom_velocity = new_zoom_velocity;
-    active.camera_zoom = new_zoom;
-    active.camera = new_camera;
-
-    if active.camera_zoom_velocity > 0.01 {
-        let camera_mouse_position: Vec2 = active.camera.screen_to_world(mouse_position().into());
-        let camera_delta_to_mouse_position = (camera_mouse_position - active.camera.target) * 0.5;
-        active.camera_target_velocity += camera_delta_to_mouse_position * dt;
-    }
-
-    active.camera_zoom_velocity = exponential_decay(active.camera_zoom_velocity, 5.0, dt);
-
-    // so we can do things on change
-    mouse_wheel_delta.1 != 0.0
-
-}
-
-fn handle_camera_panning(active: &mut GameCamera, last_mouse_position: Vec2, _dt: f32) {
-
-    let is_middle_mouse_down = is_mouse_button_down(MouseButton::Middle);
-
-    if is_middle_mouse_down {
-        let mouse_position_v: Vec2 = mouse_position().into();
-        let mouse_position_delta: Vec2 = (last_mouse_position - mouse_position_v) * 0.5;
-        active.camera_target_velocity += mouse_position_delta * active.camera_zoom;
-    }
-
+/// Moves with both the arrow keys and WASD, eases in on where it is going.
+fn create_game_camera() -> GameCamera2D {
+    GameCamera2D::with_parameters(GameCamera2DParameters {
+        bindings: GameCamera2DBindings::arrows_and_wasd(),
+        move_speed: 128.0,
+        smoothing: Some(10.0),
+        ..GameCamera2DParameters::new()
+    })
 }
 
 fn modify_in_radius(map: &mut Heightmap, center_x: i32, center_y: i32, radius: i32, v: i8) {
@@ -743,7 +629,7 @@ fn modify_in_radius(map: &mut Heightmap, center_x: i32, center_y: i32, radius: i
 
 }
 
-fn handle_height_map_input(active: &mut GameCamera, map: &mut Heightmap, _dt: f32) -> bool {
+fn handle_height_map_input(active: &mut GameCamera2D, map: &mut Heightmap, _dt: f32) -> bool {
 
     let radius = 2;
     let modification = 4;
@@ -751,7 +637,7 @@ fn handle_height_map_input(active: &mut GameCamera, map: &mut Heightmap, _dt: f3
     let is_mouse_left_down = is_mouse_button_down(MouseButton::Left);
     let is_mouse_right_down = is_mouse_button_down(MouseButton::Right);
 
-    let Vec2 { x: mouse_x, y: mouse_y } = active.camera.screen_to_world(mouse_position().into());
+    let Vec2 { x: mouse_x, y: mouse_y } = active.mouse_world_position();
     let (mouse_tile_x, mouse_tile_y) = map.world_to_tile(mouse_x, mouse_y);
 
     if is_mouse_left_down {
@@ -767,7 +653,7 @@ fn handle_height_map_input(active: &mut GameCamera, map: &mut Heightmap, _dt: f3
 }
 
 /// Handles the game input, if any change happened, this returns true.
-fn handle_game_input(active: &mut GameCamera, map: &mut Heightmap, out_seed: &mut u32, dt: f32) -> bool {
+fn handle_game_input(active: &mut GameCamera2D, map: &mut Heightmap, out_seed: &mut u32, dt: f32) -> bool {
 
     let was_reload_key_pressed = is_key_pressed(KeyCode::R);
 
@@ -783,9 +669,9 @@ fn handle_game_input(active: &mut GameCamera, map: &mut Heightmap, out_seed: &mu
 
 }
 
-fn draw_debug_text(active: &GameCamera, map: &Heightmap, debug: &mut DebugText, seed: u32) {
+fn draw_debug_text(active: &GameCamera2D, map: &Heightmap, debug: &mut DebugText, seed: u32) {
 
-    let world_pos = active.camera.screen_to_world(mouse_position().into());
+    let world_pos = active.mouse_world_position();
     let tile_pos = map.world_to_tile(world_pos.x, world_pos.y);
     let tile = map.get(tile_pos.0, tile_pos.1);
 
@@ -820,19 +706,17 @@ async fn main() {
     // #TODO: figure out what the purpose of this was, literally do not remember
     // let height_field_texture = create_height_field_buffer_texture(&height_field);
 
-    let mut last_mouse_position: Vec2 = mouse_position().into();
-    let mut active_camera = GameCamera::new(screen_dimensions());
+    let mut active_camera = create_game_camera();
     let render_debug_text = false;
     
     loop {
 
-        active_camera.size = screen_dimensions();
         let dt = get_frame_time();
 
         // re-rasterize atlas if necessary
         if should_rasterize_tile_atlas {
 
-            let line_thickness = active_camera.camera_zoom.max(1.0) * 2.0;
+            let line_thickness = active_camera.current_zoom().max(1.0) * 2.0;
             rasterized_tile_atlas = Some(rasterize_tile_atlas(BLACK.lighten(0.75), WHITE, line_thickness));
 
             // NOTE: this flushes the render passes so that our tile atlas actually gets rendered before we try to actually use it, otherwise we get unitialized memory it seems like
@@ -840,17 +724,17 @@ async fn main() {
 
         }
 
-        let camera_changed = handle_camera_input(&mut active_camera, last_mouse_position, dt);
+        let last_zoom = active_camera.current_zoom();
+        active_camera.tick(dt);
+
+        let camera_changed = active_camera.current_zoom() != last_zoom;
         let game_changed = handle_game_input(&mut active_camera, &mut height_field, &mut current_seed, dt);
         should_rasterize_tile_atlas = camera_changed || game_changed;
 
-        // update last mouse pos
-        last_mouse_position = mouse_position().into();
-
         // let pleasant_earthy_green = Color::from_rgba(104, 118, 53, 255);
         let murky_ocean_blue = Color::from_rgba(21, 119, 136, 255);
         
-        set_camera(&active_camera.camera);
+        active_camera.push();
         clear_background(murky_ocean_blue);
 
         // enable this to see the tile atlas generated
@@ -873,7 +757,7 @@ async fn main() {
 
         // now draw screen space stuff, debug text, etc
 
-        set_default_camera();
+        active_camera.pop();
 
         debug_text.new_frame();
         draw_debug_text(&active_camera, &height_field, &mut debug_text, current_seed);
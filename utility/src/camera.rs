@@ -1,4 +1,6 @@
-use macroquad::{camera::Camera2D, math::{Rect, Vec3}, prelude::{vec2, vec3, Camera, Camera3D, Mat4, Vec2}, window::{screen_height, screen_width}};
+use macroquad::{camera::Camera2D, input::{is_mouse_button_down, mouse_position, mouse_wheel, KeyCode, MouseButton}, math::{Quat, Rect, Vec3}, prelude::{vec2, vec3, Camera, Camera3D, Mat4, Vec2}, window::{screen_height, screen_width}};
+
+use crate::{is_any_key_down, mouse_wheel_zoom_factor, smoothing_factor};
 
 pub struct GameCameraParameters {
 
@@ -8,7 +10,7 @@ pub struct GameCameraParameters {
     /// rotation speed in radians per second
     pub rotation_speed: f32,
 
-    /// zoom speed in world units per second?
+    /// how much a notch of the mouse wheel changes the distance to the target by, 0.1 is 10%
     pub zoom_speed: f32
 
 }
@@ -31,7 +33,7 @@ impl GameCamera {
             parameters: GameCameraParameters {
                 movement_speed: 1.0,
                 rotation_speed: 1.0,
-                zoom_speed: 1.0
+                zoom_speed: 0.1
             },
             position: Vec3::ZERO,
             target: Vec3::ZERO + Vec3::NEG_Z,
@@ -96,6 +98,345 @@ impl GameCamera {
 
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameCameraMode {
+    /// Moves along the ground and up and down, turns with the keys and looks around while holding the rotate button.
+    Fly,
+    /// Circles around the target, dragging with the rotate button orbits, the mouse wheel changes the distance and the pan button drags the target along the screen.
+    Orbit,
+    /// Looks down on the target from a distance, moves along the ground with the keys or edge scrolling and the pan button drags the ground along.
+    Rts
+}
+
+/// Keys and buttons the 3D camera controller listens to, an empty list or None leaves that action unbound.
+#[derive(Debug, Clone)]
+pub struct GameCameraBindings {
+    pub move_forward: Vec<KeyCode>,
+    pub move_backward: Vec<KeyCode>,
+    pub move_left: Vec<KeyCode>,
+    pub move_right: Vec<KeyCode>,
+    pub move_up: Vec<KeyCode>,
+    pub move_down: Vec<KeyCode>,
+    pub turn_left: Vec<KeyCode>,
+    pub turn_right: Vec<KeyCode>,
+    /// Held to look around in fly mode, or to orbit around the target otherwise.
+    pub rotate_button: Option<MouseButton>,
+    /// Held to drag the target around, unused in fly mode.
+    pub pan_button: Option<MouseButton>
+}
+
+impl Default for GameCameraBindings {
+
+    /// WASD to move, space and left control to go up and down, Q and E to turn, right mouse to rotate and middle mouse to pan.
+    fn default() -> GameCameraBindings {
+        GameCameraBindings {
+            move_forward: vec![KeyCode::W],
+            move_backward: vec![KeyCode::S],
+            move_left: vec![KeyCode::A],
+            move_right: vec![KeyCode::D],
+            move_up: vec![KeyCode::Space],
+            move_down: vec![KeyCode::LeftControl],
+            turn_left: vec![KeyCode::Q],
+            turn_right: vec![KeyCode::E],
+            rotate_button: Some(MouseButton::Right),
+            pan_button: Some(MouseButton::Middle)
+        }
+    }
+
+}
+
+impl GameCameraBindings {
+
+    /// WASD to move, space and left control to go up and down, Q and E to turn, right mouse to rotate and middle mouse to pan.
+    pub fn new() -> GameCameraBindings {
+        GameCameraBindings::default()
+    }
+
+}
+
+/// Moves a [GameCamera] around from player input, the speeds come from the parameters of the camera.
+pub struct GameCameraController {
+
+    pub mode: GameCameraMode,
+    pub bindings: GameCameraBindings,
+    pub world_up: Vec3,
+
+    /// rotation in radians per pixel the mouse moves while holding the rotate button
+    pub mouse_sensitivity: f32,
+
+    /// movement in world units per pixel the mouse moves while holding the pan button, for each world unit the camera is away from the target
+    pub pan_sensitivity: f32,
+
+    /// limits on the distance between the camera and the target when zooming with the mouse wheel
+    pub min_distance: f32,
+    pub max_distance: f32,
+
+    /// limits on the angle in radians above the ground the camera can look at in fly mode, or look down at the target from otherwise
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+
+    /// only used in rts mode
+    pub is_edge_scrolling_enabled: bool,
+
+    /// distance from the edge of the screen in pixels where the camera starts scrolling
+    pub edge_scroll_margin: f32,
+
+    /// rate the camera eases in on where it was moved to, higher is snappier, None moves it there right away
+    pub smoothing: Option<f32>,
+
+    desired_position: Vec3,
+    desired_target: Vec3,
+
+    /// where this left the camera last tick, if the camera is somewhere else it was moved from the outside
+    last_applied: Option<(Vec3, Vec3)>,
+    last_mouse_position: Vec2
+
+}
+
+impl GameCameraController {
+
+    pub fn new(mode: GameCameraMode) -> GameCameraController {
+
+        let min_pitch = match mode {
+            GameCameraMode::Fly | GameCameraMode::Orbit => -85.0_f32.to_radians(),
+            GameCameraMode::Rts => 20.0_f32.to_radians()
+        };
+
+        GameCameraController {
+            mode,
+            bindings: GameCameraBindings::new(),
+            world_up: Vec3::Y,
+            mouse_sensitivity: 0.005,
+            pan_sensitivity: 0.0015,
+            min_distance: 1.0,
+            max_distance: 100.0,
+            min_pitch,
+            max_pitch: 85.0_f32.to_radians(),
+            is_edge_scrolling_enabled: false,
+            edge_scroll_margin: 8.0,
+            smoothing: None,
+            desired_position: Vec3::ZERO,
+            desired_target: Vec3::NEG_Z,
+            last_applied: None,
+            last_mouse_position: mouse_position().into()
+        }
+
+    }
+
+    pub fn tick(&mut self, camera: &mut GameCamera, dt: f32) {
+
+        if self.last_applied != Some((camera.position, camera.target)) {
+            self.desired_position = camera.position;
+            self.desired_target = camera.target;
+        }
+
+        let mouse_position_v: Vec2 = mouse_position().into();
+        let mouse_delta = mouse_position_v - self.last_mouse_position;
+        self.last_mouse_position = mouse_position_v;
+
+        match self.mode {
+            GameCameraMode::Fly => self.handle_fly_input(&camera.parameters, mouse_delta, dt),
+            GameCameraMode::Orbit | GameCameraMode::Rts => self.handle_orbit_input(&camera.parameters, mouse_position_v, mouse_delta, dt)
+        }
+
+        match self.smoothing {
+            Some(rate) => {
+                let t = smoothing_factor(rate, dt);
+                camera.position = camera.position.lerp(self.desired_position, t);
+                camera.target = camera.target.lerp(self.desired_target, t);
+            },
+            None => {
+                camera.position = self.desired_position;
+                camera.target = self.desired_target;
+            }
+        }
+
+        self.last_applied = Some((camera.position, camera.target));
+
+    }
+
+    /// The direction of the keys held, with forward and left along the ground.
+    fn movement_direction(&self, forward: Vec3) -> Vec3 {
+
+        let forward_in_plane = project_onto_plane(forward, self.world_up);
+        let left_in_plane = self.world_up.cross(forward_in_plane).normalize_or_zero();
+
+        let mut movement_direction = Vec3::ZERO;
+
+        if is_any_key_down(&self.bindings.move_forward) {
+            movement_direction += forward_in_plane;
+        }
+
+        if is_any_key_down(&self.bindings.move_backward) {
+            movement_direction -= forward_in_plane;
+        }
+
+        if is_any_key_down(&self.bindings.move_left) {
+            movement_direction += left_in_plane;
+        }
+
+        if is_any_key_down(&self.bindings.move_right) {
+            movement_direction -= left_in_plane;
+        }
+
+        if is_any_key_down(&self.bindings.move_up) {
+            movement_direction += self.world_up;
+        }
+
+        if is_any_key_down(&self.bindings.move_down) {
+            movement_direction -= self.world_up;
+        }
+
+        movement_direction
+
+    }
+
+    /// Rotation in radians around the world up from the turn keys, positive turns left.
+    fn turn_angle(&self, parameters: &GameCameraParameters, dt: f32) -> f32 {
+
+        let mut turn_angle = 0.0;
+
+        if is_any_key_down(&self.bindings.turn_left) {
+            turn_angle += parameters.rotation_speed * dt;
+        }
+
+        if is_any_key_down(&self.bindings.turn_right) {
+            turn_angle -= parameters.rotation_speed * dt;
+        }
+
+        turn_angle
+
+    }
+
+    fn handle_fly_input(&mut self, parameters: &GameCameraParameters, mouse_delta: Vec2, dt: f32) {
+
+        let forward = self.desired_target - self.desired_position;
+        let movement_delta = self.movement_direction(forward) * parameters.movement_speed * dt;
+
+        let mut yaw = self.turn_angle(parameters, dt);
+        let mut pitch = 0.0;
+
+        if self.bindings.rotate_button.is_some_and(is_mouse_button_down) {
+            yaw -= mouse_delta.x * self.mouse_sensitivity;
+            pitch -= mouse_delta.y * self.mouse_sensitivity;
+        }
+
+        let turned_forward = Quat::from_axis_angle(self.world_up, yaw) * forward;
+        let new_forward = rotate_elevation(turned_forward, self.world_up, pitch, self.min_pitch, self.max_pitch);
+
+        self.desired_position += movement_delta;
+        self.desired_target = self.desired_position + new_forward;
+
+    }
+
+    fn handle_orbit_input(&mut self, parameters: &GameCameraParameters, mouse_position: Vec2, mouse_delta: Vec2, dt: f32) {
+
+        let offset = self.desired_position - self.desired_target;
+        let forward = -offset;
+        let distance = offset.length();
+
+        // moving along the ground, the camera comes along with the target
+        let mut target_delta = self.movement_direction(forward) * parameters.movement_speed * dt;
+
+        if self.mode == GameCameraMode::Rts && self.is_edge_scrolling_enabled {
+            target_delta += self.edge_scroll_direction(forward, mouse_position) * parameters.movement_speed * dt;
+        }
+
+        if self.bindings.pan_button.is_some_and(is_mouse_button_down) {
+
+            let pan_scale = self.pan_sensitivity * distance;
+            let left = self.world_up.cross(forward).normalize_or_zero();
+
+            // rts drags the ground along, orbit drags along the screen
+            let screen_up = match self.mode {
+                GameCameraMode::Rts => project_onto_plane(forward, self.world_up),
+                _ => forward.cross(left).normalize_or_zero()
+            };
+
+            target_delta += (left * mouse_delta.x + screen_up * mouse_delta.y) * pan_scale;
+
+        }
+
+        let mut yaw = self.turn_angle(parameters, dt);
+        let mut pitch = 0.0;
+
+        if self.bindings.rotate_button.is_some_and(is_mouse_button_down) {
+            yaw -= mouse_delta.x * self.mouse_sensitivity;
+            pitch += mouse_delta.y * self.mouse_sensitivity;
+        }
+
+        let (_mouse_wheel_delta_x, mouse_wheel_delta_y) = mouse_wheel();
+        let new_distance = (distance * mouse_wheel_zoom_factor(mouse_wheel_delta_y, parameters.zoom_speed)).clamp(self.min_distance, self.max_distance);
+
+        let turned_offset = Quat::from_axis_angle(self.world_up, yaw) * offset;
+        let new_offset = rotate_elevation(turned_offset, self.world_up, pitch, self.min_pitch, self.max_pitch).normalize_or_zero() * new_distance;
+
+        self.desired_target += target_delta;
+        self.desired_position = self.desired_target + new_offset;
+
+    }
+
+    fn edge_scroll_direction(&self, forward: Vec3, mouse_position: Vec2) -> Vec3 {
+
+        // the mouse is reported as sitting at the origin when it has never entered the window, which would otherwise scroll forward and to the left
+        if mouse_position == Vec2::ZERO || self.bindings.pan_button.is_some_and(is_mouse_button_down) {
+            return Vec3::ZERO;
+        }
+
+        let forward_in_plane = project_onto_plane(forward, self.world_up);
+        let left_in_plane = self.world_up.cross(forward_in_plane).normalize_or_zero();
+        let margin = self.edge_scroll_margin;
+
+        let mut edge_scroll_direction = Vec3::ZERO;
+
+        if mouse_position.x <= margin {
+            edge_scroll_direction += left_in_plane;
+        }
+
+        if mouse_position.x >= screen_width() - margin {
+            edge_scroll_direction -= left_in_plane;
+        }
+
+        if mouse_position.y <= margin {
+            edge_scroll_direction += forward_in_plane;
+        }
+
+        if mouse_position.y >= screen_height() - margin {
+            edge_scroll_direction -= forward_in_plane;
+        }
+
+        edge_scroll_direction
+
+    }
+
+}
+
+/// Returns the normalized direction of v along the plane with the given normal, or zero if v is along the normal.
+fn project_onto_plane(v: Vec3, normal: Vec3) -> Vec3 {
+    (v - normal * v.dot(normal)).normalize_or_zero()
+}
+
+/// Tilts v up towards the world up by the given angle in radians, keeping it between the angles above the ground given, as well as its length.
+fn rotate_elevation(v: Vec3, up: Vec3, angle: f32, min_elevation: f32, max_elevation: f32) -> Vec3 {
+
+    let length = v.length();
+    if length == 0.0 {
+        return v;
+    }
+
+    let elevation = (v.dot(up) / length).clamp(-1.0, 1.0).asin();
+    let new_elevation = (elevation + angle).clamp(min_elevation, max_elevation);
+
+    let mut horizontal = project_onto_plane(v, up);
+    if horizontal == Vec3::ZERO {
+        // looking straight along the up, any direction along the ground is as good as another
+        horizontal = up.any_orthonormal_vector();
+    }
+
+    (horizontal * new_elevation.cos() + up * new_elevation.sin()) * length
+
+}
+
 pub fn create_camera(position: Vec3, up: Vec3, target: Vec3) -> Camera3D {
     Camera3D {
         position: position,
@@ -126,4 +467,43 @@ impl Camera2DExt for Camera2D {
         camera.zoom.y = -camera.zoom.y;
         camera
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_rotate_elevation_keeps_length_and_heading() {
+
+        let v = vec3(3.0, 0.0, 4.0);
+        let rotated = rotate_elevation(v, Vec3::Y, 45.0_f32.to_radians(), -1.5, 1.5);
+
+        assert!((rotated.length() - 5.0).abs() < 1e-4);
+        assert!((rotated.y - 5.0 * 45.0_f32.to_radians().sin()).abs() < 1e-4);
+        assert!(project_onto_plane(rotated, Vec3::Y).distance(v.normalize()) < 1e-4);
+
+    }
+
+    #[test]
+    fn test_rotate_elevation_is_clamped() {
+
+        let max_elevation = 80.0_f32.to_radians();
+        let rotated = rotate_elevation(Vec3::X, Vec3::Y, 170.0_f32.to_radians(), -max_elevation, max_elevation);
+
+        assert!((rotated.y.asin() - max_elevation).abs() < 1e-4);
+        assert!(rotated.x > 0.0);
+
+    }
+
+    #[test]
+    fn test_rotate_elevation_straight_up() {
+
+        let rotated = rotate_elevation(Vec3::Y * 2.0, Vec3::Y, 0.0, -1.0, 1.0);
+
+        assert!((rotated.length() - 2.0).abs() < 1e-4);
+        assert!((rotated.normalize().y - 1.0_f32.sin()).abs() < 1e-4);
+
+    }
+
+}
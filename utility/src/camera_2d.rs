@@ -1,43 +1,149 @@
-use macroquad::{camera::{pop_camera_state, push_camera_state, set_camera, Camera2D}, input::{is_key_down, is_mouse_button_down, mouse_position, mouse_wheel, KeyCode, MouseButton}, math::{vec2, Rect, Vec2}, texture::RenderTarget};
+use macroquad::{camera::{pop_camera_state, push_camera_state, set_camera, Camera2D}, input::{is_mouse_button_down, mouse_position, mouse_wheel, KeyCode, MouseButton}, math::{vec2, Rect, Vec2}, texture::RenderTarget};
+
+use crate::{is_any_key_down, screen_dimensions, smoothing_factor, Camera2DExt};
+
+/// Keys and buttons the 2D camera listens to, an empty list or None leaves that action unbound.
+#[derive(Debug, Clone)]
+pub struct GameCamera2DBindings {
+    pub move_up: Vec<KeyCode>,
+    pub move_down: Vec<KeyCode>,
+    pub move_left: Vec<KeyCode>,
+    pub move_right: Vec<KeyCode>,
+    /// Held to drag the world around with the mouse.
+    pub pan_button: Option<MouseButton>
+}
+
+impl Default for GameCamera2DBindings {
+
+    /// Arrow keys to move, middle mouse to pan.
+    fn default() -> GameCamera2DBindings {
+        GameCamera2DBindings {
+            move_up: vec![KeyCode::Up],
+            move_down: vec![KeyCode::Down],
+            move_left: vec![KeyCode::Left],
+            move_right: vec![KeyCode::Right],
+            pan_button: Some(MouseButton::Middle)
+        }
+    }
+
+}
+
+impl GameCamera2DBindings {
+
+    /// Arrow keys to move, middle mouse to pan.
+    pub fn new() -> GameCamera2DBindings {
+        GameCamera2DBindings::default()
+    }
+
+    /// Both the arrow keys and WASD to move, middle mouse to pan.
+    pub fn arrows_and_wasd() -> GameCamera2DBindings {
+        GameCamera2DBindings {
+            move_up: vec![KeyCode::Up, KeyCode::W],
+            move_down: vec![KeyCode::Down, KeyCode::S],
+            move_left: vec![KeyCode::Left, KeyCode::A],
+            move_right: vec![KeyCode::Right, KeyCode::D],
+            pan_button: Some(MouseButton::Middle)
+        }
+    }
+
+}
+
+#[derive(Debug, Clone)]
+pub struct GameCamera2DParameters {
+
+    pub bindings: GameCamera2DBindings,
+
+    /// zoom is in world units per pixel, so the lower bound is how far in the camera can go
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+
+    /// movement speed in pixels per second when using the keys, so it feels the same at any zoom
+    pub move_speed: f32,
+
+    /// how much a notch of the mouse wheel zooms by, 0.1 is 10%
+    pub zoom_speed: f32,
+
+    /// keeps the world position under the mouse in place while zooming, otherwise zooms around the centre of the screen
+    pub is_zoom_to_cursor_enabled: bool,
+
+    pub is_edge_scrolling_enabled: bool,
+
+    /// edge scrolling speed in pixels per second
+    pub edge_scroll_speed: f32,
+
+    /// distance from the edge of the screen in pixels where the camera starts scrolling
+    pub edge_scroll_margin: f32,
+
+    /// rate the camera eases in on where it was moved or zoomed to, higher is snappier, None moves it there right away
+    pub smoothing: Option<f32>,
+
+    /// rate the camera eases in on the position given to [GameCamera2D::smooth_move_camera_to_position]
+    pub follow_rate: f32
+
+}
+
+impl Default for GameCamera2DParameters {
+
+    fn default() -> GameCamera2DParameters {
+        GameCamera2DParameters {
+            bindings: GameCamera2DBindings::new(),
+            min_zoom: 0.5,
+            max_zoom: 4.0,
+            move_speed: 256.0,
+            zoom_speed: 0.1,
+            is_zoom_to_cursor_enabled: true,
+            is_edge_scrolling_enabled: false,
+            edge_scroll_speed: 512.0,
+            edge_scroll_margin: 8.0,
+            smoothing: None,
+            follow_rate: 8.0
+        }
+    }
 
-use crate::{screen_dimensions, Camera2DExt};
+}
+
+impl GameCamera2DParameters {
+
+    pub fn new() -> GameCamera2DParameters {
+        GameCamera2DParameters::default()
+    }
 
-struct GameCamera2DParameters {
-    min_zoom: f32,
-    max_zoom: f32,
-    move_speed: f32,
-    zoom_speed: f32
 }
 
 pub struct GameCamera2D {
     size: Vec2,
     camera_zoom: f32,
     camera: Camera2D,
+    desired_target: Vec2,
+    desired_zoom: f32,
+    follow_target: Option<Vec2>,
     last_mouse_position: Vec2,
+    is_moved_by_player: bool,
     parameters: GameCamera2DParameters
 }
 
 impl GameCamera2D {
 
     pub fn new() -> GameCamera2D {
+        GameCamera2D::with_parameters(GameCamera2DParameters::new())
+    }
+
+    pub fn with_parameters(parameters: GameCamera2DParameters) -> GameCamera2D {
 
         let size = screen_dimensions();
         let camera = Camera2D::from_display_rect_fixed(
             Rect { x: 0.0, y: 0.0, w: size.x, h: size.y }
         );
 
-        let parameters = GameCamera2DParameters {
-            min_zoom: 0.5,
-            max_zoom: 4.0,
-            move_speed: 256.0,
-            zoom_speed: 10.0
-        };
-
         GameCamera2D {
             size: size,
+            desired_target: camera.target,
             camera: camera,
             camera_zoom: 1.0,
+            desired_zoom: 1.0,
+            follow_target: None,
             last_mouse_position: mouse_position().into(),
+            is_moved_by_player: false,
             parameters
         }
 
@@ -47,6 +153,18 @@ impl GameCamera2D {
         self.camera.render_target.is_none()
     }
 
+    pub fn parameters(&self) -> &GameCamera2DParameters {
+        &self.parameters
+    }
+
+    pub fn parameters_mut(&mut self) -> &mut GameCamera2DParameters {
+        &mut self.parameters
+    }
+
+    pub fn set_parameters(&mut self, parameters: GameCamera2DParameters) {
+        self.parameters = parameters;
+    }
+
     pub fn current_zoom(&self) -> f32 {
         self.camera_zoom
     }
@@ -55,6 +173,11 @@ impl GameCamera2D {
         self.camera.target
     }
 
+    /// True if the player moved the camera themselves during the last tick, using the keys, panning or edge scrolling.
+    pub fn is_moved_by_player(&self) -> bool {
+        self.is_moved_by_player
+    }
+
     pub fn mouse_screen_position(&self) -> Vec2 {
         mouse_position().into()
     }
@@ -63,12 +186,22 @@ impl GameCamera2D {
         self.screen_to_world(self.mouse_screen_position())
     }
 
-    pub fn smooth_move_camera_to_position(&self, world_position: Vec2) {
-
+    /// Eases the camera in on the position over the next ticks at the follow rate, can be called every frame to follow something, stops as soon as the player moves the camera.
+    pub fn smooth_move_camera_to_position(&mut self, world_position: Vec2) {
+        self.follow_target = Some(world_position);
     }
 
     pub fn move_camera_to_position(&mut self, world_position: Vec2) {
-        self.camera.target = world_position
+        self.camera.target = world_position;
+        self.desired_target = world_position;
+        self.follow_target = None;
+    }
+
+    /// Zooms right away, clamped to the zoom limits.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.camera_zoom = zoom.clamp(self.parameters.min_zoom, self.parameters.max_zoom);
+        self.desired_zoom = self.camera_zoom;
+        self.update_camera();
     }
 
     pub fn screen_to_world(&self, screen_position: Vec2) -> Vec2 {
@@ -127,34 +260,81 @@ impl GameCamera2D {
         pop_camera_state();
     }
 
+    /// Rebuilds the macroquad camera from the current target, zoom and screen size.
+    fn update_camera(&mut self) {
+
+        let new_size = self.size * self.camera_zoom;
+        let new_target = self.camera.target;
+        let display_rect = Rect {
+            x: new_target.x - (new_size.x / 2.0),
+            y: new_target.y - (new_size.y / 2.0),
+            w: new_size.x,
+            h: new_size.y
+        };
+
+        self.camera = if self.should_invert_y_axis() {
+            Camera2D::from_display_rect_fixed(display_rect)
+        } else {
+            Camera2D::from_display_rect(display_rect)
+        };
+
+    }
+
     pub fn tick(&mut self, dt: f32) {
+        self.tick_ex(dt, false);
+    }
+
+    /// Set is_pointer_over_ui to stop edge scrolling while the mouse is on a window hugging the side of the screen.
+    pub fn tick_ex(&mut self, dt: f32, is_pointer_over_ui: bool) {
 
         self.size = screen_dimensions();
-        handle_camera_input(self, self.last_mouse_position, dt);
+        handle_camera_input(self, self.last_mouse_position, dt, is_pointer_over_ui);
         self.last_mouse_position = mouse_position().into();
-        
+
     }
 
 }
 
-fn handle_camera_input(active: &mut GameCamera2D, last_mouse_position: Vec2, dt: f32) -> bool {
+/// Where the camera has to be for the world position under the point to stay put when going from one zoom to another.
+pub fn zoom_towards_point(target: Vec2, point: Vec2, zoom: f32, new_zoom: f32) -> Vec2 {
+    point + (target - point) * (new_zoom / zoom)
+}
+
+/// How much to scale the zoom or distance of a camera by for a mouse wheel delta, scrolling up zooms in.
+pub fn mouse_wheel_zoom_factor(mouse_wheel_delta: f32, zoom_speed: f32) -> f32 {
+    // how much the wheel reports per notch differs between platforms, this keeps it to about a notch per frame
+    (1.0 + zoom_speed).powf(-mouse_wheel_delta.clamp(-1.0, 1.0))
+}
+
+fn handle_camera_input(active: &mut GameCamera2D, last_mouse_position: Vec2, dt: f32, is_pointer_over_ui: bool) -> bool {
+
+    let was_moved = handle_camera_movement(active, dt);
+    let zoom_changed = handle_camera_zoom(active);
+    let was_panned = handle_camera_panning(active, last_mouse_position);
+    let was_edge_scrolled = !is_pointer_over_ui && handle_camera_edge_scrolling(active, dt);
+
+    active.is_moved_by_player = was_moved || was_panned || was_edge_scrolled;
+
+    if active.is_moved_by_player {
+        active.follow_target = None;
+    }
 
-    handle_camera_movement(active, dt);
-    let zoom_changed = handle_camera_zoom(active, dt);
-    handle_camera_panning(active, last_mouse_position, dt);
+    handle_camera_smoothing(active, dt);
+    active.update_camera();
 
     zoom_changed
 
 }
 
-fn handle_camera_movement(active: &mut GameCamera2D, dt: f32) {
+fn handle_camera_movement(active: &mut GameCamera2D, dt: f32) -> bool {
 
-    let camera_speed = active.parameters.move_speed * active.camera_zoom;
+    let camera_speed = active.parameters.move_speed * active.desired_zoom;
+    let bindings = &active.parameters.bindings;
 
-    let is_up_pressed = is_key_down(KeyCode::Up);
-    let is_down_pressed = is_key_down(KeyCode::Down);
-    let is_left_pressed = is_key_down(KeyCode::Left);
-    let is_right_pressed = is_key_down(KeyCode::Right);
+    let is_up_pressed = is_any_key_down(&bindings.move_up);
+    let is_down_pressed = is_any_key_down(&bindings.move_down);
+    let is_left_pressed = is_any_key_down(&bindings.move_left);
+    let is_right_pressed = is_any_key_down(&bindings.move_right);
 
     let mut camera_delta = Vec2::ZERO;
 
@@ -173,65 +353,149 @@ fn handle_camera_movement(active: &mut GameCamera2D, dt: f32) {
     if is_right_pressed {
         camera_delta += vec2(1.0, 0.0);
     }
-    
-    active.camera.target += camera_delta * camera_speed * dt;
+
+    active.desired_target += camera_delta * camera_speed * dt;
+
+    camera_delta != Vec2::ZERO
 
 }
 
-fn handle_camera_zoom(active: &mut GameCamera2D, dt: f32) -> bool {
+fn handle_camera_zoom(active: &mut GameCamera2D) -> bool {
 
-    let (mouse_wheel_delta_x, mouse_wheel_delta_y) = mouse_wheel();
+    let (_mouse_wheel_delta_x, mouse_wheel_delta_y) = mouse_wheel();
+
+    if mouse_wheel_delta_y == 0.0 {
+        return false;
+    }
 
     let min_zoom = active.parameters.min_zoom;
     let max_zoom = active.parameters.max_zoom;
 
-    let new_zoom = (active.camera_zoom - mouse_wheel_delta_y * active.parameters.zoom_speed * dt).clamp(min_zoom, max_zoom);
-    let new_size = active.size * new_zoom;
+    let new_zoom = (active.desired_zoom * mouse_wheel_zoom_factor(mouse_wheel_delta_y, active.parameters.zoom_speed)).clamp(min_zoom, max_zoom);
 
-    let new_target = if new_zoom < active.camera_zoom {
-        let vector_to_mouse_world_position = active.mouse_world_position() - active.world_position();
-        let current_vector_to_world_position = active.world_position() + (vector_to_mouse_world_position / 4.0) / active.camera_zoom;
-        current_vector_to_world_position
-    } else {
-        active.camera.target
-    };
-
-    let new_camera = if active.should_invert_y_axis() {
-        Camera2D::from_display_rect_fixed(
-            Rect {
-                x: new_target.x - (new_size.x / 2.0),
-                y: new_target.y - (new_size.y / 2.0),
-                w: new_size.x,
-                h: new_size.y
-            }
-        )
-    } else {
-        Camera2D::from_display_rect(
-            Rect {
-                x: new_target.x - (new_size.x / 2.0),
-                y: new_target.y - (new_size.y / 2.0),
-                w: new_size.x,
-                h: new_size.y
-            }
-        )
-    };
+    if active.parameters.is_zoom_to_cursor_enabled {
+        // where the mouse will be pointing once the camera is done easing in on where it is going
+        let mouse_offset_from_target = (active.mouse_world_position() - active.camera.target) * (active.desired_zoom / active.camera_zoom);
+        let desired_mouse_world_position = active.desired_target + mouse_offset_from_target;
+        active.desired_target = zoom_towards_point(active.desired_target, desired_mouse_world_position, active.desired_zoom, new_zoom);
+    }
 
-    active.camera_zoom = new_zoom;
-    active.camera = new_camera;
+    active.desired_zoom = new_zoom;
 
     // so we can do things on change
-    mouse_wheel_delta_y != 0.0
+    true
 
 }
 
-fn handle_camera_panning(active: &mut GameCamera2D, last_mouse_position: Vec2, dt: f32) {
+fn handle_camera_panning(active: &mut GameCamera2D, last_mouse_position: Vec2) -> bool {
 
-    let is_middle_mouse_down = is_mouse_button_down(MouseButton::Middle);
+    let Some(pan_button) = active.parameters.bindings.pan_button else {
+        return false;
+    };
 
-    if is_middle_mouse_down {
+    if is_mouse_button_down(pan_button) {
         let mouse_position_v: Vec2 = mouse_position().into();
-        let mouse_position_delta: Vec2 = last_mouse_position - mouse_position_v;
-        active.camera.target += mouse_position_delta * active.camera_zoom;
+        let mouse_position_delta: Vec2 = (last_mouse_position - mouse_position_v) * active.camera_zoom;
+        // the world sticks to the mouse while dragging, so this skips the smoothing
+        active.camera.target += mouse_position_delta;
+        active.desired_target += mouse_position_delta;
+        return mouse_position_delta != Vec2::ZERO;
+    }
+
+    false
+
+}
+
+fn handle_camera_edge_scrolling(active: &mut GameCamera2D, dt: f32) -> bool {
+
+    let is_panning = active.parameters.bindings.pan_button.is_some_and(is_mouse_button_down);
+    if !active.parameters.is_edge_scrolling_enabled || is_panning {
+        return false;
+    }
+
+    let mouse_position_v: Vec2 = mouse_position().into();
+    let margin = active.parameters.edge_scroll_margin;
+
+    // the mouse is reported as sitting at the origin when it has never entered the window, which would otherwise scroll up and to the left
+    if mouse_position_v == Vec2::ZERO {
+        return false;
+    }
+
+    let mut camera_delta = Vec2::ZERO;
+
+    if mouse_position_v.x <= margin {
+        camera_delta.x -= 1.0;
+    }
+
+    if mouse_position_v.x >= active.size.x - margin {
+        camera_delta.x += 1.0;
     }
 
-}
\ No newline at end of file
+    if mouse_position_v.y <= margin {
+        camera_delta.y -= 1.0;
+    }
+
+    if mouse_position_v.y >= active.size.y - margin {
+        camera_delta.y += 1.0;
+    }
+
+    active.desired_target += camera_delta * active.parameters.edge_scroll_speed * active.desired_zoom * dt;
+
+    camera_delta != Vec2::ZERO
+
+}
+
+fn handle_camera_smoothing(active: &mut GameCamera2D, dt: f32) {
+
+    if let Some(follow_target) = active.follow_target {
+        active.desired_target = follow_target;
+    }
+
+    let target_smoothing = if active.follow_target.is_some() { Some(active.parameters.follow_rate) } else { active.parameters.smoothing };
+
+    active.camera.target = match target_smoothing {
+        Some(rate) => active.camera.target.lerp(active.desired_target, smoothing_factor(rate, dt)),
+        None => active.desired_target
+    };
+
+    active.camera_zoom = match active.parameters.smoothing {
+        Some(rate) => active.camera_zoom + (active.desired_zoom - active.camera_zoom) * smoothing_factor(rate, dt),
+        None => active.desired_zoom
+    };
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_zoom_towards_point_keeps_point_in_place() {
+
+        // zoom is in world units per pixel, so this is the world position some pixels away from the centre of the screen
+        let target = vec2(100.0, 50.0);
+        let screen_offset = vec2(40.0, -30.0);
+        let zoom = 2.0;
+        let point = target + screen_offset * zoom;
+
+        let new_zoom = 0.5;
+        let new_target = zoom_towards_point(target, point, zoom, new_zoom);
+
+        assert!((new_target + screen_offset * new_zoom).distance(point) < 1e-4);
+
+    }
+
+    #[test]
+    fn test_mouse_wheel_zoom_factor_in_and_out_cancel_out() {
+
+        let zoom_in = mouse_wheel_zoom_factor(1.0, 0.1);
+        let zoom_out = mouse_wheel_zoom_factor(-1.0, 0.1);
+
+        assert!(zoom_in < 1.0);
+        assert!((zoom_in * zoom_out - 1.0).abs() < 1e-6);
+        assert_eq!(zoom_in, mouse_wheel_zoom_factor(120.0, 0.1));
+
+    }
+
+}
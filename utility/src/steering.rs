@@ -1,6 +1,6 @@
 use macroquad::prelude::*;
 use std::f32::consts::*;
-use std::ops::Add;
+use std::ops::{Add, Mul};
 
 use crate::extensions::*;
 
@@ -69,7 +69,7 @@ impl SteeringOutputFilteredExt for SteeringOutput {
     }
 }
 
-#[derive(Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
 pub struct SteeringOutput {
     pub linear: Vec2,
     pub angular: f32
@@ -89,6 +89,29 @@ impl SteeringOutput {
             angular,
         }
     }
+
+    /// Scales the linear part down to at most max_linear and the angular part to at most max_angular, in either direction.
+    pub fn clamped(&self, max_linear: f32, max_angular: f32) -> SteeringOutput {
+        SteeringOutput {
+            linear: self.linear.clamp_length_max(max_linear),
+            angular: self.angular.clamp(-max_angular, max_angular)
+        }
+    }
+
+    pub fn is_nearly_zero(&self, epsilon: f32) -> bool {
+        self.linear.length_squared() <= epsilon * epsilon && self.angular.abs() <= epsilon
+    }
+}
+
+impl Mul<f32> for SteeringOutput {
+    type Output = Self;
+
+    fn mul(self, weight: f32) -> Self {
+        Self {
+            linear: self.linear * weight,
+            angular: self.angular * weight
+        }
+    }
 }
 
 impl Add for SteeringOutput {
@@ -122,8 +145,6 @@ pub struct SteeringParameters {
 
 }
 
-type SteeringBehaviour = dyn FnMut(&Kinematic, &Kinematic) -> SteeringOutput;
-
 pub fn seek(character: &Kinematic, target: &Kinematic) -> SteeringOutput {
 
     let vector_to_target = target.position - character.position;
@@ -355,12 +376,20 @@ pub fn look_where_you_are_going(character: &Kinematic, target: &Kinematic, max_r
 
 }
 
-pub fn wander(_character: &Kinematic, _target: &Kinematic, _max_acceleration: f32, _wander_offset: f32, _wander_radius: f32, _wander_rate: f32, _wander_orientation: f32) -> SteeringOutput {
+/// Steers towards a point on a circle in front of the character which drifts a little every call, the wander orientation is kept by the caller between calls.
+/// The random value is in [-1, 1] and comes from the caller, usually from [crate::random_binomial] or from its own seeded generator when it has to stay deterministic.
+pub fn wander(character: &Kinematic, max_acceleration: f32, wander_offset: f32, wander_radius: f32, wander_rate: f32, random_value: f32, wander_orientation: &mut f32) -> SteeringOutput {
+
+    *wander_orientation = map_to_range(*wander_orientation + random_value.clamp(-1.0, 1.0) * wander_rate);
+
+    let circle_centre = character.position + character.orientation.as_vector() * wander_offset;
+    let wander_target = circle_centre + (character.orientation + *wander_orientation).as_vector() * wander_radius;
 
     SteeringOutput {
-        linear: vec2(0.0, 0.0),
+        linear: (wander_target - character.position).normalize_or_zero() * max_acceleration,
         angular: 0.0
     }
+
 }
 
 pub fn separation<'a>(character: &Kinematic, targets: impl Iterator<Item=Kinematic>, max_acceleration: f32, threshold: f32, decay_coefficient: f32) -> SteeringOutput {
@@ -383,10 +412,563 @@ pub fn separation<'a>(character: &Kinematic, targets: impl Iterator<Item=Kinemat
     }
 }
 
-pub fn blend_steering_behaviours(_behaviours: &[&SteeringBehaviour]) -> SteeringOutput {
+/// Steers towards the average position of the targets within the threshold, keeping a flock together.
+pub fn cohesion(character: &Kinematic, targets: impl Iterator<Item=Kinematic>, max_acceleration: f32, threshold: f32) -> SteeringOutput {
+
+    let mut centre_of_mass = Vec2::ZERO;
+    let mut number_of_neighbours = 0;
+
+    for target in targets {
+        if target.position.distance(character.position) < threshold {
+            centre_of_mass += target.position;
+            number_of_neighbours += 1;
+        }
+    }
+
+    if number_of_neighbours == 0 {
+        return SteeringOutput::default();
+    }
+
+    let vector_to_centre = centre_of_mass / number_of_neighbours as f32 - character.position;
 
     SteeringOutput {
+        linear: vector_to_centre.normalize_or_zero() * max_acceleration,
+        angular: 0.0
+    }
+
+}
+
+/// Matches the average velocity of the targets within the threshold, keeping a flock heading the same way.
+pub fn alignment(character: &Kinematic, targets: impl Iterator<Item=Kinematic>, max_acceleration: f32, threshold: f32, time_to_target: f32) -> SteeringOutput {
+
+    let mut average_velocity = Vec2::ZERO;
+    let mut number_of_neighbours = 0;
+
+    for target in targets {
+        if target.position.distance(character.position) < threshold {
+            average_velocity += target.velocity;
+            number_of_neighbours += 1;
+        }
+    }
+
+    if number_of_neighbours == 0 {
+        return SteeringOutput::default();
+    }
+
+    let average_target = Kinematic {
+        velocity: average_velocity / number_of_neighbours as f32,
         ..Default::default()
+    };
+
+    velocity_match(character, &average_target, max_acceleration, time_to_target)
+
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SteeringObstacle {
+    Circle { centre: Vec2, radius: f32 },
+    Rect(Rect)
+}
+
+impl SteeringObstacle {
+
+    /// Where a ray from origin along direction first hits the obstacle grown by margin, along with the normal of the surface there, if within max_distance.
+    fn ray_intersection(&self, origin: Vec2, direction: Vec2, max_distance: f32, margin: f32) -> Option<(f32, Vec2)> {
+
+        match *self {
+            SteeringObstacle::Circle { centre, radius } => {
+
+                let radius = radius + margin;
+                let origin_to_centre = centre - origin;
+
+                // already inside, the way out is straight away from the centre
+                if origin_to_centre.length_squared() < radius * radius {
+                    return Some((0.0, (-origin_to_centre).normalize_or(-direction)));
+                }
+
+                let projected_distance = origin_to_centre.dot(direction);
+                let closest_distance_squared = origin_to_centre.length_squared() - projected_distance * projected_distance;
+
+                if projected_distance < 0.0 || closest_distance_squared > radius * radius {
+                    return None;
+                }
+
+                let hit_distance = projected_distance - (radius * radius - closest_distance_squared).sqrt();
+                if hit_distance > max_distance {
+                    return None;
+                }
+
+                let hit_position = origin + direction * hit_distance;
+                Some((hit_distance, (hit_position - centre).normalize_or(-direction)))
+
+            },
+            SteeringObstacle::Rect(rect) => {
+
+                let min = rect.point() - Vec2::splat(margin);
+                let max = rect.point() + rect.size() + Vec2::splat(margin);
+
+                if origin.cmpge(min).all() && origin.cmple(max).all() {
+                    let rect_centre = (min + max) * 0.5;
+                    return Some((0.0, (origin - rect_centre).normalize_or(-direction)));
+                }
+
+                // slab test, keeping track of which axis the ray entered through for the normal
+                let mut entry_distance = 0.0f32;
+                let mut exit_distance = max_distance;
+                let mut entry_normal = -direction;
+
+                for axis in 0..2 {
+
+                    if direction[axis].abs() < f32::EPSILON {
+                        if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                            return None;
+                        }
+                        continue;
+                    }
+
+                    let near_distance = (min[axis] - origin[axis]) / direction[axis];
+                    let far_distance = (max[axis] - origin[axis]) / direction[axis];
+                    let (near_distance, far_distance, normal_sign) = if near_distance < far_distance {
+                        (near_distance, far_distance, -1.0)
+                    } else {
+                        (far_distance, near_distance, 1.0)
+                    };
+
+                    if near_distance > entry_distance {
+                        entry_distance = near_distance;
+                        entry_normal = Vec2::ZERO;
+                        entry_normal[axis] = normal_sign;
+                    }
+
+                    exit_distance = exit_distance.min(far_distance);
+                    if entry_distance > exit_distance {
+                        return None;
+                    }
+
+                }
+
+                Some((entry_distance, entry_normal))
+
+            }
+        }
+
+    }
+
+}
+
+/// Looks ahead along the current velocity and steers away from the first obstacle it would hit, aiming for a point avoid_distance out from the surface.
+pub fn avoid_obstacles(character: &Kinematic, obstacles: impl Iterator<Item=SteeringObstacle>, max_acceleration: f32, lookahead_time: f32, avoid_distance: f32) -> Option<SteeringOutput> {
+
+    let speed = character.velocity.length();
+    if speed < f32::EPSILON {
+        return None;
+    }
+
+    let direction = character.velocity / speed;
+    let lookahead_distance = speed * lookahead_time;
+
+    let (hit_distance, hit_normal) = obstacles
+        .filter_map(|o| o.ray_intersection(character.position, direction, lookahead_distance, avoid_distance * 0.5))
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))?;
+
+    let hit_position = character.position + direction * hit_distance;
+    let avoid_target = hit_position + hit_normal * avoid_distance;
+
+    Some(SteeringOutput {
+        linear: (avoid_target - character.position).normalize_or(hit_normal) * max_acceleration,
+        angular: 0.0
+    })
+
+}
+
+/// A path made of straight segments between points, positions along it are given as distance from the first point.
+#[derive(Debug, Clone)]
+pub struct SteeringPath {
+    points: Vec<Vec2>,
+    is_looping: bool
+}
+
+impl SteeringPath {
+
+    pub fn new(points: Vec<Vec2>, is_looping: bool) -> SteeringPath {
+        SteeringPath {
+            points,
+            is_looping
+        }
+    }
+
+    fn segments(&self) -> impl Iterator<Item=(Vec2, Vec2)> + '_ {
+        let closing_segment = if self.is_looping && self.points.len() > 2 {
+            Some((self.points[self.points.len() - 1], self.points[0]))
+        } else {
+            None
+        };
+        self.points.windows(2).map(|w| (w[0], w[1])).chain(closing_segment)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.segments().map(|(a, b)| a.distance(b)).sum()
+    }
+
+    /// The distance along the path of the point on it closest to the position.
+    pub fn closest_distance_along(&self, position: Vec2) -> f32 {
+
+        let mut closest_distance_along = 0.0;
+        let mut closest_distance_squared = f32::MAX;
+        let mut segment_start_distance = 0.0;
+
+        for (a, b) in self.segments() {
+
+            let segment = b - a;
+            let segment_length = segment.length();
+            let t = if segment_length > 0.0 { ((position - a).dot(segment) / (segment_length * segment_length)).clamp(0.0, 1.0) } else { 0.0 };
+            let distance_squared = position.distance_squared(a + segment * t);
+
+            if distance_squared < closest_distance_squared {
+                closest_distance_squared = distance_squared;
+                closest_distance_along = segment_start_distance + segment_length * t;
+            }
+
+            segment_start_distance += segment_length;
+
+        }
+
+        closest_distance_along
+
+    }
+
+    /// The position at the distance along the path, wrapping around if looping or clamped to the ends otherwise.
+    pub fn position_at(&self, distance_along: f32) -> Vec2 {
+
+        let Some(&first_point) = self.points.first() else { return Vec2::ZERO; };
+
+        let path_length = self.length();
+        let distance_along = if self.is_looping && path_length > 0.0 {
+            distance_along.rem_euclid(path_length)
+        } else {
+            distance_along.clamp(0.0, path_length)
+        };
+
+        let mut remaining_distance = distance_along;
+        let mut last_point = first_point;
+
+        for (a, b) in self.segments() {
+            let segment_length = a.distance(b);
+            if remaining_distance <= segment_length && segment_length > 0.0 {
+                return a.lerp(b, remaining_distance / segment_length);
+            }
+            remaining_distance -= segment_length;
+            last_point = b;
+        }
+
+        last_point
+
     }
 
-}
\ No newline at end of file
+}
+
+/// Seeks towards the point path_offset further along the path than the closest point on it, a negative offset follows it backwards.
+pub fn follow_path(character: &Kinematic, path: &SteeringPath, path_offset: f32, max_acceleration: f32) -> SteeringOutput {
+
+    let target_distance_along = path.closest_distance_along(character.position) + path_offset;
+    let target_position = path.position_at(target_distance_along);
+
+    SteeringOutput {
+        linear: (target_position - character.position).normalize_or_zero() * max_acceleration,
+        angular: 0.0
+    }
+
+}
+
+/// Something which steers a character, returning None when it has nothing to say right now, closures taking the character implement it too.
+pub trait SteeringBehaviour {
+    fn steer(&mut self, character: &Kinematic) -> Option<SteeringOutput>;
+}
+
+impl<F> SteeringBehaviour for F where F: FnMut(&Kinematic) -> Option<SteeringOutput> {
+    fn steer(&mut self, character: &Kinematic) -> Option<SteeringOutput> {
+        self(character)
+    }
+}
+
+/// Sums up the weighted output of each behaviour, clamped to the maximum accelerations.
+pub fn blend_steering_behaviours(character: &Kinematic, behaviours: &mut [(f32, &mut dyn SteeringBehaviour)], max_acceleration: f32, max_angular_acceleration: f32) -> SteeringOutput {
+
+    let blended_output = behaviours.iter_mut()
+        .filter_map(|(weight, behaviour)| behaviour.steer(character).map(|output| output * *weight))
+        .fold(SteeringOutput::default(), |a, b| a + b);
+
+    blended_output.clamped(max_acceleration, max_angular_acceleration)
+
+}
+
+/// Owns a set of weighted behaviours and blends them, see [`blend_steering_behaviours()`].
+pub struct WeightedSteering<'a> {
+    behaviours: Vec<(f32, Box<dyn SteeringBehaviour + 'a>)>,
+    max_acceleration: f32,
+    max_angular_acceleration: f32
+}
+
+impl<'a> WeightedSteering<'a> {
+
+    pub fn new(max_acceleration: f32, max_angular_acceleration: f32) -> WeightedSteering<'a> {
+        WeightedSteering {
+            behaviours: Vec::new(),
+            max_acceleration,
+            max_angular_acceleration
+        }
+    }
+
+    pub fn with(mut self, weight: f32, behaviour: impl SteeringBehaviour + 'a) -> WeightedSteering<'a> {
+        self.behaviours.push((weight, Box::new(behaviour)));
+        self
+    }
+
+}
+
+impl<'a> SteeringBehaviour for WeightedSteering<'a> {
+    fn steer(&mut self, character: &Kinematic) -> Option<SteeringOutput> {
+        let mut behaviours: Vec<(f32, &mut dyn SteeringBehaviour)> = self.behaviours.iter_mut().map(|(w, b)| (*w, b.as_mut() as &mut dyn SteeringBehaviour)).collect();
+        Some(blend_steering_behaviours(character, &mut behaviours, self.max_acceleration, self.max_angular_acceleration))
+    }
+}
+
+/// Goes through the behaviours in order and uses the first one with anything significant to say, so for example avoiding an obstacle can override everything else.
+pub struct PrioritySteering<'a> {
+    behaviours: Vec<Box<dyn SteeringBehaviour + 'a>>,
+    epsilon: f32
+}
+
+impl<'a> PrioritySteering<'a> {
+
+    pub fn new(epsilon: f32) -> PrioritySteering<'a> {
+        PrioritySteering {
+            behaviours: Vec::new(),
+            epsilon
+        }
+    }
+
+    pub fn with(mut self, behaviour: impl SteeringBehaviour + 'a) -> PrioritySteering<'a> {
+        self.behaviours.push(Box::new(behaviour));
+        self
+    }
+
+}
+
+impl<'a> SteeringBehaviour for PrioritySteering<'a> {
+    fn steer(&mut self, character: &Kinematic) -> Option<SteeringOutput> {
+        let epsilon = self.epsilon;
+        self.behaviours.iter_mut()
+            .filter_map(|behaviour| behaviour.steer(character))
+            .find(|output| !output.is_nearly_zero(epsilon))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    fn kinematic_at(position: Vec2, velocity: Vec2) -> Kinematic {
+        Kinematic {
+            position,
+            velocity,
+            friction_value: 1.0,
+            mass: 1.0,
+            ..Default::default()
+        }
+    }
+
+    fn apply_steering(kinematic: &mut Kinematic, steering: Option<SteeringOutput>, max_speed: f32) {
+        if let Some(steering) = steering {
+            kinematic.velocity = (kinematic.velocity + steering.linear * DT).clamp_length_max(max_speed);
+            kinematic.angular_velocity += steering.angular * DT;
+        }
+        kinematic.integrate(DT);
+    }
+
+    #[test]
+    fn test_wander_same_random_values_give_same_steering() {
+
+        let character = kinematic_at(Vec2::ZERO, Vec2::ZERO);
+        let random_values = [0.25, -0.75, 0.0, 1.0, -1.0];
+
+        let mut first_orientation = 0.0;
+        let mut second_orientation = 0.0;
+
+        for random_value in random_values {
+            let first_steering = wander(&character, 10.0, 5.0, 2.0, 0.5, random_value, &mut first_orientation);
+            let second_steering = wander(&character, 10.0, 5.0, 2.0, 0.5, random_value, &mut second_orientation);
+            assert_eq!(first_steering.linear, second_steering.linear);
+        }
+
+        assert_eq!(first_orientation, second_orientation);
+
+    }
+
+    #[test]
+    fn test_wander_accelerates_at_max_and_drifts_within_rate() {
+
+        rand::srand(42);
+
+        let character = kinematic_at(Vec2::ZERO, Vec2::ZERO);
+        let mut wander_orientation = 0.0;
+
+        for _ in 0..100 {
+            let last_wander_orientation = wander_orientation;
+            let steering = wander(&character, 10.0, 5.0, 2.0, 0.5, crate::random_binomial(), &mut wander_orientation);
+            assert!((steering.linear.length() - 10.0).abs() < 0.001);
+            assert!(map_to_range(wander_orientation - last_wander_orientation).abs() <= 0.5 + 0.001);
+            // the target is always on the circle in front, so never behind the character
+            assert!(steering.linear.x > 0.0);
+        }
+
+    }
+
+    #[test]
+    fn test_cohesion_brings_character_to_flock() {
+
+        let flock = [
+            kinematic_at(vec2(100.0, 0.0), Vec2::ZERO),
+            kinematic_at(vec2(100.0, 20.0), Vec2::ZERO),
+            kinematic_at(vec2(120.0, 10.0), Vec2::ZERO)
+        ];
+
+        let mut character = kinematic_at(Vec2::ZERO, Vec2::ZERO);
+        let initial_distance = character.position.distance(vec2(106.67, 10.0));
+
+        for _ in 0..60 {
+            let steering = cohesion(&character, flock.iter().cloned(), 100.0, 500.0);
+            apply_steering(&mut character, Some(steering), 50.0);
+        }
+
+        assert!(character.position.distance(vec2(106.67, 10.0)) < initial_distance);
+
+    }
+
+    #[test]
+    fn test_cohesion_ignores_targets_beyond_threshold() {
+        let character = kinematic_at(Vec2::ZERO, Vec2::ZERO);
+        let flock = vec![kinematic_at(vec2(1000.0, 0.0), Vec2::ZERO)];
+        assert_eq!(cohesion(&character, flock.into_iter(), 10.0, 100.0), SteeringOutput::default());
+    }
+
+    #[test]
+    fn test_alignment_matches_flock_velocity() {
+
+        let flock = [
+            kinematic_at(vec2(10.0, 0.0), vec2(0.0, 10.0)),
+            kinematic_at(vec2(-10.0, 0.0), vec2(0.0, 30.0))
+        ];
+
+        let mut character = kinematic_at(Vec2::ZERO, vec2(20.0, 0.0));
+
+        for _ in 0..120 {
+            let steering = alignment(&character, flock.iter().cloned(), 100.0, 50.0, 0.1);
+            apply_steering(&mut character, Some(steering), 100.0);
+        }
+
+        assert!(character.velocity.distance(vec2(0.0, 20.0)) < 0.5);
+
+    }
+
+    #[test]
+    fn test_avoid_obstacles_steers_around_circle() {
+
+        let obstacles = [SteeringObstacle::Circle { centre: vec2(100.0, 2.0), radius: 20.0 }];
+        let mut character = kinematic_at(Vec2::ZERO, vec2(50.0, 0.0));
+
+        for _ in 0..240 {
+            let steering = avoid_obstacles(&character, obstacles.iter().copied(), 200.0, 1.0, 10.0);
+            apply_steering(&mut character, steering, 50.0);
+            assert!(character.position.distance(vec2(100.0, 2.0)) > 20.0);
+        }
+
+    }
+
+    #[test]
+    fn test_avoid_obstacles_steers_around_rect() {
+
+        let obstacles = [SteeringObstacle::Rect(Rect::new(80.0, -15.0, 40.0, 40.0))];
+        let mut character = kinematic_at(Vec2::ZERO, vec2(50.0, 0.0));
+
+        for _ in 0..240 {
+            let steering = avoid_obstacles(&character, obstacles.iter().copied(), 200.0, 1.0, 10.0);
+            apply_steering(&mut character, steering, 50.0);
+            assert!(!Rect::new(80.0, -15.0, 40.0, 40.0).contains(character.position));
+        }
+
+    }
+
+    #[test]
+    fn test_avoid_obstacles_ignores_obstacles_out_of_the_way() {
+        let obstacles = [SteeringObstacle::Circle { centre: vec2(0.0, 100.0), radius: 10.0 }, SteeringObstacle::Rect(Rect::new(-50.0, -10.0, 20.0, 20.0))];
+        let character = kinematic_at(Vec2::ZERO, vec2(50.0, 0.0));
+        assert!(avoid_obstacles(&character, obstacles.into_iter(), 100.0, 1.0, 10.0).is_none());
+    }
+
+    #[test]
+    fn test_steering_path_positions() {
+
+        let path = SteeringPath::new(vec![vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0)], false);
+
+        assert_eq!(path.length(), 20.0);
+        assert_eq!(path.position_at(15.0), vec2(10.0, 5.0));
+        assert_eq!(path.position_at(100.0), vec2(10.0, 10.0));
+        assert_eq!(path.closest_distance_along(vec2(5.0, 3.0)), 5.0);
+
+        let looping_path = SteeringPath::new(vec![vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0)], true);
+        assert!(looping_path.position_at(looping_path.length() + 5.0).distance(vec2(5.0, 0.0)) < 0.001);
+
+    }
+
+    #[test]
+    fn test_follow_path_reaches_end() {
+
+        let path = SteeringPath::new(vec![vec2(0.0, 0.0), vec2(100.0, 0.0), vec2(100.0, 100.0)], false);
+        let mut character = kinematic_at(vec2(0.0, 10.0), Vec2::ZERO);
+
+        for _ in 0..600 {
+            let steering = follow_path(&character, &path, 10.0, 100.0);
+            apply_steering(&mut character, Some(steering), 40.0);
+            character.velocity *= 0.95;
+        }
+
+        assert!(character.position.distance(vec2(100.0, 100.0)) < 10.0);
+
+    }
+
+    #[test]
+    fn test_blend_steering_behaviours_weights_and_clamps() {
+
+        let character = kinematic_at(Vec2::ZERO, Vec2::ZERO);
+        let mut right = |_: &Kinematic| Some(SteeringOutput::from_linear_velocity(vec2(1.0, 0.0)));
+        let mut up = |_: &Kinematic| Some(SteeringOutput::from_linear_velocity(vec2(0.0, 1.0)));
+        let mut nothing = |_: &Kinematic| None;
+
+        let blended = blend_steering_behaviours(&character, &mut [(2.0, &mut right), (1.0, &mut up), (5.0, &mut nothing)], 100.0, 1.0);
+        assert_eq!(blended.linear, vec2(2.0, 1.0));
+
+        let clamped = blend_steering_behaviours(&character, &mut [(20.0, &mut right)], 5.0, 1.0);
+        assert_eq!(clamped.linear, vec2(5.0, 0.0));
+
+    }
+
+    #[test]
+    fn test_priority_steering_uses_first_significant_behaviour() {
+
+        let character = kinematic_at(Vec2::ZERO, Vec2::ZERO);
+
+        let mut priority = PrioritySteering::new(0.01)
+            .with(|_: &Kinematic| None)
+            .with(|_: &Kinematic| Some(SteeringOutput::from_linear_velocity(vec2(0.001, 0.0))))
+            .with(WeightedSteering::new(10.0, 1.0).with(3.0, |_: &Kinematic| Some(SteeringOutput::from_linear_velocity(vec2(0.0, 1.0)))))
+            .with(|_: &Kinematic| Some(SteeringOutput::from_linear_velocity(vec2(1.0, 0.0))));
+
+        assert_eq!(priority.steer(&character).map(|s| s.linear), Some(vec2(0.0, 3.0)));
+
+    }
+
+}
@@ -379,6 +379,16 @@ pub fn exponential_decay(v: f32, d: f32, t: f32) -> f32 {
     v * (1.0 / (1.0 + (t * d)))
 }
 
+/// How far to move towards something this frame when easing in on it at the given rate, higher rates are snappier, works out the same at any frame rate.
+pub fn smoothing_factor(rate: f32, dt: f32) -> f32 {
+    1.0 - (-rate * dt).exp()
+}
+
+/// True if any of the keys are held, for actions bound to more than one key.
+pub fn is_any_key_down(keys: &[KeyCode]) -> bool {
+    keys.iter().any(|key| is_key_down(*key))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -435,4 +445,15 @@ mod tests {
 
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_smoothing_factor_is_frame_rate_independent() {
+
+        let one_step = smoothing_factor(8.0, 0.1);
+        let two_half_steps = 1.0 - (1.0 - smoothing_factor(8.0, 0.05)).powi(2);
+
+        assert!((one_step - two_half_steps).abs() < 1e-5);
+        assert_eq!(0.0, smoothing_factor(8.0, 0.0));
+
+    }
+
+}
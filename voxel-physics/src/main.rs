@@ -5,7 +5,7 @@ use macroquad::{prelude::{*}, rand::gen_range};
 
 use rapier3d::{prelude::{CCDSolver, MultibodyJointSet, ImpulseJointSet, ColliderSet, RigidBodySet, BroadPhase, IslandManager, PhysicsPipeline, IntegrationParameters, Vector, Real, NarrowPhase, vector, Aabb, Shape, MassProperties, ShapeType, TypedShape, RayIntersection, Ray, PointProjection, FeatureId, Point, Cuboid, Isometry, TOI, SimdCompositeShape, RigidBodyBuilder, ColliderBuilder, RigidBodyHandle, SharedShape, Translation, RigidBody}, parry::{bounding_volume::{BoundingSphere, BoundingVolume}, query::{PointQuery, RayCast, DefaultQueryDispatcher, QueryDispatcher, ClosestPoints, Unsupported, Contact, NonlinearRigidMotion, ContactManifoldsWorkspace, PersistentQueryDispatcher, TypedWorkspaceData, WorkspaceData, visitors::BoundingVolumeIntersectionsVisitor, ContactManifold}, utils::IsometryOpt}};
 use nalgebra::{self, Point3};
-use utility::{GameCamera, create_camera_from_game_camera, DebugText, TextPosition, BenchmarkWithDebugText, voxel_traversal_3d, AdjustHue, draw_sphere_wires_ex, draw_cube_wires_ex};
+use utility::{GameCamera, GameCameraController, GameCameraMode, create_camera_from_game_camera, DebugText, TextPosition, BenchmarkWithDebugText, voxel_traversal_3d, AdjustHue, draw_sphere_wires_ex, draw_cube_wires_ex};
 
 const WORLD_UP: Vec3 = Vec3::Y;
 
@@ -1229,6 +1229,7 @@ impl PhysicsWorld {
 pub struct Game {
 
     camera: GameCamera,
+    camera_controller: GameCameraController,
     debug_text: DebugText,
     voxel_world: Arc<RwLock<dyn VoxelWorld>>,
     physics_world: PhysicsWorld,
@@ -1249,11 +1250,20 @@ impl GameDebugParameters {
     }
 }
 
+/// Both mouse buttons are used to modify the world, so looking around is on the middle mouse button.
+fn create_camera_controller() -> GameCameraController {
+    let mut camera_controller = GameCameraController::new(GameCameraMode::Fly);
+    camera_controller.world_up = WORLD_UP;
+    camera_controller.bindings.rotate_button = Some(MouseButton::Middle);
+    camera_controller
+}
+
 impl Game {
     pub fn new() -> Game {
         Game {
 
             camera: GameCamera::new(),
+            camera_controller: create_camera_controller(),
             debug_text: DebugText::new(),
             voxel_world: Arc::new(RwLock::new(VoxelWorldSimple::new())),
             physics_world: PhysicsWorld::new(),
@@ -1265,51 +1275,6 @@ impl Game {
     }
 }
 
-fn handle_camera_input(active: &mut GameCamera, dt: f32) {
-
-    let is_forwards_pressed = is_key_down(KeyCode::W);
-    let is_backwards_pressed = is_key_down(KeyCode::S);
-    let is_left_pressed = is_key_down(KeyCode::A);
-    let is_right_pressed = is_key_down(KeyCode::D);
-
-    let is_up_pressed = is_key_down(KeyCode::Space);
-    let is_down_pressed = is_key_down(KeyCode::LeftControl);
-
-    let mut camera_movement_delta = Vec3::ZERO;
-
-    let forward_in_plane = vec3(active.forward().x, 0.0, active.forward().z);
-    let left_in_plane = vec3(active.left().x, 0.0, active.left().z);
-    let up_in_plane = WORLD_UP;
-
-    if is_forwards_pressed {
-        camera_movement_delta += forward_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_backwards_pressed {
-        camera_movement_delta -= forward_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_left_pressed {
-        camera_movement_delta += left_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_right_pressed {
-        camera_movement_delta -= left_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_up_pressed {
-        camera_movement_delta += up_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    if is_down_pressed {
-        camera_movement_delta -= up_in_plane * active.parameters.movement_speed * dt;
-    }
-
-    active.position += camera_movement_delta;
-    active.target += camera_movement_delta;
-
-}
-
 fn is_voxel_occluded(voxel_world: &dyn VoxelWorld, position: IVec3) -> bool {
 
     let above = (voxel_world.get_block(position + ivec3(0, 1, 0)) != VoxelKind::Air) as i32;
@@ -1654,7 +1619,7 @@ async fn main() {
         render_current_block_under_mouse(&mut game);
 
         // update camera position etc
-        handle_camera_input(&mut game.camera, dt);
+        game.camera_controller.tick(&mut game.camera, dt);
 
         // handle spawning shit, modifying
         handle_spawning_objects_and_modifying_world(&mut game);